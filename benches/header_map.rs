@@ -0,0 +1,65 @@
+use backblaze_b2_client::{
+    definitions::{
+        headers::{B2UploadFileHeaders, B2UploadPartHeaders},
+        shared::{B2FileLegalHold, B2FileRetentionMode},
+    },
+    util::{serialize_into_header_map, IntoHeaderMap},
+};
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+
+const SHA1: &str = "2aae6c35c94fcfb415dbe95f408b9ce91ee846ed";
+
+fn part_headers() -> B2UploadPartHeaders {
+    B2UploadPartHeaders::builder()
+        .authorization("4_0022623512fc8f80000000001_01a8bb8b_a1b2c3_upld_abcdefghijk=".into())
+        .part_number(42)
+        .content_length(100 * 1024 * 1024)
+        .content_sha1(SHA1.into())
+        .build()
+}
+
+fn file_headers() -> B2UploadFileHeaders {
+    B2UploadFileHeaders::builder()
+        .authorization("4_0022623512fc8f80000000001_01a8bb8b_a1b2c3_upld_abcdefghijk=".into())
+        .file_name("photos/2024/summer/beach%20day.jpg".into())
+        .content_type("b2/x-auto".into())
+        .content_length(4 * 1024 * 1024)
+        .content_sha1(SHA1.into())
+        .src_last_modified_millis(Some(1_700_000_000_000))
+        .b2_cache_control(Some("max-age=3600".into()))
+        .legal_hold(Some(B2FileLegalHold::On))
+        .retention_mode(Some(B2FileRetentionMode::Governance))
+        .retention_retain_until_timestamp(Some(1_800_000_000_000))
+        .build()
+}
+
+fn upload_part_headers(c: &mut Criterion) {
+    let mut group = c.benchmark_group("upload_part_headers");
+    let headers = part_headers();
+
+    group.bench_function("direct", |b| {
+        b.iter(|| black_box(headers.clone()).into_header_map().unwrap())
+    });
+    group.bench_function("serde_json", |b| {
+        b.iter(|| serialize_into_header_map(black_box(&headers)).unwrap())
+    });
+
+    group.finish();
+}
+
+fn upload_file_headers(c: &mut Criterion) {
+    let mut group = c.benchmark_group("upload_file_headers");
+    let headers = file_headers();
+
+    group.bench_function("direct", |b| {
+        b.iter(|| black_box(headers.clone()).into_header_map().unwrap())
+    });
+    group.bench_function("serde_json", |b| {
+        b.iter(|| serialize_into_header_map(black_box(&headers)).unwrap())
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, upload_part_headers, upload_file_headers);
+criterion_main!(benches);
@@ -0,0 +1,91 @@
+use std::time::Duration;
+
+use backblaze_b2_client::{
+    tasks::upload::upload_buffer::UploadBuffer, throttle::Throttle, util::SizeUnit,
+};
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use sha1_smol::Sha1;
+use tokio::runtime::Runtime;
+
+/// A small file, the smallest part B2 allows, and the recommended part size.
+const SIZES: [u64; 3] = [
+    SizeUnit::KIBIBYTE * 64,
+    SizeUnit::MEBIBYTE * 5,
+    SizeUnit::MEBIBYTE * 100,
+];
+
+/// The chunk size small file uploads are streamed in.
+const UPLOAD_CHUNK_SIZE: usize = (SizeUnit::KIBIBYTE * 80) as usize;
+
+fn sized_label(size: u64) -> String {
+    SizeUnit::from(size as f64).to_string()
+}
+
+fn sha1_hashing(c: &mut Criterion) {
+    let mut group = c.benchmark_group("sha1_hashing");
+    group.sample_size(10);
+
+    for size in SIZES {
+        let data = vec![0xa5u8; size as usize];
+
+        group.throughput(Throughput::Bytes(size));
+        group.bench_with_input(
+            BenchmarkId::from_parameter(sized_label(size)),
+            &data,
+            |b, data| b.iter(|| Sha1::from(black_box(data)).hexdigest()),
+        );
+    }
+
+    group.finish();
+}
+
+fn buffer_chunking(c: &mut Criterion) {
+    let mut group = c.benchmark_group("buffer_chunking");
+
+    for size in SIZES {
+        let buffer = UploadBuffer::new(vec![0xa5u8; size as usize]);
+
+        group.throughput(Throughput::Bytes(size));
+        group.bench_with_input(
+            BenchmarkId::from_parameter(sized_label(size)),
+            &buffer,
+            |b, buffer| {
+                b.iter(|| {
+                    buffer
+                        .chunks(UPLOAD_CHUNK_SIZE)
+                        .map(|chunk| black_box(chunk).len())
+                        .sum::<usize>()
+                })
+            },
+        );
+    }
+
+    group.finish();
+}
+
+/// Only the bookkeeping, the limit is never reached so the throttle never sleeps.
+fn throttle_overhead(c: &mut Criterion) {
+    let runtime = Runtime::new().expect("runtime should build");
+    let mut group = c.benchmark_group("throttle_overhead");
+
+    group.bench_function("advance", |b| {
+        b.to_async(&runtime).iter_batched(
+            || Throttle::new(u64::MAX, Duration::from_secs(3600)),
+            |mut throttle| async move { throttle.advance().await },
+            criterion::BatchSize::SmallInput,
+        )
+    });
+
+    group.bench_function("advance_by_chunk", |b| {
+        b.to_async(&runtime).iter_batched(
+            || Throttle::new(u64::MAX, Duration::from_secs(3600)),
+            |mut throttle| async move { throttle.advance_by(UPLOAD_CHUNK_SIZE as u64).await },
+            criterion::BatchSize::SmallInput,
+        )
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, sha1_hashing, buffer_chunking, throttle_overhead);
+criterion_main!(benches);
@@ -0,0 +1,443 @@
+//! The [B2Api] trait, the calls of the B2 native API as [B2SimpleClient] makes them, so code using them can be tested with a mock.
+
+use std::collections::HashMap;
+
+use futures::future::BoxFuture;
+
+use crate::{
+    definitions::{
+        bodies::{
+            B2CopyFileBody, B2CopyPartBody, B2CreateBucketBody, B2CreateKeyBody,
+            B2DeleteFileVersionBody, B2FinishLargeFileBody, B2GetDownloadAuthorizationBody,
+            B2ListBucketsBody, B2StartLargeFileUploadBody, B2UpdateBucketBody,
+            B2UpdateFileLegalHoldBodyResponse, B2UpdateFileRetentionBody,
+        },
+        headers::{B2UploadFileHeaders, B2UploadPartHeaders},
+        query_params::{
+            B2DownloadFileQueryParameters, B2ListFileNamesQueryParameters,
+            B2ListFileVersionsQueryParameters, B2ListKeysParameters, B2ListPartsQueryParameters,
+            B2ListUnfinishedLargeFilesQueryParameters,
+        },
+        responses::{
+            B2AuthData, B2BucketNotificationRulesResponseBody, B2CancelLargeFileResponse,
+            B2DeleteFileVersionResponse, B2FilePart, B2GetDownloadAuthorizationBodyResponse,
+            B2GetUploadPartUrlResponse, B2GetUploadUrlResponse, B2ListBucketsResponse,
+            B2ListFileVersionsResponse, B2ListFilesResponse, B2ListKeysResponse,
+            B2ListPartsResponse, B2ListUnfinishedLargeFilesResponse, B2UpdateFileRetentionResponse,
+        },
+        shared::{B2AppKey, B2Bucket, B2DownloadFileContent, B2File},
+    },
+    error::B2Error,
+    simple_client::B2SimpleClient,
+};
+
+/// One method per B2 native API call, implemented by [B2SimpleClient].
+/// <br> Code that takes a `&dyn B2Api` or a generic `impl B2Api` instead of the client can be given a mock in tests,
+/// like the `test-util` feature's [MockB2Client](crate::mock::MockB2Client).
+/// The client's helpers built on top of the calls, like listing streams and `try_` variants, aren't part of it.
+pub trait B2Api: Send + Sync {
+    /// The identifier of the authorized account.
+    fn account_id(&self) -> &str;
+
+    /// [b2_authorize_account](https://www.backblaze.com/apidocs/b2-authorize-account)
+    fn authorize_account<'a>(
+        &'a self,
+        key_id: &'a str,
+        application_key: &'a str,
+    ) -> BoxFuture<'a, Result<B2AuthData, B2Error>>;
+
+    /// [b2_cancel_large_file](https://www.backblaze.com/apidocs/b2-cancel-large-file)
+    fn cancel_large_file(
+        &self,
+        file_id: String,
+    ) -> BoxFuture<'_, Result<B2CancelLargeFileResponse, B2Error>>;
+
+    /// [b2_copy_file](https://www.backblaze.com/apidocs/b2-copy-file)
+    fn copy_file(&self, body: B2CopyFileBody) -> BoxFuture<'_, Result<B2File, B2Error>>;
+
+    /// [b2_copy_part](https://www.backblaze.com/apidocs/b2-copy-part)
+    fn copy_part(&self, body: B2CopyPartBody) -> BoxFuture<'_, Result<B2FilePart, B2Error>>;
+
+    /// [b2_create_bucket](https://www.backblaze.com/apidocs/b2-create-bucket)
+    fn create_bucket(&self, body: B2CreateBucketBody) -> BoxFuture<'_, Result<B2Bucket, B2Error>>;
+
+    /// [b2_create_key](https://www.backblaze.com/apidocs/b2-create-key)
+    fn create_key(&self, body: B2CreateKeyBody) -> BoxFuture<'_, Result<B2AppKey, B2Error>>;
+
+    /// [b2_delete_bucket](https://www.backblaze.com/apidocs/b2-delete-bucket)
+    fn delete_bucket(
+        &self,
+        account_id: String,
+        bucket_id: String,
+    ) -> BoxFuture<'_, Result<B2Bucket, B2Error>>;
+
+    /// [b2_delete_file_version](https://www.backblaze.com/apidocs/b2-delete-file-version)
+    fn delete_file_version(
+        &self,
+        body: B2DeleteFileVersionBody,
+    ) -> BoxFuture<'_, Result<B2DeleteFileVersionResponse, B2Error>>;
+
+    /// [b2_delete_key](https://www.backblaze.com/apidocs/b2-delete-key)
+    fn delete_key(&self, application_key_id: String) -> BoxFuture<'_, Result<B2AppKey, B2Error>>;
+
+    /// [b2_download_file_by_id](https://www.backblaze.com/apidocs/b2-download-file-by-id)
+    fn download_file_by_id(
+        &self,
+        file_id: String,
+        query: Option<B2DownloadFileQueryParameters>,
+    ) -> BoxFuture<'_, Result<B2DownloadFileContent, B2Error>>;
+
+    /// [b2_download_file_by_name](https://www.backblaze.com/apidocs/b2-download-file-by-name)
+    fn download_file_by_name(
+        &self,
+        bucket_name: String,
+        file_name: String,
+        query: Option<B2DownloadFileQueryParameters>,
+    ) -> BoxFuture<'_, Result<B2DownloadFileContent, B2Error>>;
+
+    /// [b2_finish_large_file](https://www.backblaze.com/apidocs/b2-finish-large-file)
+    fn finish_large_file(
+        &self,
+        body: B2FinishLargeFileBody,
+    ) -> BoxFuture<'_, Result<B2File, B2Error>>;
+
+    /// [b2_get_bucket_notification_rules](https://www.backblaze.com/apidocs/b2-get-bucket-notification-rules)
+    fn get_bucket_notification_rules(
+        &self,
+        bucket_id: String,
+    ) -> BoxFuture<'_, Result<B2BucketNotificationRulesResponseBody, B2Error>>;
+
+    /// [b2_get_download_authorization](https://www.backblaze.com/apidocs/b2-get-download-authorization)
+    fn get_download_authorization(
+        &self,
+        body: B2GetDownloadAuthorizationBody,
+    ) -> BoxFuture<'_, Result<B2GetDownloadAuthorizationBodyResponse, B2Error>>;
+
+    /// [b2_get_file_info](https://www.backblaze.com/apidocs/b2-get-file-info)
+    fn get_file_info(&self, file_id: String) -> BoxFuture<'_, Result<B2File, B2Error>>;
+
+    /// [b2_get_upload_part_url](https://www.backblaze.com/apidocs/b2-get-upload-part-url)
+    fn get_upload_part_url(
+        &self,
+        file_id: String,
+    ) -> BoxFuture<'_, Result<B2GetUploadPartUrlResponse, B2Error>>;
+
+    /// [b2_get_upload_url](https://www.backblaze.com/apidocs/b2-get-upload-url)
+    fn get_upload_url(
+        &self,
+        bucket_id: String,
+    ) -> BoxFuture<'_, Result<B2GetUploadUrlResponse, B2Error>>;
+
+    /// [b2_hide_file](https://www.backblaze.com/apidocs/b2-hide-file)
+    fn hide_file(
+        &self,
+        bucket_id: String,
+        file_name: String,
+    ) -> BoxFuture<'_, Result<B2File, B2Error>>;
+
+    /// [b2_list_buckets](https://www.backblaze.com/apidocs/b2-list-buckets)
+    fn list_buckets(
+        &self,
+        body: B2ListBucketsBody,
+    ) -> BoxFuture<'_, Result<B2ListBucketsResponse, B2Error>>;
+
+    /// [b2_list_file_names](https://www.backblaze.com/apidocs/b2-list-file-names)
+    fn list_file_names(
+        &self,
+        query: B2ListFileNamesQueryParameters,
+    ) -> BoxFuture<'_, Result<B2ListFilesResponse, B2Error>>;
+
+    /// [b2_list_file_versions](https://www.backblaze.com/apidocs/b2-list-file-versions)
+    fn list_file_versions(
+        &self,
+        query: B2ListFileVersionsQueryParameters,
+    ) -> BoxFuture<'_, Result<B2ListFileVersionsResponse, B2Error>>;
+
+    /// [b2_list_keys](https://www.backblaze.com/apidocs/b2-list-keys)
+    fn list_keys(
+        &self,
+        query: B2ListKeysParameters,
+    ) -> BoxFuture<'_, Result<B2ListKeysResponse, B2Error>>;
+
+    /// [b2_list_parts](https://www.backblaze.com/apidocs/b2-list-parts)
+    fn list_parts(
+        &self,
+        query: B2ListPartsQueryParameters,
+    ) -> BoxFuture<'_, Result<B2ListPartsResponse, B2Error>>;
+
+    /// [b2_list_unfinished_large_files](https://www.backblaze.com/apidocs/b2-list-unfinished-large-files)
+    fn list_unfinished_large_files(
+        &self,
+        query: B2ListUnfinishedLargeFilesQueryParameters,
+    ) -> BoxFuture<'_, Result<B2ListUnfinishedLargeFilesResponse, B2Error>>;
+
+    /// [b2_set_bucket_notification_rules](https://www.backblaze.com/apidocs/b2-set-bucket-notification-rules)
+    fn set_bucket_notification_rules(
+        &self,
+        body: B2BucketNotificationRulesResponseBody,
+    ) -> BoxFuture<'_, Result<B2BucketNotificationRulesResponseBody, B2Error>>;
+
+    /// [b2_start_large_file](https://www.backblaze.com/apidocs/b2-start-large-file)
+    fn start_large_file(
+        &self,
+        body: B2StartLargeFileUploadBody,
+    ) -> BoxFuture<'_, Result<B2File, B2Error>>;
+
+    /// [b2_update_bucket](https://www.backblaze.com/apidocs/b2-update-bucket)
+    fn update_bucket(&self, body: B2UpdateBucketBody) -> BoxFuture<'_, Result<B2Bucket, B2Error>>;
+
+    /// [b2_update_file_legal_hold](https://www.backblaze.com/apidocs/b2-update-file-legal-hold)
+    fn update_file_legal_hold(
+        &self,
+        body: B2UpdateFileLegalHoldBodyResponse,
+    ) -> BoxFuture<'_, Result<B2UpdateFileLegalHoldBodyResponse, B2Error>>;
+
+    /// [b2_update_file_retention](https://www.backblaze.com/apidocs/b2-update-file-retention)
+    fn update_file_retention(
+        &self,
+        body: B2UpdateFileRetentionBody,
+    ) -> BoxFuture<'_, Result<B2UpdateFileRetentionResponse, B2Error>>;
+
+    /// [b2_upload_file](https://www.backblaze.com/apidocs/b2-upload-file)
+    fn upload_file(
+        &self,
+        file: reqwest::Body,
+        upload_url: String,
+        headers: B2UploadFileHeaders,
+        file_info: Option<HashMap<String, String>>,
+    ) -> BoxFuture<'_, Result<B2File, B2Error>>;
+
+    /// [b2_upload_part](https://www.backblaze.com/apidocs/b2-upload-part)
+    fn upload_part(
+        &self,
+        headers: B2UploadPartHeaders,
+        part: reqwest::Body,
+        upload_url: String,
+    ) -> BoxFuture<'_, Result<B2FilePart, B2Error>>;
+}
+
+impl B2Api for B2SimpleClient {
+    fn account_id(&self) -> &str {
+        B2SimpleClient::account_id(self)
+    }
+
+    fn authorize_account<'a>(
+        &'a self,
+        key_id: &'a str,
+        application_key: &'a str,
+    ) -> BoxFuture<'a, Result<B2AuthData, B2Error>> {
+        Box::pin(B2SimpleClient::authorize_account(
+            self,
+            key_id,
+            application_key,
+        ))
+    }
+
+    fn cancel_large_file(
+        &self,
+        file_id: String,
+    ) -> BoxFuture<'_, Result<B2CancelLargeFileResponse, B2Error>> {
+        Box::pin(B2SimpleClient::cancel_large_file(self, file_id))
+    }
+
+    fn copy_file(&self, body: B2CopyFileBody) -> BoxFuture<'_, Result<B2File, B2Error>> {
+        Box::pin(B2SimpleClient::copy_file(self, body))
+    }
+
+    fn copy_part(&self, body: B2CopyPartBody) -> BoxFuture<'_, Result<B2FilePart, B2Error>> {
+        Box::pin(B2SimpleClient::copy_part(self, body))
+    }
+
+    fn create_bucket(&self, body: B2CreateBucketBody) -> BoxFuture<'_, Result<B2Bucket, B2Error>> {
+        Box::pin(B2SimpleClient::create_bucket(self, body))
+    }
+
+    fn create_key(&self, body: B2CreateKeyBody) -> BoxFuture<'_, Result<B2AppKey, B2Error>> {
+        Box::pin(B2SimpleClient::create_key(self, body))
+    }
+
+    fn delete_bucket(
+        &self,
+        account_id: String,
+        bucket_id: String,
+    ) -> BoxFuture<'_, Result<B2Bucket, B2Error>> {
+        Box::pin(B2SimpleClient::delete_bucket(self, account_id, bucket_id))
+    }
+
+    fn delete_file_version(
+        &self,
+        body: B2DeleteFileVersionBody,
+    ) -> BoxFuture<'_, Result<B2DeleteFileVersionResponse, B2Error>> {
+        Box::pin(B2SimpleClient::delete_file_version(self, body))
+    }
+
+    fn delete_key(&self, application_key_id: String) -> BoxFuture<'_, Result<B2AppKey, B2Error>> {
+        Box::pin(B2SimpleClient::delete_key(self, application_key_id))
+    }
+
+    fn download_file_by_id(
+        &self,
+        file_id: String,
+        query: Option<B2DownloadFileQueryParameters>,
+    ) -> BoxFuture<'_, Result<B2DownloadFileContent, B2Error>> {
+        Box::pin(B2SimpleClient::download_file_by_id(self, file_id, query))
+    }
+
+    fn download_file_by_name(
+        &self,
+        bucket_name: String,
+        file_name: String,
+        query: Option<B2DownloadFileQueryParameters>,
+    ) -> BoxFuture<'_, Result<B2DownloadFileContent, B2Error>> {
+        Box::pin(B2SimpleClient::download_file_by_name(
+            self,
+            bucket_name,
+            file_name,
+            query,
+        ))
+    }
+
+    fn finish_large_file(
+        &self,
+        body: B2FinishLargeFileBody,
+    ) -> BoxFuture<'_, Result<B2File, B2Error>> {
+        Box::pin(B2SimpleClient::finish_large_file(self, body))
+    }
+
+    fn get_bucket_notification_rules(
+        &self,
+        bucket_id: String,
+    ) -> BoxFuture<'_, Result<B2BucketNotificationRulesResponseBody, B2Error>> {
+        Box::pin(B2SimpleClient::get_bucket_notification_rules(
+            self, bucket_id,
+        ))
+    }
+
+    fn get_download_authorization(
+        &self,
+        body: B2GetDownloadAuthorizationBody,
+    ) -> BoxFuture<'_, Result<B2GetDownloadAuthorizationBodyResponse, B2Error>> {
+        Box::pin(B2SimpleClient::get_download_authorization(self, body))
+    }
+
+    fn get_file_info(&self, file_id: String) -> BoxFuture<'_, Result<B2File, B2Error>> {
+        Box::pin(B2SimpleClient::get_file_info(self, file_id))
+    }
+
+    fn get_upload_part_url(
+        &self,
+        file_id: String,
+    ) -> BoxFuture<'_, Result<B2GetUploadPartUrlResponse, B2Error>> {
+        Box::pin(B2SimpleClient::get_upload_part_url(self, file_id))
+    }
+
+    fn get_upload_url(
+        &self,
+        bucket_id: String,
+    ) -> BoxFuture<'_, Result<B2GetUploadUrlResponse, B2Error>> {
+        Box::pin(B2SimpleClient::get_upload_url(self, bucket_id))
+    }
+
+    fn hide_file(
+        &self,
+        bucket_id: String,
+        file_name: String,
+    ) -> BoxFuture<'_, Result<B2File, B2Error>> {
+        Box::pin(B2SimpleClient::hide_file(self, bucket_id, file_name))
+    }
+
+    fn list_buckets(
+        &self,
+        body: B2ListBucketsBody,
+    ) -> BoxFuture<'_, Result<B2ListBucketsResponse, B2Error>> {
+        Box::pin(B2SimpleClient::list_buckets(self, body))
+    }
+
+    fn list_file_names(
+        &self,
+        query: B2ListFileNamesQueryParameters,
+    ) -> BoxFuture<'_, Result<B2ListFilesResponse, B2Error>> {
+        Box::pin(B2SimpleClient::list_file_names(self, query))
+    }
+
+    fn list_file_versions(
+        &self,
+        query: B2ListFileVersionsQueryParameters,
+    ) -> BoxFuture<'_, Result<B2ListFileVersionsResponse, B2Error>> {
+        Box::pin(B2SimpleClient::list_file_versions(self, query))
+    }
+
+    fn list_keys(
+        &self,
+        query: B2ListKeysParameters,
+    ) -> BoxFuture<'_, Result<B2ListKeysResponse, B2Error>> {
+        Box::pin(B2SimpleClient::list_keys(self, query))
+    }
+
+    fn list_parts(
+        &self,
+        query: B2ListPartsQueryParameters,
+    ) -> BoxFuture<'_, Result<B2ListPartsResponse, B2Error>> {
+        Box::pin(B2SimpleClient::list_parts(self, query))
+    }
+
+    fn list_unfinished_large_files(
+        &self,
+        query: B2ListUnfinishedLargeFilesQueryParameters,
+    ) -> BoxFuture<'_, Result<B2ListUnfinishedLargeFilesResponse, B2Error>> {
+        Box::pin(B2SimpleClient::list_unfinished_large_files(self, query))
+    }
+
+    fn set_bucket_notification_rules(
+        &self,
+        body: B2BucketNotificationRulesResponseBody,
+    ) -> BoxFuture<'_, Result<B2BucketNotificationRulesResponseBody, B2Error>> {
+        Box::pin(B2SimpleClient::set_bucket_notification_rules(self, body))
+    }
+
+    fn start_large_file(
+        &self,
+        body: B2StartLargeFileUploadBody,
+    ) -> BoxFuture<'_, Result<B2File, B2Error>> {
+        Box::pin(B2SimpleClient::start_large_file(self, body))
+    }
+
+    fn update_bucket(&self, body: B2UpdateBucketBody) -> BoxFuture<'_, Result<B2Bucket, B2Error>> {
+        Box::pin(B2SimpleClient::update_bucket(self, body))
+    }
+
+    fn update_file_legal_hold(
+        &self,
+        body: B2UpdateFileLegalHoldBodyResponse,
+    ) -> BoxFuture<'_, Result<B2UpdateFileLegalHoldBodyResponse, B2Error>> {
+        Box::pin(B2SimpleClient::update_file_legal_hold(self, body))
+    }
+
+    fn update_file_retention(
+        &self,
+        body: B2UpdateFileRetentionBody,
+    ) -> BoxFuture<'_, Result<B2UpdateFileRetentionResponse, B2Error>> {
+        Box::pin(B2SimpleClient::update_file_retention(self, body))
+    }
+
+    fn upload_file(
+        &self,
+        file: reqwest::Body,
+        upload_url: String,
+        headers: B2UploadFileHeaders,
+        file_info: Option<HashMap<String, String>>,
+    ) -> BoxFuture<'_, Result<B2File, B2Error>> {
+        Box::pin(B2SimpleClient::upload_file(
+            self, file, upload_url, headers, file_info,
+        ))
+    }
+
+    fn upload_part(
+        &self,
+        headers: B2UploadPartHeaders,
+        part: reqwest::Body,
+        upload_url: String,
+    ) -> BoxFuture<'_, Result<B2FilePart, B2Error>> {
+        Box::pin(B2SimpleClient::upload_part(self, headers, part, upload_url))
+    }
+}
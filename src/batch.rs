@@ -0,0 +1,442 @@
+use std::{
+    collections::HashSet,
+    future::Future,
+    num::NonZeroUsize,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+};
+
+use futures::{stream, StreamExt, TryStreamExt};
+use serde::{Deserialize, Serialize};
+use tokio::sync::Mutex;
+
+use crate::{
+    definitions::{
+        bodies::B2DeleteFileVersionBody,
+        query_params::{
+            B2ListFileVersionsQueryParameters, B2ListUnfinishedLargeFilesQueryParameters,
+        },
+        shared::{B2Action, B2Bucket, B2File},
+    },
+    error::B2Error,
+    pagination::ListingGuard,
+    simple_client::B2SimpleClient,
+    throttle::Throttle,
+    util::{B2Callback, Clock, InvalidValue, RetryContext, RetryStrategy, SystemClock},
+};
+
+/// Options for [BatchExecutor].
+#[derive(Debug)]
+pub struct BatchOptions {
+    /// Max operations running at once.
+    /// <br> Default is 8.
+    pub concurrency: NonZeroUsize,
+    /// Limits how many operations are started per period, retries included, can be used as
+    /// ```rust
+    /// # use backblaze_b2_client::throttle::Throttle;
+    /// // At most 100 requests a second
+    /// let rate_limit = Throttle::per_second(100u64);
+    /// ```
+    /// <br> Default is None.
+    pub rate_limit: Option<Throttle<u64>>,
+    /// Retry strategy for operations failing with a retryable error, see [B2Error::is_retryable].
+    /// <br> Defaults to RetryStrategy::Dynamic([crate::util::DefaultRetryStrategy]).
+    pub retry_strategy: RetryStrategy,
+    /// Whether failures that may have taken effect are retried, see [B2Error::is_ambiguous].
+    /// Turn it off for operations that aren't [idempotent](crate::definitions::shared::B2Endpoint::is_idempotent),
+    /// like copies, so a call whose response got lost isn't made twice.
+    /// <br> Default is true.
+    pub retry_ambiguous: bool,
+    /// Called every time an operation finishes, successfully or not.
+    /// <br> Default is None.
+    pub on_progress: Option<B2Callback<BatchProgress>>,
+    /// Clock the retry waits sleep on, refer to [Clock].
+    /// <br> Default is [SystemClock].
+    pub clock: Arc<dyn Clock>,
+}
+
+impl Default for BatchOptions {
+    fn default() -> Self {
+        Self {
+            concurrency: NonZeroUsize::new(8).expect("8 is not zero"),
+            rate_limit: None,
+            retry_strategy: RetryStrategy::default(),
+            retry_ambiguous: true,
+            on_progress: None,
+            clock: SystemClock::shared(),
+        }
+    }
+}
+
+/// Progress of a batch run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BatchProgress {
+    pub total: u64,
+    pub succeeded: u64,
+    pub failed: u64,
+}
+
+impl BatchProgress {
+    pub fn finished(&self) -> u64 {
+        self.succeeded + self.failed
+    }
+}
+
+/// Runs many B2 operations with bounded concurrency, an optional rate limit, retries and progress reporting.
+/// <br> Used by the bulk helpers on [B2Client](crate::client::B2Client), and usable for any batch of B2 calls.
+#[derive(Debug, Default)]
+pub struct BatchExecutor {
+    options: BatchOptions,
+}
+
+impl BatchExecutor {
+    pub fn new(options: BatchOptions) -> Self {
+        Self { options }
+    }
+
+    /// Runs the operation on every item, returning the results in the same order as the items.
+    /// <br> An item is retried with a clone of itself, a failed item doesn't stop the others.
+    pub async fn run<I, T, F, Fut>(
+        &self,
+        items: impl IntoIterator<Item = I>,
+        operation: F,
+    ) -> Vec<Result<T, B2Error>>
+    where
+        I: Clone,
+        F: Fn(I) -> Fut,
+        Fut: Future<Output = Result<T, B2Error>>,
+    {
+        let items: Vec<I> = items.into_iter().collect();
+        let total = items.len();
+
+        let rate_limit = self.options.rate_limit.clone().map(Mutex::new);
+        let succeeded = AtomicU64::new(0);
+        let failed = AtomicU64::new(0);
+
+        let (rate_limit, succeeded, failed, operation) =
+            (&rate_limit, &succeeded, &failed, &operation);
+
+        let mut results: Vec<(usize, Result<T, B2Error>)> =
+            stream::iter(items.into_iter().enumerate())
+                .map(|(index, item)| async move {
+                    let result = self.run_one(item, rate_limit, operation).await;
+
+                    match result.is_ok() {
+                        true => succeeded.fetch_add(1, Ordering::Relaxed),
+                        false => failed.fetch_add(1, Ordering::Relaxed),
+                    };
+
+                    let progress = BatchProgress {
+                        total: total as u64,
+                        succeeded: succeeded.load(Ordering::Relaxed),
+                        failed: failed.load(Ordering::Relaxed),
+                    };
+
+                    match &self.options.on_progress {
+                        Some(B2Callback::Fn(fun)) => fun(progress),
+                        Some(B2Callback::AsyncFn(fun)) => fun(progress).await,
+                        None => {}
+                    }
+
+                    (index, result)
+                })
+                .buffer_unordered(self.options.concurrency.get())
+                .collect()
+                .await;
+
+        results.sort_by_key(|(index, _)| *index);
+        results.into_iter().map(|(_, result)| result).collect()
+    }
+
+    async fn run_one<I, T, F, Fut>(
+        &self,
+        item: I,
+        rate_limit: &Option<Mutex<Throttle<u64>>>,
+        operation: &F,
+    ) -> Result<T, B2Error>
+    where
+        I: Clone,
+        F: Fn(I) -> Fut,
+        Fut: Future<Output = Result<T, B2Error>>,
+    {
+        let max_attempts = self.options.retry_strategy.max_attempts();
+        let mut attempt = 0;
+
+        loop {
+            attempt += 1;
+
+            if let Some(rate_limit) = rate_limit {
+                rate_limit.lock().await.advance().await;
+            }
+
+            let error = match operation(item.clone()).await {
+                Ok(value) => return Ok(value),
+                Err(error) => error,
+            };
+
+            if !error.is_retryable()
+                || (!self.options.retry_ambiguous && error.is_ambiguous())
+                || attempt >= max_attempts
+            {
+                return Err(error);
+            }
+
+            self.options
+                .clock
+                .sleep(self.options.retry_strategy.retry_wait(&RetryContext {
+                    endpoint: "batch",
+                    attempt: attempt + 1,
+                    last_error: Some(&error),
+                    retry_after: error.retry_after(),
+                }))
+                .await;
+        }
+    }
+}
+
+/// Confirms a destructive bulk operation, like [delete_prefix], by the number of versions it's expected to delete.
+/// <br> Get the number from a [DeletePlan] first. The operation lists the versions again and deletes nothing if the count differs,
+/// so a wrong prefix or bucket doesn't go unnoticed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Destruction {
+    expected_count: usize,
+}
+
+impl Destruction {
+    pub fn confirmed(expected_count: usize) -> Self {
+        Self { expected_count }
+    }
+
+    pub fn expected_count(&self) -> usize {
+        self.expected_count
+    }
+}
+
+/// The versions a destructive bulk operation would delete, listed without deleting anything.
+/// <br> [execute](DeletePlan::execute) deletes exactly these versions once confirmed with their count.
+/// <br> Can be serialized for review, and [filtered](DeletePlan::filter) down to the approved versions before it's executed.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DeletePlan {
+    pub versions: Vec<B2File>,
+}
+
+impl DeletePlan {
+    /// Lists every version of the file, hide markers included.
+    pub async fn file_all_versions(
+        client: &B2SimpleClient,
+        bucket_id: String,
+        file_name: String,
+    ) -> Result<Self, B2Error> {
+        let versions = client
+            .list_all_file_versions(
+                B2ListFileVersionsQueryParameters::builder()
+                    .bucket_id(bucket_id)
+                    .start_file_name(Some(file_name.clone()))
+                    .prefix(Some(file_name.clone()))
+                    .build(),
+                ListingGuard::default(),
+            )
+            .try_take_while(|version| std::future::ready(Ok(version.file_name == file_name)))
+            .try_collect()
+            .await?;
+
+        Ok(Self { versions })
+    }
+
+    /// Lists every version of every file starting with the prefix, hide markers and unfinished large files included.
+    pub async fn prefix(
+        client: &B2SimpleClient,
+        bucket_id: String,
+        prefix: String,
+    ) -> Result<Self, B2Error> {
+        let versions = client
+            .list_all_file_versions(
+                B2ListFileVersionsQueryParameters::builder()
+                    .bucket_id(bucket_id)
+                    .prefix((!prefix.is_empty()).then_some(prefix))
+                    .build(),
+                ListingGuard::default(),
+            )
+            .try_filter(|version| std::future::ready(version.action != B2Action::Folder))
+            .try_collect()
+            .await?;
+
+        Ok(Self { versions })
+    }
+
+    /// Lists every version in the bucket, and every unfinished large file, so deleting them empties the bucket.
+    pub async fn bucket(client: &B2SimpleClient, bucket_id: String) -> Result<Self, B2Error> {
+        let mut plan = Self::prefix(client, bucket_id.clone(), String::new()).await?;
+
+        let listed: HashSet<String> = plan
+            .versions
+            .iter()
+            .map(|version| version.file_id.clone())
+            .collect();
+
+        let unfinished: Vec<B2File> = client
+            .list_all_unfinished_large_files(
+                B2ListUnfinishedLargeFilesQueryParameters::builder()
+                    .bucket_id(bucket_id)
+                    .build(),
+                ListingGuard::default(),
+            )
+            .try_filter(|file| std::future::ready(!listed.contains(&file.file_id)))
+            .try_collect()
+            .await?;
+
+        plan.versions.extend(unfinished);
+
+        Ok(plan)
+    }
+
+    pub fn len(&self) -> usize {
+        self.versions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.versions.is_empty()
+    }
+
+    /// Bytes stored by the planned versions.
+    pub fn bytes(&self) -> u64 {
+        self.versions
+            .iter()
+            .map(|version| version.content_length)
+            .sum()
+    }
+
+    /// Keeps only the versions the predicate approves of, confirm the execution with the new [len](DeletePlan::len).
+    pub fn filter<F: FnMut(&B2File) -> bool>(mut self, predicate: F) -> Self {
+        self.versions.retain(predicate);
+        self
+    }
+
+    /// Deletes the planned versions, unfinished large files are canceled instead.
+    /// <br> Fails without deleting anything if the confirmation's count isn't the number of planned versions.
+    pub async fn execute(
+        &self,
+        client: &B2SimpleClient,
+        confirmation: Destruction,
+        options: BatchOptions,
+    ) -> Result<DeleteReport, B2Error> {
+        if confirmation.expected_count != self.len() {
+            return Err(B2Error::InvalidValue(InvalidValue {
+                object_name: "Destruction".into(),
+                value_name: "expected_count".into(),
+                value_as_string: confirmation.expected_count.to_string(),
+                expected: format!("{}, the number of versions to delete", self.len()),
+            }));
+        }
+
+        let results = BatchExecutor::new(options)
+            .run(self.versions.clone(), |version: B2File| async move {
+                match version.action {
+                    B2Action::Start => client.cancel_large_file(version.file_id).await.map(|_| ()),
+                    _ => client
+                        .delete_file_version(
+                            B2DeleteFileVersionBody::builder()
+                                .file_name(version.file_name)
+                                .file_id(version.file_id)
+                                .build(),
+                        )
+                        .await
+                        .map(|_| ()),
+                }
+            })
+            .await;
+
+        let mut report = DeleteReport::default();
+
+        for (version, result) in self.versions.iter().cloned().zip(results) {
+            match result {
+                Ok(_) => report.deleted.push(version),
+                Err(error) => report.failed.push((version, error)),
+            }
+        }
+
+        Ok(report)
+    }
+}
+
+/// What deleting many file versions did, see [DeletePlan::execute].
+#[derive(Debug, Default)]
+pub struct DeleteReport {
+    /// Versions deleted, unfinished large files included, which are canceled instead.
+    pub deleted: Vec<B2File>,
+    /// Versions that failed to delete, like ones still under Object Lock.
+    pub failed: Vec<(B2File, B2Error)>,
+}
+
+/// What [delete_bucket_recursive] did.
+#[derive(Debug, Default)]
+pub struct BucketDeleteReport {
+    /// The deleted bucket, None when any version failed to delete, since the bucket isn't empty.
+    pub bucket: Option<B2Bucket>,
+    pub files: DeleteReport,
+}
+
+/// Deletes every version in the bucket, canceling unfinished large files, then deletes the bucket.
+/// <br> The versions are listed again and checked against the [confirmation](Destruction), see [DeletePlan::bucket]
+/// to count them without deleting. Progress is reported through [BatchOptions::on_progress].
+pub async fn delete_bucket_recursive(
+    client: &B2SimpleClient,
+    bucket_id: String,
+    confirmation: Destruction,
+    options: BatchOptions,
+) -> Result<BucketDeleteReport, B2Error> {
+    let files = DeletePlan::bucket(client, bucket_id.clone())
+        .await?
+        .execute(client, confirmation, options)
+        .await?;
+
+    if !files.failed.is_empty() {
+        return Ok(BucketDeleteReport {
+            bucket: None,
+            files,
+        });
+    }
+
+    let bucket = client
+        .delete_bucket(client.account_id().to_string(), bucket_id)
+        .await?;
+
+    Ok(BucketDeleteReport {
+        bucket: Some(bucket),
+        files,
+    })
+}
+
+/// Deletes every version of the file, hide markers included, so the name is gone from the bucket.
+/// <br> The versions are listed again and checked against the [confirmation](Destruction), see [DeletePlan::file_all_versions]
+/// to count them without deleting.
+pub async fn delete_file_all_versions(
+    client: &B2SimpleClient,
+    bucket_id: String,
+    file_name: String,
+    confirmation: Destruction,
+    options: BatchOptions,
+) -> Result<DeleteReport, B2Error> {
+    DeletePlan::file_all_versions(client, bucket_id, file_name)
+        .await?
+        .execute(client, confirmation, options)
+        .await
+}
+
+/// Deletes every version of every file starting with the prefix, hide markers and unfinished large files included.
+/// <br> The versions are listed again and checked against the [confirmation](Destruction), see [DeletePlan::prefix]
+/// to count them without deleting.
+pub async fn delete_prefix(
+    client: &B2SimpleClient,
+    bucket_id: String,
+    prefix: String,
+    confirmation: Destruction,
+    options: BatchOptions,
+) -> Result<DeleteReport, B2Error> {
+    DeletePlan::prefix(client, bucket_id, prefix)
+        .await?
+        .execute(client, confirmation, options)
+        .await
+}
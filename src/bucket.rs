@@ -0,0 +1,214 @@
+use std::{collections::HashMap, num::NonZeroU32, sync::Arc};
+
+use async_stream::try_stream;
+use bytes::Bytes;
+use futures::{Stream, StreamExt};
+use tokio::sync::OnceCell;
+
+use crate::{
+    client::B2Client,
+    definitions::{
+        bodies::B2DeleteFileVersionBody,
+        query_params::B2ListFileNamesQueryParameters,
+        responses::B2DeleteFileVersionResponse,
+        shared::{B2Action, B2Bucket, B2File},
+    },
+    error::B2Error,
+    pagination::ListingGuard,
+    sync::B2Location,
+    tasks::{
+        download::{FileDownload, FileDownloadOptions, FileDownloadSource},
+        shared::{AsyncFileReader, AsyncFileWriter},
+        upload::{FileUpload, FileUploadOptions},
+    },
+};
+
+/// A bucket of a [B2Client], for working in one bucket without passing its ID around, see [B2Client::bucket].
+/// <br> The bucket is looked up by name the first time its ID is needed, and cached for the handle's lifetime,
+/// so a handle should be dropped if the bucket is deleted and recreated.
+pub struct BucketHandle<'a> {
+    client: &'a B2Client,
+    name: String,
+    bucket: OnceCell<B2Bucket>,
+}
+
+impl<'a> BucketHandle<'a> {
+    pub(crate) fn new(client: &'a B2Client, name: String) -> Self {
+        Self {
+            client,
+            name,
+            bucket: OnceCell::new(),
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The bucket's details, looked up on first use.
+    pub async fn bucket(&self) -> Result<&B2Bucket, B2Error> {
+        self.bucket
+            .get_or_try_init(|| async {
+                B2Location::new(self.name.clone(), "")
+                    .bucket(&self.client.basic_client())
+                    .await
+            })
+            .await
+    }
+
+    pub async fn id(&self) -> Result<&str, B2Error> {
+        Ok(&self.bucket().await?.bucket_id)
+    }
+
+    /// Creates an upload tracker into the bucket, like [create_upload](B2Client::create_upload).
+    /// Tracker doesn't start upload automatically.
+    pub async fn upload<T>(
+        &self,
+        file: T,
+        file_name: String,
+        optional_info: Option<HashMap<String, String>>,
+        file_size: u64,
+        options: Option<FileUploadOptions>,
+    ) -> Result<Arc<FileUpload>, B2Error>
+    where
+        T: AsyncFileReader + 'static,
+    {
+        let bucket_id = self.id().await?.to_string();
+
+        Ok(self
+            .client
+            .create_upload(
+                file,
+                file_name,
+                bucket_id,
+                optional_info,
+                file_size,
+                options,
+            )
+            .await)
+    }
+
+    /// Creates an upload tracker for data in memory, like [upload_bytes](B2Client::upload_bytes).
+    /// Tracker doesn't start upload automatically.
+    pub async fn upload_bytes<B: Into<Bytes>>(
+        &self,
+        bytes: B,
+        file_name: String,
+        optional_info: Option<HashMap<String, String>>,
+        options: Option<FileUploadOptions>,
+    ) -> Result<Arc<FileUpload>, B2Error> {
+        let bucket_id = self.id().await?.to_string();
+
+        Ok(self
+            .client
+            .upload_bytes(bytes, file_name, bucket_id, optional_info, options)
+            .await)
+    }
+
+    /// Creates a download tracker for the latest version of the file, like [create_download](B2Client::create_download).
+    /// Tracker doesn't start download automatically.
+    pub async fn download<W>(
+        &self,
+        file_name: String,
+        destination: W,
+        options: Option<FileDownloadOptions>,
+    ) -> Arc<FileDownload>
+    where
+        W: AsyncFileWriter + 'static,
+    {
+        self.client
+            .create_download(
+                FileDownloadSource::FileName {
+                    bucket_name: self.name.clone(),
+                    file_name,
+                },
+                destination,
+                options,
+            )
+            .await
+    }
+
+    /// Streams the latest version of every visible file starting with the prefix, or every file without one.
+    pub fn list(
+        &self,
+        prefix: Option<String>,
+        guard: ListingGuard,
+    ) -> impl Stream<Item = Result<B2File, B2Error>> + '_ {
+        try_stream! {
+            let query = B2ListFileNamesQueryParameters::builder()
+                .bucket_id(self.id().await?.to_string())
+                .prefix(prefix)
+                .build();
+
+            let client = self.client.basic_client();
+            let files = client.list_all_file_names(query, guard);
+            futures::pin_mut!(files);
+
+            while let Some(file) = files.next().await {
+                yield file?;
+            }
+        }
+    }
+
+    /// The latest version of the file, None if there's no such file or it's hidden.
+    pub async fn get_file_info(&self, file_name: &str) -> Result<Option<B2File>, B2Error> {
+        let response = self
+            .client
+            .basic_client()
+            .list_file_names(
+                B2ListFileNamesQueryParameters::builder()
+                    .bucket_id(self.id().await?.to_string())
+                    .start_file_name(Some(file_name.to_string()))
+                    .prefix(Some(file_name.to_string()))
+                    .max_file_count(NonZeroU32::new(1))
+                    .build(),
+            )
+            .await?;
+
+        Ok(response
+            .files
+            .into_iter()
+            .find(|file| file.file_name == file_name && file.action == B2Action::Upload))
+    }
+
+    /// Deletes the file's latest version, returning None if there's no such file or it's hidden.
+    /// <br> Older versions are kept, so the previous version becomes the file's content.
+    pub async fn delete_file(
+        &self,
+        file_name: &str,
+    ) -> Result<Option<B2DeleteFileVersionResponse>, B2Error> {
+        let Some(file) = self.get_file_info(file_name).await? else {
+            return Ok(None);
+        };
+
+        self.delete_file_version(file.file_name, file.file_id)
+            .await
+            .map(Some)
+    }
+
+    pub async fn delete_file_version(
+        &self,
+        file_name: String,
+        file_id: String,
+    ) -> Result<B2DeleteFileVersionResponse, B2Error> {
+        self.client
+            .basic_client()
+            .delete_file_version(
+                B2DeleteFileVersionBody::builder()
+                    .file_name(file_name)
+                    .file_id(file_id)
+                    .build(),
+            )
+            .await
+    }
+
+    /// Hides the file, so it no longer shows in listings or downloads by name, its versions are kept.
+    pub async fn hide_file(&self, file_name: String) -> Result<B2File, B2Error> {
+        let bucket_id = self.id().await?.to_string();
+
+        self.client
+            .basic_client()
+            .hide_file(bucket_id, file_name)
+            .await
+    }
+}
@@ -0,0 +1,247 @@
+use std::{
+    collections::HashMap,
+    io::{self, Cursor},
+    num::NonZeroU32,
+    path::Path,
+    pin::Pin,
+    sync::Arc,
+    task::{ready, Context, Poll},
+};
+
+use crate::{
+    definitions::{
+        bodies::B2DeleteFileVersionBody,
+        query_params::B2ListFileNamesQueryParameters,
+        shared::{B2Action, B2File},
+    },
+    error::B2Error,
+    simple_client::B2SimpleClient,
+    tasks::{
+        shared::file_sha1,
+        upload::{error::FileUploadError, upload_stream, StreamUploadOptions},
+    },
+};
+use bytes::Bytes;
+use sha1_smol::Sha1;
+use tokio::io::{AsyncRead, ReadBuf};
+
+/// Result of a [CasStore] put.
+#[derive(Debug, Clone)]
+pub struct CasPut {
+    /// The blob's file name in the bucket, the same for every put of the same content.
+    pub key: String,
+    /// The content's SHA1 as lowercase hex.
+    pub sha1: String,
+    /// False when the blob was already stored, and nothing was uploaded.
+    pub uploaded: bool,
+    /// The stored file, the existing one when nothing was uploaded.
+    pub file: B2File,
+}
+
+/// Content addressable storage in a bucket, every blob is stored once under `<prefix>sha1/<hex>`.
+/// <br> Puts hash the content first and skip the upload when a file with its key already exists,
+/// so storing the same content again costs a listing call instead of an upload.
+/// ```rust
+/// # use std::sync::Arc;
+/// # use backblaze_b2_client::{cas::CasStore, simple_client::B2SimpleClient};
+/// # fn example(client: Arc<B2SimpleClient>) {
+/// let store = CasStore::new(client, "bucket_id".into()).with_prefix("backups/");
+///
+/// assert_eq!(
+///     store.key("2aae6c35c94fcfb415dbe95f408b9ce91ee846ed"),
+///     "backups/sha1/2aae6c35c94fcfb415dbe95f408b9ce91ee846ed"
+/// );
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct CasStore {
+    client: Arc<B2SimpleClient>,
+    bucket_id: String,
+    prefix: String,
+}
+
+impl CasStore {
+    pub fn new(client: Arc<B2SimpleClient>, bucket_id: String) -> Self {
+        Self {
+            client,
+            bucket_id,
+            prefix: String::new(),
+        }
+    }
+
+    /// Stores the blobs under `<prefix>sha1/`, the prefix should end with a `/` to act as a folder.
+    pub fn with_prefix<S: Into<String>>(mut self, prefix: S) -> Self {
+        self.prefix = prefix.into();
+        self
+    }
+
+    pub fn prefix(&self) -> &str {
+        &self.prefix
+    }
+
+    pub fn bucket_id(&self) -> &str {
+        &self.bucket_id
+    }
+
+    /// The file name a blob with the given SHA1 is stored under.
+    pub fn key(&self, sha1: &str) -> String {
+        format!("{}sha1/{}", self.prefix, sha1.to_ascii_lowercase())
+    }
+
+    /// The stored blob with the given SHA1, None if it isn't stored or its latest version is hidden.
+    pub async fn get(&self, sha1: &str) -> Result<Option<B2File>, B2Error> {
+        let key = self.key(sha1);
+
+        let response = self
+            .client
+            .list_file_names(
+                B2ListFileNamesQueryParameters::builder()
+                    .bucket_id(self.bucket_id.clone())
+                    .start_file_name(Some(key.clone()))
+                    .prefix(Some(key.clone()))
+                    .max_file_count(NonZeroU32::new(1))
+                    .build(),
+            )
+            .await?;
+
+        Ok(response
+            .files
+            .into_iter()
+            .find(|file| file.file_name == key && file.action == B2Action::Upload))
+    }
+
+    pub async fn contains(&self, sha1: &str) -> Result<bool, B2Error> {
+        Ok(self.get(sha1).await?.is_some())
+    }
+
+    /// Stores the bytes unless a blob with the same content is already stored.
+    pub async fn put_bytes<B: Into<Bytes>>(
+        &self,
+        bytes: B,
+        options: Option<StreamUploadOptions>,
+    ) -> Result<CasPut, FileUploadError> {
+        let bytes: Bytes = bytes.into();
+        let sha1 = Sha1::from(&bytes).digest().to_string();
+        let length = bytes.len() as u64;
+
+        self.put(
+            sha1,
+            length,
+            Cursor::new(bytes),
+            options.unwrap_or_default(),
+        )
+        .await
+    }
+
+    /// Stores the file's content unless a blob with the same content is already stored.
+    /// <br> The file is read twice, once to hash it and once to upload it. The bytes uploaded are hashed again,
+    /// a file that changed in between fails with [ChecksumMismatch](FileUploadError::ChecksumMismatch) and isn't kept.
+    pub async fn put_file<P: AsRef<Path>>(
+        &self,
+        path: P,
+        options: Option<StreamUploadOptions>,
+    ) -> Result<CasPut, FileUploadError> {
+        let path = path.as_ref();
+        let (length, sha1) = file_sha1(path)
+            .await
+            .map_err(FileUploadError::FailedToReadFile)?;
+
+        let file = tokio::fs::File::open(path)
+            .await
+            .map_err(FileUploadError::FailedToReadFile)?;
+
+        self.put(sha1, length, file, options.unwrap_or_default())
+            .await
+    }
+
+    async fn put<R: AsyncRead + Unpin + Send>(
+        &self,
+        sha1: String,
+        length: u64,
+        reader: R,
+        options: StreamUploadOptions,
+    ) -> Result<CasPut, FileUploadError> {
+        let key = self.key(&sha1);
+
+        if let Some(file) = self.get(&sha1).await? {
+            return Ok(CasPut {
+                key,
+                sha1,
+                uploaded: false,
+                file,
+            });
+        }
+
+        let part_size = options
+            .part_size
+            .unwrap_or_else(|| self.client.recommended_part_size());
+
+        // Content that goes up as a large file gets no content SHA1 from B2, so it's kept in the file info instead
+        let file_info = (length > part_size)
+            .then(|| HashMap::from([("large_file_sha1".to_string(), sha1.clone())]));
+
+        let mut reader = HashingReader {
+            inner: reader,
+            sha1: Sha1::new(),
+        };
+
+        let file = upload_stream(
+            &self.client,
+            &mut reader,
+            key.clone(),
+            self.bucket_id.clone(),
+            file_info,
+            options,
+        )
+        .await?;
+
+        // The key has to be the hash of what was sent, not of what was read before
+        let sent_sha1 = reader.sha1.digest().to_string();
+
+        if sent_sha1 != sha1 {
+            self.client
+                .delete_file_version(
+                    B2DeleteFileVersionBody::builder()
+                        .file_name(file.file_name)
+                        .file_id(file.file_id)
+                        .build(),
+                )
+                .await
+                .ok();
+
+            return Err(FileUploadError::ChecksumMismatch {
+                expected: sha1,
+                actual: sent_sha1,
+            });
+        }
+
+        Ok(CasPut {
+            key,
+            sha1,
+            uploaded: true,
+            file,
+        })
+    }
+}
+
+/// Hashes the bytes read through it.
+struct HashingReader<R> {
+    inner: R,
+    sha1: Sha1,
+}
+
+impl<R: AsyncRead + Unpin> AsyncRead for HashingReader<R> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        let filled = buf.filled().len();
+
+        ready!(Pin::new(&mut this.inner).poll_read(cx, buf))?;
+        this.sha1.update(&buf.filled()[filled..]);
+
+        Poll::Ready(Ok(()))
+    }
+}
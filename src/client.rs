@@ -1,19 +1,69 @@
 use std::{
     collections::HashMap,
+    io::{self, Cursor},
+    num::NonZeroUsize,
+    path::Path,
     sync::Arc,
     time::{Duration, SystemTime},
 };
 
-use tokio::{sync::RwLock, task::JoinHandle, time::sleep};
+use bytes::Bytes;
+use tokio::{io::AsyncRead, sync::RwLock, task::JoinHandle};
 
+#[cfg(feature = "signals")]
+use crate::signals::{ShutdownSignals, SignalAbortOptions};
 use crate::{
+    batch::{
+        self, BatchExecutor, BatchOptions, BucketDeleteReport, DeletePlan, DeleteReport,
+        Destruction,
+    },
+    bucket::BucketHandle,
+    cas::CasStore,
+    definitions::{
+        bodies::{
+            B2CopyFileBody, B2CopyPartBody, B2DeleteFileVersionBody, B2ListBucketsBody,
+            B2UpdateFileLegalHoldBodyResponse, B2UpdateFileRetentionBody,
+        },
+        query_params::B2ListFileNamesQueryParameters,
+        responses::{
+            B2DeleteFileVersionResponse, B2FilePart, B2ListFilesResponse, B2RetentionExtension,
+            B2UpdateFileRetentionResponse,
+        },
+        shared::{B2BucketFileRetention, B2File, B2KeyCapability, B2MetadataDirective},
+    },
+    doctor::{self, DoctorOptions, DoctorReport},
+    download_authorizer::{DownloadAuthorizer, DownloadAuthorizerOptions},
     error::B2Error,
-    simple_client::B2SimpleClient,
+    naming::KeyNamingStrategy,
+    prefix_size::{prefix_size, PrefixSize, PrefixSizeOptions},
+    simple_client::{B2SimpleClient, B2SimpleClientBuilder},
+    sync::{
+        sync_down, sync_up, B2Location, LocalPath, SyncDownOptions, SyncDownReport, SyncPlan,
+        SyncUpOptions, SyncUpReport,
+    },
     tasks::{
-        shared::AsyncFileReader,
-        upload::{file_upload::FileUpload, FileUploadOptions},
+        backup::{backup, restore, BackupFile, BackupOptions, BackupReport, RestoreReport},
+        copy::{self, verify, CopyOptions, FileCopyError, MoveOptions},
+        download::{
+            error::FileDownloadError, split_download, B2RemoteFile, B2RemoteFileOptions,
+            FileDownload, FileDownloadOptions, FileDownloadSource, SplitDownloadOptions,
+            SplitManifest,
+        },
+        error::TransferError,
+        shared::{AsyncFileReader, AsyncFileWriter},
+        upload::{
+            error::FileUploadError, file_upload::FileUpload, upload_stream, B2FileUploadSettings,
+            ConstantLargeFileLoadStrategy, FileRegion, FileUploadOptions, LargeFileLoadStrategy,
+            LargePartUploader, LargePartUploaderOptions, QueuedUploadResult, StagingPolicy,
+            StreamUploadOptions, UploadQueue, UploadTaskDescriptor, UPLOAD_TASK_DESCRIPTOR_VERSION,
+        },
+    },
+    throttle::SharedThrottle,
+    usage::UsageTracker,
+    util::{
+        file_info_has_tags, file_info_with_tags, B2Callback, Clock, InvalidValue, SystemClock,
+        WriteLockArc,
     },
-    util::{B2Callback, WriteLockArc},
 };
 
 #[derive(Debug, Clone)]
@@ -24,36 +74,125 @@ pub enum B2ClientStatus {
     KeyExpired,
 }
 
+/// Options for [B2Client::spawn_keep_alive].
+#[derive(Debug)]
+pub struct KeepAliveOptions {
+    /// How often the client is [readied](B2Client::ensure_ready).
+    /// <br> Default is 5 minutes.
+    pub interval: Duration,
+    /// Called when readying the client fails, the task carries on and tries again next time.
+    /// <br> Default is None.
+    pub on_error: Option<B2Callback<B2Error>>,
+}
+
+impl Default for KeepAliveOptions {
+    fn default() -> Self {
+        Self {
+            interval: Duration::from_secs(5 * 60),
+            on_error: None,
+        }
+    }
+}
+
+/// How many uploads the [upload queue](B2Client::upload_queue) runs at once, until changed.
+const DEFAULT_MAX_CONCURRENT_UPLOADS: usize = 4;
+
+/// Tasks tracked by a [B2Client], a finished task's slot is emptied for the next one.
+type TrackedTasks<T> = Arc<RwLock<Vec<Option<Arc<T>>>>>;
+
+trait TrackedTask {
+    fn task_id(&self) -> u64;
+}
+
+impl TrackedTask for FileUpload {
+    fn task_id(&self) -> u64 {
+        self.id()
+    }
+}
+
+impl TrackedTask for FileDownload {
+    fn task_id(&self) -> u64 {
+        self.id()
+    }
+}
+
 pub struct B2Client {
     client: Arc<B2SimpleClient>,
-    uploading_files: Arc<RwLock<Vec<Option<Arc<FileUpload>>>>>,
+    uploading_files: TrackedTasks<FileUpload>,
+    downloading_files: TrackedTasks<FileDownload>,
+    upload_queue: UploadQueue,
+    shared_throttle: Option<Arc<SharedThrottle>>,
+    naming: Option<Arc<dyn KeyNamingStrategy>>,
+    usage: Arc<UsageTracker>,
     reauth_handle: JoinHandle<()>,
     status: WriteLockArc<B2ClientStatus>,
+    key_id: Arc<str>,
+    application_key: Arc<str>,
+    clock: Arc<dyn Clock>,
 }
 
 impl B2Client {
     pub async fn new(key_id: String, application_key: String) -> Result<Self, B2Error> {
+        Self::new_with_clock(key_id, application_key, SystemClock::shared()).await
+    }
+
+    /// Like [new](B2Client::new), with the reauthorization loop timed by the given clock instead of the system's, refer to [Clock].
+    /// <br> The key is reauthorized every 10 hours of the clock's time, and the client becomes [KeyExpired](B2ClientStatus::KeyExpired)
+    /// once the clock's [system time](Clock::system_time) reaches the key's expiration.
+    /// <br> Retry waits, stall timeouts and the [usage](B2Client::usage) tracker go by the same clock.
+    pub async fn new_with_clock(
+        key_id: String,
+        application_key: String,
+        clock: Arc<dyn Clock>,
+    ) -> Result<Self, B2Error> {
+        Self::create(key_id, application_key, B2SimpleClient::builder(), clock).await
+    }
+
+    /// Like [new](B2Client::new), with the HTTP client configured by the builder, for proxies and timeouts, refer to [B2SimpleClientBuilder].
+    pub async fn new_with_builder(
+        key_id: String,
+        application_key: String,
+        builder: B2SimpleClientBuilder,
+    ) -> Result<Self, B2Error> {
+        Self::create(key_id, application_key, builder, SystemClock::shared()).await
+    }
+
+    async fn create(
+        key_id: String,
+        application_key: String,
+        builder: B2SimpleClientBuilder,
+        clock: Arc<dyn Clock>,
+    ) -> Result<Self, B2Error> {
         let key_id: Arc<str> = Arc::from(key_id.into_boxed_str());
         let application_key: Arc<str> = Arc::from(application_key.into_boxed_str());
         let status = WriteLockArc::new(B2ClientStatus::Authed);
 
-        let client = Arc::new(B2SimpleClient::new(&key_id, &application_key).await?);
+        let client = Arc::new(
+            builder
+                .build(&key_id, &application_key)
+                .await?
+                .with_clock(clock.clone()),
+        );
 
         let reauth_client = client.clone();
         let status_expire = status.clone();
+        let (reauth_key_id, reauth_application_key) = (key_id.clone(), application_key.clone());
+        let reauth_clock = clock.clone();
 
         let reauth_handle = tokio::spawn(async move {
             let client = reauth_client.clone();
             let status = status_expire.clone();
+            let (key_id, application_key, clock) =
+                (reauth_key_id, reauth_application_key, reauth_clock);
 
             loop {
-                let now = SystemTime::now();
+                let now = clock.system_time();
                 // 10 hours
-                let mut end_time = SystemTime::now() + Duration::from_secs(60 * 60 * 10);
+                let mut end_time = now + Duration::from_secs(60 * 60 * 10);
                 let mut expiring = false;
 
                 if let Some(timestamp) = client.auth_data().application_key_expiration_timestamp {
-                    let end = SystemTime::UNIX_EPOCH + Duration::from_secs(timestamp);
+                    let end = SystemTime::UNIX_EPOCH + Duration::from_millis(timestamp);
 
                     if end < end_time {
                         expiring = true;
@@ -66,7 +205,7 @@ impl B2Client {
                     Err(error) => error.duration(),
                 };
 
-                sleep(wait).await;
+                clock.sleep(wait).await;
 
                 if expiring {
                     status.set(B2ClientStatus::KeyExpired).await;
@@ -78,12 +217,23 @@ impl B2Client {
         });
 
         let uploading_files = Arc::new(RwLock::new(vec![]));
+        let downloading_files = Arc::new(RwLock::new(vec![]));
 
         Ok(Self {
             client,
             reauth_handle,
             uploading_files,
+            downloading_files,
+            upload_queue: UploadQueue::new(
+                NonZeroUsize::new(DEFAULT_MAX_CONCURRENT_UPLOADS).expect("4 is not zero"),
+            ),
+            shared_throttle: None,
+            naming: None,
+            usage: Arc::new(UsageTracker::with_clock(clock.clone())),
             status,
+            key_id,
+            application_key,
+            clock,
         })
     }
 
@@ -92,6 +242,88 @@ impl B2Client {
         (*self.status).clone()
     }
 
+    /// Makes sure the next call is fast and authorized, for long running apps whose calls are hours apart.
+    /// <br> Makes a cheap [b2_list_buckets](B2SimpleClient::list_buckets) call, which also opens a fresh connection,
+    /// and reauthorizes the account if the token was rejected, or the key can't list buckets.
+    pub async fn ensure_ready(&self) -> Result<(), B2Error> {
+        ensure_ready(&self.client, &self.key_id, &self.application_key).await
+    }
+
+    /// Spawns a task that [readies](B2Client::ensure_ready) the client every [interval](KeepAliveOptions::interval),
+    /// so idle connections and tokens don't go stale between rare transfers.
+    /// <br> Runs until the client is dropped or the returned handle is aborted.
+    pub fn spawn_keep_alive(&self, options: KeepAliveOptions) -> JoinHandle<()> {
+        let client = Arc::downgrade(&self.client);
+        let (key_id, application_key) = (self.key_id.clone(), self.application_key.clone());
+        let clock = self.clock.clone();
+
+        tokio::spawn(async move {
+            loop {
+                clock.sleep(options.interval).await;
+
+                let Some(client) = client.upgrade() else {
+                    break;
+                };
+
+                if let Err(error) = ensure_ready(&client, &key_id, &application_key).await {
+                    match &options.on_error {
+                        Some(B2Callback::Fn(fun)) => fun(error),
+                        Some(B2Callback::AsyncFn(fun)) => fun(error).await,
+                        None => {}
+                    }
+                }
+            }
+        })
+    }
+
+    /// Makes every upload and download the client creates share the throttle, so together they stay under its limit,
+    /// refer to [SharedThrottle]. <br><br>
+    /// Transfers whose options already have a shared throttle keep their own.
+    pub fn with_shared_throttle(mut self, throttle: Arc<SharedThrottle>) -> Self {
+        self.shared_throttle = Some(throttle);
+        self
+    }
+
+    pub fn shared_throttle(&self) -> Option<Arc<SharedThrottle>> {
+        self.shared_throttle.clone()
+    }
+
+    /// Names every file the client's upload helpers upload with the strategy, refer to [KeyNamingStrategy]. <br><br>
+    /// Uploads recreated from a descriptor keep the name they had. [sync_up](B2Client::sync_up) doesn't use it, since a sync needs
+    /// a strategy that gives a file the same name every time, set one in its [options](SyncUpOptions::naming) instead.
+    pub fn with_naming_strategy(mut self, naming: Arc<dyn KeyNamingStrategy>) -> Self {
+        self.naming = Some(naming);
+        self
+    }
+
+    pub fn naming_strategy(&self) -> Option<Arc<dyn KeyNamingStrategy>> {
+        self.naming.clone()
+    }
+
+    fn file_name(&self, file_name: String) -> String {
+        match &self.naming {
+            Some(naming) => naming.file_name(&file_name),
+            None => file_name,
+        }
+    }
+
+    /// Makes the client record its transfers into the given tracker instead of its own, like one shared by several clients.
+    pub fn with_usage_tracker(mut self, usage: Arc<UsageTracker>) -> Self {
+        self.usage = usage;
+        self
+    }
+
+    /// Bytes uploaded and downloaded by the client's transfers, in total and per bucket, refer to [UsageTracker]. <br><br>
+    /// Transfers whose options already have a usage tracker record into their own.
+    pub fn usage(&self) -> &Arc<UsageTracker> {
+        &self.usage
+    }
+
+    /// A handle for working in the bucket with the given name, the bucket's ID is looked up on first use, refer to [BucketHandle].
+    pub fn bucket<S: Into<String>>(&self, name: S) -> BucketHandle<'_> {
+        BucketHandle::new(self, name.into())
+    }
+
     /// Returns reference to inner basic client
     pub fn basic_client(&self) -> Arc<B2SimpleClient> {
         self.client.clone()
@@ -111,17 +343,55 @@ impl B2Client {
     where
         T: AsyncFileReader + 'static,
     {
+        self.create_named_upload(
+            file,
+            self.file_name(file_name),
+            bucket_id,
+            optional_info,
+            file_size,
+            options,
+        )
+        .await
+    }
+
+    /// Like [create_upload](B2Client::create_upload), with the file name already given by the naming strategy.
+    async fn create_named_upload<T>(
+        &self,
+        file: T,
+        file_name: String,
+        bucket_id: String,
+        optional_info: Option<HashMap<String, String>>,
+        file_size: u64,
+        options: Option<FileUploadOptions>,
+    ) -> Arc<FileUpload>
+    where
+        T: AsyncFileReader + 'static,
+    {
+        let mut options = options.unwrap_or_default();
+
+        if options.shared_throttle.is_none() {
+            options.shared_throttle = self.shared_throttle.clone();
+        }
+
+        if options.usage.is_none() {
+            options.usage = Some(self.usage.clone());
+        }
+
+        if options.clock.is_none() {
+            options.clock = Some(self.clock.clone());
+        }
+
         let file_handle = FileUpload::new(
             file,
             file_name,
             bucket_id,
             optional_info,
             file_size,
-            options.unwrap_or_else(|| FileUploadOptions::default()),
+            options,
             self.client.clone(),
         );
 
-        self.push_upload(file_handle.clone()).await;
+        B2Client::push_task(&self.uploading_files, file_handle.clone()).await;
         let id = file_handle.id();
         let uploading_files = self.uploading_files.clone();
 
@@ -130,7 +400,51 @@ impl B2Client {
                 let uploading_files = uploading_files.clone();
 
                 async move {
-                    B2Client::abort_upload_inner(uploading_files, id).await;
+                    B2Client::untrack_task(uploading_files, id).await;
+                }
+            }))
+            .await;
+
+        file_handle
+    }
+
+    /// Creates file download tracker that writes the file to `destination`, and returns reference to it. <br><br>
+    /// Tracker doesn't start download automatically, it needs to be started manually.
+    pub async fn create_download<W>(
+        &self,
+        source: FileDownloadSource,
+        destination: W,
+        options: Option<FileDownloadOptions>,
+    ) -> Arc<FileDownload>
+    where
+        W: AsyncFileWriter + 'static,
+    {
+        let mut options = options.unwrap_or_default();
+
+        if options.shared_throttle.is_none() {
+            options.shared_throttle = self.shared_throttle.clone();
+        }
+
+        if options.usage.is_none() {
+            options.usage = Some(self.usage.clone());
+        }
+
+        if options.clock.is_none() {
+            options.clock = Some(self.clock.clone());
+        }
+
+        let file_handle = FileDownload::new(source, destination, options, self.client.clone());
+
+        B2Client::push_task(&self.downloading_files, file_handle.clone()).await;
+        let id = file_handle.id();
+        let downloading_files = self.downloading_files.clone();
+
+        file_handle
+            .add_finish_callback(B2Callback::from_async_fn(move |_| {
+                let downloading_files = downloading_files.clone();
+
+                async move {
+                    B2Client::untrack_task(downloading_files, id).await;
                 }
             }))
             .await;
@@ -138,6 +452,732 @@ impl B2Client {
         file_handle
     }
 
+    /// Downloads a file into chunk files in `directory` with parallel ranged downloads, writing a manifest with every chunk's SHA1 next to them,
+    /// refer to [split_download].
+    pub async fn split_download<P: AsRef<Path>>(
+        &self,
+        file_id: String,
+        directory: P,
+        options: Option<SplitDownloadOptions>,
+    ) -> Result<SplitManifest, FileDownloadError> {
+        split_download(
+            &self.client,
+            file_id,
+            directory,
+            options.unwrap_or_default(),
+        )
+        .await
+    }
+
+    /// Mirrors the files under `source` into the `destination` directory, downloading new and changed files, refer to [sync_down]. <br><br>
+    /// Transfers get the client's [shared throttle](B2Client::with_shared_throttle) and [usage tracker](B2Client::usage) unless the options have their own.
+    pub async fn sync_down(
+        &self,
+        source: &B2Location,
+        destination: &LocalPath,
+        options: Option<SyncDownOptions>,
+    ) -> Result<SyncDownReport, TransferError> {
+        let mut options = options.unwrap_or_default();
+
+        if options.shared_throttle.is_none() {
+            options.shared_throttle = self.shared_throttle.clone();
+        }
+
+        if options.usage.is_none() {
+            options.usage = Some(self.usage.clone());
+        }
+
+        sync_down(self.client.clone(), source, destination, options).await
+    }
+
+    /// Mirrors the `source` directory into the files under `destination`, uploading new and changed files, refer to [sync_up]. <br><br>
+    /// Transfers get the client's [shared throttle](B2Client::with_shared_throttle) and [usage tracker](B2Client::usage)
+    /// unless the options have their own. The client's naming strategy isn't used, only the options' [naming](SyncUpOptions::naming).
+    pub async fn sync_up(
+        &self,
+        source: &LocalPath,
+        destination: &B2Location,
+        options: Option<SyncUpOptions>,
+    ) -> Result<SyncUpReport, TransferError> {
+        let options = self.sync_up_options(options);
+
+        sync_up(self.client.clone(), source, destination, options).await
+    }
+
+    /// Compares the `source` directory against the files under `destination` without changing anything, refer to [SyncPlan::sync_up].
+    /// <br> Only the options' [naming](SyncUpOptions::naming) is used, not the client's naming strategy.
+    pub async fn plan_sync_up(
+        &self,
+        source: &LocalPath,
+        destination: &B2Location,
+        options: Option<SyncUpOptions>,
+    ) -> Result<SyncPlan, TransferError> {
+        let options = self.sync_up_options(options);
+
+        SyncPlan::sync_up(&self.client, source, destination, &options).await
+    }
+
+    /// Does what the plan says, confirmed with its [deleted_versions](SyncPlan::deleted_versions), refer to [SyncPlan::execute]. <br><br>
+    /// Uploads get the client's [shared throttle](B2Client::with_shared_throttle) and [usage tracker](B2Client::usage) unless the options have their own.
+    pub async fn execute_sync_plan(
+        &self,
+        plan: SyncPlan,
+        options: Option<SyncUpOptions>,
+        confirmation: Destruction,
+    ) -> Result<SyncUpReport, TransferError> {
+        let options = self.sync_up_options(options);
+
+        plan.execute(self.client.clone(), &options, confirmation)
+            .await
+    }
+
+    fn sync_up_options(&self, options: Option<SyncUpOptions>) -> SyncUpOptions {
+        let mut options = options.unwrap_or_default();
+
+        if options.shared_throttle.is_none() {
+            options.shared_throttle = self.shared_throttle.clone();
+        }
+
+        if options.usage.is_none() {
+            options.usage = Some(self.usage.clone());
+        }
+
+        options
+    }
+
+    /// Counts the files and bytes under the prefix, refer to [prefix_size].
+    pub async fn prefix_size(
+        &self,
+        bucket_id: String,
+        prefix: String,
+        options: Option<PrefixSizeOptions>,
+    ) -> Result<PrefixSize, B2Error> {
+        prefix_size(
+            &self.client,
+            bucket_id,
+            prefix,
+            &options.unwrap_or_default(),
+        )
+        .await
+    }
+
+    /// A [CasStore] storing deduplicated blobs in the bucket, using the client's underlying [B2SimpleClient].
+    pub fn cas_store(&self, bucket_id: String) -> CasStore {
+        CasStore::new(self.client.clone(), bucket_id)
+    }
+
+    /// Uploads the files and a manifest listing them under `destination`, resuming an earlier backup to the same location,
+    /// refer to [backup]. <br><br>
+    /// Transfers get the client's [shared throttle](B2Client::with_shared_throttle) and [usage tracker](B2Client::usage) unless the options have their own.
+    pub async fn backup(
+        &self,
+        files: Vec<BackupFile>,
+        destination: &B2Location,
+        options: Option<BackupOptions>,
+    ) -> Result<BackupReport, TransferError> {
+        backup(
+            self.client.clone(),
+            files,
+            destination,
+            self.backup_options(options),
+        )
+        .await
+    }
+
+    /// Downloads and verifies every file of the backup at `source` into `destination`, refer to [restore]. <br><br>
+    /// Transfers get the client's [shared throttle](B2Client::with_shared_throttle) and [usage tracker](B2Client::usage) unless the options have their own.
+    pub async fn restore(
+        &self,
+        source: &B2Location,
+        destination: &LocalPath,
+        options: Option<BackupOptions>,
+    ) -> Result<RestoreReport, TransferError> {
+        restore(
+            self.client.clone(),
+            source,
+            destination,
+            self.backup_options(options),
+        )
+        .await
+    }
+
+    fn backup_options(&self, options: Option<BackupOptions>) -> BackupOptions {
+        let mut options = options.unwrap_or_default();
+
+        if options.shared_throttle.is_none() {
+            options.shared_throttle = self.shared_throttle.clone();
+        }
+
+        if options.usage.is_none() {
+            options.usage = Some(self.usage.clone());
+        }
+
+        options
+    }
+
+    /// The client's upload queue, runs 4 uploads at once unless [changed](UploadQueue::set_max_concurrent). <br><br>
+    /// Uploads created by the client are only queued when passed to [enqueue_upload](B2Client::enqueue_upload).
+    pub fn upload_queue(&self) -> &UploadQueue {
+        &self.upload_queue
+    }
+
+    /// Adds an upload tracker to the [upload queue](B2Client::upload_queue) instead of starting it,
+    /// it's started once enough queued uploads before it finish.
+    pub fn enqueue_upload(&self, upload: Arc<FileUpload>) -> QueuedUploadResult {
+        self.upload_queue.enqueue(upload)
+    }
+
+    /// Creates a files upload tracker that uploads only `length` bytes of the file starting at `offset`, like [create_upload](B2Client::create_upload). <br><br>
+    /// Fails if the range goes past the end of the file.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn create_region_upload<T>(
+        &self,
+        file: T,
+        offset: u64,
+        length: u64,
+        file_name: String,
+        bucket_id: String,
+        optional_info: Option<HashMap<String, String>>,
+        options: Option<FileUploadOptions>,
+    ) -> Result<Arc<FileUpload>, FileUploadError>
+    where
+        T: AsyncFileReader + 'static,
+    {
+        let region = FileRegion::new(file, offset, length)
+            .await
+            .map_err(FileUploadError::FailedToReadFile)?;
+
+        Ok(self
+            .create_upload(region, file_name, bucket_id, optional_info, length, options)
+            .await)
+    }
+
+    /// Creates a files upload tracker for data already in memory, like [create_upload](B2Client::create_upload). <br><br>
+    /// Anything convertible to [Bytes] works, like a `Vec<u8>` or a `String`, a borrowed slice can be passed with [Bytes::copy_from_slice].
+    /// The data is uploaded as a large file when it's over the cutoff, same as a file. Tracker doesn't start upload automatically.
+    pub async fn upload_bytes<B: Into<Bytes>>(
+        &self,
+        bytes: B,
+        file_name: String,
+        bucket_id: String,
+        optional_info: Option<HashMap<String, String>>,
+        options: Option<FileUploadOptions>,
+    ) -> Arc<FileUpload> {
+        let bytes: Bytes = bytes.into();
+        let file_size = bytes.len() as u64;
+
+        self.create_upload(
+            Cursor::new(bytes),
+            file_name,
+            bucket_id,
+            optional_info,
+            file_size,
+            options,
+        )
+        .await
+    }
+
+    /// Creates a files upload tracker for the file at `path`, named after the path's file name, like [create_upload](B2Client::create_upload). <br><br>
+    /// The size is read from the file's metadata, and unless they're set in the options, the content type is guessed from the extension
+    /// and `src_last_modified_millis` is the file's modification time. Tracker doesn't start upload automatically.
+    pub async fn upload_file_from_path<P: AsRef<Path>>(
+        &self,
+        path: P,
+        bucket_id: String,
+        options: Option<FileUploadOptions>,
+    ) -> Result<Arc<FileUpload>, FileUploadError> {
+        let path = path.as_ref();
+
+        let file_name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .ok_or_else(|| {
+                FileUploadError::FailedToReadFile(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("{} has no file name", path.display()),
+                ))
+            })?;
+
+        let file = tokio::fs::File::open(path).await?;
+        let metadata = file.metadata().await?;
+
+        let mut options = options.unwrap_or_default();
+        let settings = &mut options.options;
+
+        if settings.content_type == B2FileUploadSettings::default().content_type {
+            if let Some(mime) = mime_guess::from_path(path).first() {
+                settings.content_type = mime.essence_str().to_string();
+            }
+        }
+
+        if settings.src_last_modified_millis.is_none() {
+            settings.src_last_modified_millis = metadata
+                .modified()
+                .ok()
+                .and_then(|modified| modified.duration_since(SystemTime::UNIX_EPOCH).ok())
+                .map(|since_epoch| since_epoch.as_millis() as u64);
+        }
+
+        Ok(self
+            .create_upload(
+                file,
+                file_name,
+                bucket_id,
+                None,
+                metadata.len(),
+                Some(options),
+            )
+            .await)
+    }
+
+    /// Recreates an upload tracker from a [descriptor](FileUpload::descriptor), opening its source file. <br><br>
+    /// If the descriptor has a checkpoint the upload continues that large file, skipping parts already stored in B2,
+    /// otherwise it starts from the beginning. Tracker doesn't start upload automatically, same as [create_upload](B2Client::create_upload).
+    pub async fn create_upload_from_descriptor(
+        &self,
+        descriptor: UploadTaskDescriptor,
+    ) -> Result<Arc<FileUpload>, FileUploadError> {
+        if descriptor.version != UPLOAD_TASK_DESCRIPTOR_VERSION {
+            return Err(FileUploadError::InvalidOptions(InvalidValue {
+                object_name: "UploadTaskDescriptor".into(),
+                value_name: "version".into(),
+                value_as_string: descriptor.version.to_string(),
+                expected: UPLOAD_TASK_DESCRIPTOR_VERSION.to_string(),
+            }));
+        }
+
+        let file = tokio::fs::File::open(&descriptor.source.path).await?;
+        let region =
+            FileRegion::new(file, descriptor.source.offset, descriptor.source.length).await?;
+
+        let mut options: FileUploadOptions = descriptor.options.into();
+
+        if let Some(checkpoint) = &descriptor.checkpoint {
+            options.file_load_strategy =
+                LargeFileLoadStrategy::Constant(ConstantLargeFileLoadStrategy {
+                    part_size: checkpoint.part_size,
+                    ..Default::default()
+                });
+        }
+
+        let file_handle = self
+            .create_named_upload(
+                region,
+                descriptor.file_name,
+                descriptor.bucket_id,
+                descriptor.file_info,
+                descriptor.source.length,
+                Some(options),
+            )
+            .await;
+
+        if let Some(checkpoint) = descriptor.checkpoint {
+            file_handle.resume_from(checkpoint).await;
+        }
+
+        Ok(file_handle)
+    }
+
+    /// Uploads everything read from the stream as it's read, holding at most two parts in memory, refer to [upload_stream]. <br><br>
+    /// Unlike [create_stream_upload](B2Client::create_stream_upload) the stream isn't staged, and the upload isn't tracked.
+    pub async fn upload_stream<T>(
+        &self,
+        stream: T,
+        file_name: String,
+        bucket_id: String,
+        optional_info: Option<HashMap<String, String>>,
+        options: Option<StreamUploadOptions>,
+    ) -> Result<B2File, FileUploadError>
+    where
+        T: AsyncRead + Unpin + Send,
+    {
+        upload_stream(
+            &self.client,
+            stream,
+            self.file_name(file_name),
+            bucket_id,
+            optional_info,
+            options.unwrap_or_default(),
+        )
+        .await
+    }
+
+    /// Creates an uploader for parts of a large file started elsewhere, refer to [LargePartUploader]. <br><br>
+    /// The uploader gets the client's [shared throttle](B2Client::with_shared_throttle) unless the options have their own.
+    pub fn large_part_uploader(
+        &self,
+        file_id: String,
+        options: Option<LargePartUploaderOptions>,
+    ) -> LargePartUploader {
+        let mut options = options.unwrap_or_default();
+
+        if options.shared_throttle.is_none() {
+            options.shared_throttle = self.shared_throttle.clone();
+        }
+
+        LargePartUploader::new(self.client.clone(), file_id, options)
+    }
+
+    /// Creates a files upload tracker for a stream of unknown length, like [create_upload](B2Client::create_upload). <br><br>
+    /// The stream is read fully into the given staging first, the staged data is cleaned up once the upload finishes
+    /// or is aborted, or when the tracker is dropped.
+    pub async fn create_stream_upload<T>(
+        &self,
+        mut stream: T,
+        file_name: String,
+        bucket_id: String,
+        optional_info: Option<HashMap<String, String>>,
+        staging: &StagingPolicy,
+        options: Option<FileUploadOptions>,
+    ) -> Result<Arc<FileUpload>, FileUploadError>
+    where
+        T: AsyncRead + Unpin + Send,
+    {
+        let staged = staging
+            .stage(&mut stream)
+            .await
+            .map_err(FileUploadError::FailedToReadFile)?;
+
+        let guard = staged.guard();
+        let file_size = staged.size();
+
+        let file_handle = self
+            .create_upload(
+                staged,
+                file_name,
+                bucket_id,
+                optional_info,
+                file_size,
+                options,
+            )
+            .await;
+
+        if let Some(guard) = guard {
+            file_handle
+                .add_finish_callback(B2Callback::from_fn(move |_| guard.cleanup()))
+                .await;
+        }
+
+        Ok(file_handle)
+    }
+
+    /// Opens a remote file that can be read and seeked with ranged downloads, see [B2RemoteFile].
+    pub async fn open_remote_file(
+        &self,
+        file_id: String,
+        options: Option<B2RemoteFileOptions>,
+    ) -> Result<B2RemoteFile, B2Error> {
+        B2RemoteFile::open(self.client.clone(), file_id, options.unwrap_or_default()).await
+    }
+
+    /// Creates a [DownloadAuthorizer] sharing this client, for coalescing bursts of download authorization requests.
+    pub fn download_authorizer(&self, options: DownloadAuthorizerOptions) -> DownloadAuthorizer {
+        DownloadAuthorizer::new(self.client.clone(), options)
+    }
+
+    /// Copies a file with [b2_copy_file](B2SimpleClient::copy_file), then verifies the new file's length and SHA1 match the source.
+    /// <br> If they don't, the bad copy is deleted and a mismatch error is returned.
+    pub async fn copy_file_verified(&self, body: B2CopyFileBody) -> Result<B2File, FileCopyError> {
+        verify::copy_file_verified(&self.client, body).await
+    }
+
+    /// Copies a part with [b2_copy_part](B2SimpleClient::copy_part), then verifies the part's length matches the copied range.
+    pub async fn copy_part_verified(
+        &self,
+        body: B2CopyPartBody,
+    ) -> Result<B2FilePart, FileCopyError> {
+        verify::copy_part_verified(&self.client, body).await
+    }
+
+    /// Replaces the tags of a file, stored in its file info as `tag-<key>`.
+    /// <br> B2 file info can't be edited in place, so this copies the file onto itself with the new file info,
+    /// creating a new version that is returned. The old version is kept, and Object Lock settings aren't carried over.
+    /// <br> Since it's a [b2_copy_file](B2SimpleClient::copy_file), the file has to be at most 5 GB.
+    pub async fn set_tags(
+        &self,
+        file_id: String,
+        tags: HashMap<String, String>,
+    ) -> Result<B2File, B2Error> {
+        let file = self.client.get_file_info(file_id).await?;
+        let file_info = file_info_with_tags(file.file_info, tags)?;
+
+        self.client
+            .copy_file(
+                B2CopyFileBody::builder()
+                    .source_file_id(file.file_id)
+                    .file_name(file.file_name)
+                    .metadata_directive(Some(B2MetadataDirective::Replace))
+                    .content_type(file.content_type)
+                    .file_info(Some(file_info))
+                    .build(),
+            )
+            .await
+    }
+
+    /// Gets the tags of a file, see [set_tags](B2Client::set_tags).
+    pub async fn get_tags(&self, file_id: String) -> Result<HashMap<String, String>, B2Error> {
+        Ok(self.client.get_file_info(file_id).await?.tags())
+    }
+
+    /// Lists a page of file names with [b2_list_file_names](B2SimpleClient::list_file_names),
+    /// keeping only the files that have every one of the given tags.
+    /// <br> Filtering happens client side, so a page can come back with fewer files than requested, or none,
+    /// keep paging with [next_file_name](B2ListFilesResponse::next_file_name) until it's None.
+    pub async fn list_file_names_with_tags(
+        &self,
+        query: B2ListFileNamesQueryParameters,
+        tags: &HashMap<String, String>,
+    ) -> Result<B2ListFilesResponse, B2Error> {
+        let mut response = self.client.list_file_names(query).await?;
+
+        response
+            .files
+            .retain(|file| file_info_has_tags(&file.file_info, tags));
+
+        Ok(response)
+    }
+
+    /// Checks the client is ready to use, its key, capabilities, clock, connectivity and upload settings,
+    /// returning a report of every check for support tooling, refer to [DoctorOptions].
+    /// <br> Makes one [b2_list_buckets](B2SimpleClient::list_buckets) call, plus a request to the API and download hosts.
+    pub async fn doctor(&self, options: DoctorOptions) -> DoctorReport {
+        doctor::diagnose(&self.client, self.status(), options).await
+    }
+
+    /// Deletes many file versions with [b2_delete_file_version](B2SimpleClient::delete_file_version), see [BatchExecutor].
+    /// <br> Results are in the same order as the bodies.
+    pub async fn delete_file_versions(
+        &self,
+        bodies: Vec<B2DeleteFileVersionBody>,
+        options: BatchOptions,
+    ) -> Vec<Result<B2DeleteFileVersionResponse, B2Error>> {
+        BatchExecutor::new(options)
+            .run(bodies, |body| self.client.delete_file_version(body))
+            .await
+    }
+
+    /// Lists every version of the file without deleting anything, the plan's count confirms
+    /// [delete_file_all_versions](B2Client::delete_file_all_versions).
+    pub async fn plan_delete_file_all_versions(
+        &self,
+        bucket_id: String,
+        file_name: String,
+    ) -> Result<DeletePlan, B2Error> {
+        DeletePlan::file_all_versions(&self.client, bucket_id, file_name).await
+    }
+
+    /// Deletes every version of the file, hide markers included, once confirmed with their count,
+    /// refer to [delete_file_all_versions](batch::delete_file_all_versions).
+    pub async fn delete_file_all_versions(
+        &self,
+        bucket_id: String,
+        file_name: String,
+        confirmation: Destruction,
+        options: BatchOptions,
+    ) -> Result<DeleteReport, B2Error> {
+        batch::delete_file_all_versions(&self.client, bucket_id, file_name, confirmation, options)
+            .await
+    }
+
+    /// Lists every version under the prefix without deleting anything, the plan's count confirms
+    /// [delete_prefix](B2Client::delete_prefix).
+    pub async fn plan_delete_prefix(
+        &self,
+        bucket_id: String,
+        prefix: String,
+    ) -> Result<DeletePlan, B2Error> {
+        DeletePlan::prefix(&self.client, bucket_id, prefix).await
+    }
+
+    /// Deletes every version of every file starting with the prefix once confirmed with their count,
+    /// refer to [delete_prefix](batch::delete_prefix).
+    /// <br> An empty prefix empties the whole bucket.
+    pub async fn delete_prefix(
+        &self,
+        bucket_id: String,
+        prefix: String,
+        confirmation: Destruction,
+        options: BatchOptions,
+    ) -> Result<DeleteReport, B2Error> {
+        batch::delete_prefix(&self.client, bucket_id, prefix, confirmation, options).await
+    }
+
+    /// Lists every version and unfinished large file in the bucket without deleting anything, the plan's count confirms
+    /// [delete_bucket_recursive](B2Client::delete_bucket_recursive).
+    pub async fn plan_delete_bucket(&self, bucket_id: String) -> Result<DeletePlan, B2Error> {
+        DeletePlan::bucket(&self.client, bucket_id).await
+    }
+
+    /// Empties the bucket, once confirmed with the number of versions in it, then deletes it,
+    /// refer to [delete_bucket_recursive](batch::delete_bucket_recursive).
+    pub async fn delete_bucket_recursive(
+        &self,
+        bucket_id: String,
+        confirmation: Destruction,
+        options: BatchOptions,
+    ) -> Result<BucketDeleteReport, B2Error> {
+        batch::delete_bucket_recursive(&self.client, bucket_id, confirmation, options).await
+    }
+
+    /// Copies a whole file of any size, as a large file copied part by part when it's over
+    /// [MAX_COPY_FILE_SIZE](copy::MAX_COPY_FILE_SIZE), refer to [copy](copy::copy).
+    pub async fn copy(
+        &self,
+        source_file_id: String,
+        file_name: String,
+        options: Option<CopyOptions>,
+    ) -> Result<B2File, FileCopyError> {
+        copy::copy(
+            &self.client,
+            source_file_id,
+            file_name,
+            options.unwrap_or_default(),
+        )
+        .await
+    }
+
+    /// Moves a file version to `new_name`, in `destination_bucket_id` or the source's bucket, by copying it then deleting
+    /// or hiding the original, refer to [move_file](copy::move_file).
+    /// <br> `destination_bucket_id` takes precedence over the one in the options.
+    pub async fn move_file(
+        &self,
+        source_file_id: String,
+        new_name: String,
+        destination_bucket_id: Option<String>,
+        options: Option<MoveOptions>,
+    ) -> Result<B2File, FileCopyError> {
+        let mut options = options.unwrap_or_default();
+
+        if destination_bucket_id.is_some() {
+            options.copy.destination_bucket_id = destination_bucket_id;
+        }
+
+        copy::move_file(&self.client, source_file_id, new_name, options).await
+    }
+
+    /// Replaces a file's info and content type by copying it onto its own name, optionally deleting the old version,
+    /// refer to [replace_file_metadata](copy::replace_file_metadata).
+    pub async fn replace_file_metadata(
+        &self,
+        file_id: String,
+        file_info: HashMap<String, String>,
+        content_type: Option<String>,
+        delete_old_version: bool,
+    ) -> Result<B2File, FileCopyError> {
+        copy::replace_file_metadata(
+            &self.client,
+            file_id,
+            file_info,
+            content_type,
+            delete_old_version,
+        )
+        .await
+    }
+
+    /// Copies many files with [b2_copy_file](B2SimpleClient::copy_file), see [BatchExecutor].
+    /// <br> Results are in the same order as the bodies.
+    pub async fn copy_files(
+        &self,
+        bodies: Vec<B2CopyFileBody>,
+        options: BatchOptions,
+    ) -> Vec<Result<B2File, B2Error>> {
+        BatchExecutor::new(options)
+            .run(bodies, |body| self.client.copy_file(body))
+            .await
+    }
+
+    /// Updates the retention of many files with [b2_update_file_retention](B2SimpleClient::update_file_retention), see [BatchExecutor].
+    /// <br> Results are in the same order as the bodies.
+    pub async fn update_file_retentions(
+        &self,
+        bodies: Vec<B2UpdateFileRetentionBody>,
+        options: BatchOptions,
+    ) -> Vec<Result<B2UpdateFileRetentionResponse, B2Error>> {
+        BatchExecutor::new(options)
+            .run(bodies, |body| self.client.update_file_retention(body))
+            .await
+    }
+
+    /// Sets the legal hold of many files with [b2_update_file_legal_hold](B2SimpleClient::update_file_legal_hold), see [BatchExecutor].
+    /// <br> Fails before making any request if the key can't write legal holds, otherwise results are in the same order as the items.
+    pub async fn update_legal_hold_bulk(
+        &self,
+        items: Vec<B2UpdateFileLegalHoldBodyResponse>,
+        options: BatchOptions,
+    ) -> Result<Vec<Result<B2UpdateFileLegalHoldBodyResponse, B2Error>>, B2Error> {
+        self.client
+            .has_capabilities(&[B2KeyCapability::WriteFileLegalHolds])?;
+
+        Ok(BatchExecutor::new(options)
+            .run(items, |item| self.client.update_file_legal_hold(item))
+            .await)
+    }
+
+    /// Extends the retention of many files, given by ID, to `until` in milliseconds since 1970, keeping each file's retention mode.
+    /// <br> Files already retained until then or later are left as they are, and files without a retention fail,
+    /// since there's no mode to keep. Every file takes a [b2_get_file_info](B2SimpleClient::get_file_info) call before being updated.
+    /// <br> Fails before making any request if the key can't read and write retentions, otherwise results are in the same order as the items.
+    pub async fn extend_retention_bulk(
+        &self,
+        file_ids: Vec<String>,
+        until: u64,
+        options: BatchOptions,
+    ) -> Result<Vec<Result<B2RetentionExtension, B2Error>>, B2Error> {
+        self.client.has_capabilities(&[
+            B2KeyCapability::ReadFileRetentions,
+            B2KeyCapability::WriteFileRetentions,
+        ])?;
+
+        Ok(BatchExecutor::new(options)
+            .run(file_ids, |file_id| self.extend_retention(file_id, until))
+            .await)
+    }
+
+    async fn extend_retention(
+        &self,
+        file_id: String,
+        until: u64,
+    ) -> Result<B2RetentionExtension, B2Error> {
+        let file = self.client.get_file_info(file_id).await?;
+
+        let retention = file
+            .file_retention
+            .and_then(|retention| retention.value)
+            .filter(|retention| retention.mode.is_some());
+
+        let Some(retention) = retention else {
+            return Err(B2Error::InvalidValue(InvalidValue {
+                object_name: "B2File".into(),
+                value_name: "file_retention".into(),
+                value_as_string: file.file_id,
+                expected: "a file with a retention mode to extend".into(),
+            }));
+        };
+
+        if retention
+            .retain_until_timestamp
+            .is_some_and(|retain_until| retain_until >= until)
+        {
+            return Ok(B2RetentionExtension::AlreadyRetained(retention));
+        }
+
+        let response = self
+            .client
+            .update_file_retention(
+                B2UpdateFileRetentionBody::builder()
+                    .file_id(file.file_id)
+                    .file_name(file.file_name)
+                    .file_retention(B2BucketFileRetention {
+                        mode: retention.mode,
+                        retain_until_timestamp: Some(until),
+                    })
+                    .build(),
+            )
+            .await?;
+
+        Ok(B2RetentionExtension::Extended(response))
+    }
+
     /// Gets the list of current tracked upload tasks
     pub async fn get_current_tracked_uploads(&self) -> Vec<Arc<FileUpload>> {
         let lock_guard = self.uploading_files.read().await;
@@ -145,39 +1185,158 @@ impl B2Client {
         lock_guard.iter().filter_map(|e| e.clone()).collect()
     }
 
-    /// Aborts a specific upload using its ID
+    /// Gets the list of current tracked download tasks
+    pub async fn get_current_tracked_downloads(&self) -> Vec<Arc<FileDownload>> {
+        let lock_guard = self.downloading_files.read().await;
+
+        lock_guard.iter().filter_map(|e| e.clone()).collect()
+    }
+
+    /// Spawns a task that waits for SIGINT or SIGTERM (Ctrl+C on non unix platforms), then aborts every tracked upload and download,
+    /// cancelling the uploads' unfinished large files. The [upload queue](B2Client::upload_queue) is paused and cleared first,
+    /// so queued uploads aren't started while the others are aborted. <br><br>
+    /// If aborting doesn't finish within the grace period, or a second signal arrives, it hard aborts as set in the options.
+    /// Otherwise the uploads' `start` calls and the queued uploads' results are [Aborted](FileUploadError::Aborted).
+    /// <br> The handler replaces the default handling of the signals, so once it's done, or when it doesn't exit,
+    /// the next signal exits the process with the code the signal would have exited it with by default.
+    #[cfg(feature = "signals")]
+    pub fn abort_on_signals(&self, options: SignalAbortOptions) -> JoinHandle<()> {
+        let uploading_files = self.uploading_files.clone();
+        let downloading_files = self.downloading_files.clone();
+        let upload_queue = self.upload_queue.clone();
+
+        tokio::spawn(async move {
+            let Ok(mut signals) = ShutdownSignals::new() else {
+                return;
+            };
+
+            if signals.recv().await.is_err() {
+                return;
+            }
+
+            // Queued uploads are still pending, which abort leaves alone
+            upload_queue.pause();
+            upload_queue.clear();
+
+            let uploads: Vec<Arc<FileUpload>> = uploading_files
+                .read()
+                .await
+                .iter()
+                .flatten()
+                .cloned()
+                .collect();
+
+            let downloads: Vec<Arc<FileDownload>> = downloading_files
+                .read()
+                .await
+                .iter()
+                .flatten()
+                .cloned()
+                .collect();
+
+            let abort_all = futures::future::join(
+                futures::future::join_all(uploads.iter().map(|upload| upload.abort())),
+                futures::future::join_all(downloads.iter().map(|download| download.abort())),
+            );
+
+            let hard_abort = tokio::select! {
+                _ = abort_all => false,
+                _ = tokio::time::sleep(options.grace_period) => true,
+                _ = signals.recv() => true,
+            };
+
+            if let (true, Some(exit_code)) = (hard_abort, options.exit_code) {
+                std::process::exit(exit_code);
+            }
+
+            if let Ok(exit_code) = signals.recv().await {
+                std::process::exit(exit_code);
+            }
+        })
+    }
+
+    /// Aborts a specific upload using its ID, or takes it out of the [upload queue](B2Client::upload_queue) if it's still waiting there
     pub async fn abort_upload(&self, upload_id: u64) {
-        B2Client::abort_upload_inner(self.uploading_files.clone(), upload_id).await;
+        self.upload_queue.cancel(upload_id);
+        B2Client::untrack_task(self.uploading_files.clone(), upload_id).await;
     }
 
-    async fn push_upload(&self, upload: Arc<FileUpload>) {
-        let lock_guard = self.uploading_files.read().await;
+    /// Aborts a specific download using its ID, bytes already written to its destination are left there
+    pub async fn abort_download(&self, download_id: u64) {
+        let download = self
+            .get_current_tracked_downloads()
+            .await
+            .into_iter()
+            .find(|download| download.id() == download_id);
+
+        if let Some(download) = download {
+            download.abort().await;
+        }
+
+        B2Client::untrack_task(self.downloading_files.clone(), download_id).await;
+    }
+
+    async fn push_task<T>(tasks: &TrackedTasks<T>, task: Arc<T>) {
+        let lock_guard = tasks.read().await;
         let set_index = lock_guard.iter().position(|slot| slot.is_none());
         drop(lock_guard);
 
-        let mut lock_guard = self.uploading_files.write().await;
+        let mut lock_guard = tasks.write().await;
 
         match set_index {
-            Some(index) => lock_guard[index] = Some(upload),
-            None => lock_guard.push(Some(upload)),
+            Some(index) => lock_guard[index] = Some(task),
+            None => lock_guard.push(Some(task)),
         };
     }
 
-    async fn abort_upload_inner(
-        uploads: Arc<RwLock<Vec<Option<Arc<FileUpload>>>>>,
-        upload_id: u64,
-    ) {
-        let uploads_lock = uploads.read().await;
-        let upload_to_remove = uploads_lock.iter().position(|slot| match slot {
-            Some(upload) => upload.id() == upload_id,
+    async fn untrack_task<T: TrackedTask>(tasks: TrackedTasks<T>, task_id: u64) {
+        let tasks_lock = tasks.read().await;
+        let task_to_remove = tasks_lock.iter().position(|slot| match slot {
+            Some(task) => task.task_id() == task_id,
             None => false,
         });
-        drop(uploads_lock);
+        drop(tasks_lock);
+
+        if let Some(index) = task_to_remove {
+            let mut tasks_lock = tasks.write().await;
+            tasks_lock[index] = None;
+        }
+    }
+}
+
+async fn ensure_ready(
+    client: &B2SimpleClient,
+    key_id: &str,
+    application_key: &str,
+) -> Result<(), B2Error> {
+    let bucket_id = client.auth_data().api_info.storage_api.bucket_id;
+
+    let ping = client
+        .list_buckets(
+            B2ListBucketsBody::builder()
+                .account_id(client.account_id().to_string())
+                .bucket_id(bucket_id)
+                .build(),
+        )
+        .await;
 
-        if let Some(index) = upload_to_remove {
-            let mut uploads_lock = uploads.write().await;
-            uploads_lock[index] = None;
+    match ping {
+        Ok(_) => Ok(()),
+        Err(B2Error::MissingCapability(_)) => client
+            .authorize_account(key_id, application_key)
+            .await
+            .map(|_| ()),
+        Err(error)
+            if error
+                .request_error()
+                .is_some_and(|error| error.status.get() == 401) =>
+        {
+            client
+                .authorize_account(key_id, application_key)
+                .await
+                .map(|_| ())
         }
+        Err(error) => Err(error),
     }
 }
 
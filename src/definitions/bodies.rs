@@ -19,7 +19,7 @@ pub struct B2CopyFileBody {
     pub source_file_id: String,
     /// The ID of the bucket where the copied file will be stored. If this is not set, the copied file will be added to the same bucket as the source file.
     /// <br> Note that the bucket containing the source file and the destination bucket must belong to the same account.
-    pub large_file_id: Option<String>,
+    pub destination_bucket_id: Option<String>,
     #[builder(!default)]
     /// The name of the new file being created.
     pub file_name: String,
@@ -286,7 +286,7 @@ pub struct B2ListBucketsBody {
     /// If present, this will be used as a filter for bucket types returned in the list buckets response. If not present, only buckets with bucket types "allPublic", "allPrivate" and "snapshot" will be returned. A special filter value of ["all"] will return all bucket types.
     /// <br><br>If present, it must be in the form of a json array of strings containing valid bucket types in quotes and separated by a comma. Valid bucket types include "allPrivate", "allPublic", "restricted", "snapshot", "shared", and other values added in the future.
     /// <br><br>A bad request error will be returned if "all" is used with other bucketTypes, bucketTypes is empty, or invalid bucketTypes are requested.
-    pub bucket_types: Option<Vec<B2BucketTypeList>>,
+    pub bucket_types: Option<B2BucketTypeList>,
 }
 
 #[derive(Clone, Serialize, Debug, TypedBuilder)]
@@ -1,7 +1,11 @@
 use super::shared::{
     B2FileLegalHold, B2FileRetentionMode, B2ServerSideEncryption, B2ServerSideEncryptionAlgorithm,
 };
-use crate::util::IntoHeaderMap;
+use crate::{
+    error::IntoHeaderMapError,
+    util::{insert_number_header, insert_static_header, insert_string_header, IntoHeaderMap},
+};
+use reqwest::header::HeaderMap;
 use serde::Serialize;
 use typed_builder::TypedBuilder;
 
@@ -18,6 +22,8 @@ pub struct B2UploadPartHeaders {
     #[serde(rename = "Content-Length")]
     #[builder(!default)]
     pub content_length: u64,
+    /// The part's SHA1 hex, or [HEX_DIGITS_AT_END](crate::util::HEX_DIGITS_AT_END) to send it after the part's content,
+    /// see [append_sha1_hex](crate::util::append_sha1_hex).
     #[serde(rename = "X-Bz-Content-Sha1")]
     #[builder(!default)]
     pub content_sha1: String,
@@ -44,6 +50,8 @@ pub struct B2UploadFileHeaders {
     #[builder(!default)]
     #[serde(rename = "Content-Length")]
     pub content_length: u64,
+    /// The file's SHA1 hex, or [HEX_DIGITS_AT_END](crate::util::HEX_DIGITS_AT_END) to send it after the file's content,
+    /// see [append_sha1_hex](crate::util::append_sha1_hex).
     #[builder(!default)]
     #[serde(rename = "X-Bz-Content-Sha1")]
     pub content_sha1: String,
@@ -77,5 +85,151 @@ pub struct B2UploadFileHeaders {
     pub server_side_encryption_customer_key_md5: Option<String>,
 }
 
-impl IntoHeaderMap for B2UploadPartHeaders {}
-impl IntoHeaderMap for B2UploadFileHeaders {}
+// Both are built for every upload request, so they skip going through serde_json
+
+impl IntoHeaderMap for B2UploadPartHeaders {
+    fn into_header_map(self) -> Result<HeaderMap, IntoHeaderMapError> {
+        let mut headers = HeaderMap::with_capacity(7);
+
+        insert_string_header(&mut headers, "authorization", self.authorization)?;
+        insert_number_header(&mut headers, "x-bz-part-number", self.part_number.into());
+        insert_number_header(&mut headers, "content-length", self.content_length);
+        insert_string_header(&mut headers, "x-bz-content-sha1", self.content_sha1)?;
+        insert_customer_encryption_headers(
+            &mut headers,
+            self.server_side_encryption_customer_algorithm,
+            self.server_side_encryption_customer_key,
+            self.server_side_encryption_customer_key_md5,
+        )?;
+
+        Ok(headers)
+    }
+}
+
+impl IntoHeaderMap for B2UploadFileHeaders {
+    fn into_header_map(self) -> Result<HeaderMap, IntoHeaderMapError> {
+        let mut headers = HeaderMap::with_capacity(20);
+
+        insert_string_header(&mut headers, "authorization", self.authorization)?;
+        insert_string_header(&mut headers, "x-bz-file-name", self.file_name)?;
+        insert_string_header(&mut headers, "content-type", self.content_type)?;
+        insert_number_header(&mut headers, "content-length", self.content_length);
+        insert_string_header(&mut headers, "x-bz-content-sha1", self.content_sha1)?;
+
+        if let Some(millis) = self.src_last_modified_millis {
+            insert_number_header(&mut headers, "x-bz-info-src_last_modified_millis", millis);
+        }
+
+        let info_headers = [
+            (
+                "x-bz-info-b2-content-disposition",
+                self.b2_content_disposition,
+            ),
+            ("x-bz-info-b2-content-language", self.b2_content_language),
+            ("x-bz-info-b2-expires", self.b2_expires),
+            ("x-bz-info-b2-cache-control", self.b2_cache_control),
+            ("x-bz-info-b2-content-encoding", self.b2_content_encoding),
+        ];
+
+        for (name, value) in info_headers {
+            if let Some(value) = value {
+                insert_string_header(&mut headers, name, value)?;
+            }
+        }
+
+        if let Some(timestamp) = self.custom_upload_timestamp {
+            insert_number_header(&mut headers, "x-bz-custom-upload-timestamp", timestamp);
+        }
+
+        if let Some(legal_hold) = self.legal_hold {
+            let value = match legal_hold {
+                B2FileLegalHold::On => "on",
+                B2FileLegalHold::Off => "off",
+            };
+
+            insert_static_header(&mut headers, "x-bz-file-legal-hold", value);
+        }
+
+        if let Some(mode) = self.retention_mode {
+            let value = match mode {
+                B2FileRetentionMode::Governance => "governance",
+                B2FileRetentionMode::Compliance => "compliance",
+            };
+
+            insert_static_header(&mut headers, "x-bz-file-retention-mode", value);
+        }
+
+        if let Some(timestamp) = self.retention_retain_until_timestamp {
+            insert_number_header(
+                &mut headers,
+                "x-bz-file-retention-retain-until-timestamp",
+                timestamp,
+            );
+        }
+
+        // B2 takes SSE-B2 as just the algorithm, and SSE-C through the customer headers,
+        // which the customer fields below override when they're set
+        match self.server_side_encryption {
+            Some(B2ServerSideEncryption::SseB2 { algorithm }) => insert_static_header(
+                &mut headers,
+                "x-bz-server-side-encryption",
+                algorithm_header_value(&algorithm),
+            ),
+            Some(B2ServerSideEncryption::SseC {
+                algorithm,
+                customer_key,
+                customer_key_md5,
+            }) => insert_customer_encryption_headers(
+                &mut headers,
+                Some(algorithm),
+                Some(customer_key),
+                Some(customer_key_md5),
+            )?,
+            Some(B2ServerSideEncryption::Disabled) | None => {}
+        }
+
+        insert_customer_encryption_headers(
+            &mut headers,
+            self.server_side_encryption_customer_algorithm,
+            self.server_side_encryption_customer_key,
+            self.server_side_encryption_customer_key_md5,
+        )?;
+
+        Ok(headers)
+    }
+}
+
+fn algorithm_header_value(algorithm: &B2ServerSideEncryptionAlgorithm) -> &'static str {
+    match algorithm {
+        B2ServerSideEncryptionAlgorithm::AES256 => "AES256",
+    }
+}
+
+fn insert_customer_encryption_headers(
+    headers: &mut HeaderMap,
+    algorithm: Option<B2ServerSideEncryptionAlgorithm>,
+    key: Option<String>,
+    key_md5: Option<String>,
+) -> Result<(), IntoHeaderMapError> {
+    if let Some(algorithm) = algorithm {
+        insert_static_header(
+            headers,
+            "x-bz-server-side-encryption-customer-algorithm",
+            algorithm_header_value(&algorithm),
+        );
+    }
+
+    if let Some(key) = key {
+        insert_string_header(headers, "x-bz-server-side-encryption-customer-key", key)?;
+    }
+
+    if let Some(key_md5) = key_md5 {
+        insert_string_header(
+            headers,
+            "x-bz-server-side-encryption-customer-key-md5",
+            key_md5,
+        )?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,117 @@
+//! Typed wrappers of the IDs B2 uses, so a bucket name or a file ID can't be passed where a bucket ID is expected.
+//! <br> The definitions keep plain strings, the wrappers are opt-in: they convert from and into [String], read and write
+//! as plain JSON strings, and the definitions' typed getters like [B2Bucket::id](super::shared::B2Bucket::id) return them.
+//! ```rust
+//! # use backblaze_b2_client::definitions::ids::{BucketId, FileId};
+//! let bucket_id = BucketId::from("4a48fe8875c6214145260818");
+//! let file_id: FileId = serde_json::from_str("\"4_z27c88f1d182b150646ff0b16_f1004ba650fe24e6b_d20150809_m012853_c100_v0009990_t0000\"").unwrap();
+//!
+//! assert_eq!(serde_json::to_string(&bucket_id).unwrap(), "\"4a48fe8875c6214145260818\"");
+//! assert!(file_id.starts_with("4_z27c"));
+//!
+//! // Plain string APIs take them with into
+//! let bucket_id: String = bucket_id.into();
+//! ```
+
+use std::{borrow::Borrow, fmt, ops::Deref};
+
+use serde::{Deserialize, Serialize};
+
+macro_rules! id_type {
+    ($(#[$doc:meta])* $name:ident) => {
+        $(#[$doc])*
+        #[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+        #[serde(transparent)]
+        pub struct $name(String);
+
+        impl $name {
+            pub fn new<S: Into<String>>(id: S) -> Self {
+                Self(id.into())
+            }
+
+            pub fn as_str(&self) -> &str {
+                &self.0
+            }
+
+            pub fn into_string(self) -> String {
+                self.0
+            }
+        }
+
+        impl From<String> for $name {
+            fn from(id: String) -> Self {
+                Self(id)
+            }
+        }
+
+        impl From<&str> for $name {
+            fn from(id: &str) -> Self {
+                Self(id.to_string())
+            }
+        }
+
+        impl From<$name> for String {
+            fn from(id: $name) -> Self {
+                id.0
+            }
+        }
+
+        impl Deref for $name {
+            type Target = str;
+
+            fn deref(&self) -> &str {
+                &self.0
+            }
+        }
+
+        impl AsRef<str> for $name {
+            fn as_ref(&self) -> &str {
+                &self.0
+            }
+        }
+
+        impl Borrow<str> for $name {
+            fn borrow(&self) -> &str {
+                &self.0
+            }
+        }
+
+        impl PartialEq<str> for $name {
+            fn eq(&self, other: &str) -> bool {
+                self.0 == other
+            }
+        }
+
+        impl PartialEq<&str> for $name {
+            fn eq(&self, other: &&str) -> bool {
+                self.0 == *other
+            }
+        }
+
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str(&self.0)
+            }
+        }
+    };
+}
+
+id_type!(
+    /// The unique identifier of a bucket, not its name.
+    BucketId
+);
+
+id_type!(
+    /// The unique identifier of a file version, or of an unfinished large file.
+    FileId
+);
+
+id_type!(
+    /// The identifier of an account.
+    AccountId
+);
+
+id_type!(
+    /// The identifier of an application key, the public half used to authorize.
+    KeyId
+);
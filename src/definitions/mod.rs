@@ -1,5 +1,6 @@
 pub mod bodies;
 pub mod headers;
+pub mod ids;
 pub mod query_params;
 pub mod responses;
 pub mod shared;
@@ -12,10 +12,13 @@ pub struct B2ListUnfinishedLargeFilesQueryParameters {
     pub bucket_id: String,
     /// When a `namePrefix` is provided, only files whose names match the prefix will be returned.
     /// Whe using an application key that is restricted to a name prefix, you must provide a prefix here that is at least as restrictive.
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub name_prefix: Option<String>,
     /// The first upload to return. If there is an upload with this ID, it will be returned in the list. If not, the first upload after this the first one after this ID.
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub start_file_id: Option<String>,
     /// The maximum number of files to return from this call. The default value is 100, and the maximum allowed is 100.
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub max_file_count: Option<u8>,
 }
 
@@ -29,8 +32,10 @@ pub struct B2ListPartsQueryParameters {
     pub file_id: String,
     /// The first part to return. If there is a part with this number, it will be returned as the first in the list.
     /// If not, the returned list will start with the first part number after this one.
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub start_part_number: Option<u32>,
     /// The maximum number of parts to return from this call. The default value is 100, and the maximum allowed is 1000.
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub max_part_count: Option<u16>,
 }
 
@@ -47,9 +52,11 @@ pub struct B2ListKeysParameters {
     /// The maximum number of files returned per transaction is 1000. If you set maxFileCount to more than 1000 and more than 1000 are returned,
     /// the call will be billed as multiple transactions, as if you had made requests in a loop asking for 1000 at a time. For example:
     /// if you set maxFileCount to 10000 and 3123 items are returned, you will be billed for 4 Class C transactions.
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub max_key_count: Option<u16>,
     /// The first key to return. Used when a query hits the maxKeyCount, and you want to get more.
     /// Set to the value returned as the nextApplicationKeyId in the previous query.
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub start_application_key_id: Option<String>,
 }
 
@@ -64,8 +71,10 @@ pub struct B2ListFileVersionsQueryParameters {
     /// <br> If there are no files with this name, the first version of the file with the first name after the given name will be the first in the list.
     /// <br> If startFileId is also specified, the name-and-id pair is the starting point. If there is a file with the given name and ID, it will be first in the list.
     /// Otherwise, the first file version that comes after the given name and ID will be first in the list.
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub start_file_name: Option<String>,
     /// The first file ID to return. `startFileName` must also be provided if `startFileId` is specified. (See [startFileName](B2ListFileVersionsQueryParameters::start_file_name))
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub start_file_id: Option<String>,
     /// The maximum number of files to return from this call. The default value is 100, and the maximum is 10000. Passing in 0 means to use the default of 100.
     /// <br><br><Br>
@@ -73,11 +82,14 @@ pub struct B2ListFileVersionsQueryParameters {
     /// The maximum number of files returned per transaction is 1000. If you set maxFileCount to more than 1000 and more than 1000 are returned,
     /// the call will be billed as multiple transactions, as if you had made requests in a loop asking for 1000 at a time. For example:
     /// if you set maxFileCount to 10000 and 3123 items are returned, you will be billed for 4 Class C transactions.
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub max_file_count: Option<NonZeroU32>,
     /// Files returned will be limited to those with the given prefix. Defaults to the empty string, which matches all files.
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub prefix: Option<String>,
     /// Files returned will be limited to those within the top folder, or any one subfolder. Defaults to NULL.
     /// Folder names will also be returned. The delimiter character will be used to "break" file names into folders.
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub delimiter: Option<String>,
 }
 
@@ -89,6 +101,7 @@ pub struct B2ListFileNamesQueryParameters {
     /// The bucket to look for file names in. Returned by [b2_list_buckets](crate::simple_client::B2SimpleClient::list_buckets).
     pub bucket_id: String,
     /// The first file name to return. If there is a file with this name, it will be returned in the list. If not, the first file name after this the first one after this name.
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub start_file_name: Option<String>,
     /// The maximum number of files to return from this call. The default value is 100, and the maximum is 10000. Passing in 0 means to use the default of 100.
     /// <br><br><Br>
@@ -96,11 +109,14 @@ pub struct B2ListFileNamesQueryParameters {
     /// The maximum number of files returned per transaction is 1000. If you set maxFileCount to more than 1000 and more than 1000 are returned,
     /// the call will be billed as multiple transactions, as if you had made requests in a loop asking for 1000 at a time. For example:
     /// if you set maxFileCount to 10000 and 3123 items are returned, you will be billed for 4 Class C transactions.
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub max_file_count: Option<NonZeroU32>,
     /// Files returned will be limited to those with the given prefix. Defaults to the empty string, which matches all files.
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub prefix: Option<String>,
     /// Files returned will be limited to those within the top folder, or any one subfolder. Defaults to NULL.
     /// Folder names will also be returned. The delimiter character will be used to "break" file names into folders.
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub delimiter: Option<String>,
 }
 
@@ -152,4 +168,8 @@ pub struct B2DownloadFileQueryParameters {
     /// See [Files](https://www.backblaze.com/docs/cloud-storage-files) for further details about HTTP header size limit.
     /// <br><br>Requests with this specified must also have an authorization token.
     pub b2_content_type: Option<String>,
+    /// The bytes to download, e.g. `bytes=0-99` with both ends inclusive. Sent as the `Range` header instead of a query parameter.
+    /// <br> The response then only holds those bytes, and its content length is the range's length.
+    #[serde(skip)]
+    pub range: Option<String>,
 }
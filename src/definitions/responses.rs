@@ -1,13 +1,15 @@
 use std::num::NonZeroU64;
 
 use serde::{Deserialize, Serialize};
+use typed_builder::TypedBuilder;
 
+use super::ids::AccountId;
 use super::shared::{
-    B2AppKey, B2Bucket, B2EventNotificationRule, B2File, B2BucketFileRetention, B2KeyCapability,
+    B2AppKey, B2Bucket, B2BucketFileRetention, B2EventNotificationRule, B2File, B2KeyCapability,
     B2ServerSideEncryption,
 };
 
-#[derive(Clone, Debug, Deserialize)]
+#[derive(Clone, Debug, Deserialize, Serialize, TypedBuilder)]
 #[serde(rename_all = "camelCase")]
 pub struct B2UpdateFileRetentionResponse {
     /// The unique identifier for this version of this file.
@@ -18,7 +20,16 @@ pub struct B2UpdateFileRetentionResponse {
     pub file_retention: B2BucketFileRetention,
 }
 
-#[derive(Clone, Debug, Deserialize)]
+/// Result of extending a single file's retention with [extend_retention_bulk](crate::client::B2Client::extend_retention_bulk).
+#[derive(Clone, Debug)]
+pub enum B2RetentionExtension {
+    /// The retention was moved to the new date.
+    Extended(B2UpdateFileRetentionResponse),
+    /// The file was already retained until the new date or later, nothing was changed.
+    AlreadyRetained(B2BucketFileRetention),
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, TypedBuilder)]
 #[serde(rename_all = "camelCase")]
 pub struct B2GetUploadPartUrlResponse {
     /// The unique ID of file being uploaded.
@@ -30,17 +41,18 @@ pub struct B2GetUploadPartUrlResponse {
     pub authorization_token: String,
 }
 
-#[derive(Clone, Deserialize, Debug)]
+#[derive(Clone, Deserialize, Serialize, Debug, TypedBuilder)]
 #[serde(rename_all = "camelCase")]
 pub struct B2ListFilesResponse {
     /// The array of files
     pub files: Vec<B2File>,
     /// What to pass in to [`startFileName`](super::query_params::B2ListFileNamesQueryParameters::start_file_name) for the next search to continue where this one left off,
     /// or null if there are no more files. Note this this may not be the name of an actual file, but using it is guaranteed to find the next file in the bucket.
+    #[builder(default)]
     pub next_file_name: Option<String>,
 }
 
-#[derive(Clone, Deserialize, Debug)]
+#[derive(Clone, Deserialize, Serialize, Debug, TypedBuilder)]
 #[serde(rename_all = "camelCase")]
 pub struct B2GetUploadUrlResponse {
     /// The identifier for the bucket.
@@ -52,20 +64,22 @@ pub struct B2GetUploadUrlResponse {
     pub authorization_token: String,
 }
 
-#[derive(Clone, Deserialize, Debug)]
+#[derive(Clone, Deserialize, Serialize, Debug, TypedBuilder)]
 #[serde(rename_all = "camelCase")]
 pub struct B2ListFileVersionsResponse {
     /// Array of B2 files.
     pub files: Vec<B2File>,
     /// What to pass in to startFileName for the next search to continue where this one left off, or null if there are no more files.
     /// Note this this may not be the name of an actual file, but using it is guaranteed to find the next file version in the bucket.
+    #[builder(default)]
     pub next_file_name: Option<String>,
     /// What to pass in to startFileId for the next search to continue where this one left off, or null if there are no more files.
     /// Note this this may not be the ID of an actual file, but using it is guaranteed to find the next file version in the bucket.
+    #[builder(default)]
     pub next_file_id: Option<String>,
 }
 
-#[derive(Clone, Deserialize, Debug)]
+#[derive(Clone, Deserialize, Serialize, Debug, TypedBuilder)]
 #[serde(rename_all = "camelCase")]
 pub struct B2FilePart {
     /// The file ID for uploading this file.
@@ -78,6 +92,7 @@ pub struct B2FilePart {
     /// Large files do not have SHA1 checksums, and the value is "none". The value is null when the action is ["hide"](B2Action::Hide), or ["folder"](B2Action::Folder).
     pub content_sha1: String,
     /// The MD5 of the bytes stored in the part. Not all parts have an MD5 checksum, so this field is optional, and set to null for parts that do not have one.
+    #[builder(default)]
     pub content_md5: Option<String>,
     /// When the part is encrypted with [Server-Side Encryption](https://www.backblaze.com/docs/cloud-storage-enable-server-side-encryption-with-the-native-api),
     /// the mode ("SSE-B2" or "SSE-C") and algorithm used to encrypt the data.
@@ -91,7 +106,7 @@ pub struct B2FilePart {
     pub upload_timestamp: u64,
 }
 
-#[derive(Clone, Deserialize, Debug)]
+#[derive(Clone, Deserialize, Serialize, Debug)]
 #[serde(rename_all = "camelCase")]
 pub enum B2AuthDataApiInfoType {
     GroupsApi,
@@ -99,22 +114,25 @@ pub enum B2AuthDataApiInfoType {
     BackupApi,
 }
 
-#[derive(Clone, Deserialize, Debug)]
+#[derive(Clone, Deserialize, Serialize, Debug, TypedBuilder)]
 #[serde(rename_all = "camelCase")]
 pub struct B2AuthDataStorageApiInfo {
     pub absolute_minimum_part_size: NonZeroU64,
     pub api_url: String,
+    #[builder(default)]
     pub bucket_id: Option<String>,
+    #[builder(default)]
     pub bucket_name: Option<String>,
     pub capabilities: Vec<B2KeyCapability>,
     pub download_url: String,
     pub info_type: B2AuthDataApiInfoType,
+    #[builder(default)]
     pub name_prefix: Option<String>,
     pub recommended_part_size: NonZeroU64,
     pub s3_api_url: String,
 }
 
-#[derive(Clone, Deserialize, Debug)]
+#[derive(Clone, Deserialize, Serialize, Debug, TypedBuilder)]
 #[serde(rename_all = "camelCase")]
 pub struct B2AuthDataGroupsApiInfo {
     pub capabilities: Vec<String>,
@@ -122,7 +140,7 @@ pub struct B2AuthDataGroupsApiInfo {
     pub info_type: B2AuthDataApiInfoType,
 }
 
-#[derive(Clone, Deserialize, Debug)]
+#[derive(Clone, Deserialize, Serialize, Debug, TypedBuilder)]
 #[serde(rename_all = "camelCase")]
 pub struct B2AuthDataBackupApiInfo {
     pub capabilities: Vec<String>,
@@ -130,7 +148,7 @@ pub struct B2AuthDataBackupApiInfo {
     pub info_type: B2AuthDataApiInfoType,
 }
 
-#[derive(Clone, Deserialize, Debug)]
+#[derive(Clone, Deserialize, Serialize, Debug, TypedBuilder)]
 #[serde(rename_all = "camelCase")]
 pub struct B2AuthDataApiInfo {
     // pub groups_api: B2AuthDataGroupsApiInfo,
@@ -139,7 +157,7 @@ pub struct B2AuthDataApiInfo {
     // pub backup_api: B2AuthDataBackupApiInfo,
 }
 
-#[derive(Clone, Deserialize, Debug)]
+#[derive(Clone, Deserialize, Serialize, Debug, TypedBuilder)]
 #[serde(rename_all = "camelCase")]
 pub struct B2AuthData {
     /// The identifier for the account.
@@ -149,47 +167,58 @@ pub struct B2AuthData {
     /// An authorization token to use with all calls, other than b2_authorize_account, that need an Authorization header. This authorization token is valid for at most 24 hours.
     pub authorization_token: String,
     /// Expiration timestamp for the application key.
+    #[builder(default)]
     pub application_key_expiration_timestamp: Option<u64>,
 }
 
-#[derive(Clone, Deserialize, Debug)]
+impl B2AuthData {
+    /// The [account_id](B2AuthData::account_id) as an [AccountId].
+    pub fn account(&self) -> AccountId {
+        AccountId::from(self.account_id.as_str())
+    }
+}
+
+#[derive(Clone, Deserialize, Serialize, Debug, TypedBuilder)]
 #[serde(rename_all = "camelCase")]
 pub struct B2ListUnfinishedLargeFilesResponse {
     /// An array of objects, each one describing one unfinished file.
     pub files: Vec<B2File>,
     /// What to pass in to [`startFileId`](super::query_params::B2ListUnfinishedLargeFilesQueryParameters::start_file_id) for the next search to continue where this one left off, or null if there are no more files.
     /// Note this this may not be the ID of an actual upload, but using it is guaranteed to find the next upload.
+    #[builder(default)]
     pub next_file_id: Option<String>,
 }
 
-#[derive(Clone, Deserialize, Debug)]
+#[derive(Clone, Deserialize, Serialize, Debug, TypedBuilder)]
 #[serde(rename_all = "camelCase")]
 pub struct B2ListPartsResponse {
     /// What to pass in to [`startPartNumber`](super::query_params::B2ListPartsQueryParameters::start_part_number)
     /// for the next search to continue where this one left off, or null if there are no more files.
     /// Note this this may not be the number of an actual part, but using it is guaranteed to find the next file in the bucket.
-    pub next_part_number: Vec<u32>,
+    #[builder(default)]
+    pub next_part_number: Option<u32>,
     /// Array of B2 file parts
-    pub parts: Option<B2FilePart>,
+    pub parts: Vec<B2FilePart>,
 }
 
-#[derive(Clone, Deserialize, Debug)]
+#[derive(Clone, Deserialize, Serialize, Debug, TypedBuilder)]
 #[serde(rename_all = "camelCase")]
 pub struct B2ListKeysResponse {
     /// An array of keys.
     pub keys: Vec<B2AppKey>,
     /// Set if there are more keys beyond the ones that were returned. Pass this value the startApplicationKeyId in the next query to continue listing keys.
     /// <br>Note that this value may not be a valid application key ID, but can still be used as the starting point for the next query.
+    #[builder(default)]
     pub next_application_key_id: Option<String>,
 }
 
-#[derive(Clone, Debug, Deserialize)]
+#[derive(Clone, Debug, Deserialize, Serialize, TypedBuilder)]
 #[serde(rename_all = "camelCase")]
 pub struct B2ListBucketsResponse {
     pub buckets: Vec<B2Bucket>,
 }
 
-#[derive(Clone, Debug, Deserialize)]
+#[derive(Clone, Debug, Deserialize, Serialize, TypedBuilder)]
 #[serde(rename_all = "camelCase")]
 pub struct B2GetDownloadAuthorizationBodyResponse {
     /// The identifier for the bucket.
@@ -201,7 +230,7 @@ pub struct B2GetDownloadAuthorizationBodyResponse {
     pub authorization_token: String,
 }
 
-#[derive(Clone, Debug, Deserialize, Serialize)]
+#[derive(Clone, Debug, Deserialize, Serialize, TypedBuilder)]
 #[serde(rename_all = "camelCase")]
 pub struct B2BucketNotificationRulesResponseBody {
     /// The unique identifier for the bucket containing the event notification rules.
@@ -211,7 +240,16 @@ pub struct B2BucketNotificationRulesResponseBody {
     pub event_notification_rules: Vec<B2EventNotificationRule>,
 }
 
-#[derive(Clone, Debug, Deserialize)]
+impl B2BucketNotificationRulesResponseBody {
+    /// The rules B2 has suspended, see [B2EventNotificationRule::suspension].
+    pub fn suspended_rules(&self) -> impl Iterator<Item = &B2EventNotificationRule> {
+        self.event_notification_rules
+            .iter()
+            .filter(|rule| rule.suspension().is_suspended())
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, TypedBuilder)]
 #[serde(rename_all = "camelCase")]
 pub struct B2DeleteFileVersionResponse {
     /// The unique ID of the file version that was deleted.
@@ -220,7 +258,7 @@ pub struct B2DeleteFileVersionResponse {
     pub file_name: String,
 }
 
-#[derive(Clone, Debug, Deserialize)]
+#[derive(Clone, Debug, Deserialize, Serialize, TypedBuilder)]
 #[serde(rename_all = "camelCase")]
 pub struct B2CancelLargeFileResponse {
     /// The ID of the file whose upload that was canceled.
@@ -5,10 +5,15 @@ use serde::{
 };
 use std::{collections::HashMap, fmt};
 use strum_macros::Display;
+use typed_builder::TypedBuilder;
 
-use crate::util::B2FileStream;
+use crate::{
+    definitions::ids::{AccountId, BucketId, FileId, KeyId},
+    error::ResponseHeaderError,
+    util::{encoding::decode_header_value, file_info_tags, B2FileStream, FileInfoExt},
+};
 
-#[derive(Debug, Display, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Display, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 #[strum(serialize_all = "snake_case")]
 pub enum B2Endpoint {
@@ -45,6 +50,136 @@ pub enum B2Endpoint {
     B2UploadPart,
 }
 
+impl B2Endpoint {
+    /// Every endpoint, in declaration order.
+    /// ```rust
+    /// # use backblaze_b2_client::definitions::shared::B2Endpoint;
+    /// for (index, endpoint) in B2Endpoint::ALL.into_iter().enumerate() {
+    ///     assert_eq!(endpoint as usize, index);
+    /// }
+    /// ```
+    pub const ALL: [B2Endpoint; 31] = {
+        use B2Endpoint::*;
+
+        [
+            B2AuthorizeAccount,
+            B2CancelLargeFile,
+            B2CopyFile,
+            B2CopyPart,
+            B2CreateBucket,
+            B2CreateKey,
+            B2DeleteBucket,
+            B2DeleteFileVersion,
+            B2DeleteKey,
+            B2DownloadFileById,
+            B2DownloadFileByName,
+            B2FinishLargeFile,
+            B2GetBucketNotificationRules,
+            B2GetDownloadAuthorization,
+            B2GetFileInfo,
+            B2GetUploadPartUrl,
+            B2GetUploadUrl,
+            B2HideFile,
+            B2ListBuckets,
+            B2ListFileNames,
+            B2ListFileVersions,
+            B2ListKeys,
+            B2ListParts,
+            B2ListUnfinishedLargeFiles,
+            B2SetBucketNotificationRules,
+            B2StartLargeFile,
+            B2UpdateBucket,
+            B2UpdateFileLegalHold,
+            B2UpdateFileRetention,
+            B2UploadFile,
+            B2UploadPart,
+        ]
+    };
+
+    /// The class the endpoint's calls are billed as, see [transaction pricing](https://www.backblaze.com/cloud-storage/transaction-pricing).
+    pub fn transaction_class(&self) -> B2TransactionClass {
+        use B2Endpoint::*;
+
+        match self {
+            B2CancelLargeFile
+            | B2DeleteBucket
+            | B2DeleteFileVersion
+            | B2DeleteKey
+            | B2FinishLargeFile
+            | B2GetUploadPartUrl
+            | B2GetUploadUrl
+            | B2HideFile
+            | B2StartLargeFile
+            | B2UpdateFileLegalHold
+            | B2UpdateFileRetention
+            | B2UploadFile
+            | B2UploadPart => B2TransactionClass::A,
+            B2DownloadFileById | B2DownloadFileByName | B2GetFileInfo => B2TransactionClass::B,
+            _ => B2TransactionClass::C,
+        }
+    }
+
+    /// Whether repeating a call has the same effect as making it once, so a call whose response got lost can be retried safely.
+    /// <br> Calls that create something, like [b2_copy_file](crate::simple_client::B2SimpleClient::copy_file), or that fail when repeated,
+    /// like deletes, aren't. Neither are uploads, since their body can only be sent once.
+    pub fn is_idempotent(&self) -> bool {
+        use B2Endpoint::*;
+
+        matches!(
+            self,
+            B2AuthorizeAccount
+                | B2CopyPart
+                | B2DownloadFileById
+                | B2DownloadFileByName
+                | B2GetBucketNotificationRules
+                | B2GetDownloadAuthorization
+                | B2GetFileInfo
+                | B2GetUploadPartUrl
+                | B2GetUploadUrl
+                | B2ListBuckets
+                | B2ListFileNames
+                | B2ListFileVersions
+                | B2ListKeys
+                | B2ListParts
+                | B2ListUnfinishedLargeFiles
+                | B2SetBucketNotificationRules
+                | B2UpdateFileLegalHold
+                | B2UpdateFileRetention
+        )
+    }
+
+    /// The HTTP method the endpoint is documented with in the [B2 API docs](https://www.backblaze.com/apidocs).
+    /// <br> Calls documented as GET send their parameters in the query string, everything else sends a JSON body.
+    /// <br> The list calls and [b2_get_file_info](crate::simple_client::B2SimpleClient::get_file_info) are documented as POST, B2 also takes them as GET.
+    /// ```rust
+    /// # use backblaze_b2_client::definitions::shared::B2Endpoint;
+    /// assert_eq!(B2Endpoint::B2ListFileNames.documented_method(), reqwest::Method::POST);
+    /// assert_eq!(B2Endpoint::B2GetFileInfo.documented_method(), reqwest::Method::POST);
+    /// assert_eq!(B2Endpoint::B2GetUploadUrl.documented_method(), reqwest::Method::GET);
+    /// ```
+    pub fn documented_method(&self) -> reqwest::Method {
+        use B2Endpoint::*;
+
+        match self {
+            B2AuthorizeAccount
+            | B2DownloadFileById
+            | B2DownloadFileByName
+            | B2GetBucketNotificationRules
+            | B2GetUploadPartUrl
+            | B2GetUploadUrl => reqwest::Method::GET,
+            _ => reqwest::Method::POST,
+        }
+    }
+}
+
+/// How B2 bills a call, Class A calls are free, Class B and C calls are charged past a daily free allowance.
+#[derive(Debug, Display, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum B2TransactionClass {
+    A,
+    B,
+    C,
+}
+
 #[derive(Debug, Display, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub enum B2KeyCapability {
@@ -287,7 +422,7 @@ pub enum B2FileAction {
     Folder,
 }
 
-#[derive(Clone, Deserialize, Debug, Serialize)]
+#[derive(Clone, Deserialize, Debug, Serialize, TypedBuilder)]
 #[serde(rename_all = "camelCase")]
 pub struct B2File {
     /// The account that owns the file.
@@ -301,19 +436,23 @@ pub struct B2File {
     pub content_length: u64,
     /// The SHA1 of the bytes stored in the file as a 40-digit hex string.
     /// Large files do not have SHA1 checksums, and the value is "none". The value is null when the action is ["hide"](B2Action::Hide), or ["folder"](B2Action::Folder).
+    #[builder(default)]
     pub content_sha1: Option<String>,
     /// The MD5 of the bytes stored in the file as a 32-digit hex string.
     /// Not all files have an MD5 checksum, so this field is optional, and set to null for files that do not have one.
     /// Large files do not have MD5 checksums, and the value is null. The value is also null when the action is ["hide"](B2Action::Hide), or ["folder"](B2Action::Folder).
+    #[builder(default)]
     pub content_md5: Option<String>,
     /// When the action is ["upload"](B2Action::Upload) or ["start"](B2Action::Start), the MIME type of the file, as specified when the file was uploaded.
     /// For ["hide"](B2Action::Hide) action, always "application/x-bz-hide-marker". For ["folder"](B2Action::Folder) action, always null.
+    #[builder(default)]
     pub content_type: Option<String>,
     /// The unique identifier for this version of this file.
     /// Used with b2_get_file_info, b2_download_file_by_id, and b2_delete_file_version.
     /// The value is null when for action ["folder"](B2Action::Folder).
     pub file_id: String,
     /// The custom information that was uploaded with the file. This is a JSON object, holding the name/value pairs that were uploaded with the file.
+    #[builder(default)]
     pub file_info: HashMap<String, String>,
     /// The name of this file, which can be used with b2_download_file_by_name.
     pub file_name: String,
@@ -321,18 +460,22 @@ pub struct B2File {
     /// This field is filtered based on application key capabilities; the [`readFileRetentions`](B2KeyCapability::ReadFileRetentions) capability is required to access the value.
     /// See [Object Lock](https://www.backblaze.com/docs/cloud-storage-enable-object-lock-with-the-native-api)
     /// for more details on response structure. This field is omitted when the action is ["hide"](B2Action::Hide), or ["folder"](B2Action::Folder).
+    #[builder(default)]
     pub file_retention: Option<B2ObjectLock<B2BucketFileRetention>>,
     /// The Object Lock legal hold status for this file, if any.
     /// This field is filtered based on application key capabilities; the [`readFileLegalHolds`](B2KeyCapability::ReadFileLegalHolds) capability is required to access the value.
     /// See [Object Lock](https://www.backblaze.com/docs/cloud-storage-enable-object-lock-with-the-native-api)
     /// for more details on response structure. This field is omitted when the action is ["hide"](B2Action::Hide), or ["folder"](B2Action::Folder).
+    #[builder(default)]
     pub legal_hold: Option<B2ObjectLock<B2FileLegalHold>>,
     /// The Replication Status for this file, if any. This field is omitted when the file is not part of a replication rule.
+    #[builder(default)]
     pub replication_status: Option<B2ReplicationStatus>,
     /// When the file is encrypted with [Server-Side Encryption](https://www.backblaze.com/docs/cloud-storage-enable-server-side-encryption-with-the-native-api),
     /// the mode ("SSE-B2" or "SSE-C") and algorithm used to encrypt the data.
     /// If the file is not encrypted with Server-Side Encryption, then both mode and algorithm will be null.
     /// This field is omitted when the action is ["hide"](B2Action::Hide), or ["folder"](B2Action::Folder).
+    #[builder(default)]
     pub server_side_encryption: Option<B2ServerSideEncryption>,
     /// This is a UTC time when this file was uploaded.
     /// It is a base 10 number of milliseconds since midnight, January 1, 1970 UTC.
@@ -343,6 +486,28 @@ pub struct B2File {
     pub upload_timestamp: u64,
 }
 
+impl B2File {
+    /// Gets a [file info](B2File::file_info) value, ignoring the key's case.
+    pub fn file_info_value(&self, key: &str) -> Option<&str> {
+        self.file_info.get_info(key)
+    }
+
+    /// Gets the tags stored in the [file info](B2File::file_info), see [file_info_tags].
+    pub fn tags(&self) -> HashMap<String, String> {
+        file_info_tags(&self.file_info)
+    }
+
+    /// The [file_id](B2File::file_id) as a [FileId].
+    pub fn id(&self) -> FileId {
+        FileId::from(self.file_id.as_str())
+    }
+
+    /// The [bucket_id](B2File::bucket_id) as a [BucketId].
+    pub fn bucket(&self) -> BucketId {
+        BucketId::from(self.bucket_id.as_str())
+    }
+}
+
 #[derive(Clone, Deserialize, Serialize, Debug)]
 pub enum B2ServerSideEncryptionAlgorithm {
     AES256,
@@ -506,11 +671,13 @@ pub struct B2EventNotificationRule {
     pub name: String,
     /// Specifies which object(s) in the bucket the event notification rule applies to.
     pub object_name_prefix: String,
-    /// Whether the event notification rule is suspended.
+    /// Whether the event notification rule is suspended, only set by B2 and left out when setting rules.
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub is_suspended: Option<bool>,
     /// Represents the maximum number of events a user will receive per webhook invocation. The value must be a number between 1 and 50. The default value is 1.
     pub max_events_per_batch: Option<u8>,
-    /// A brief description of why the event notification rule was suspended.
+    /// A brief description of why the event notification rule was suspended, only set by B2 and left out when setting rules.
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub suspension_reason: Option<String>,
     /// The target configuration for the event notification rule.
     /// <br><br>This object will always contain the `targetType`` field. Currently, the only valid value for `targetType`` is "webhook."
@@ -518,6 +685,54 @@ pub struct B2EventNotificationRule {
     pub target_configuration: B2NotificationConfiguration,
 }
 
+impl B2EventNotificationRule {
+    /// Whether B2 suspended the rule, and why, see [B2RuleSuspension].
+    pub fn suspension(&self) -> B2RuleSuspension {
+        match self.is_suspended {
+            Some(true) => B2RuleSuspension::Suspended {
+                reason: self.suspension_reason.clone(),
+            },
+            _ => B2RuleSuspension::Active,
+        }
+    }
+
+    /// Clears the suspension fields, so setting the rule again resumes it.
+    pub fn clear_suspension(&mut self) {
+        self.is_suspended = None;
+        self.suspension_reason = None;
+    }
+}
+
+/// Suspension state of an event notification rule.
+/// <br> B2 suspends a rule when its webhook keeps failing, and stops sending its events until the rule is set again.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum B2RuleSuspension {
+    Active,
+    Suspended { reason: Option<String> },
+}
+
+impl B2RuleSuspension {
+    pub fn is_suspended(&self) -> bool {
+        matches!(self, Self::Suspended { .. })
+    }
+
+    pub fn reason(&self) -> Option<&str> {
+        match self {
+            Self::Suspended { reason } => reason.as_deref(),
+            Self::Active => None,
+        }
+    }
+}
+
+/// A suspended rule found when fetching a bucket's notification rules,
+/// see [with_suspended_rule_callback](crate::simple_client::B2SimpleClient::with_suspended_rule_callback).
+#[derive(Clone, Debug)]
+pub struct B2SuspendedNotificationRule {
+    pub bucket_id: String,
+    pub rule_name: String,
+    pub reason: Option<String>,
+}
+
 #[derive(Clone, Serialize, Debug, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct B2NotificationConfiguration {
@@ -543,7 +758,7 @@ pub struct B2FilePart {
     pub upload_timestamp: u64,
 }
 
-#[derive(Clone, Serialize, Debug, Deserialize)]
+#[derive(Clone, Serialize, Debug, Deserialize, TypedBuilder)]
 #[serde(rename_all = "camelCase")]
 pub struct B2AppKey {
     /// Your account ID.
@@ -551,22 +766,60 @@ pub struct B2AppKey {
     /// The ID of the newly created key.
     pub application_key_id: String,
     /// The secret part of the key. Only returned when creating a new key with [b2_create_key](crate::simple_client::B2SimpleClient::create_key).
+    #[builder(default)]
     pub application_key: Option<String>,
     /// When present, restricts access to one bucket.
+    #[builder(default)]
     pub bucket_id: Option<String>,
     /// The list of capabilities this key has.
     pub capabilities: Vec<B2KeyCapability>,
     /// When present, says when this key will expire, in milliseconds since 1970.
+    #[builder(default)]
     pub expiration_timestamp: Option<u64>,
     /// The name assigned when the key was created.
     pub key_name: String,
     /// When present, restricts access to files whose names start with the prefix.
+    #[builder(default)]
     pub name_prefix: Option<String>,
     /// When present and set to s3, the key can be used to sign requests to the [S3 Compatible API](https://www.backblaze.com/apidocs/introduction-to-the-s3-compatible-api).
+    #[builder(default)]
     pub options: Option<Vec<B2BucketOption>>,
 }
 
-#[derive(Clone, Debug, Deserialize)]
+impl B2AppKey {
+    /// The [application_key_id](B2AppKey::application_key_id) as a [KeyId].
+    pub fn id(&self) -> KeyId {
+        KeyId::from(self.application_key_id.as_str())
+    }
+
+    /// The [bucket_id](B2AppKey::bucket_id) as a [BucketId], for keys restricted to one bucket.
+    pub fn bucket(&self) -> Option<BucketId> {
+        self.bucket_id.as_deref().map(BucketId::from)
+    }
+}
+
+/// Result of [create_bucket_if_not_exists](crate::simple_client::B2SimpleClient::create_bucket_if_not_exists).
+#[derive(Clone, Debug)]
+pub enum B2BucketCreation {
+    Created(B2Bucket),
+    AlreadyExisted(B2Bucket),
+}
+
+impl B2BucketCreation {
+    pub fn bucket(&self) -> &B2Bucket {
+        match self {
+            Self::Created(bucket) | Self::AlreadyExisted(bucket) => bucket,
+        }
+    }
+
+    pub fn into_bucket(self) -> B2Bucket {
+        match self {
+            Self::Created(bucket) | Self::AlreadyExisted(bucket) => bucket,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, TypedBuilder)]
 #[serde(rename_all = "camelCase")]
 pub struct B2Bucket {
     /// Your account ID.
@@ -578,9 +831,11 @@ pub struct B2Bucket {
     /// The bucket type.
     pub bucket_type: B2BucketType,
     /// The user data stored with this bucket.
+    #[builder(default)]
     pub bucket_info: HashMap<String, String>,
     /// The initial list of CORS rules for this bucket.
     /// See [CORS Rules](https://www.backblaze.com/docs/cloud-storage-cross-origin-resource-sharing-rules) for an overview and the rule structure.
+    #[builder(default)]
     pub cors_rules: Vec<B2CorsRule>,
     /// The Object Lock configuration for this bucket.
     /// This field is filtered based on application key capabilities; the [`readBucketRetentions`](B2KeyCapability::ReadBucketRetentions) capability is required to access the value.
@@ -592,6 +847,7 @@ pub struct B2Bucket {
     pub default_server_side_encryption: B2ServerSideEncryption,
     /// The initial list of lifecycle rules for this bucket.
     /// See [Lifecycle Rules](https://www.backblaze.com/docs/cloud-storage-lifecycle-rules) for an overview and the rule structure.
+    #[builder(default)]
     pub life_cycle_rules: Option<Vec<B2LifeCycleRules>>,
     /// The list of replication rules for this bucket. See [Cloud Replication](https://www.backblaze.com/docs/cloud-storage-create-a-cloud-replication-rule-with-the-native-api) Rules.
     /// <br><br> NOTE: The first time that you configure Cloud Replication, complete the following tasks to ensure that you have the correct permission:
@@ -602,9 +858,22 @@ pub struct B2Bucket {
     /// and can be used with the [`ifRevisionIs`](super::bodies::B2UpdateBucketBody::if_revision_is) parameter to b2_update_bucket to prevent colliding, simultaneous updates.
     pub revision: u32,
     /// When present and set to s3, the bucket can be accessed through the [`S3 Compatible API`](https://www.backblaze.com/apidocs/introduction-to-the-s3-compatible-api).
+    #[builder(default)]
     pub options: Option<Vec<B2BucketOption>>,
 }
 
+impl B2Bucket {
+    /// The [bucket_id](B2Bucket::bucket_id) as a [BucketId].
+    pub fn id(&self) -> BucketId {
+        BucketId::from(self.bucket_id.as_str())
+    }
+
+    /// The [account_id](B2Bucket::account_id) as an [AccountId].
+    pub fn account(&self) -> AccountId {
+        AccountId::from(self.account_id.as_str())
+    }
+}
+
 #[derive(Clone, Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct B2CustomerAgnosticServerSideEncryption {
@@ -618,16 +887,113 @@ pub struct B2CustomerAgnosticServerSideEncryption {
 #[serde(rename_all = "UPPERCASE")]
 pub enum B2MetadataDirective {
     Copy,
+    Replace,
 }
 
-#[derive(Clone, Debug, Deserialize, Serialize)]
+#[derive(Clone, Debug, Deserialize, Serialize, TypedBuilder)]
 #[serde(rename_all = "camelCase")]
 pub struct B2FileDownloadDetails {
     pub content_length: u64,
     pub content_type: String,
     pub file_id: String,
     pub file_name: String,
+    #[builder(default)]
     pub content_sha1: Option<String>,
     pub upload_timestamp: u64,
+    #[builder(default)]
     pub file_info: Option<HashMap<String, String>>,
 }
+
+impl B2FileDownloadDetails {
+    /// Reads the details from the headers of a download response, removing the headers it reads, `x-bz-info-*` headers included.
+    /// <br> Returns an error instead of panicking when a header is missing or malformed.
+    /// ```rust
+    /// # use std::collections::HashMap;
+    /// # use backblaze_b2_client::definitions::shared::B2FileDownloadDetails;
+    /// let mut headers = HashMap::from([
+    ///     ("x-bz-file-name".to_string(), "photo%20one.jpg".to_string()),
+    ///     ("x-bz-content-sha1".to_string(), "none".to_string()),
+    ///     ("x-bz-file-id".to_string(), "4_z27c8".to_string()),
+    ///     ("content-length".to_string(), "12".to_string()),
+    ///     ("content-type".to_string(), "image/jpeg".to_string()),
+    /// ]);
+    ///
+    /// // The upload timestamp is missing
+    /// assert!(B2FileDownloadDetails::from_headers(&mut headers.clone()).is_err());
+    ///
+    /// headers.insert("x-bz-upload-timestamp".into(), "1700000000000".into());
+    /// let details = B2FileDownloadDetails::from_headers(&mut headers).unwrap();
+    ///
+    /// assert_eq!(details.file_name, "photo one.jpg");
+    /// assert_eq!(details.content_sha1, None);
+    /// ```
+    pub fn from_headers(
+        headers: &mut HashMap<String, String>,
+    ) -> Result<Self, ResponseHeaderError> {
+        fn take(
+            headers: &mut HashMap<String, String>,
+            name: &str,
+        ) -> Result<String, ResponseHeaderError> {
+            headers
+                .remove(name)
+                .ok_or_else(|| ResponseHeaderError::Missing(name.into()))
+        }
+
+        fn take_number(
+            headers: &mut HashMap<String, String>,
+            name: &str,
+        ) -> Result<u64, ResponseHeaderError> {
+            let value = take(headers, name)?;
+
+            value
+                .trim()
+                .parse()
+                .map_err(|_| ResponseHeaderError::Invalid {
+                    name: name.into(),
+                    value,
+                })
+        }
+
+        let file_name = decode_header_value(take(headers, "x-bz-file-name")?);
+        let sha1 = take(headers, "x-bz-content-sha1")?;
+
+        let mut details = Self {
+            file_id: take(headers, "x-bz-file-id")?,
+            file_name,
+            content_length: take_number(headers, "content-length")?,
+            content_type: take(headers, "content-type")?,
+            content_sha1: (sha1 != "none").then_some(sha1),
+            upload_timestamp: take_number(headers, "x-bz-upload-timestamp")?,
+            file_info: None,
+        };
+
+        let info_keys: Vec<String> = headers
+            .keys()
+            .filter(|key| key.starts_with("x-bz-info-"))
+            .cloned()
+            .collect();
+
+        let file_info: HashMap<String, String> = info_keys
+            .into_iter()
+            .filter_map(|key| {
+                let value = headers.remove(&key)?;
+
+                Some((
+                    key["x-bz-info-".len()..].to_string(),
+                    decode_header_value(value),
+                ))
+            })
+            .collect();
+
+        if !file_info.is_empty() {
+            details.file_info = Some(file_info);
+        }
+
+        Ok(details)
+    }
+
+    /// Gets a [file info](B2FileDownloadDetails::file_info) value, ignoring the key's case.
+    pub fn file_info_value(&self, key: &str) -> Option<&str> {
+        self.file_info.as_ref().and_then(|info| info.get_info(key))
+    }
+}
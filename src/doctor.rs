@@ -0,0 +1,377 @@
+use std::{
+    fmt,
+    time::{Duration, SystemTime},
+};
+
+use serde::Serialize;
+
+use crate::{
+    client::B2ClientStatus,
+    definitions::{bodies::B2ListBucketsBody, shared::B2KeyCapability},
+    simple_client::B2SimpleClient,
+    util::SizeUnit,
+};
+
+/// A key expiring sooner than this is reported as a warning.
+const KEY_EXPIRY_WARNING: Duration = Duration::from_secs(60 * 60 * 24);
+
+/// A part taking longer than this to upload is reported as a warning, since a failed part starts over.
+const SLOW_PART_WARNING: Duration = Duration::from_secs(60 * 10);
+
+/// What [B2Client::doctor](crate::client::B2Client::doctor) checks the client against.
+#[derive(Debug, Clone)]
+pub struct DoctorOptions {
+    /// Capabilities the key is going to need, any missing one fails the check.
+    /// <br> Default is empty.
+    pub required_capabilities: Vec<B2KeyCapability>,
+    /// Part size large file uploads are going to use.
+    /// <br> Default is None, which checks the account's recommended part size.
+    pub part_size: Option<u64>,
+    /// Expected upload bandwidth in bytes per second, used to check how long a part takes to upload.
+    /// <br> Default is None.
+    pub upload_bandwidth: Option<u64>,
+    /// Clock difference from B2's servers above which a warning is reported.
+    /// <br> Default is 1 minute.
+    pub max_clock_skew: Duration,
+}
+
+impl Default for DoctorOptions {
+    fn default() -> Self {
+        Self {
+            required_capabilities: vec![],
+            part_size: None,
+            upload_bandwidth: None,
+            max_clock_skew: Duration::from_secs(60),
+        }
+    }
+}
+
+/// The things [B2Client::doctor](crate::client::B2Client::doctor) checks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum DiagnosticCheck {
+    /// The key is valid, not expired, and accepted by B2.
+    Auth,
+    /// The key has every [required capability](DoctorOptions::required_capabilities).
+    Capabilities,
+    /// The local clock agrees with B2's servers.
+    ClockSkew,
+    /// The API host can be reached.
+    ApiConnectivity,
+    /// The download host can be reached.
+    DownloadConnectivity,
+    /// The part size suits the account and the upload bandwidth.
+    PartSize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+pub enum DiagnosticStatus {
+    Ok,
+    Warning,
+    Failed,
+}
+
+/// The result of a single check.
+#[derive(Debug, Clone, Serialize)]
+pub struct Diagnostic {
+    pub check: DiagnosticCheck,
+    pub status: DiagnosticStatus,
+    pub message: String,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[{:?}] {:?}: {}", self.status, self.check, self.message)
+    }
+}
+
+/// Every check [B2Client::doctor](crate::client::B2Client::doctor) ran, in the order they ran.
+#[derive(Debug, Clone, Serialize)]
+pub struct DoctorReport {
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+impl DoctorReport {
+    /// Whether no check failed, warnings are allowed.
+    pub fn is_healthy(&self) -> bool {
+        self.diagnostics
+            .iter()
+            .all(|diagnostic| diagnostic.status != DiagnosticStatus::Failed)
+    }
+
+    /// The worst status of all checks.
+    pub fn status(&self) -> DiagnosticStatus {
+        self.diagnostics
+            .iter()
+            .map(|diagnostic| diagnostic.status)
+            .max()
+            .unwrap_or(DiagnosticStatus::Ok)
+    }
+
+    /// The checks that didn't pass, failed or warned.
+    pub fn problems(&self) -> impl Iterator<Item = &Diagnostic> {
+        self.diagnostics
+            .iter()
+            .filter(|diagnostic| diagnostic.status != DiagnosticStatus::Ok)
+    }
+
+    /// The result of a single check, if it ran.
+    pub fn get(&self, check: DiagnosticCheck) -> Option<&Diagnostic> {
+        self.diagnostics
+            .iter()
+            .find(|diagnostic| diagnostic.check == check)
+    }
+
+    fn push<S: Into<String>>(
+        &mut self,
+        check: DiagnosticCheck,
+        status: DiagnosticStatus,
+        message: S,
+    ) {
+        self.diagnostics.push(Diagnostic {
+            check,
+            status,
+            message: message.into(),
+        });
+    }
+}
+
+impl fmt::Display for DoctorReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for diagnostic in &self.diagnostics {
+            writeln!(f, "{}", diagnostic)?;
+        }
+
+        Ok(())
+    }
+}
+
+pub(crate) async fn diagnose(
+    client: &B2SimpleClient,
+    status: B2ClientStatus,
+    options: DoctorOptions,
+) -> DoctorReport {
+    let mut report = DoctorReport {
+        diagnostics: vec![],
+    };
+
+    check_auth(client, status, &mut report).await;
+    check_capabilities(client, &options, &mut report);
+
+    let api_date = check_host(
+        client,
+        client.api_base_url(),
+        DiagnosticCheck::ApiConnectivity,
+        &mut report,
+    )
+    .await;
+    let download_date = check_host(
+        client,
+        client.download_base_url(),
+        DiagnosticCheck::DownloadConnectivity,
+        &mut report,
+    )
+    .await;
+
+    check_clock_skew(api_date.or(download_date), &options, &mut report);
+    check_part_size(client, &options, &mut report);
+
+    report
+}
+
+async fn check_auth(client: &B2SimpleClient, status: B2ClientStatus, report: &mut DoctorReport) {
+    if let B2ClientStatus::KeyExpired = status {
+        return report.push(
+            DiagnosticCheck::Auth,
+            DiagnosticStatus::Failed,
+            "The key has expired, the client has to be re-created with a new key",
+        );
+    }
+
+    let expires_in = client
+        .auth_data()
+        .application_key_expiration_timestamp
+        .map(|timestamp| SystemTime::UNIX_EPOCH + Duration::from_millis(timestamp))
+        .map(|expiration| {
+            expiration
+                .duration_since(SystemTime::now())
+                .unwrap_or_default()
+        });
+
+    if !client.has_capability(&B2KeyCapability::ListBuckets) {
+        return report.push(
+            DiagnosticCheck::Auth,
+            DiagnosticStatus::Warning,
+            "The key can't list buckets, so it couldn't be checked with a request",
+        );
+    }
+
+    let bucket_id = client.auth_data().api_info.storage_api.bucket_id;
+
+    let result = client
+        .list_buckets(
+            B2ListBucketsBody::builder()
+                .account_id(client.account_id().to_string())
+                .bucket_id(bucket_id)
+                .build(),
+        )
+        .await;
+
+    match (result, expires_in) {
+        (Err(error), _) => report.push(
+            DiagnosticCheck::Auth,
+            DiagnosticStatus::Failed,
+            format!("B2 rejected the key: {}", error),
+        ),
+        (Ok(_), Some(expires_in)) if expires_in < KEY_EXPIRY_WARNING => report.push(
+            DiagnosticCheck::Auth,
+            DiagnosticStatus::Warning,
+            format!("The key expires in {:?}", expires_in),
+        ),
+        (Ok(_), _) => report.push(
+            DiagnosticCheck::Auth,
+            DiagnosticStatus::Ok,
+            "The key is valid",
+        ),
+    }
+}
+
+fn check_capabilities(client: &B2SimpleClient, options: &DoctorOptions, report: &mut DoctorReport) {
+    let missing: Vec<String> = options
+        .required_capabilities
+        .iter()
+        .filter(|capability| !client.has_capability(capability))
+        .map(|capability| capability.to_string())
+        .collect();
+
+    match missing.is_empty() {
+        true => report.push(
+            DiagnosticCheck::Capabilities,
+            DiagnosticStatus::Ok,
+            "The key has every required capability",
+        ),
+        false => report.push(
+            DiagnosticCheck::Capabilities,
+            DiagnosticStatus::Failed,
+            format!("The key is missing: {}", missing.join(", ")),
+        ),
+    }
+}
+
+/// Returns the host's `Date` header, if it responded with one.
+async fn check_host(
+    client: &B2SimpleClient,
+    url: &str,
+    check: DiagnosticCheck,
+    report: &mut DoctorReport,
+) -> Option<SystemTime> {
+    match client.probe_host(url).await {
+        Ok((latency, date)) => {
+            report.push(
+                check,
+                DiagnosticStatus::Ok,
+                format!("{} responded in {:?}", url, latency),
+            );
+
+            date
+        }
+        Err(error) => {
+            report.push(
+                check,
+                DiagnosticStatus::Failed,
+                format!("Couldn't reach {}: {}", url, error),
+            );
+
+            None
+        }
+    }
+}
+
+fn check_clock_skew(
+    server_date: Option<SystemTime>,
+    options: &DoctorOptions,
+    report: &mut DoctorReport,
+) {
+    let Some(server_date) = server_date else {
+        return report.push(
+            DiagnosticCheck::ClockSkew,
+            DiagnosticStatus::Warning,
+            "No host responded with a date to compare against",
+        );
+    };
+
+    let now = SystemTime::now();
+    let skew = match now.duration_since(server_date) {
+        Ok(ahead) => ahead,
+        Err(behind) => behind.duration(),
+    };
+
+    // The Date header only has second precision
+    match skew > options.max_clock_skew + Duration::from_secs(1) {
+        true => report.push(
+            DiagnosticCheck::ClockSkew,
+            DiagnosticStatus::Warning,
+            format!(
+                "The local clock is {:?} {} B2's servers, timestamps and key expiration will be off",
+                Duration::from_secs(skew.as_secs()),
+                if now > server_date { "ahead of" } else { "behind" }
+            ),
+        ),
+        false => report.push(
+            DiagnosticCheck::ClockSkew,
+            DiagnosticStatus::Ok,
+            "The local clock agrees with B2's servers",
+        ),
+    }
+}
+
+fn check_part_size(client: &B2SimpleClient, options: &DoctorOptions, report: &mut DoctorReport) {
+    let recommended = client.recommended_part_size();
+    let minimum = client.min_part_size();
+    let part_size = options.part_size.unwrap_or(recommended);
+
+    if part_size < minimum {
+        return report.push(
+            DiagnosticCheck::PartSize,
+            DiagnosticStatus::Failed,
+            format!(
+                "Part size of {} is below the minimum of {}",
+                SizeUnit::from(part_size as f64),
+                SizeUnit::from(minimum as f64)
+            ),
+        );
+    }
+
+    if let Some(bandwidth) = options.upload_bandwidth.filter(|bandwidth| *bandwidth > 0) {
+        let part_time = Duration::from_secs_f64(part_size as f64 / bandwidth as f64);
+
+        if part_time > SLOW_PART_WARNING {
+            return report.push(
+                DiagnosticCheck::PartSize,
+                DiagnosticStatus::Warning,
+                format!(
+                    "A {} part takes {:?} to upload at {}/s, a failed part starts over, consider smaller parts",
+                    SizeUnit::from(part_size as f64),
+                    Duration::from_secs(part_time.as_secs()),
+                    SizeUnit::from(bandwidth as f64)
+                ),
+            );
+        }
+    }
+
+    match part_size < recommended {
+        true => report.push(
+            DiagnosticCheck::PartSize,
+            DiagnosticStatus::Warning,
+            format!(
+                "Part size of {} is below the recommended {}, uploads will make more requests than needed",
+                SizeUnit::from(part_size as f64),
+                SizeUnit::from(recommended as f64)
+            ),
+        ),
+        false => report.push(
+            DiagnosticCheck::PartSize,
+            DiagnosticStatus::Ok,
+            format!("Part size of {} suits the account", SizeUnit::from(part_size as f64)),
+        ),
+    }
+}
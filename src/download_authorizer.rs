@@ -0,0 +1,128 @@
+use std::{
+    collections::HashMap,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use tokio::sync::{Mutex, OnceCell};
+
+use crate::{
+    definitions::{
+        bodies::B2GetDownloadAuthorizationBody, responses::B2GetDownloadAuthorizationBodyResponse,
+    },
+    error::B2Error,
+    simple_client::B2SimpleClient,
+    util::{RetryContext, RetryStrategy},
+};
+
+/// Options for [DownloadAuthorizer].
+#[derive(Debug)]
+pub struct DownloadAuthorizerOptions {
+    /// How long an issued token is handed to callers asking for the same authorization.
+    /// <br> A token handed out later has less of its [valid duration](B2GetDownloadAuthorizationBody::valid_duration_in_seconds) left,
+    /// so this should be a small fraction of it.
+    /// <br> Default is 5 seconds.
+    pub window: Duration,
+    /// Retry strategy when issuing a token fails with a 429, a 5xx, or fails to send.
+    /// <br> Defaults to RetryStrategy::Dynamic([crate::util::DefaultRetryStrategy]).
+    pub retry_strategy: RetryStrategy,
+}
+
+impl Default for DownloadAuthorizerOptions {
+    fn default() -> Self {
+        Self {
+            window: Duration::from_secs(5),
+            retry_strategy: RetryStrategy::default(),
+        }
+    }
+}
+
+struct IssuedToken {
+    created: Instant,
+    token: Arc<OnceCell<B2GetDownloadAuthorizationBodyResponse>>,
+}
+
+/// Coalesces [get_download_authorization](B2SimpleClient::get_download_authorization) calls, for services
+/// that sign download URLs in bursts.
+/// <br> Concurrent and close together requests for the same authorization, same bucket, prefix, duration and overrides,
+/// share one token issued by a single call. When issuing fails, the next waiting caller tries again itself.
+pub struct DownloadAuthorizer {
+    client: Arc<B2SimpleClient>,
+    options: DownloadAuthorizerOptions,
+    tokens: Mutex<HashMap<String, IssuedToken>>,
+}
+
+impl DownloadAuthorizer {
+    pub fn new(client: Arc<B2SimpleClient>, options: DownloadAuthorizerOptions) -> Self {
+        Self {
+            client,
+            options,
+            tokens: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Gets a download authorization, reusing one issued within the [window](DownloadAuthorizerOptions::window) for the same request.
+    pub async fn get_download_authorization(
+        &self,
+        request_body: B2GetDownloadAuthorizationBody,
+    ) -> Result<B2GetDownloadAuthorizationBodyResponse, B2Error> {
+        let key = serde_json::to_string(&request_body).map_err(B2Error::JsonParseError)?;
+
+        let token = {
+            let mut tokens = self.tokens.lock().await;
+            let now = self.client.clock().now();
+
+            tokens.retain(|_, issued| now.duration_since(issued.created) < self.options.window);
+
+            tokens
+                .entry(key)
+                .or_insert_with(|| IssuedToken {
+                    created: now,
+                    token: Arc::new(OnceCell::new()),
+                })
+                .token
+                .clone()
+        };
+
+        let token = token
+            .get_or_try_init(|| self.issue_with_retry(request_body))
+            .await?;
+
+        Ok(token.clone())
+    }
+
+    async fn issue_with_retry(
+        &self,
+        request_body: B2GetDownloadAuthorizationBody,
+    ) -> Result<B2GetDownloadAuthorizationBodyResponse, B2Error> {
+        let max_attempts = self.options.retry_strategy.max_attempts();
+        let mut attempt = 0;
+
+        loop {
+            attempt += 1;
+
+            let error = match self
+                .client
+                .get_download_authorization(request_body.clone())
+                .await
+            {
+                Ok(response) => return Ok(response),
+                Err(error) => error,
+            };
+
+            if !error.is_retryable() || attempt >= max_attempts {
+                return Err(error);
+            }
+
+            self.client
+                .clock()
+                .sleep(self.options.retry_strategy.retry_wait(&RetryContext {
+                    endpoint: "b2_get_download_authorization",
+                    attempt: attempt + 1,
+                    last_error: Some(&error),
+                    retry_after: error.retry_after(),
+                }))
+                .await;
+        }
+    }
+}
@@ -0,0 +1,236 @@
+use std::{
+    cell::Cell,
+    collections::HashMap,
+    future::Future,
+    sync::atomic::{AtomicU64, Ordering},
+    time::Duration,
+};
+
+use crate::{
+    definitions::shared::{B2Endpoint, B2TransactionClass},
+    error::B2Error,
+    util::{B2Callback, Clock},
+};
+
+/// Calls made to a single endpoint, see [endpoint_stats](crate::simple_client::B2SimpleClient::endpoint_stats).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct EndpointStats {
+    /// Calls made, failed ones included.
+    pub calls: u64,
+    /// Calls that failed to send or got an error response.
+    pub failures: u64,
+    /// How long the latest call took to get a response, None before the first call.
+    /// <br> Downloads count until the response headers arrived, not until the body was read.
+    pub last_latency: Option<Duration>,
+    pub total_latency: Duration,
+}
+
+impl EndpointStats {
+    pub fn average_latency(&self) -> Option<Duration> {
+        (self.calls > 0).then(|| self.total_latency / self.calls as u32)
+    }
+}
+
+/// The [EndpointStats] of every endpoint called since the client was created or the stats were reset.
+#[derive(Debug, Clone, Default)]
+pub struct EndpointStatsSnapshot {
+    /// Stats per endpoint, only endpoints that were called are included.
+    pub endpoints: HashMap<B2Endpoint, EndpointStats>,
+}
+
+impl EndpointStatsSnapshot {
+    /// Calls billed as the class, for estimating transaction costs.
+    /// <br> A listing asking for more than 1000 files is billed as several transactions but counted as one call.
+    pub fn transactions(&self, class: B2TransactionClass) -> u64 {
+        self.endpoints
+            .iter()
+            .filter(|(endpoint, _)| endpoint.transaction_class() == class)
+            .map(|(_, stats)| stats.calls)
+            .sum()
+    }
+}
+
+/// A single call, passed to the [metrics callback](crate::simple_client::B2SimpleClientBuilder::with_metrics)
+/// once it finished, retries are separate calls.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct B2RequestMetrics {
+    pub endpoint: B2Endpoint,
+    /// How long the call took to get a response, downloads count until the response headers arrived.
+    pub duration: Duration,
+    /// The response's status code, None when the call failed to send.
+    pub status: Option<u16>,
+    /// Size of the request body, None when it wasn't known up front, like a streamed body of a call that isn't an upload.
+    pub bytes_sent: Option<u64>,
+    /// The response's `Content-Length`, None when it wasn't sent or there was no response.
+    pub bytes_received: Option<u64>,
+}
+
+tokio::task_local! {
+    static CALL_SAMPLE: CallSample;
+}
+
+/// What a call noted about its request and response while it ran inside [timed](EndpointStatsRecorder::timed).
+#[derive(Default)]
+struct CallSample {
+    status: Cell<Option<u16>>,
+    bytes_sent: Cell<Option<u64>>,
+    bytes_received: Cell<Option<u64>>,
+}
+
+/// Notes the size of the running call's request body.
+pub(crate) fn note_bytes_sent(bytes: u64) {
+    let _ = CALL_SAMPLE.try_with(|sample| sample.bytes_sent.set(Some(bytes)));
+
+    #[cfg(feature = "tracing")]
+    tracing::Span::current().record("bytes_sent", bytes);
+}
+
+/// Notes the running call's response status and `Content-Length`.
+pub(crate) fn note_response(status: u16, bytes_received: Option<u64>) {
+    let _ = CALL_SAMPLE.try_with(|sample| {
+        sample.status.set(Some(status));
+        sample.bytes_received.set(bytes_received);
+    });
+}
+
+/// The counters behind [EndpointStats], one per endpoint so recording a call is a few atomic adds.
+#[derive(Debug, Default)]
+pub(crate) struct EndpointStatsRecorder {
+    counters: [EndpointCounters; B2Endpoint::ALL.len()],
+    metrics: Option<B2Callback<B2RequestMetrics>>,
+}
+
+impl CallSample {
+    fn take(&self) -> (Option<u16>, Option<u64>, Option<u64>) {
+        (
+            self.status.take(),
+            self.bytes_sent.take(),
+            self.bytes_received.take(),
+        )
+    }
+}
+
+#[derive(Debug, Default)]
+struct EndpointCounters {
+    calls: AtomicU64,
+    failures: AtomicU64,
+    /// Nanoseconds plus one, so zero means no call yet.
+    last_latency: AtomicU64,
+    total_latency: AtomicU64,
+}
+
+impl EndpointStatsRecorder {
+    pub(crate) fn new(metrics: Option<B2Callback<B2RequestMetrics>>) -> Self {
+        Self {
+            metrics,
+            ..Default::default()
+        }
+    }
+
+    fn record(&self, endpoint: &B2Endpoint, latency: Duration, succeeded: bool) {
+        let counters = &self.counters[endpoint.clone() as usize];
+        let nanos = latency.as_nanos().min(u64::MAX as u128 - 1) as u64;
+
+        counters.calls.fetch_add(1, Ordering::Relaxed);
+        counters.last_latency.store(nanos + 1, Ordering::Relaxed);
+        counters.total_latency.fetch_add(nanos, Ordering::Relaxed);
+
+        if !succeeded {
+            counters.failures.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// Runs the call, recording how long it took and whether it failed, and passing it to the metrics callback.
+    /// <br> With the `tracing` feature it runs in a `b2_api_call` span.
+    pub(crate) async fn timed<T>(
+        &self,
+        endpoint: B2Endpoint,
+        clock: &dyn Clock,
+        call: impl Future<Output = Result<T, B2Error>>,
+    ) -> Result<T, B2Error> {
+        let start = clock.now();
+
+        #[cfg(feature = "tracing")]
+        let call = tracing::Instrument::instrument(
+            call,
+            tracing::debug_span!(
+                "b2_api_call",
+                endpoint = %endpoint,
+                bytes_sent = tracing::field::Empty,
+                part_number = tracing::field::Empty,
+            ),
+        );
+
+        let (result, sample) = CALL_SAMPLE
+            .scope(CallSample::default(), async {
+                let result = call.await;
+                (result, CALL_SAMPLE.with(|sample| sample.take()))
+            })
+            .await;
+
+        let duration = clock.now().saturating_duration_since(start);
+
+        #[cfg(feature = "tracing")]
+        match &result {
+            Ok(_) => {
+                tracing::debug!(endpoint = %endpoint, elapsed = ?duration, "B2 call succeeded")
+            }
+            Err(error) => {
+                tracing::debug!(endpoint = %endpoint, elapsed = ?duration, %error, "B2 call failed")
+            }
+        }
+
+        self.record(&endpoint, duration, result.is_ok());
+
+        let (status, bytes_sent, bytes_received) = sample;
+        let metrics = B2RequestMetrics {
+            endpoint,
+            duration,
+            status,
+            bytes_sent,
+            bytes_received,
+        };
+
+        match &self.metrics {
+            Some(B2Callback::Fn(fun)) => fun(metrics),
+            Some(B2Callback::AsyncFn(fun)) => fun(metrics).await,
+            None => {}
+        }
+
+        result
+    }
+
+    pub(crate) fn snapshot(&self) -> EndpointStatsSnapshot {
+        let endpoints = B2Endpoint::ALL
+            .into_iter()
+            .zip(&self.counters)
+            .filter(|(_, counters)| counters.calls.load(Ordering::Relaxed) > 0)
+            .map(|(endpoint, counters)| {
+                let stats = EndpointStats {
+                    calls: counters.calls.load(Ordering::Relaxed),
+                    failures: counters.failures.load(Ordering::Relaxed),
+                    last_latency: match counters.last_latency.load(Ordering::Relaxed) {
+                        0 => None,
+                        nanos => Some(Duration::from_nanos(nanos - 1)),
+                    },
+                    total_latency: Duration::from_nanos(
+                        counters.total_latency.load(Ordering::Relaxed),
+                    ),
+                };
+
+                (endpoint, stats)
+            })
+            .collect();
+
+        EndpointStatsSnapshot { endpoints }
+    }
+
+    pub(crate) fn reset(&self) {
+        for counters in &self.counters {
+            counters.calls.store(0, Ordering::Relaxed);
+            counters.failures.store(0, Ordering::Relaxed);
+            counters.last_latency.store(0, Ordering::Relaxed);
+            counters.total_latency.store(0, Ordering::Relaxed);
+        }
+    }
+}
@@ -1,22 +1,109 @@
 use core::fmt;
-use std::{error::Error, num::NonZeroU16};
+use std::{error::Error, num::NonZeroU16, time::Duration};
 
 use serde::{Deserialize, Serialize};
 
-use crate::definitions::shared::B2KeyCapability;
+use crate::{definitions::shared::B2KeyCapability, pagination::ListingCost, util::InvalidValue};
 
 #[derive(Debug)]
 pub enum B2Error {
     // NotAuthenticated,
     JsonParseError(serde_json::Error),
     RequestError(B2RequestError),
+    /// The request failed because the file doesn't exist, B2 codes `not_found`, `no_such_file` and `file_not_present`.
+    FileNotFound(B2RequestError),
+    /// The request failed because the bucket doesn't exist, B2 code `bad_bucket_id`.
+    BucketNotFound(B2RequestError),
+    /// A bucket with this name already exists, in this account or another one, B2 code `duplicate_bucket_name`.
+    DuplicateBucketName(B2RequestError),
     RequestSendError(reqwest::Error),
+    /// The HTTP client couldn't be built from the [B2SimpleClientBuilder](crate::simple_client::B2SimpleClientBuilder)'s options.
+    HttpClientBuildError(reqwest::Error),
     MissingCapability(B2KeyCapability),
     InvalidHeaders(IntoHeaderMapError),
+    /// A response header B2 always sends is missing or malformed, like one altered by a proxy.
+    InvalidResponseHeader(ResponseHeaderError),
+    InvalidValue(InvalidValue),
+    /// A listing stream hit one of its [ListingGuard](crate::pagination::ListingGuard) caps, holds what it cost until then.
+    ListingCapReached(ListingCost),
 }
 
 impl Error for B2Error {}
 
+impl B2Error {
+    /// Wraps an error returned by B2, mapping known codes to their own variants.
+    pub fn from_request_error(error: B2RequestError) -> Self {
+        match error.code.as_str() {
+            "not_found" | "no_such_file" | "file_not_present" => Self::FileNotFound(error),
+            "bad_bucket_id" => Self::BucketNotFound(error),
+            "duplicate_bucket_name" => Self::DuplicateBucketName(error),
+            _ => Self::RequestError(error),
+        }
+    }
+
+    /// The error returned by B2, if the request got a response.
+    pub fn request_error(&self) -> Option<&B2RequestError> {
+        match self {
+            Self::RequestError(error)
+            | Self::FileNotFound(error)
+            | Self::BucketNotFound(error)
+            | Self::DuplicateBucketName(error) => Some(error),
+            _ => None,
+        }
+    }
+
+    /// Whether the request failed because the file or bucket doesn't exist.
+    pub fn is_not_found(&self) -> bool {
+        matches!(self, Self::FileNotFound(_) | Self::BucketNotFound(_))
+    }
+
+    /// Whether B2 rejected an update because the resource changed since it was read,
+    /// like [b2_update_bucket](crate::simple_client::B2SimpleClient::update_bucket) with an outdated `ifRevisionIs`.
+    pub fn is_conflict(&self) -> bool {
+        self.request_error()
+            .is_some_and(|error| error.status.get() == 409)
+    }
+
+    /// Whether B2 responded with a 429, the account is making requests too fast and should back off,
+    /// honoring [retry_after](B2Error::retry_after) when it's sent.
+    pub fn is_too_many_requests(&self) -> bool {
+        self.request_error()
+            .is_some_and(|error| error.status.get() == 429)
+    }
+
+    /// Whether B2 responded with a 503, for uploads it means the upload URL is too busy and a new one should be fetched.
+    pub fn is_service_unavailable(&self) -> bool {
+        self.request_error()
+            .is_some_and(|error| error.status.get() == 503)
+    }
+
+    /// The `Retry-After` the server responded with, if any.
+    pub fn retry_after(&self) -> Option<Duration> {
+        self.request_error().and_then(|error| error.retry_after)
+    }
+
+    /// Whether the call may have taken effect even though it failed, so repeating it can do it twice:
+    /// the request failed after it could have reached B2, or B2 responded with a 5xx.
+    pub fn is_ambiguous(&self) -> bool {
+        match self {
+            Self::RequestSendError(error) => !error.is_connect() && !error.is_builder(),
+            error => error
+                .request_error()
+                .is_some_and(|error| error.status.get() >= 500),
+        }
+    }
+
+    /// Whether trying again can succeed, when the request failed to send or B2 responded with a 429 or a 5xx.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            Self::RequestSendError(_) => true,
+            error => error
+                .request_error()
+                .is_some_and(|error| error.status.get() == 429 || error.status.get() >= 500),
+        }
+    }
+}
+
 impl fmt::Display for B2Error {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "B2 request encountered an error, ")?;
@@ -24,11 +111,20 @@ impl fmt::Display for B2Error {
         match self {
             Self::JsonParseError(err) => write!(f, "Failed to parse JSON: {}", err),
             Self::RequestError(err) => write!(f, "Request returned an error: {}", err),
+            Self::FileNotFound(err) => write!(f, "File not found: {}", err),
+            Self::BucketNotFound(err) => write!(f, "Bucket not found: {}", err),
+            Self::DuplicateBucketName(err) => write!(f, "Bucket name already taken: {}", err),
             Self::RequestSendError(err) => write!(f, "Failed to send request: {}", err),
+            Self::HttpClientBuildError(err) => write!(f, "Failed to build HTTP client: {}", err),
             Self::MissingCapability(capability) => {
                 write!(f, "Client is missing capability: {}", capability)
             }
             Self::InvalidHeaders(err) => write!(f, "Invalid headers passed: {}", err),
+            Self::InvalidResponseHeader(err) => write!(f, "Invalid response header: {}", err),
+            Self::InvalidValue(err) => write!(f, "{}", err),
+            Self::ListingCapReached(cost) => {
+                write!(f, "Listing stopped at its cap after {}", cost)
+            }
         }
     }
 }
@@ -59,6 +155,38 @@ impl fmt::Display for IntoHeaderMapError {
     }
 }
 
+/// A header of a B2 response that can't be used.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ResponseHeaderError {
+    Missing(String),
+    Invalid { name: String, value: String },
+}
+
+impl Error for ResponseHeaderError {}
+
+impl fmt::Display for ResponseHeaderError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Missing(name) => write!(f, "[{}] is missing.", name),
+            Self::Invalid { name, value } => {
+                write!(f, "[{}] has an invalid value [{}].", name, value)
+            }
+        }
+    }
+}
+
+impl From<ResponseHeaderError> for B2Error {
+    fn from(error: ResponseHeaderError) -> Self {
+        B2Error::InvalidResponseHeader(error)
+    }
+}
+
+impl From<InvalidValue> for B2Error {
+    fn from(error: InvalidValue) -> Self {
+        B2Error::InvalidValue(error)
+    }
+}
+
 impl From<IntoHeaderMapError> for B2Error {
     fn from(error: IntoHeaderMapError) -> Self {
         B2Error::InvalidHeaders(error)
@@ -70,6 +198,9 @@ pub struct B2RequestError {
     pub status: NonZeroU16,
     pub code: String,
     pub message: Option<String>,
+    /// Parsed from the `Retry-After` response header, B2 sends it with some 429 and 503 responses.
+    #[serde(skip)]
+    pub retry_after: Option<Duration>,
 }
 
 impl fmt::Display for B2RequestError {
@@ -10,6 +10,33 @@
 //! - Auto re-auth with Backblaze B2.
 //! - Easy file upload handler.
 //! - Mapped all b2 storage request in simple client.
+//! - `signals` feature: abort tracked uploads on SIGINT/SIGTERM with `B2Client::abort_on_signals`.
+//! - `tracing` feature: spans and events through [tracing] for every API call, retry and reauthorization, with B2's `x-bz-request-id`, status codes and byte counts.
+//! - `capability-checks` feature (enabled by default): fail calls the key has no capability for without sending them.
+//! - `test-util` feature: `mock::MockB2Client`, a test double of the `api::B2Api` trait `B2SimpleClient` implements, and the internals the benches use.
+//! - TLS backend features: `default-tls` (enabled by default), `native-tls` and `rustls-tls`, for `rustls` disable the default features, see [the breaking change](https://github.com/SalahaldinBilal/backblaze-b2-client-rs#breaking-change-tls-features).
+//!
+//! ## Runtimes
+//!
+//! The clients and tasks run on Tokio. [B2SimpleClient](simple_client::B2SimpleClient) can also be awaited from other executors,
+//! like async-std, by driving its requests on a Tokio runtime kept in the background, see
+//! [with_runtime](simple_client::B2SimpleClient::with_runtime):
+//!
+//! ```rust,no_run
+//! use backblaze_b2_client::{simple_client::B2SimpleClientBuilder, util::TokioRuntime};
+//!
+//! let tokio = tokio::runtime::Runtime::new().unwrap();
+//!
+//! futures::executor::block_on(async {
+//!     let client = B2SimpleClientBuilder::new()
+//!         .with_runtime(TokioRuntime::shared(tokio.handle().clone()))
+//!         .build("your_key_id", "your_application_key")
+//!         .await
+//!         .unwrap();
+//!
+//!     println!("authorized {}", client.account_id());
+//! });
+//! ```
 //!
 //! ## Installation
 //!
@@ -23,19 +50,23 @@
 //!
 //! ### File Upload
 //!
-//! ```rust
-//! use backblaze_b2_client::B2Client;
-//! use tokio::fs::File;
+//! ```rust,no_run
+//! use std::time::Duration;
+//!
+//! use backblaze_b2_client::client::B2Client;
+//! use tokio::{fs::File, time::sleep};
 //!
 //! #[tokio::main]
-//! fn main() {
-//!     let client = B2Client::new("your_account_id", "your_application_key");
+//! async fn main() {
+//!     let client = B2Client::new("your_key_id".into(), "your_application_key".into())
+//!         .await
+//!         .unwrap();
 //!   
 //!     let file = File::open("path_to_file").await.unwrap();
 //!
-//!     let metadata = open_file.metadata().await.unwrap();
+//!     let metadata = file.metadata().await.unwrap();
 //!
-//!     let upload = client.create_upload(
+//!     let file_handle = client.create_upload(
 //!         file,
 //!         "file_name".into(),
 //!         "bucket_id".into(),
@@ -59,17 +90,37 @@
 //!     });
 //!
 //!     // Starts the file upload and waits for it to finish
-//!     let file = upload.start().await.unwrap();
+//!     let file = file_handle.start().await.unwrap();
 //!
 //!     println!("{:#?}", file);
 //! }
 //! ```
+pub mod api;
+pub mod batch;
+pub mod bucket;
+pub mod cas;
 pub mod client;
 pub mod definitions;
+pub mod doctor;
+pub mod download_authorizer;
+pub mod endpoint_stats;
 pub mod error;
+#[cfg(feature = "test-util")]
+pub mod mock;
+pub mod naming;
+pub mod notifications;
+pub mod pagination;
+pub mod prefix_size;
+#[cfg(feature = "prelude")]
+pub mod prelude;
+pub mod retention;
+#[cfg(feature = "signals")]
+pub mod signals;
 pub mod simple_client;
+pub mod sync;
 pub mod tasks;
 pub mod throttle;
+pub mod usage;
 pub mod util;
 
 pub use reqwest;
@@ -0,0 +1,463 @@
+//! [MockB2Client], a [B2Api] test double answering calls with canned responses, behind the `test-util` feature.
+
+use std::{
+    collections::HashMap,
+    fmt,
+    num::NonZeroU16,
+    sync::{Arc, Mutex},
+};
+
+use bytes::Bytes;
+use futures::future::{self, BoxFuture};
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::{json, Value};
+
+use crate::{
+    api::B2Api,
+    definitions::{
+        bodies::{
+            B2CopyFileBody, B2CopyPartBody, B2CreateBucketBody, B2CreateKeyBody,
+            B2DeleteFileVersionBody, B2FinishLargeFileBody, B2GetDownloadAuthorizationBody,
+            B2ListBucketsBody, B2StartLargeFileUploadBody, B2UpdateBucketBody,
+            B2UpdateFileLegalHoldBodyResponse, B2UpdateFileRetentionBody,
+        },
+        headers::{B2UploadFileHeaders, B2UploadPartHeaders},
+        query_params::{
+            B2DownloadFileQueryParameters, B2ListFileNamesQueryParameters,
+            B2ListFileVersionsQueryParameters, B2ListKeysParameters, B2ListPartsQueryParameters,
+            B2ListUnfinishedLargeFilesQueryParameters,
+        },
+        responses::{
+            B2AuthData, B2BucketNotificationRulesResponseBody, B2CancelLargeFileResponse,
+            B2DeleteFileVersionResponse, B2FilePart, B2GetDownloadAuthorizationBodyResponse,
+            B2GetUploadPartUrlResponse, B2GetUploadUrlResponse, B2ListBucketsResponse,
+            B2ListFileVersionsResponse, B2ListFilesResponse, B2ListKeysResponse,
+            B2ListPartsResponse, B2ListUnfinishedLargeFilesResponse, B2UpdateFileRetentionResponse,
+        },
+        shared::{
+            B2AppKey, B2Bucket, B2DownloadFileContent, B2Endpoint, B2File, B2FileDownloadDetails,
+        },
+    },
+    error::{B2Error, B2RequestError},
+    util::B2FileStream,
+};
+
+type MockHandler = Box<dyn Fn(Value) -> Result<Value, B2Error> + Send + Sync>;
+type MockDownloadHandler =
+    Box<dyn Fn(Value) -> Result<(B2FileDownloadDetails, Bytes), B2Error> + Send + Sync>;
+
+/// A call made to a [MockB2Client].
+#[derive(Debug, Clone, PartialEq)]
+pub struct MockCall {
+    pub endpoint: B2Endpoint,
+    /// The call's arguments as JSON, the body or query parameters as B2 would get them,
+    /// with the arguments of calls that take several under their names, like `{"bucketId": ..., "fileName": ...}`.
+    pub request: Value,
+}
+
+/// A [B2Api] that answers calls with the handlers it was given instead of calling B2, and records every call.
+/// <br> Handlers get a call's [request](MockCall::request) and return the response as JSON, which is read into the call's response type.
+/// Calls to an endpoint without a handler fail with a 501 `not_mocked` [request error](B2Error::RequestError).
+/// ```rust
+/// # use backblaze_b2_client::{api::B2Api, definitions::shared::B2Endpoint, mock::MockB2Client};
+/// # use serde_json::json;
+/// # tokio::runtime::Runtime::new().unwrap().block_on(async {
+/// let mock = MockB2Client::new().with_handler(B2Endpoint::B2GetUploadUrl, |request| {
+///     Ok(json!({
+///         "bucketId": request["bucketId"],
+///         "uploadUrl": "https://pod-000-1005-03.backblaze.com/b2api/v3/b2_upload_file",
+///         "authorizationToken": "token"
+///     }))
+/// });
+///
+/// let api: &dyn B2Api = &mock;
+/// let upload_url = api.get_upload_url("bucket".into()).await.unwrap();
+///
+/// assert_eq!(upload_url.bucket_id, "bucket");
+/// assert_eq!(mock.calls_to(B2Endpoint::B2GetUploadUrl).len(), 1);
+/// assert!(api.get_file_info("file".into()).await.is_err());
+/// # });
+/// ```
+pub struct MockB2Client {
+    account_id: String,
+    handlers: HashMap<B2Endpoint, MockHandler>,
+    download_handler: Option<MockDownloadHandler>,
+    calls: Arc<Mutex<Vec<MockCall>>>,
+}
+
+impl fmt::Debug for MockB2Client {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MockB2Client")
+            .field("account_id", &self.account_id)
+            .field("handlers", &self.handlers.keys().collect::<Vec<_>>())
+            .field("download_handler", &self.download_handler.is_some())
+            .field("calls", &self.calls)
+            .finish()
+    }
+}
+
+impl Default for MockB2Client {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MockB2Client {
+    /// A mock with no handlers and the account ID `mock_account`.
+    pub fn new() -> Self {
+        Self {
+            account_id: "mock_account".into(),
+            handlers: HashMap::new(),
+            download_handler: None,
+            calls: Arc::new(Mutex::new(vec![])),
+        }
+    }
+
+    /// The account ID returned by [account_id](B2Api::account_id).
+    pub fn with_account_id<S: Into<String>>(mut self, account_id: S) -> Self {
+        self.account_id = account_id.into();
+        self
+    }
+
+    /// Answers calls to the endpoint with the handler, replacing the endpoint's previous handler.
+    /// <br> Downloads are answered by the [download handler](MockB2Client::with_download_handler) instead.
+    pub fn with_handler<F>(mut self, endpoint: B2Endpoint, handler: F) -> Self
+    where
+        F: Fn(Value) -> Result<Value, B2Error> + Send + Sync + 'static,
+    {
+        self.handlers.insert(endpoint, Box::new(handler));
+        self
+    }
+
+    /// Answers every call to the endpoint with the same response.
+    pub fn with_response<T: Serialize>(self, endpoint: B2Endpoint, response: T) -> Self {
+        let response = serde_json::to_value(response).expect("Mock response is serializable");
+
+        self.with_handler(endpoint, move |_| Ok(response.clone()))
+    }
+
+    /// Answers both download endpoints with the file's details and content.
+    pub fn with_download_handler<F>(mut self, handler: F) -> Self
+    where
+        F: Fn(Value) -> Result<(B2FileDownloadDetails, Bytes), B2Error> + Send + Sync + 'static,
+    {
+        self.download_handler = Some(Box::new(handler));
+        self
+    }
+
+    /// Every call made so far, in order.
+    pub fn calls(&self) -> Vec<MockCall> {
+        self.calls.lock().expect("Calls lock is poisoned").clone()
+    }
+
+    /// The requests of the calls made to the endpoint so far, in order.
+    pub fn calls_to(&self, endpoint: B2Endpoint) -> Vec<Value> {
+        self.calls
+            .lock()
+            .expect("Calls lock is poisoned")
+            .iter()
+            .filter(|call| call.endpoint == endpoint)
+            .map(|call| call.request.clone())
+            .collect()
+    }
+
+    fn record(&self, endpoint: B2Endpoint, request: Value) {
+        self.calls
+            .lock()
+            .expect("Calls lock is poisoned")
+            .push(MockCall { endpoint, request });
+    }
+
+    fn not_mocked(endpoint: &B2Endpoint) -> B2Error {
+        B2Error::RequestError(B2RequestError {
+            status: NonZeroU16::new(501).expect("501 is not zero"),
+            code: "not_mocked".into(),
+            message: Some(format!("MockB2Client has no handler for {}", endpoint)),
+            retry_after: None,
+        })
+    }
+
+    fn call<T: Serialize, R: DeserializeOwned + Send + 'static>(
+        &self,
+        endpoint: B2Endpoint,
+        request: T,
+    ) -> BoxFuture<'static, Result<R, B2Error>> {
+        let result = serde_json::to_value(request)
+            .map_err(B2Error::JsonParseError)
+            .and_then(|request| {
+                self.record(endpoint.clone(), request.clone());
+
+                let handler = self
+                    .handlers
+                    .get(&endpoint)
+                    .ok_or_else(|| MockB2Client::not_mocked(&endpoint))?;
+
+                serde_json::from_value(handler(request)?).map_err(B2Error::JsonParseError)
+            });
+
+        Box::pin(future::ready(result))
+    }
+
+    fn download(
+        &self,
+        endpoint: B2Endpoint,
+        request: Value,
+    ) -> BoxFuture<'static, Result<B2DownloadFileContent, B2Error>> {
+        self.record(endpoint.clone(), request.clone());
+
+        let result = match &self.download_handler {
+            Some(handler) => handler(request).map(|(file_details, content)| {
+                let size = content.len();
+
+                B2DownloadFileContent {
+                    file: B2FileStream::new(futures::stream::once(future::ok(content)), size),
+                    file_details,
+                    remaining_headers: HashMap::new(),
+                }
+            }),
+            None => Err(MockB2Client::not_mocked(&endpoint)),
+        };
+
+        Box::pin(future::ready(result))
+    }
+}
+
+impl B2Api for MockB2Client {
+    fn account_id(&self) -> &str {
+        &self.account_id
+    }
+
+    fn authorize_account<'a>(
+        &'a self,
+        key_id: &'a str,
+        _application_key: &'a str,
+    ) -> BoxFuture<'a, Result<B2AuthData, B2Error>> {
+        // The application key is left out, so it can't end up in test output
+        self.call(B2Endpoint::B2AuthorizeAccount, json!({ "keyId": key_id }))
+    }
+
+    fn cancel_large_file(
+        &self,
+        file_id: String,
+    ) -> BoxFuture<'_, Result<B2CancelLargeFileResponse, B2Error>> {
+        self.call(B2Endpoint::B2CancelLargeFile, json!({ "fileId": file_id }))
+    }
+
+    fn copy_file(&self, body: B2CopyFileBody) -> BoxFuture<'_, Result<B2File, B2Error>> {
+        self.call(B2Endpoint::B2CopyFile, body)
+    }
+
+    fn copy_part(&self, body: B2CopyPartBody) -> BoxFuture<'_, Result<B2FilePart, B2Error>> {
+        self.call(B2Endpoint::B2CopyPart, body)
+    }
+
+    fn create_bucket(&self, body: B2CreateBucketBody) -> BoxFuture<'_, Result<B2Bucket, B2Error>> {
+        self.call(B2Endpoint::B2CreateBucket, body)
+    }
+
+    fn create_key(&self, body: B2CreateKeyBody) -> BoxFuture<'_, Result<B2AppKey, B2Error>> {
+        self.call(B2Endpoint::B2CreateKey, body)
+    }
+
+    fn delete_bucket(
+        &self,
+        account_id: String,
+        bucket_id: String,
+    ) -> BoxFuture<'_, Result<B2Bucket, B2Error>> {
+        self.call(
+            B2Endpoint::B2DeleteBucket,
+            json!({ "accountId": account_id, "bucketId": bucket_id }),
+        )
+    }
+
+    fn delete_file_version(
+        &self,
+        body: B2DeleteFileVersionBody,
+    ) -> BoxFuture<'_, Result<B2DeleteFileVersionResponse, B2Error>> {
+        self.call(B2Endpoint::B2DeleteFileVersion, body)
+    }
+
+    fn delete_key(&self, application_key_id: String) -> BoxFuture<'_, Result<B2AppKey, B2Error>> {
+        self.call(
+            B2Endpoint::B2DeleteKey,
+            json!({ "applicationKeyId": application_key_id }),
+        )
+    }
+
+    fn download_file_by_id(
+        &self,
+        file_id: String,
+        query: Option<B2DownloadFileQueryParameters>,
+    ) -> BoxFuture<'_, Result<B2DownloadFileContent, B2Error>> {
+        self.download(
+            B2Endpoint::B2DownloadFileById,
+            json!({ "fileId": file_id, "query": query }),
+        )
+    }
+
+    fn download_file_by_name(
+        &self,
+        bucket_name: String,
+        file_name: String,
+        query: Option<B2DownloadFileQueryParameters>,
+    ) -> BoxFuture<'_, Result<B2DownloadFileContent, B2Error>> {
+        self.download(
+            B2Endpoint::B2DownloadFileByName,
+            json!({ "bucketName": bucket_name, "fileName": file_name, "query": query }),
+        )
+    }
+
+    fn finish_large_file(
+        &self,
+        body: B2FinishLargeFileBody,
+    ) -> BoxFuture<'_, Result<B2File, B2Error>> {
+        self.call(B2Endpoint::B2FinishLargeFile, body)
+    }
+
+    fn get_bucket_notification_rules(
+        &self,
+        bucket_id: String,
+    ) -> BoxFuture<'_, Result<B2BucketNotificationRulesResponseBody, B2Error>> {
+        self.call(
+            B2Endpoint::B2GetBucketNotificationRules,
+            json!({ "bucketId": bucket_id }),
+        )
+    }
+
+    fn get_download_authorization(
+        &self,
+        body: B2GetDownloadAuthorizationBody,
+    ) -> BoxFuture<'_, Result<B2GetDownloadAuthorizationBodyResponse, B2Error>> {
+        self.call(B2Endpoint::B2GetDownloadAuthorization, body)
+    }
+
+    fn get_file_info(&self, file_id: String) -> BoxFuture<'_, Result<B2File, B2Error>> {
+        self.call(B2Endpoint::B2GetFileInfo, json!({ "fileId": file_id }))
+    }
+
+    fn get_upload_part_url(
+        &self,
+        file_id: String,
+    ) -> BoxFuture<'_, Result<B2GetUploadPartUrlResponse, B2Error>> {
+        self.call(B2Endpoint::B2GetUploadPartUrl, json!({ "fileId": file_id }))
+    }
+
+    fn get_upload_url(
+        &self,
+        bucket_id: String,
+    ) -> BoxFuture<'_, Result<B2GetUploadUrlResponse, B2Error>> {
+        self.call(B2Endpoint::B2GetUploadUrl, json!({ "bucketId": bucket_id }))
+    }
+
+    fn hide_file(
+        &self,
+        bucket_id: String,
+        file_name: String,
+    ) -> BoxFuture<'_, Result<B2File, B2Error>> {
+        self.call(
+            B2Endpoint::B2HideFile,
+            json!({ "bucketId": bucket_id, "fileName": file_name }),
+        )
+    }
+
+    fn list_buckets(
+        &self,
+        body: B2ListBucketsBody,
+    ) -> BoxFuture<'_, Result<B2ListBucketsResponse, B2Error>> {
+        self.call(B2Endpoint::B2ListBuckets, body)
+    }
+
+    fn list_file_names(
+        &self,
+        query: B2ListFileNamesQueryParameters,
+    ) -> BoxFuture<'_, Result<B2ListFilesResponse, B2Error>> {
+        self.call(B2Endpoint::B2ListFileNames, query)
+    }
+
+    fn list_file_versions(
+        &self,
+        query: B2ListFileVersionsQueryParameters,
+    ) -> BoxFuture<'_, Result<B2ListFileVersionsResponse, B2Error>> {
+        self.call(B2Endpoint::B2ListFileVersions, query)
+    }
+
+    fn list_keys(
+        &self,
+        query: B2ListKeysParameters,
+    ) -> BoxFuture<'_, Result<B2ListKeysResponse, B2Error>> {
+        self.call(B2Endpoint::B2ListKeys, query)
+    }
+
+    fn list_parts(
+        &self,
+        query: B2ListPartsQueryParameters,
+    ) -> BoxFuture<'_, Result<B2ListPartsResponse, B2Error>> {
+        self.call(B2Endpoint::B2ListParts, query)
+    }
+
+    fn list_unfinished_large_files(
+        &self,
+        query: B2ListUnfinishedLargeFilesQueryParameters,
+    ) -> BoxFuture<'_, Result<B2ListUnfinishedLargeFilesResponse, B2Error>> {
+        self.call(B2Endpoint::B2ListUnfinishedLargeFiles, query)
+    }
+
+    fn set_bucket_notification_rules(
+        &self,
+        body: B2BucketNotificationRulesResponseBody,
+    ) -> BoxFuture<'_, Result<B2BucketNotificationRulesResponseBody, B2Error>> {
+        self.call(B2Endpoint::B2SetBucketNotificationRules, body)
+    }
+
+    fn start_large_file(
+        &self,
+        body: B2StartLargeFileUploadBody,
+    ) -> BoxFuture<'_, Result<B2File, B2Error>> {
+        self.call(B2Endpoint::B2StartLargeFile, body)
+    }
+
+    fn update_bucket(&self, body: B2UpdateBucketBody) -> BoxFuture<'_, Result<B2Bucket, B2Error>> {
+        self.call(B2Endpoint::B2UpdateBucket, body)
+    }
+
+    fn update_file_legal_hold(
+        &self,
+        body: B2UpdateFileLegalHoldBodyResponse,
+    ) -> BoxFuture<'_, Result<B2UpdateFileLegalHoldBodyResponse, B2Error>> {
+        self.call(B2Endpoint::B2UpdateFileLegalHold, body)
+    }
+
+    fn update_file_retention(
+        &self,
+        body: B2UpdateFileRetentionBody,
+    ) -> BoxFuture<'_, Result<B2UpdateFileRetentionResponse, B2Error>> {
+        self.call(B2Endpoint::B2UpdateFileRetention, body)
+    }
+
+    /// The file's content isn't read, the request holds the upload URL, headers and file info.
+    fn upload_file(
+        &self,
+        _file: reqwest::Body,
+        upload_url: String,
+        headers: B2UploadFileHeaders,
+        file_info: Option<HashMap<String, String>>,
+    ) -> BoxFuture<'_, Result<B2File, B2Error>> {
+        self.call(
+            B2Endpoint::B2UploadFile,
+            json!({ "uploadUrl": upload_url, "headers": headers, "fileInfo": file_info }),
+        )
+    }
+
+    /// The part's content isn't read, the request holds the upload URL and headers.
+    fn upload_part(
+        &self,
+        headers: B2UploadPartHeaders,
+        _part: reqwest::Body,
+        upload_url: String,
+    ) -> BoxFuture<'_, Result<B2FilePart, B2Error>> {
+        self.call(
+            B2Endpoint::B2UploadPart,
+            json!({ "uploadUrl": upload_url, "headers": headers }),
+        )
+    }
+}
@@ -0,0 +1,176 @@
+//! Strategies for the file names uploads are stored under, so an app's key layout lives in one place, refer to [KeyNamingStrategy].
+
+use std::{fmt::Debug, sync::Arc, time::SystemTime};
+
+use sha1_smol::Sha1;
+
+use crate::util::{Clock, SystemClock};
+
+/// Turns the file name an upload was given into the name it's stored under.
+/// <br> Applied by [B2Client](crate::client::B2Client)'s upload helpers, refer to [with_naming_strategy](crate::client::B2Client::with_naming_strategy),
+/// and by [sync_up](crate::sync::sync_up) when set in its [options](crate::sync::SyncUpOptions::naming). Syncs compare local files against the names
+/// the strategy gives them, so a strategy used for syncing has to give a file the same name every time, unlike [DatePrefixNaming].
+/// ```rust
+/// # use std::sync::Arc;
+/// # use backblaze_b2_client::naming::{HashPrefixNaming, KeyNamingStrategy, NamingChain, SanitizeNaming};
+/// let naming = NamingChain(vec![
+///     Arc::new(SanitizeNaming::default()),
+///     Arc::new(HashPrefixNaming { length: 2 }),
+/// ]);
+///
+/// assert_eq!(SanitizeNaming::default().file_name("/logs//a\tb.txt/"), "logs/a_b.txt");
+/// assert_eq!(naming.file_name("/logs//a\tb.txt/"), naming.file_name("logs/a_b.txt"));
+/// assert!(naming.file_name("logs/a_b.txt").ends_with("/logs/a_b.txt"));
+/// ```
+pub trait KeyNamingStrategy: Debug + Send + Sync {
+    fn file_name(&self, name: &str) -> String;
+}
+
+/// Prefixes names with the current UTC date, like `2024/05/30/name`.
+/// ```rust
+/// # use std::{sync::Arc, time::{Duration, SystemTime}};
+/// # use backblaze_b2_client::{naming::{DatePrefixNaming, KeyNamingStrategy}, util::ManualClock};
+/// let clock = ManualClock::starting_at(SystemTime::UNIX_EPOCH + Duration::from_secs(1_717_027_200));
+/// let naming = DatePrefixNaming { clock: Arc::new(clock) };
+///
+/// assert_eq!(naming.file_name("a.txt"), "2024/05/30/a.txt");
+/// ```
+#[derive(Debug, Clone)]
+pub struct DatePrefixNaming {
+    /// Clock the date is read from.
+    /// <br> Default is [SystemClock].
+    pub clock: Arc<dyn Clock>,
+}
+
+impl Default for DatePrefixNaming {
+    fn default() -> Self {
+        Self {
+            clock: SystemClock::shared(),
+        }
+    }
+}
+
+impl KeyNamingStrategy for DatePrefixNaming {
+    fn file_name(&self, name: &str) -> String {
+        let days = self
+            .clock
+            .system_time()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .map(|since_epoch| since_epoch.as_secs() / 86_400)
+            .unwrap_or(0);
+
+        let (year, month, day) = civil_from_days(days as i64);
+
+        format!("{:04}/{:02}/{:02}/{}", year, month, day, name)
+    }
+}
+
+/// Prefixes names with the first characters of their SHA1, like `3f/name`, spreading files evenly over prefixes
+/// so listings of a prefix stay small.
+#[derive(Debug, Clone, Copy)]
+pub struct HashPrefixNaming {
+    /// Hex characters of the SHA1 used, up to 40.
+    /// <br> Default is 2.
+    pub length: usize,
+}
+
+impl Default for HashPrefixNaming {
+    fn default() -> Self {
+        Self { length: 2 }
+    }
+}
+
+impl KeyNamingStrategy for HashPrefixNaming {
+    fn file_name(&self, name: &str) -> String {
+        let sha1 = Sha1::from(name).digest().to_string();
+
+        format!("{}/{}", &sha1[..self.length.min(sha1.len())], name)
+    }
+}
+
+/// Fixes names B2 would reject, replacing control characters, and dropping empty path segments and leading or trailing slashes.
+/// <br> Backslashes can be turned into slashes, for names built from Windows paths. Names are cut to B2's limit of 1024 bytes.
+#[derive(Debug, Clone, Copy)]
+pub struct SanitizeNaming {
+    /// Replaces every control character.
+    /// <br> Default is `_`.
+    pub replacement: char,
+    /// Turns backslashes into slashes.
+    /// <br> Default is false.
+    pub backslash_as_separator: bool,
+}
+
+impl Default for SanitizeNaming {
+    fn default() -> Self {
+        Self {
+            replacement: '_',
+            backslash_as_separator: false,
+        }
+    }
+}
+
+/// B2 rejects file names longer than this many bytes.
+const MAX_FILE_NAME_BYTES: usize = 1024;
+
+impl KeyNamingStrategy for SanitizeNaming {
+    fn file_name(&self, name: &str) -> String {
+        let replaced: String = name
+            .chars()
+            .map(|char| match char {
+                '\\' if self.backslash_as_separator => '/',
+                char if char.is_control() => self.replacement,
+                char => char,
+            })
+            .collect();
+
+        let mut sanitized = replaced
+            .split('/')
+            .filter(|segment| !segment.is_empty())
+            .collect::<Vec<_>>()
+            .join("/");
+
+        if sanitized.len() > MAX_FILE_NAME_BYTES {
+            let mut end = MAX_FILE_NAME_BYTES;
+
+            while !sanitized.is_char_boundary(end) {
+                end -= 1;
+            }
+
+            sanitized.truncate(end);
+        }
+
+        sanitized
+    }
+}
+
+/// Applies the strategies in order, each to the name the previous one gave.
+#[derive(Debug, Clone, Default)]
+pub struct NamingChain(pub Vec<Arc<dyn KeyNamingStrategy>>);
+
+impl KeyNamingStrategy for NamingChain {
+    fn file_name(&self, name: &str) -> String {
+        self.0
+            .iter()
+            .fold(name.to_string(), |name, strategy| strategy.file_name(&name))
+    }
+}
+
+/// The proleptic Gregorian date of a day counted from 1970-01-01.
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * month_index + 2) / 5 + 1) as u32;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    } as u32;
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+    (year, month, day)
+}
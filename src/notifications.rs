@@ -0,0 +1,243 @@
+//! Signing and verification of [event notification](https://www.backblaze.com/docs/cloud-storage-event-notifications) deliveries.
+//!
+//! B2 signs every delivery with the rule's signing secret, as an HMAC-SHA256 of the raw request body
+//! sent in the [SIGNATURE_HEADER] header as `v1=<hex digest>`.
+//!
+//! A signature doesn't expire, so [verify] accepts a captured delivery sent again. [DeliveryVerifier] also rejects
+//! deliveries with events that are too old or were already delivered, by their `eventTimestamp` and `eventId`.
+
+use std::{
+    collections::HashMap,
+    error::Error,
+    fmt,
+    sync::{Arc, Mutex, MutexGuard},
+    time::{Duration, SystemTime},
+};
+
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+
+use crate::util::{Clock, SystemClock};
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Header B2 sends the delivery signature in.
+pub const SIGNATURE_HEADER: &str = "x-bz-event-notification-signature";
+
+const SIGNATURE_VERSION: &str = "v1=";
+
+/// Produces the [SIGNATURE_HEADER] value for a body, the same way B2 signs its deliveries.
+/// <br> Useful for relays and for sending test deliveries to your own endpoint.
+pub fn sign<S: AsRef<[u8]>, B: AsRef<[u8]>>(secret: S, body: B) -> String {
+    let digest = signature_mac(secret.as_ref(), body.as_ref())
+        .finalize()
+        .into_bytes();
+
+    let hex: String = digest.iter().map(|byte| format!("{:02x}", byte)).collect();
+
+    format!("{}{}", SIGNATURE_VERSION, hex)
+}
+
+/// Whether a [SIGNATURE_HEADER] value is a valid signature of the body.
+/// <br> The header may hold several comma separated signatures, any valid `v1` one is enough.
+/// Digests are compared in constant time.
+/// <br> Replayed deliveries are accepted, use a [DeliveryVerifier] to reject them.
+pub fn verify<S: AsRef<[u8]>, B: AsRef<[u8]>>(secret: S, body: B, header_value: &str) -> bool {
+    header_value
+        .split(',')
+        .filter_map(|signature| signature.trim().strip_prefix(SIGNATURE_VERSION))
+        .filter_map(decode_hex)
+        .any(|digest| {
+            signature_mac(secret.as_ref(), body.as_ref())
+                .verify_slice(&digest)
+                .is_ok()
+        })
+}
+
+/// An event of a notification delivery.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct B2NotificationEvent {
+    pub account_id: String,
+    pub bucket_id: String,
+    pub bucket_name: String,
+    /// Unique to the event, the same in every delivery of it.
+    pub event_id: String,
+    /// When the event happened, in milliseconds since the epoch.
+    pub event_timestamp: u64,
+    pub event_type: String,
+    pub event_version: u32,
+    pub matched_rule_name: String,
+    /// Not set for test events.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub object_name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub object_size: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub object_version_id: Option<String>,
+}
+
+/// Body of a notification delivery.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct B2NotificationDelivery {
+    pub events: Vec<B2NotificationEvent>,
+}
+
+/// Why a [DeliveryVerifier] rejected a delivery.
+#[derive(Debug)]
+pub enum DeliveryError {
+    /// No signature in the header is valid for the body.
+    InvalidSignature,
+    /// The body isn't a delivery.
+    InvalidBody(serde_json::Error),
+    /// An event's timestamp is further than [max_age](DeliveryVerifier::new) from now.
+    Stale {
+        event_id: String,
+        event_timestamp: u64,
+    },
+    /// An event was already delivered.
+    Replayed { event_id: String },
+}
+
+impl Error for DeliveryError {}
+
+impl fmt::Display for DeliveryError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::InvalidSignature => write!(f, "Delivery signature is invalid."),
+            Self::InvalidBody(err) => write!(f, "Delivery body is invalid: {}", err),
+            Self::Stale {
+                event_id,
+                event_timestamp,
+            } => write!(
+                f,
+                "Event [{}] from {} is too old or too far in the future.",
+                event_id, event_timestamp
+            ),
+            Self::Replayed { event_id } => write!(f, "Event [{}] was already delivered.", event_id),
+        }
+    }
+}
+
+/// Verifies deliveries like [verify], and rejects replays: deliveries with an event whose timestamp isn't within `max_age`
+/// of now, or whose ID was already accepted. IDs are remembered for `max_age`, older events are rejected as stale anyway.
+/// <br> B2 delivers an event again when the endpoint doesn't respond with a success, so a delivery that was accepted but
+/// failed to be handled has to be [forgotten](DeliveryVerifier::forget), or its redelivery is rejected as a replay.
+/// ```rust
+/// # use std::time::{Duration, SystemTime, UNIX_EPOCH};
+/// # use backblaze_b2_client::notifications::{sign, DeliveryError, DeliveryVerifier};
+/// let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_millis();
+/// let body = format!(
+///     r#"{{"events":[{{"accountId":"a","bucketId":"b","bucketName":"c","eventId":"e1","eventTimestamp":{},
+///     "eventType":"b2:ObjectCreated:Upload","eventVersion":1,"matchedRuleName":"r","objectName":"f.txt"}}]}}"#,
+///     now
+/// );
+/// let signature = sign("secret", &body);
+///
+/// let verifier = DeliveryVerifier::new("secret", Duration::from_secs(300));
+///
+/// assert_eq!(verifier.verify(&body, &signature).unwrap()[0].event_id, "e1");
+/// assert!(matches!(verifier.verify(&body, &signature), Err(DeliveryError::Replayed { .. })));
+/// ```
+#[derive(Debug)]
+pub struct DeliveryVerifier {
+    secret: Vec<u8>,
+    max_age: Duration,
+    clock: Arc<dyn Clock>,
+    /// Accepted event IDs, with their timestamps.
+    seen: Mutex<HashMap<String, u64>>,
+}
+
+impl DeliveryVerifier {
+    pub fn new<S: AsRef<[u8]>>(secret: S, max_age: Duration) -> Self {
+        Self {
+            secret: secret.as_ref().to_vec(),
+            max_age,
+            clock: SystemClock::shared(),
+            seen: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Clock event timestamps are compared against, refer to [Clock].
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Checks the delivery's signature and that none of its events are stale or replayed, returning its events.
+    /// <br> The events are remembered only when the whole delivery is accepted.
+    pub fn verify<B: AsRef<[u8]>>(
+        &self,
+        body: B,
+        header_value: &str,
+    ) -> Result<Vec<B2NotificationEvent>, DeliveryError> {
+        if !verify(&self.secret, body.as_ref(), header_value) {
+            return Err(DeliveryError::InvalidSignature);
+        }
+
+        let delivery: B2NotificationDelivery =
+            serde_json::from_slice(body.as_ref()).map_err(DeliveryError::InvalidBody)?;
+
+        let now = self
+            .clock
+            .system_time()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis() as u64;
+        let max_age = self.max_age.as_millis() as u64;
+
+        let mut seen = self.seen();
+        seen.retain(|_, timestamp| now.abs_diff(*timestamp) <= max_age);
+
+        for event in &delivery.events {
+            if now.abs_diff(event.event_timestamp) > max_age {
+                return Err(DeliveryError::Stale {
+                    event_id: event.event_id.clone(),
+                    event_timestamp: event.event_timestamp,
+                });
+            }
+
+            if seen.contains_key(&event.event_id) {
+                return Err(DeliveryError::Replayed {
+                    event_id: event.event_id.clone(),
+                });
+            }
+        }
+
+        for event in &delivery.events {
+            seen.insert(event.event_id.clone(), event.event_timestamp);
+        }
+
+        Ok(delivery.events)
+    }
+
+    /// Forgets an accepted event, so its redelivery is accepted again.
+    pub fn forget(&self, event_id: &str) {
+        self.seen().remove(event_id);
+    }
+
+    fn seen(&self) -> MutexGuard<'_, HashMap<String, u64>> {
+        match self.seen.lock() {
+            Ok(seen) => seen,
+            Err(poisoned) => poisoned.into_inner(),
+        }
+    }
+}
+
+fn signature_mac(secret: &[u8], body: &[u8]) -> HmacSha256 {
+    let mut mac = HmacSha256::new_from_slice(secret).expect("HMAC accepts keys of any size");
+    mac.update(body);
+    mac
+}
+
+fn decode_hex(hex: &str) -> Option<Vec<u8>> {
+    if hex.len() % 2 != 0 {
+        return None;
+    }
+
+    (0..hex.len())
+        .step_by(2)
+        .map(|index| u8::from_str_radix(hex.get(index..index + 2)?, 16).ok())
+        .collect()
+}
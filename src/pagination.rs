@@ -0,0 +1,171 @@
+use std::{fmt, future::Future};
+
+use async_stream::stream;
+use futures::Stream;
+
+use crate::{error::B2Error, util::B2Callback};
+
+/// What a listing cost so far.
+/// <br> Every list call is a Class C transaction, so `transactions` is what the listing is billed for.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ListingCost {
+    /// List calls made, each one is a Class C transaction.
+    pub transactions: u64,
+    /// Items returned by those calls.
+    pub items: u64,
+}
+
+impl fmt::Display for ListingCost {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} Class C transactions, {} items",
+            self.transactions, self.items
+        )
+    }
+}
+
+/// Caps and cost reporting for listing streams, so an accidental full scan of a huge bucket
+/// stops with [B2Error::ListingCapReached] instead of running up Class C transactions.
+/// <br> The default guard has no caps and no callback.
+#[derive(Default)]
+pub struct ListingGuard {
+    /// Max list calls the listing is allowed to make.
+    /// <br> Default is None.
+    pub max_transactions: Option<u64>,
+    /// Max items the listing is allowed to yield.
+    /// <br> Default is None.
+    pub max_items: Option<u64>,
+    /// Called with the running cost after every list call.
+    /// <br> Default is None.
+    pub on_page: Option<B2Callback<ListingCost>>,
+}
+
+impl ListingGuard {
+    pub fn with_max_transactions(mut self, max_transactions: u64) -> Self {
+        self.max_transactions = Some(max_transactions);
+        self
+    }
+
+    pub fn with_max_items(mut self, max_items: u64) -> Self {
+        self.max_items = Some(max_items);
+        self
+    }
+
+    pub fn with_on_page(mut self, on_page: B2Callback<ListingCost>) -> Self {
+        self.on_page = Some(on_page);
+        self
+    }
+}
+
+impl fmt::Debug for ListingGuard {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ListingGuard")
+            .field("max_transactions", &self.max_transactions)
+            .field("max_items", &self.max_items)
+            .field("on_page", &self.on_page.is_some())
+            .finish()
+    }
+}
+
+/// Tracks a single listing against its [ListingGuard].
+pub(crate) struct ListingTracker {
+    guard: ListingGuard,
+    cost: ListingCost,
+    yielded: u64,
+}
+
+impl ListingTracker {
+    pub(crate) fn new(guard: ListingGuard) -> Self {
+        Self {
+            guard,
+            cost: ListingCost::default(),
+            yielded: 0,
+        }
+    }
+
+    /// Checks that another list call is allowed.
+    pub(crate) fn before_page(&self) -> Result<(), B2Error> {
+        match self.guard.max_transactions {
+            Some(max) if self.cost.transactions >= max => {
+                Err(B2Error::ListingCapReached(self.cost))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Records a finished list call and reports the running cost.
+    pub(crate) async fn after_page(&mut self, items: usize) {
+        self.cost.transactions += 1;
+        self.cost.items += items as u64;
+
+        match &self.guard.on_page {
+            Some(B2Callback::Fn(fun)) => fun(self.cost),
+            Some(B2Callback::AsyncFn(fun)) => fun(self.cost).await,
+            None => {}
+        }
+    }
+
+    /// Checks that another item is allowed to be yielded, counting it if it is.
+    pub(crate) fn take_item(&mut self) -> Result<(), B2Error> {
+        if let Some(max) = self.guard.max_items {
+            if self.yielded >= max {
+                return Err(B2Error::ListingCapReached(self.cost));
+            }
+        }
+
+        self.yielded += 1;
+
+        Ok(())
+    }
+}
+
+/// Streams the items of every page, `fetch_page` returns a page's items and the query for the next page,
+/// None once the listing is done. Stops at the first error, or when the [ListingGuard] stops it.
+pub(crate) fn paginate<'a, Q, T, F, Fut>(
+    query: Q,
+    guard: ListingGuard,
+    fetch_page: F,
+) -> impl Stream<Item = Result<T, B2Error>> + 'a
+where
+    Q: 'a,
+    T: 'a,
+    F: Fn(Q) -> Fut + 'a,
+    Fut: Future<Output = Result<(Vec<T>, Option<Q>), B2Error>> + 'a,
+{
+    stream! {
+        let mut tracker = ListingTracker::new(guard);
+        let mut query = query;
+
+        loop {
+            if let Err(error) = tracker.before_page() {
+                yield Err(error);
+                return;
+            }
+
+            let (items, next_query) = match fetch_page(query).await {
+                Ok(page) => page,
+                Err(error) => {
+                    yield Err(error);
+                    return;
+                }
+            };
+
+            tracker.after_page(items.len()).await;
+
+            for item in items {
+                if let Err(error) = tracker.take_item() {
+                    yield Err(error);
+                    return;
+                }
+
+                yield Ok(item);
+            }
+
+            match next_query {
+                Some(next_query) => query = next_query,
+                None => return,
+            }
+        }
+    }
+}
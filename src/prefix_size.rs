@@ -0,0 +1,250 @@
+//! Counts the files and bytes under a prefix, refer to [prefix_size].
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt::Debug,
+    num::NonZeroU32,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use futures::future::BoxFuture;
+use serde::{Deserialize, Serialize};
+use tokio::sync::Mutex as AsyncMutex;
+
+use crate::{
+    definitions::{query_params::B2ListFileNamesQueryParameters, shared::B2Action},
+    error::B2Error,
+    simple_client::B2SimpleClient,
+    throttle::Throttle,
+    util::{Clock, SystemClock},
+};
+
+/// Files and bytes stored under a prefix, only the latest version of every file counts.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PrefixSize {
+    pub prefix: String,
+    /// Files under the prefix, the ones in folders included.
+    pub files: u64,
+    /// Bytes of those files.
+    pub bytes: u64,
+    /// The folders right under the prefix by their full prefix, each with its own breakdown.
+    /// <br> Only filled with a [delimiter](PrefixSizeOptions::delimiter).
+    pub folders: BTreeMap<String, PrefixSize>,
+}
+
+/// What a [PrefixSize] is cached under.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct PrefixSizeKey {
+    pub bucket_id: String,
+    pub prefix: String,
+    /// Sizes counted without a delimiter don't have a folder breakdown, so they're cached apart.
+    pub delimiter: Option<String>,
+}
+
+/// Keeps computed [PrefixSize]s, so prefixes asked for again aren't scanned again.
+/// <br> With a [delimiter](PrefixSizeOptions::delimiter) every folder is cached on its own, so a folder that's still cached
+/// isn't scanned when its parent is.
+pub trait PrefixSizeCache: Debug + Send + Sync {
+    fn get(&self, key: &PrefixSizeKey) -> Option<PrefixSize>;
+    fn put(&self, key: PrefixSizeKey, size: PrefixSize);
+}
+
+/// Caches sizes in memory, for `max_age` after they were computed.
+/// ```rust
+/// # use std::{sync::Arc, time::Duration};
+/// # use backblaze_b2_client::{prefix_size::*, util::ManualClock};
+/// let clock = Arc::new(ManualClock::new());
+/// let cache = MemoryPrefixSizeCache::with_clock(Duration::from_secs(300), clock.clone());
+/// let key = PrefixSizeKey { bucket_id: "bucket".into(), prefix: "logs/".into(), delimiter: None };
+///
+/// cache.put(key.clone(), PrefixSize { prefix: "logs/".into(), files: 3, bytes: 1024, ..Default::default() });
+/// assert_eq!(cache.get(&key).map(|size| size.bytes), Some(1024));
+///
+/// clock.advance(Duration::from_secs(300));
+/// assert_eq!(cache.get(&key), None);
+/// ```
+#[derive(Debug)]
+pub struct MemoryPrefixSizeCache {
+    max_age: Duration,
+    clock: Arc<dyn Clock>,
+    entries: Mutex<HashMap<PrefixSizeKey, (Instant, PrefixSize)>>,
+}
+
+impl MemoryPrefixSizeCache {
+    pub fn new(max_age: Duration) -> Self {
+        Self::with_clock(max_age, SystemClock::shared())
+    }
+
+    /// Uses the clock to age the entries, refer to [Clock].
+    pub fn with_clock(max_age: Duration, clock: Arc<dyn Clock>) -> Self {
+        Self {
+            max_age,
+            clock,
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Drops every cached size, like after a big upload.
+    pub fn clear(&self) {
+        match self.entries.lock() {
+            Ok(mut entries) => entries.clear(),
+            Err(poisoned) => poisoned.into_inner().clear(),
+        }
+    }
+}
+
+impl PrefixSizeCache for MemoryPrefixSizeCache {
+    fn get(&self, key: &PrefixSizeKey) -> Option<PrefixSize> {
+        let mut entries = match self.entries.lock() {
+            Ok(entries) => entries,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+
+        match entries.get(key) {
+            Some((computed, size)) if self.clock.now() - *computed < self.max_age => {
+                Some(size.clone())
+            }
+            Some(_) => {
+                entries.remove(key);
+                None
+            }
+            None => None,
+        }
+    }
+
+    fn put(&self, key: PrefixSizeKey, size: PrefixSize) {
+        let entry = (self.clock.now(), size);
+
+        match self.entries.lock() {
+            Ok(mut entries) => entries.insert(key, entry),
+            Err(poisoned) => poisoned.into_inner().insert(key, entry),
+        };
+    }
+}
+
+/// Options for [prefix_size].
+#[derive(Debug, Default)]
+pub struct PrefixSizeOptions {
+    /// Breaks the size down per folder, file names are split into folders on it, usually `/`.
+    /// <br> Every folder is listed on its own, which takes more list calls than a flat scan.
+    /// <br> Default is None, which lists every file under the prefix in one go.
+    pub delimiter: Option<String>,
+    /// Limits how many list calls are made per period, can be used as
+    /// ```rust
+    /// # use backblaze_b2_client::throttle::Throttle;
+    /// // At most 10 list calls a second
+    /// let rate_limit = Throttle::per_second(10u64);
+    /// ```
+    /// <br> Default is None.
+    pub rate_limit: Option<Throttle<u64>>,
+    /// Where computed sizes are kept and looked up, refer to [PrefixSizeCache].
+    /// <br> Default is None.
+    pub cache: Option<Arc<dyn PrefixSizeCache>>,
+}
+
+/// Counts the files and bytes under the prefix, paging through [list_file_names](B2SimpleClient::list_file_names),
+/// with an optional per folder breakdown and cache, refer to [PrefixSizeOptions].
+/// <br> Every list call is a Class C transaction, a cache keeps dashboards that refresh often from scanning the same prefixes again.
+pub async fn prefix_size(
+    client: &B2SimpleClient,
+    bucket_id: String,
+    prefix: String,
+    options: &PrefixSizeOptions,
+) -> Result<PrefixSize, B2Error> {
+    let scan = PrefixScan {
+        client,
+        options,
+        rate_limit: options.rate_limit.clone().map(AsyncMutex::new),
+    };
+
+    scan.size(bucket_id, prefix).await
+}
+
+/// A single [prefix_size] run, shares its rate limit between every folder it lists.
+struct PrefixScan<'a> {
+    client: &'a B2SimpleClient,
+    options: &'a PrefixSizeOptions,
+    rate_limit: Option<AsyncMutex<Throttle<u64>>>,
+}
+
+impl PrefixScan<'_> {
+    fn size(
+        &self,
+        bucket_id: String,
+        prefix: String,
+    ) -> BoxFuture<'_, Result<PrefixSize, B2Error>> {
+        Box::pin(async move {
+            let key = PrefixSizeKey {
+                bucket_id: bucket_id.clone(),
+                prefix: prefix.clone(),
+                delimiter: self.options.delimiter.clone(),
+            };
+
+            if let Some(cached) = self
+                .options
+                .cache
+                .as_ref()
+                .and_then(|cache| cache.get(&key))
+            {
+                return Ok(cached);
+            }
+
+            let mut size = PrefixSize {
+                prefix: prefix.clone(),
+                ..Default::default()
+            };
+            let mut folders = vec![];
+            let mut start_file_name = None;
+
+            loop {
+                if let Some(rate_limit) = &self.rate_limit {
+                    rate_limit.lock().await.advance().await;
+                }
+
+                let response = self
+                    .client
+                    .list_file_names(
+                        B2ListFileNamesQueryParameters::builder()
+                            .bucket_id(bucket_id.clone())
+                            .start_file_name(start_file_name)
+                            .max_file_count(NonZeroU32::new(1000))
+                            .prefix((!prefix.is_empty()).then(|| prefix.clone()))
+                            .delimiter(self.options.delimiter.clone())
+                            .build(),
+                    )
+                    .await?;
+
+                for file in response.files {
+                    match file.action {
+                        B2Action::Folder => folders.push(file.file_name),
+                        _ => {
+                            size.files += 1;
+                            size.bytes += file.content_length;
+                        }
+                    }
+                }
+
+                match response.next_file_name {
+                    Some(next_file_name) => start_file_name = Some(next_file_name),
+                    None => break,
+                }
+            }
+
+            for folder in folders {
+                let folder_size = self.size(bucket_id.clone(), folder.clone()).await?;
+
+                size.files += folder_size.files;
+                size.bytes += folder_size.bytes;
+                size.folders.insert(folder, folder_size);
+            }
+
+            if let Some(cache) = &self.options.cache {
+                cache.put(key, size.clone());
+            }
+
+            Ok(size)
+        })
+    }
+}
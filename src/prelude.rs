@@ -0,0 +1,48 @@
+//! Re-exports the commonly used types, so they can be imported with a single
+//! `use backblaze_b2_client::prelude::*;`.
+
+pub use crate::{
+    api::B2Api,
+    batch::{
+        BatchExecutor, BatchOptions, BucketDeleteReport, DeletePlan, DeleteReport, Destruction,
+    },
+    bucket::BucketHandle,
+    cas::{CasPut, CasStore},
+    client::{B2Client, B2ClientStatus, KeepAliveOptions},
+    definitions::{
+        bodies::*,
+        ids::*,
+        query_params::*,
+        shared::{B2Bucket, B2BucketType, B2Endpoint, B2File, B2KeyCapability, B2TransactionClass},
+    },
+    endpoint_stats::{B2RequestMetrics, EndpointStats, EndpointStatsSnapshot},
+    error::{B2Error, B2RequestError},
+    naming::{DatePrefixNaming, HashPrefixNaming, KeyNamingStrategy, NamingChain, SanitizeNaming},
+    pagination::{ListingCost, ListingGuard},
+    prefix_size::{MemoryPrefixSizeCache, PrefixSize, PrefixSizeCache, PrefixSizeOptions},
+    retention::{FileHistory, GfsPolicy, VersionState},
+    simple_client::{B2SimpleClient, B2SimpleClientBuilder, B2UploadConnectionPolicy},
+    tasks::{
+        backup::{BackupFile, BackupManifest, BackupOptions},
+        copy::{CopyOptions, FileCopyError, MoveOptions},
+        download::{
+            error::FileDownloadError, FileDownload, FileDownloadOptions, FileDownloadSource,
+            SplitDownloadOptions, SplitManifest,
+        },
+        error::TransferError,
+        shared::{CurrentFileNetworkStats, FileStatus, ProgressCallbackOptions},
+        upload::{
+            error::FileUploadError, ContentEncodingCheck, FanOutDestination, FileUpload,
+            FileUploadOptions, LargeFileCutoff, LargeFileLoadStrategy, LargePartUploader,
+            LargePartUploaderOptions, PartLayout, PartProgress, PartState, RangeSource,
+            RemoteFileOpener, RemoteFileReader, SampleVerificationOptions, StreamUploadOptions,
+            UploadCacheError, UploadCacheOptions, UploadQueue,
+        },
+    },
+    throttle::{SharedThrottle, Throttle},
+    usage::{Usage, UsageSnapshot, UsageTracker},
+    util::{
+        B2Callback, CallbackRunOptions, Clock, ConstantRetryStrategy, FileInfoExt, ManualClock,
+        RetryBudget, RetryJitter, RetryStrategy, SizeUnit, SystemClock,
+    },
+};
@@ -0,0 +1,304 @@
+use std::collections::BTreeSet;
+
+use async_stream::try_stream;
+use futures::{Stream, StreamExt};
+
+use crate::{
+    definitions::{
+        bodies::B2DeleteFileVersionBody,
+        query_params::B2ListFileVersionsQueryParameters,
+        shared::{B2Action, B2File},
+    },
+    error::B2Error,
+    pagination::ListingGuard,
+    simple_client::B2SimpleClient,
+};
+
+const MILLIS_PER_DAY: u64 = 24 * 60 * 60 * 1000;
+
+/// What a file version means for its file, given the versions newer than it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum VersionState {
+    /// The newest upload of a file that isn't hidden, what downloading the file by name returns.
+    Current,
+    /// An upload replaced by a newer upload.
+    Superseded,
+    /// An upload hidden by a newer hide marker, the file counts as deleted until it's uploaded again.
+    Deleted,
+    /// A hide marker, the file is hidden while this is its newest version.
+    HideMarker,
+    /// A large file that was started but never finished or canceled.
+    Unfinished,
+}
+
+/// Every version of a single file, newest first, as listed by [list_file_histories].
+#[derive(Debug, Clone)]
+pub struct FileHistory {
+    pub file_name: String,
+    pub versions: Vec<B2File>,
+}
+
+impl FileHistory {
+    /// Every version with its [VersionState], newest first.
+    pub fn states(&self) -> Vec<(VersionState, &B2File)> {
+        let mut seen_upload = false;
+        let mut hidden = false;
+
+        self.versions
+            .iter()
+            .map(|version| {
+                let state = match version.action {
+                    B2Action::Upload => {
+                        let state = match (seen_upload, hidden) {
+                            (false, false) => VersionState::Current,
+                            (false, true) => VersionState::Deleted,
+                            (true, _) => VersionState::Superseded,
+                        };
+
+                        seen_upload = true;
+                        state
+                    }
+                    B2Action::Hide => {
+                        hidden |= !seen_upload;
+                        VersionState::HideMarker
+                    }
+                    B2Action::Start | B2Action::Folder => VersionState::Unfinished,
+                };
+
+                (state, version)
+            })
+            .collect()
+    }
+
+    /// The version downloading the file by name returns, None if the file is hidden or was never finished uploading.
+    pub fn current(&self) -> Option<&B2File> {
+        self.states()
+            .into_iter()
+            .find(|(state, _)| *state == VersionState::Current)
+            .map(|(_, version)| version)
+    }
+
+    /// Whether the file's newest finished version is a hide marker.
+    pub fn is_deleted(&self) -> bool {
+        self.versions
+            .iter()
+            .find(|version| matches!(version.action, B2Action::Upload | B2Action::Hide))
+            .is_some_and(|version| version.action == B2Action::Hide)
+    }
+
+    /// The uploaded versions, newest first, hidden ones included.
+    pub fn uploads(&self) -> impl Iterator<Item = &B2File> {
+        self.versions
+            .iter()
+            .filter(|version| version.action == B2Action::Upload)
+    }
+}
+
+/// Streams the [FileHistory] of every file matching the query, with hide markers and unfinished large files included.
+/// <br> B2 lists a file's versions together and newest first, so every history holds all of its file's versions,
+/// unless the [ListingGuard] stops the listing in the middle of one.
+pub fn list_file_histories(
+    client: &B2SimpleClient,
+    query: B2ListFileVersionsQueryParameters,
+    guard: ListingGuard,
+) -> impl Stream<Item = Result<FileHistory, B2Error>> + '_ {
+    try_stream! {
+        let versions = client.list_all_file_versions(query, guard);
+        futures::pin_mut!(versions);
+
+        let mut history: Option<FileHistory> = None;
+
+        while let Some(version) = versions.next().await {
+            let version = version?;
+
+            match &mut history {
+                Some(history) if history.file_name == version.file_name => {
+                    history.versions.push(version);
+                    continue;
+                }
+                _ => {}
+            }
+
+            let next = FileHistory {
+                file_name: version.file_name.clone(),
+                versions: vec![version],
+            };
+
+            if let Some(finished) = history.replace(next) {
+                yield finished;
+            }
+        }
+
+        if let Some(finished) = history {
+            yield finished;
+        }
+    }
+}
+
+/// A grandfather-father-son retention policy, keeping the newest upload of each of the last few days, weeks, months and years.
+/// <br> Days, weeks and months are in UTC, weeks start on Monday. Periods without an upload don't count towards the limits,
+/// so a file backed up rarely keeps versions further back.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct GfsPolicy {
+    /// Newest uploads kept regardless of when they were made.
+    pub keep_last: usize,
+    pub daily: usize,
+    pub weekly: usize,
+    pub monthly: usize,
+    pub yearly: usize,
+}
+
+impl GfsPolicy {
+    /// Which of the upload timestamps, in milliseconds and newest first, the policy keeps, as indices into the slice.
+    /// ```rust
+    /// # use backblaze_b2_client::retention::GfsPolicy;
+    /// const DAY: u64 = 24 * 60 * 60 * 1000;
+    ///
+    /// let policy = GfsPolicy { keep_last: 1, daily: 2, ..Default::default() };
+    /// // Two uploads today, one yesterday and one the day before
+    /// let uploads = [10 * DAY + 500, 10 * DAY + 100, 9 * DAY, 8 * DAY];
+    ///
+    /// assert_eq!(policy.select(&uploads).into_iter().collect::<Vec<_>>(), vec![0, 2]);
+    /// ```
+    pub fn select(&self, upload_timestamps: &[u64]) -> BTreeSet<usize> {
+        let mut kept: BTreeSet<usize> = (0..self.keep_last.min(upload_timestamps.len())).collect();
+
+        let periods: [(usize, PeriodOf); 4] = [
+            (self.daily, day_period),
+            (self.weekly, week_period),
+            (self.monthly, month_period),
+            (self.yearly, year_period),
+        ];
+
+        for (count, period_of) in periods {
+            let mut last_period = None;
+            let mut taken = 0;
+
+            for (index, timestamp) in upload_timestamps.iter().enumerate() {
+                if taken >= count {
+                    break;
+                }
+
+                let period = period_of(*timestamp);
+
+                if last_period != Some(period) {
+                    last_period = Some(period);
+                    taken += 1;
+                    kept.insert(index);
+                }
+            }
+        }
+
+        kept
+    }
+
+    /// Splits a file's versions into the ones the policy keeps and the ones to prune.
+    /// <br> The current version is always kept, and so is the newest hide marker of a deleted file, since pruning it would bring the file back.
+    /// Older hide markers are pruned, unfinished large files are left alone.
+    pub fn plan<'a>(&self, history: &'a FileHistory) -> PrunePlan<'a> {
+        let uploads: Vec<&B2File> = history.uploads().collect();
+        let timestamps: Vec<u64> = uploads.iter().map(|file| file.upload_timestamp).collect();
+        let selected = self.select(&timestamps);
+        let kept_uploads: BTreeSet<&str> = selected
+            .into_iter()
+            .map(|index| uploads[index].file_id.as_str())
+            .collect();
+
+        let mut plan = PrunePlan::default();
+        let mut newest_hide_marker = true;
+
+        for (state, version) in history.states() {
+            let keep = match state {
+                VersionState::Current | VersionState::Unfinished => true,
+                VersionState::Superseded | VersionState::Deleted => {
+                    kept_uploads.contains(version.file_id.as_str())
+                }
+                VersionState::HideMarker => {
+                    let keep = newest_hide_marker && history.is_deleted();
+                    newest_hide_marker = false;
+                    keep
+                }
+            };
+
+            match keep {
+                true => plan.keep.push(version),
+                false => plan.prune.push(version),
+            }
+        }
+
+        plan
+    }
+}
+
+/// Versions of a file a retention policy keeps and prunes, newest first.
+#[derive(Debug, Default)]
+pub struct PrunePlan<'a> {
+    pub keep: Vec<&'a B2File>,
+    pub prune: Vec<&'a B2File>,
+}
+
+impl PrunePlan<'_> {
+    /// Deletes the versions to prune with [delete_file_version](B2SimpleClient::delete_file_version), one at a time.
+    /// <br> Deleting carries on after a failure, like a version still under Object Lock, returning the failures.
+    pub async fn execute(&self, client: &B2SimpleClient) -> Vec<(B2File, B2Error)> {
+        let mut failed = vec![];
+
+        for version in &self.prune {
+            let result = client
+                .delete_file_version(
+                    B2DeleteFileVersionBody::builder()
+                        .file_name(version.file_name.clone())
+                        .file_id(version.file_id.clone())
+                        .build(),
+                )
+                .await;
+
+            if let Err(error) = result {
+                failed.push(((*version).clone(), error));
+            }
+        }
+
+        failed
+    }
+}
+
+/// Maps an upload timestamp to the period it falls in, timestamps in the same period map to the same value.
+type PeriodOf = fn(u64) -> i64;
+
+fn day_period(millis: u64) -> i64 {
+    (millis / MILLIS_PER_DAY) as i64
+}
+
+/// The epoch was a Thursday, shifting by 3 days makes the weeks start on Monday.
+fn week_period(millis: u64) -> i64 {
+    (day_period(millis) + 3).div_euclid(7)
+}
+
+fn month_period(millis: u64) -> i64 {
+    let (year, month, _) = civil_date(day_period(millis));
+    year * 12 + month as i64
+}
+
+fn year_period(millis: u64) -> i64 {
+    civil_date(day_period(millis)).0
+}
+
+/// The (year, month, day) of days since the epoch, month and day starting at 1.
+fn civil_date(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * month_index + 2) / 5 + 1) as u32;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    } as u32;
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+    (year, month, day)
+}
@@ -0,0 +1,67 @@
+use std::{io, time::Duration};
+
+/// Options for [B2Client::abort_on_signals](crate::client::B2Client::abort_on_signals).
+#[derive(Debug, Clone)]
+pub struct SignalAbortOptions {
+    /// How long to wait for tracked uploads to finish aborting, before the hard abort.
+    /// <br> Default is 10 seconds.
+    pub grace_period: Duration,
+    /// Exit code the process exits with on hard abort, which happens once the grace period runs out or on a second signal.
+    /// <br> None doesn't exit, just stops waiting. A signal after that still ends the process like it would without the handler.
+    /// <br> Default is Some(130).
+    pub exit_code: Option<i32>,
+}
+
+impl Default for SignalAbortOptions {
+    fn default() -> Self {
+        Self {
+            grace_period: Duration::from_secs(10),
+            exit_code: Some(130),
+        }
+    }
+}
+
+/// Listens for SIGINT and SIGTERM, or Ctrl+C on non unix platforms.
+/// <br> Listening replaces the default handling of the signals for the rest of the process.
+pub(crate) struct ShutdownSignals {
+    #[cfg(unix)]
+    interrupt: tokio::signal::unix::Signal,
+    #[cfg(unix)]
+    terminate: tokio::signal::unix::Signal,
+}
+
+impl ShutdownSignals {
+    pub fn new() -> Result<Self, io::Error> {
+        #[cfg(unix)]
+        {
+            use tokio::signal::unix::{signal, SignalKind};
+
+            Ok(Self {
+                interrupt: signal(SignalKind::interrupt())?,
+                terminate: signal(SignalKind::terminate())?,
+            })
+        }
+
+        #[cfg(not(unix))]
+        {
+            Ok(Self {})
+        }
+    }
+
+    /// Waits for the next signal, returning the exit code the process would have exited with by default.
+    pub async fn recv(&mut self) -> Result<i32, io::Error> {
+        #[cfg(unix)]
+        {
+            tokio::select! {
+                _ = self.interrupt.recv() => Ok(128 + 2),
+                _ = self.terminate.recv() => Ok(128 + 15),
+            }
+        }
+
+        #[cfg(not(unix))]
+        {
+            // STATUS_CONTROL_C_EXIT
+            tokio::signal::ctrl_c().await.map(|_| 0xC000013Au32 as i32)
+        }
+    }
+}
@@ -1,16 +1,24 @@
 use base64::{engine::general_purpose, Engine as _};
+use futures::Stream;
 use reqwest::{
     header::{HeaderMap, HeaderName, HeaderValue},
     Method, RequestBuilder, Response,
 };
-use serde::de::DeserializeOwned;
+use serde::{de::DeserializeOwned, Serialize};
 use serde_json::json;
-use std::{collections::HashMap, num::NonZeroU16, str::FromStr};
+use std::{
+    collections::HashMap,
+    fmt,
+    num::NonZeroU16,
+    str::FromStr,
+    sync::Arc,
+    time::{Duration, SystemTime},
+};
 
 use crate::{
     definitions::{
         bodies::{
-            B2CopyFileBody, B2CopyPartBody, B2CreateBucketBody, B2CreateKeyBody,
+            B2BucketTypeList, B2CopyFileBody, B2CopyPartBody, B2CreateBucketBody, B2CreateKeyBody,
             B2DeleteFileVersionBody, B2FinishLargeFileBody, B2GetDownloadAuthorizationBody,
             B2ListBucketsBody, B2StartLargeFileUploadBody, B2UpdateBucketBody,
             B2UpdateFileLegalHoldBodyResponse, B2UpdateFileRetentionBody,
@@ -29,43 +37,276 @@ use crate::{
             B2ListPartsResponse, B2ListUnfinishedLargeFilesResponse, B2UpdateFileRetentionResponse,
         },
         shared::{
-            B2AppKey, B2Bucket, B2DownloadFileContent, B2Endpoint, B2File, B2FileDownloadDetails,
-            B2KeyCapability,
+            B2AppKey, B2Bucket, B2BucketCreation, B2DownloadFileContent, B2Endpoint,
+            B2EventNotificationRule, B2File, B2FileDownloadDetails, B2KeyCapability,
+            B2ReplicationConfig, B2ReplicationRule, B2SuspendedNotificationRule,
         },
     },
+    endpoint_stats::{self, B2RequestMetrics, EndpointStatsRecorder, EndpointStatsSnapshot},
     error::{B2Error, B2RequestError},
-    util::{B2FileStream, IntoHeaderMap, WriteLockArc},
+    pagination::{paginate, ListingGuard},
+    util::{
+        debug_log::{loggable_body, loggable_json},
+        encoding::{
+            encode_file_info_value, encode_header_value, encode_query_value, encode_url_path,
+            encode_url_path_segment,
+        },
+        runtime::run_on,
+        B2Callback, B2FileStream, Clock, IntoHeaderMap, InvalidValue, RetryContext, RetryStrategy,
+        Runtime, SystemClock, WriteLockArc,
+    },
 };
 
-use percent_encoding::{utf8_percent_encode, AsciiSet, CONTROLS};
-
-const ENCODE_SET: &AsciiSet = &CONTROLS
-    .add(b' ')
-    .add(b'"')
-    .add(b'#')
-    .add(b'<')
-    .add(b'>')
-    .add(b'[')
-    .add(b']')
-    .add(b'{')
-    .add(b'}')
-    .add(b'|')
-    .add(b'\\')
-    .add(b'^')
-    .add(b'%')
-    .add(b'`');
+/// How many times a read-modify-write bucket update is tried when another writer changes the bucket in between.
+const BUCKET_REVISION_ATTEMPTS: u32 = 5;
+
+/// Which HTTP method API calls are sent with.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum B2RequestMethodPolicy {
+    /// Uses the method each endpoint is documented with, see [B2Endpoint::documented_method].
+    #[default]
+    Documented,
+    /// Sends every API call as POST with a JSON body, which B2 accepts for all calls.
+    /// <br> Useful behind proxies that strip or cap query strings. Downloads are always sent as GET.
+    AlwaysPost,
+}
 
-#[derive(Clone, Debug)]
-pub struct B2SimpleClient {
-    client: reqwest::Client,
-    auth_data: WriteLockArc<B2AuthData>,
+/// The B2 native API version calls are made against, the `v3` in `/b2api/v3/`.
+/// <br> The request and response types are modeled after v3, so another version only works for calls it didn't change.
+///
+/// ```rust
+/// # use backblaze_b2_client::{definitions::shared::B2Endpoint, simple_client::B2ApiVersion};
+/// assert_eq!(B2ApiVersion::default(), B2ApiVersion::V3);
+/// assert_eq!(B2ApiVersion::V3.to_string(), "v3");
+///
+/// assert_eq!(
+///     B2ApiVersion::V3.endpoint_url("https://api001.backblazeb2.com", B2Endpoint::B2ListBuckets),
+///     "https://api001.backblazeb2.com/b2api/v3/b2_list_buckets"
+/// );
+/// assert_eq!(
+///     B2ApiVersion(4).endpoint_url("https://api001.backblazeb2.com", B2Endpoint::B2ListBuckets),
+///     "https://api001.backblazeb2.com/b2api/v4/b2_list_buckets"
+/// );
+/// assert_eq!(
+///     B2ApiVersion::V2.endpoint_url("https://api.backblazeb2.com", B2Endpoint::B2AuthorizeAccount),
+///     "https://api.backblazeb2.com/b2api/v2/b2_authorize_account"
+/// );
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct B2ApiVersion(pub u16);
+
+impl B2ApiVersion {
+    pub const V2: Self = Self(2);
+    pub const V3: Self = Self(3);
+
+    /// The URL of an API call on the given host.
+    pub fn endpoint_url(&self, base_url: &str, endpoint: B2Endpoint) -> String {
+        format!("{}/b2api/{}/{}", base_url, self, endpoint)
+    }
 }
 
-impl B2SimpleClient {
-    pub async fn new<S: AsRef<str>, K: AsRef<str>>(
+impl Default for B2ApiVersion {
+    fn default() -> Self {
+        Self::V3
+    }
+}
+
+impl fmt::Display for B2ApiVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "v{}", self.0)
+    }
+}
+
+/// Opt-in debug logging of API calls, refer to [with_debug_logging](B2SimpleClient::with_debug_logging).
+/// <br> Every call gets a random request ID, logged with its query parameters or JSON body and again with its response,
+/// at the debug level through [tracing]. Keys, tokens and other secrets in them are redacted.
+#[derive(Clone, Copy, Debug)]
+pub struct B2DebugLogging {
+    /// Logged bodies longer than this many bytes are cut off.
+    /// <br> Default is 4096.
+    pub max_body_len: usize,
+}
+
+impl Default for B2DebugLogging {
+    fn default() -> Self {
+        Self { max_body_len: 4096 }
+    }
+}
+
+/// Host [b2_authorize_account](https://www.backblaze.com/apidocs/b2-authorize-account) is called on,
+/// every other call goes to the API URL it returns.
+pub const AUTHORIZE_ACCOUNT_BASE_URL: &str = "https://api.backblazeb2.com";
+
+/// How uploads reach the upload URLs, which point to per-pod hosts instead of the API host.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum B2UploadConnectionPolicy {
+    /// Uploads use the same HTTP client, proxies and connection pool as every other call.
+    #[default]
+    Shared,
+    /// Uploads get their own connection pool, through the same proxies, so long uploads don't hold the API calls' connections.
+    SeparatePool,
+    /// Uploads get their own connection pool and connect to the pod hosts directly, bypassing the proxies,
+    /// for networks that only proxy the API host.
+    BypassProxy,
+}
+
+/// Builds a [B2SimpleClient] with a configured HTTP client, for proxies, timeouts and custom root certificates.
+/// ```rust,no_run
+/// # use std::time::Duration;
+/// # use backblaze_b2_client::simple_client::B2SimpleClient;
+/// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+/// let client = B2SimpleClient::builder()
+///     .with_connect_timeout(Duration::from_secs(10))
+///     .with_proxy(reqwest::Proxy::https("http://proxy.internal:3128")?)
+///     .with_user_agent("my-app/1.0")
+///     .build("key_id", "application_key")
+///     .await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Default)]
+pub struct B2SimpleClientBuilder {
+    http_client: Option<reqwest::Client>,
+    connect_timeout: Option<Duration>,
+    timeout: Option<Duration>,
+    proxies: Vec<reqwest::Proxy>,
+    #[cfg(any(
+        feature = "default-tls",
+        feature = "native-tls",
+        feature = "rustls-tls"
+    ))]
+    root_certificates: Vec<reqwest::Certificate>,
+    user_agent: Option<String>,
+    api_version: B2ApiVersion,
+    api_base_url: Option<String>,
+    upload_connections: B2UploadConnectionPolicy,
+    retry_strategy: Option<RetryStrategy>,
+    metrics: Option<B2Callback<B2RequestMetrics>>,
+    runtime: Option<Arc<dyn Runtime>>,
+}
+
+impl B2SimpleClientBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sends every call with the given client, the builder's other HTTP options are ignored, other than for [uploads](B2SimpleClientBuilder::with_upload_connections).
+    pub fn with_http_client(mut self, client: reqwest::Client) -> Self {
+        self.http_client = Some(client);
+        self
+    }
+
+    /// How long connecting to a host may take.
+    /// <br> Default is None, which waits for the OS to give up.
+    pub fn with_connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = Some(timeout);
+        self
+    }
+
+    /// How long a whole request may take, from connecting until the response body is read.
+    /// <br> Applies to uploads and downloads too, so it has to fit the largest transfer,
+    /// [stall timeouts](crate::tasks::upload::FileUploadOptions::stall_timeout) are usually a better fit for them.
+    /// <br> Default is None, which never times out.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Sends calls through the proxy, can be called again to add proxies for other schemes.
+    /// <br> Default uses the proxies in the `HTTP_PROXY` and `HTTPS_PROXY` environment variables.
+    pub fn with_proxy(mut self, proxy: reqwest::Proxy) -> Self {
+        self.proxies.push(proxy);
+        self
+    }
+
+    /// Trusts the certificate on top of the system's root certificates, like a corporate proxy's.
+    /// <br> Needs one of the TLS features.
+    #[cfg(any(
+        feature = "default-tls",
+        feature = "native-tls",
+        feature = "rustls-tls"
+    ))]
+    pub fn with_root_certificate(mut self, certificate: reqwest::Certificate) -> Self {
+        self.root_certificates.push(certificate);
+        self
+    }
+
+    /// The `User-Agent` header sent with every call.
+    /// <br> Default is None, which sends no user agent.
+    pub fn with_user_agent<S: Into<String>>(mut self, user_agent: S) -> Self {
+        self.user_agent = Some(user_agent.into());
+        self
+    }
+
+    /// The API version calls are made against, the account is authorized with it too, refer to [B2ApiVersion].
+    /// <br> Default is [B2ApiVersion::V3].
+    pub fn with_api_version(mut self, api_version: B2ApiVersion) -> Self {
+        self.api_version = api_version;
+        self
+    }
+
+    /// Host the account is authorized on, like a mock server's `http://localhost:8080` in tests, or a B2 compatible server.
+    /// <br> Every other call goes to the URLs the authorization returns, so a mock has to return its own URLs for them to reach it.
+    /// <br> Default is None, which is [AUTHORIZE_ACCOUNT_BASE_URL].
+    pub fn with_api_base_url<S: Into<String>>(mut self, api_base_url: S) -> Self {
+        self.api_base_url = Some(api_base_url.into());
+        self
+    }
+
+    /// How uploads connect to the upload hosts, refer to [B2UploadConnectionPolicy].
+    /// <br> With a [custom HTTP client](B2SimpleClientBuilder::with_http_client), uploads that don't share it use a client built from the builder's other options.
+    /// <br> Default is [B2UploadConnectionPolicy::Shared].
+    pub fn with_upload_connections(mut self, policy: B2UploadConnectionPolicy) -> Self {
+        self.upload_connections = policy;
+        self
+    }
+
+    /// Retries idempotent API calls, refer to [B2SimpleClient::with_retry_strategy].
+    /// <br> Default is None, which makes a single attempt.
+    pub fn with_retry_strategy(mut self, retry_strategy: RetryStrategy) -> Self {
+        self.retry_strategy = Some(retry_strategy);
+        self
+    }
+
+    /// Called after every call with its endpoint, duration, status code and payload sizes, like for exporting Prometheus metrics,
+    /// [endpoint_stats](B2SimpleClient::endpoint_stats) has totals without a callback.
+    /// <br> Clones of the client share the callback. An async callback is awaited before the call returns, so it should be quick.
+    /// <br> Default is None.
+    pub fn with_metrics(mut self, metrics: B2Callback<B2RequestMetrics>) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+
+    /// Drives the authorization and the client's requests on the runtime, refer to [B2SimpleClient::with_runtime].
+    /// <br> Default is None, which drives them on the caller's Tokio runtime.
+    pub fn with_runtime(mut self, runtime: Arc<dyn Runtime>) -> Self {
+        self.runtime = Some(runtime);
+        self
+    }
+
+    /// Builds the HTTP client and authorizes the account.
+    pub async fn build<S: AsRef<str>, K: AsRef<str>>(
+        mut self,
         key_id: S,
         application_key: K,
     ) -> Result<B2SimpleClient, B2Error> {
+        let api_version = self.api_version;
+        let authorize_base_url: Arc<str> = self
+            .api_base_url
+            .as_deref()
+            .unwrap_or(AUTHORIZE_ACCOUNT_BASE_URL)
+            .trim_end_matches('/')
+            .into();
+        let client = match &self.http_client {
+            Some(client) => client.clone(),
+            None => self.http_client(true)?,
+        };
+        let upload_client = match self.upload_connections {
+            B2UploadConnectionPolicy::Shared => client.clone(),
+            B2UploadConnectionPolicy::SeparatePool => self.http_client(true)?,
+            B2UploadConnectionPolicy::BypassProxy => self.http_client(false)?,
+        };
+
         let auth_token = format!(
             "Basic {}",
             general_purpose::STANDARD_NO_PAD.encode(format!(
@@ -75,20 +316,180 @@ impl B2SimpleClient {
             ))
         );
 
-        let client = reqwest::Client::new();
+        let endpoint_stats = Arc::new(EndpointStatsRecorder::new(self.metrics.take()));
 
-        let auth_response = client
-            .get("https://api.backblazeb2.com/b2api/v3/b2_authorize_account")
-            .header("Authorization", auth_token)
-            .send()
-            .await;
+        let auth_data = endpoint_stats
+            .timed(B2Endpoint::B2AuthorizeAccount, &SystemClock, async {
+                let request = client
+                    .get(
+                        api_version
+                            .endpoint_url(&authorize_base_url, B2Endpoint::B2AuthorizeAccount),
+                    )
+                    .header("Authorization", auth_token);
+                let auth_response = run_on(self.runtime.as_ref(), request.send()).await;
+
+                B2SimpleClient::handle_response(auth_response, &SystemClock).await
+            })
+            .await?;
 
         Ok(B2SimpleClient {
             client,
-            auth_data: WriteLockArc::new(B2SimpleClient::handle_response(auth_response).await?),
+            auth_data: WriteLockArc::new(auth_data),
+            request_method_policy: B2RequestMethodPolicy::default(),
+            api_version,
+            debug_logging: None,
+            suspended_rule_callback: None,
+            endpoint_stats,
+            authorize_base_url,
+            upload_client,
+            retry_strategy: self.retry_strategy.take().map(Arc::new),
+            clock: SystemClock::shared(),
+            runtime: self.runtime.take(),
         })
     }
 
+    fn http_client(&self, use_proxies: bool) -> Result<reqwest::Client, B2Error> {
+        let mut builder = reqwest::Client::builder();
+
+        if let Some(timeout) = self.connect_timeout {
+            builder = builder.connect_timeout(timeout);
+        }
+
+        if let Some(timeout) = self.timeout {
+            builder = builder.timeout(timeout);
+        }
+
+        match use_proxies {
+            true => {
+                for proxy in &self.proxies {
+                    builder = builder.proxy(proxy.clone());
+                }
+            }
+            false => builder = builder.no_proxy(),
+        }
+
+        #[cfg(any(
+            feature = "default-tls",
+            feature = "native-tls",
+            feature = "rustls-tls"
+        ))]
+        for certificate in &self.root_certificates {
+            builder = builder.add_root_certificate(certificate.clone());
+        }
+
+        if let Some(user_agent) = &self.user_agent {
+            builder = builder.user_agent(user_agent);
+        }
+
+        builder.build().map_err(B2Error::HttpClientBuildError)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct B2SimpleClient {
+    client: reqwest::Client,
+    auth_data: WriteLockArc<B2AuthData>,
+    request_method_policy: B2RequestMethodPolicy,
+    api_version: B2ApiVersion,
+    debug_logging: Option<B2DebugLogging>,
+    suspended_rule_callback: Option<Arc<B2Callback<B2SuspendedNotificationRule>>>,
+    endpoint_stats: Arc<EndpointStatsRecorder>,
+    authorize_base_url: Arc<str>,
+    upload_client: reqwest::Client,
+    retry_strategy: Option<Arc<RetryStrategy>>,
+    clock: Arc<dyn Clock>,
+    runtime: Option<Arc<dyn Runtime>>,
+}
+
+impl B2SimpleClient {
+    pub async fn new<S: AsRef<str>, K: AsRef<str>>(
+        key_id: S,
+        application_key: K,
+    ) -> Result<B2SimpleClient, B2Error> {
+        B2SimpleClientBuilder::default()
+            .build(key_id, application_key)
+            .await
+    }
+
+    /// Configures the HTTP client the calls are sent with, refer to [B2SimpleClientBuilder].
+    pub fn builder() -> B2SimpleClientBuilder {
+        B2SimpleClientBuilder::default()
+    }
+
+    /// Sets which HTTP method API calls are sent with, refer to [B2RequestMethodPolicy].
+    pub fn with_request_method_policy(mut self, policy: B2RequestMethodPolicy) -> Self {
+        self.request_method_policy = policy;
+        self
+    }
+
+    pub fn request_method_policy(&self) -> B2RequestMethodPolicy {
+        self.request_method_policy
+    }
+
+    /// Sets the API version calls are made against, refer to [B2ApiVersion].
+    /// <br> The account was already authorized with the default version by [new](B2SimpleClient::new),
+    /// later [authorize_account](B2SimpleClient::authorize_account) calls use this one. To authorize with it too, use [B2SimpleClientBuilder::with_api_version].
+    pub fn with_api_version(mut self, api_version: B2ApiVersion) -> Self {
+        self.api_version = api_version;
+        self
+    }
+
+    pub fn api_version(&self) -> B2ApiVersion {
+        self.api_version
+    }
+
+    /// Logs API calls and their responses for debugging, refer to [B2DebugLogging].
+    /// <br> Only calls with query parameters or a JSON body are logged, not file uploads and downloads.
+    /// Default is None, which logs nothing.
+    pub fn with_debug_logging(mut self, debug_logging: Option<B2DebugLogging>) -> Self {
+        self.debug_logging = debug_logging;
+        self
+    }
+
+    pub fn debug_logging(&self) -> Option<B2DebugLogging> {
+        self.debug_logging
+    }
+
+    /// Sets a callback called for every suspended rule found by [get_bucket_notification_rules](B2SimpleClient::get_bucket_notification_rules),
+    /// so suspended webhooks don't go unnoticed.
+    pub fn with_suspended_rule_callback(
+        mut self,
+        callback: B2Callback<B2SuspendedNotificationRule>,
+    ) -> Self {
+        self.suspended_rule_callback = Some(Arc::new(callback));
+        self
+    }
+
+    /// Retries idempotent API calls, see [B2Endpoint::is_idempotent], when they fail to send or B2 responds with a 429 or a 5xx,
+    /// waiting between attempts as the strategy says. Uploads and downloads are left to their tasks, which retry them on their own.
+    /// <br> Default is None, which makes a single attempt.
+    pub fn with_retry_strategy(mut self, retry_strategy: Option<RetryStrategy>) -> Self {
+        self.retry_strategy = retry_strategy.map(Arc::new);
+        self
+    }
+
+    /// Clock the [retry](B2SimpleClient::with_retry_strategy) waits sleep on, refer to [Clock].
+    /// <br> Default is [SystemClock].
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Drives the client's requests and [retry](B2SimpleClient::with_retry_strategy) waits on the runtime,
+    /// so the client can be awaited from executors other than Tokio, like async-std or [futures::executor].
+    /// <br> reqwest's connections only run on Tokio, so the runtime should spawn on one, like a [TokioRuntime](crate::util::TokioRuntime)
+    /// with the handle of a runtime kept in the background, the [clock](B2SimpleClient::with_clock)'s sleeps are polled on it too.
+    /// <br> Only the simple client's calls go through it, [B2Client](crate::client::B2Client) and its tasks need a Tokio runtime.
+    /// <br> Default is None, which drives them on the caller's Tokio runtime.
+    pub fn with_runtime(mut self, runtime: Option<Arc<dyn Runtime>>) -> Self {
+        self.runtime = runtime;
+        self
+    }
+
+    pub(crate) fn clock(&self) -> &Arc<dyn Clock> {
+        &self.clock
+    }
+
     pub fn auth_data(&self) -> B2AuthData {
         (*self.auth_data).clone()
     }
@@ -107,16 +508,27 @@ impl B2SimpleClient {
             ))
         );
 
-        let auth_response = self
-            .client
-            .get("https://api.backblazeb2.com/b2api/v3/b2_authorize_account")
-            .header("Authorization", auth_token)
-            .send()
-            .await;
+        let auth_data = self
+            .endpoint_stats
+            .timed(B2Endpoint::B2AuthorizeAccount, self.clock.as_ref(), async {
+                let request = self
+                    .client
+                    .get(
+                        self.api_version
+                            .endpoint_url(&self.authorize_base_url, B2Endpoint::B2AuthorizeAccount),
+                    )
+                    .header("Authorization", auth_token);
+                let auth_response = self.send(request).await;
+
+                B2SimpleClient::handle_response(auth_response, self.clock.as_ref()).await
+            })
+            .await?;
+
+        self.auth_data.set(auth_data).await;
+
+        #[cfg(feature = "tracing")]
+        tracing::info!(account_id = self.account_id(), "B2 account authorized");
 
-        self.auth_data
-            .set(B2SimpleClient::handle_response(auth_response).await?)
-            .await;
         Ok(self.auth_data())
     }
 
@@ -127,13 +539,8 @@ impl B2SimpleClient {
     ) -> Result<B2CancelLargeFileResponse, B2Error> {
         self.has_capabilities(&[B2KeyCapability::WriteFiles])?;
 
-        let response = self
-            .create_request_with_token(Method::POST, B2Endpoint::B2CancelLargeFile)
-            .json(&json!({ "fileId": file_id }))
-            .send()
-            .await;
-
-        B2SimpleClient::handle_response(response).await
+        self.send_api_request(B2Endpoint::B2CancelLargeFile, &json!({ "fileId": file_id }))
+            .await
     }
 
     /// [b2_copy_file](https://www.backblaze.com/apidocs/b2-copy-file)
@@ -150,26 +557,15 @@ impl B2SimpleClient {
 
         self.has_capabilities(&needed_capabilities)?;
 
-        let response = self
-            .create_request_with_token(Method::POST, B2Endpoint::B2CopyFile)
-            .json(&body)
-            .send()
-            .await;
-
-        B2SimpleClient::handle_response(response).await
+        self.send_api_request(B2Endpoint::B2CopyFile, &body).await
     }
 
     /// [b2_copy_part](https://www.backblaze.com/apidocs/b2-copy-part)
     pub async fn copy_part(&self, request_body: B2CopyPartBody) -> Result<B2FilePart, B2Error> {
         self.has_capabilities(&[B2KeyCapability::WriteFiles])?;
 
-        let response = self
-            .create_request_with_token(Method::POST, B2Endpoint::B2CopyPart)
-            .json(&request_body)
-            .send()
-            .await;
-
-        B2SimpleClient::handle_response(response).await
+        self.send_api_request(B2Endpoint::B2CopyPart, &request_body)
+            .await
     }
 
     /// [b2_create_bucket](https://www.backblaze.com/apidocs/b2-create-bucket)
@@ -188,26 +584,37 @@ impl B2SimpleClient {
 
         self.has_capabilities(&needed_capabilities)?;
 
-        let response = self
-            .create_request_with_token(Method::POST, B2Endpoint::B2CreateBucket)
-            .json(&body)
-            .send()
-            .await;
+        self.send_api_request(B2Endpoint::B2CreateBucket, &body)
+            .await
+    }
 
-        B2SimpleClient::handle_response(response).await
+    /// Creates a bucket with [create_bucket](B2SimpleClient::create_bucket), or gets it by name if it already exists in this account.
+    /// <br> An existing bucket is returned as is, even if its settings differ from the body.
+    /// If the name is taken by another account, the [DuplicateBucketName](B2Error::DuplicateBucketName) error is returned.
+    pub async fn create_bucket_if_not_exists(
+        &self,
+        body: B2CreateBucketBody,
+    ) -> Result<B2BucketCreation, B2Error> {
+        let bucket_name = body.bucket_name.clone();
+
+        match self.create_bucket(body).await {
+            Ok(bucket) => Ok(B2BucketCreation::Created(bucket)),
+            Err(B2Error::DuplicateBucketName(error)) => {
+                match self.get_bucket_by_name(bucket_name).await? {
+                    Some(bucket) => Ok(B2BucketCreation::AlreadyExisted(bucket)),
+                    None => Err(B2Error::DuplicateBucketName(error)),
+                }
+            }
+            Err(error) => Err(error),
+        }
     }
 
     /// [b2_create_key](https://www.backblaze.com/apidocs/b2-create-key)
     pub async fn create_key(&self, request_body: B2CreateKeyBody) -> Result<B2AppKey, B2Error> {
         self.has_capabilities(&[B2KeyCapability::WriteKeys])?;
 
-        let response = self
-            .create_request_with_token(Method::POST, B2Endpoint::B2CreateKey)
-            .json(&request_body)
-            .send()
-            .await;
-
-        B2SimpleClient::handle_response(response).await
+        self.send_api_request(B2Endpoint::B2CreateKey, &request_body)
+            .await
     }
 
     /// [b2_delete_bucket](https://www.backblaze.com/apidocs/b2-delete-bucket)
@@ -218,13 +625,11 @@ impl B2SimpleClient {
     ) -> Result<B2Bucket, B2Error> {
         self.has_capabilities(&[B2KeyCapability::DeleteBuckets])?;
 
-        let response = self
-            .create_request_with_token(Method::POST, B2Endpoint::B2DeleteBucket)
-            .json(&json!({ "accountId": account_id, "bucketId": bucket_id }))
-            .send()
-            .await;
-
-        B2SimpleClient::handle_response(response).await
+        self.send_api_request(
+            B2Endpoint::B2DeleteBucket,
+            &json!({ "accountId": account_id, "bucketId": bucket_id }),
+        )
+        .await
     }
 
     /// [b2_delete_file_version](https://www.backblaze.com/apidocs/b2-delete-file-version)
@@ -234,24 +639,17 @@ impl B2SimpleClient {
     ) -> Result<B2DeleteFileVersionResponse, B2Error> {
         self.has_capabilities(&[B2KeyCapability::DeleteFiles])?;
 
-        let response = self
-            .create_request_with_token(Method::POST, B2Endpoint::B2DeleteFileVersion)
-            .json(&request_body)
-            .send()
-            .await;
-
-        B2SimpleClient::handle_response(response).await
+        self.send_api_request(B2Endpoint::B2DeleteFileVersion, &request_body)
+            .await
     }
 
     /// [b2_delete_key](https://www.backblaze.com/apidocs/b2-delete-key)
     pub async fn delete_key(&self, application_key_id: String) -> Result<B2AppKey, B2Error> {
-        let response = self
-            .create_request_with_token(Method::GET, B2Endpoint::B2DeleteKey)
-            .json(&json!({ "applicationKeyId": application_key_id }))
-            .send()
-            .await;
-
-        B2SimpleClient::handle_response(response).await
+        self.send_api_request(
+            B2Endpoint::B2DeleteKey,
+            &json!({ "applicationKeyId": application_key_id }),
+        )
+        .await
     }
 
     /// [b2_download_file_by_id](https://www.backblaze.com/apidocs/b2-download-file-by-id)
@@ -260,14 +658,32 @@ impl B2SimpleClient {
         file_id: String,
         request_query_params: Option<B2DownloadFileQueryParameters>,
     ) -> Result<B2DownloadFileContent, B2Error> {
-        let response = self
+        let range = B2SimpleClient::download_range_header(&request_query_params);
+
+        let request = self
             .create_request_with_token(Method::GET, B2Endpoint::B2DownloadFileById)
             .query(&[("fileId", file_id)])
             .query(&request_query_params)
-            .send()
-            .await;
+            .headers(range);
 
-        B2SimpleClient::handle_file_response(response).await
+        self.endpoint_stats
+            .timed(B2Endpoint::B2DownloadFileById, self.clock.as_ref(), async {
+                B2SimpleClient::handle_file_response(self.send(request).await, self.clock.as_ref())
+                    .await
+            })
+            .await
+    }
+
+    /// Same as [download_file_by_id](B2SimpleClient::download_file_by_id), but returns None if the file doesn't exist.
+    pub async fn try_download_file_by_id(
+        &self,
+        file_id: String,
+        request_query_params: Option<B2DownloadFileQueryParameters>,
+    ) -> Result<Option<B2DownloadFileContent>, B2Error> {
+        B2SimpleClient::file_not_found_as_none(
+            self.download_file_by_id(file_id, request_query_params)
+                .await,
+        )
     }
 
     /// [b2_download_file_by_name](https://www.backblaze.com/apidocs/b2-download-file-by-name)
@@ -277,18 +693,46 @@ impl B2SimpleClient {
         file_name: String,
         request_query_params: Option<B2DownloadFileQueryParameters>,
     ) -> Result<B2DownloadFileContent, B2Error> {
-        let response = self
+        let range = B2SimpleClient::download_range_header(&request_query_params);
+
+        let request = self
             .client
             .get(format!(
                 "{}/file/{}/{}",
-                self.auth_data.api_info.storage_api.download_url, bucket_name, file_name
+                self.download_base_url(),
+                encode_url_path_segment(bucket_name),
+                encode_url_path(file_name)
             ))
             .header("Authorization", self.get_authorization_token())
             .query(&request_query_params)
-            .send()
-            .await;
+            .headers(range);
+
+        self.endpoint_stats
+            .timed(
+                B2Endpoint::B2DownloadFileByName,
+                self.clock.as_ref(),
+                async {
+                    B2SimpleClient::handle_file_response(
+                        self.send(request).await,
+                        self.clock.as_ref(),
+                    )
+                    .await
+                },
+            )
+            .await
+    }
 
-        B2SimpleClient::handle_file_response(response).await
+    /// Same as [download_file_by_name](B2SimpleClient::download_file_by_name), but returns None if the file doesn't exist.
+    pub async fn try_download_file_by_name(
+        &self,
+        bucket_name: String,
+        file_name: String,
+        request_query_params: Option<B2DownloadFileQueryParameters>,
+    ) -> Result<Option<B2DownloadFileContent>, B2Error> {
+        B2SimpleClient::file_not_found_as_none(
+            self.download_file_by_name(bucket_name, file_name, request_query_params)
+                .await,
+        )
     }
 
     /// [b2_finish_large_file](https://www.backblaze.com/apidocs/b2-finish-large-file)
@@ -298,13 +742,8 @@ impl B2SimpleClient {
     ) -> Result<B2File, B2Error> {
         self.has_capabilities(&[B2KeyCapability::WriteFiles])?;
 
-        let response = self
-            .create_request_with_token(Method::POST, B2Endpoint::B2FinishLargeFile)
-            .json(&request_body)
-            .send()
-            .await;
-
-        B2SimpleClient::handle_response(response).await
+        self.send_api_request(B2Endpoint::B2FinishLargeFile, &request_body)
+            .await
     }
 
     /// [b2_get_bucket_notification_rules](https://www.backblaze.com/apidocs/b2-get-bucket-notification-rules)
@@ -314,13 +753,50 @@ impl B2SimpleClient {
     ) -> Result<B2BucketNotificationRulesResponseBody, B2Error> {
         self.has_capabilities(&[B2KeyCapability::ReadBucketNotifications])?;
 
-        let response = self
-            .create_request_with_token(Method::GET, B2Endpoint::B2GetBucketNotificationRules)
-            .query(&[("bucketId", bucket_id)])
-            .send()
-            .await;
+        let rules: B2BucketNotificationRulesResponseBody = self
+            .send_api_request(
+                B2Endpoint::B2GetBucketNotificationRules,
+                &json!({ "bucketId": bucket_id }),
+            )
+            .await?;
+
+        if let Some(callback) = &self.suspended_rule_callback {
+            for rule in rules.suspended_rules() {
+                let suspended = B2SuspendedNotificationRule {
+                    bucket_id: rules.bucket_id.clone(),
+                    rule_name: rule.name.clone(),
+                    reason: rule.suspension_reason.clone(),
+                };
+
+                match callback.as_ref() {
+                    B2Callback::Fn(fun) => fun(suspended),
+                    B2Callback::AsyncFn(fun) => fun(suspended).await,
+                }
+            }
+        }
+
+        Ok(rules)
+    }
+
+    /// Resumes the bucket's suspended notification rules by setting its rules again without their suspension,
+    /// returns the rules as they are after. Makes no change if no rule is suspended.
+    /// <br> The cause of the suspension, usually a failing webhook, should be fixed first or the rule gets suspended again.
+    pub async fn resume_suspended_rules(
+        &self,
+        bucket_id: String,
+    ) -> Result<B2BucketNotificationRulesResponseBody, B2Error> {
+        let mut rules = self.get_bucket_notification_rules(bucket_id).await?;
+
+        if rules.suspended_rules().next().is_none() {
+            return Ok(rules);
+        }
+
+        rules
+            .event_notification_rules
+            .iter_mut()
+            .for_each(B2EventNotificationRule::clear_suspension);
 
-        B2SimpleClient::handle_response(response).await
+        self.set_bucket_notification_rules(rules).await
     }
 
     /// [b2_get_download_authorization](https://www.backblaze.com/apidocs/b2-get-download-authorization)
@@ -330,26 +806,21 @@ impl B2SimpleClient {
     ) -> Result<B2GetDownloadAuthorizationBodyResponse, B2Error> {
         self.has_capabilities(&[B2KeyCapability::ShareFiles])?;
 
-        let response = self
-            .create_request_with_token(Method::POST, B2Endpoint::B2GetDownloadAuthorization)
-            .json(&request_body)
-            .send()
-            .await;
-
-        B2SimpleClient::handle_response(response).await
+        self.send_api_request(B2Endpoint::B2GetDownloadAuthorization, &request_body)
+            .await
     }
 
     /// [b2_get_file_info](https://www.backblaze.com/apidocs/b2-get-file-info)
     pub async fn get_file_info(&self, file_id: String) -> Result<B2File, B2Error> {
         self.has_capabilities(&[B2KeyCapability::ReadFiles])?;
 
-        let response = self
-            .create_request_with_token(Method::GET, B2Endpoint::B2GetFileInfo)
-            .query(&[("fileId", file_id)])
-            .send()
-            .await;
+        self.send_api_request(B2Endpoint::B2GetFileInfo, &json!({ "fileId": file_id }))
+            .await
+    }
 
-        B2SimpleClient::handle_response(response).await
+    /// Same as [get_file_info](B2SimpleClient::get_file_info), but returns None if the file doesn't exist.
+    pub async fn try_get_file_info(&self, file_id: String) -> Result<Option<B2File>, B2Error> {
+        B2SimpleClient::file_not_found_as_none(self.get_file_info(file_id).await)
     }
 
     /// [b2_get_upload_part_url](https://www.backblaze.com/apidocs/b2-get-upload-part-url)
@@ -359,13 +830,11 @@ impl B2SimpleClient {
     ) -> Result<B2GetUploadPartUrlResponse, B2Error> {
         self.has_capabilities(&[B2KeyCapability::WriteFiles])?;
 
-        let response = self
-            .create_request_with_token(Method::GET, B2Endpoint::B2GetUploadPartUrl)
-            .query(&[("fileId", file_id)])
-            .send()
-            .await;
-
-        B2SimpleClient::handle_response(response).await
+        self.send_api_request(
+            B2Endpoint::B2GetUploadPartUrl,
+            &json!({ "fileId": file_id }),
+        )
+        .await
     }
 
     /// [b2_get_upload_url](https://www.backblaze.com/apidocs/b2-get-upload-url)
@@ -375,26 +844,22 @@ impl B2SimpleClient {
     ) -> Result<B2GetUploadUrlResponse, B2Error> {
         self.has_capabilities(&[B2KeyCapability::WriteFiles])?;
 
-        let response = self
-            .create_request_with_token(Method::GET, B2Endpoint::B2GetUploadUrl)
-            .query(&[("bucketId", bucket_id)])
-            .send()
-            .await;
-
-        B2SimpleClient::handle_response(response).await
+        self.send_api_request(
+            B2Endpoint::B2GetUploadUrl,
+            &json!({ "bucketId": bucket_id }),
+        )
+        .await
     }
 
     /// [b2_hide_file](https://www.backblaze.com/apidocs/b2-hide-file)
     pub async fn hide_file(&self, bucket_id: String, file_name: String) -> Result<B2File, B2Error> {
         self.has_capabilities(&[B2KeyCapability::WriteFiles])?;
 
-        let response = self
-            .create_request_with_token(Method::POST, B2Endpoint::B2HideFile)
-            .json(&json!({ "bucketId": bucket_id, "fileName": file_name }))
-            .send()
-            .await;
-
-        B2SimpleClient::handle_response(response).await
+        self.send_api_request(
+            B2Endpoint::B2HideFile,
+            &json!({ "bucketId": bucket_id, "fileName": file_name }),
+        )
+        .await
     }
 
     /// [b2_list_buckets](https://www.backblaze.com/apidocs/b2-list-buckets)
@@ -404,13 +869,43 @@ impl B2SimpleClient {
     ) -> Result<B2ListBucketsResponse, B2Error> {
         self.has_capabilities(&[B2KeyCapability::ListBuckets])?;
 
+        self.send_api_request(B2Endpoint::B2ListBuckets, &request_body)
+            .await
+    }
+
+    /// Gets a single bucket by its ID using [b2_list_buckets](https://www.backblaze.com/apidocs/b2-list-buckets),
+    /// returns None if the account does not have a bucket with this ID.
+    pub async fn get_bucket(&self, bucket_id: String) -> Result<Option<B2Bucket>, B2Error> {
         let response = self
-            .create_request_with_token(Method::POST, B2Endpoint::B2ListBuckets)
-            .json(&request_body)
-            .send()
-            .await;
+            .list_buckets(
+                B2ListBucketsBody::builder()
+                    .account_id(self.account_id().to_string())
+                    .bucket_id(Some(bucket_id))
+                    .bucket_types(Some(B2BucketTypeList::All))
+                    .build(),
+            )
+            .await?;
+
+        Ok(response.buckets.into_iter().next())
+    }
+
+    /// Gets a single bucket by its name using [b2_list_buckets](https://www.backblaze.com/apidocs/b2-list-buckets),
+    /// returns None if the account does not have a bucket with this name.
+    pub async fn get_bucket_by_name(
+        &self,
+        bucket_name: String,
+    ) -> Result<Option<B2Bucket>, B2Error> {
+        let response = self
+            .list_buckets(
+                B2ListBucketsBody::builder()
+                    .account_id(self.account_id().to_string())
+                    .bucket_name(Some(bucket_name))
+                    .bucket_types(Some(B2BucketTypeList::All))
+                    .build(),
+            )
+            .await?;
 
-        B2SimpleClient::handle_response(response).await
+        Ok(response.buckets.into_iter().next())
     }
 
     /// [b2_list_file_names](https://www.backblaze.com/apidocs/b2-list-file-names)
@@ -420,13 +915,155 @@ impl B2SimpleClient {
     ) -> Result<B2ListFilesResponse, B2Error> {
         self.has_capabilities(&[B2KeyCapability::ListFiles])?;
 
-        let response = self
-            .create_request_with_token(Method::GET, B2Endpoint::B2ListFileNames)
-            .query(&request_body)
-            .send()
-            .await;
+        self.send_api_request(B2Endpoint::B2ListFileNames, &request_body)
+            .await
+    }
 
-        B2SimpleClient::handle_response(response).await
+    /// Streams every file name matching the query, following [next_file_name](B2ListFilesResponse::next_file_name)
+    /// until the listing is done or the [ListingGuard] stops it.
+    pub fn list_all_file_names(
+        &self,
+        query: B2ListFileNamesQueryParameters,
+        guard: ListingGuard,
+    ) -> impl Stream<Item = Result<B2File, B2Error>> + '_ {
+        paginate(
+            query,
+            guard,
+            move |query: B2ListFileNamesQueryParameters| async move {
+                let response = self.list_file_names(query.clone()).await?;
+
+                let next_query =
+                    response
+                        .next_file_name
+                        .map(|next_file_name| B2ListFileNamesQueryParameters {
+                            start_file_name: Some(next_file_name),
+                            ..query
+                        });
+
+                Ok((response.files, next_query))
+            },
+        )
+    }
+
+    /// Streams every file version matching the query, following [next_file_name](B2ListFileVersionsResponse::next_file_name)
+    /// and [next_file_id](B2ListFileVersionsResponse::next_file_id) until the listing is done or the [ListingGuard] stops it.
+    pub fn list_all_file_versions(
+        &self,
+        query: B2ListFileVersionsQueryParameters,
+        guard: ListingGuard,
+    ) -> impl Stream<Item = Result<B2File, B2Error>> + '_ {
+        paginate(
+            query,
+            guard,
+            move |query: B2ListFileVersionsQueryParameters| async move {
+                let response = self.list_file_versions(query.clone()).await?;
+
+                let next_query = response.next_file_name.map(|next_file_name| {
+                    B2ListFileVersionsQueryParameters {
+                        start_file_name: Some(next_file_name),
+                        start_file_id: response.next_file_id,
+                        ..query
+                    }
+                });
+
+                Ok((response.files, next_query))
+            },
+        )
+    }
+
+    /// Streams every bucket matching the request, [b2_list_buckets](https://www.backblaze.com/apidocs/b2-list-buckets) isn't paginated,
+    /// so this makes a single call, for symmetry with the other listings.
+    pub fn list_all_buckets(
+        &self,
+        request_body: B2ListBucketsBody,
+        guard: ListingGuard,
+    ) -> impl Stream<Item = Result<B2Bucket, B2Error>> + '_ {
+        paginate(
+            request_body,
+            guard,
+            move |request_body: B2ListBucketsBody| async move {
+                let response = self.list_buckets(request_body).await?;
+
+                Ok((response.buckets, None))
+            },
+        )
+    }
+
+    /// Streams every key matching the request, following [next_application_key_id](B2ListKeysResponse::next_application_key_id)
+    /// until the listing is done or the [ListingGuard] stops it.
+    pub fn list_all_keys(
+        &self,
+        request_body: B2ListKeysParameters,
+        guard: ListingGuard,
+    ) -> impl Stream<Item = Result<B2AppKey, B2Error>> + '_ {
+        paginate(
+            request_body,
+            guard,
+            move |request_body: B2ListKeysParameters| async move {
+                let response = self.list_keys(request_body.clone()).await?;
+
+                let next_request_body =
+                    response
+                        .next_application_key_id
+                        .map(|next_key_id| B2ListKeysParameters {
+                            start_application_key_id: Some(next_key_id),
+                            ..request_body
+                        });
+
+                Ok((response.keys, next_request_body))
+            },
+        )
+    }
+
+    /// Streams every uploaded part of a large file, following [next_part_number](B2ListPartsResponse::next_part_number)
+    /// until the listing is done or the [ListingGuard] stops it.
+    pub fn list_all_parts(
+        &self,
+        query: B2ListPartsQueryParameters,
+        guard: ListingGuard,
+    ) -> impl Stream<Item = Result<B2FilePart, B2Error>> + '_ {
+        paginate(
+            query,
+            guard,
+            move |query: B2ListPartsQueryParameters| async move {
+                let response = self.list_parts(query.clone()).await?;
+
+                let next_query =
+                    response
+                        .next_part_number
+                        .map(|next_part_number| B2ListPartsQueryParameters {
+                            start_part_number: Some(next_part_number),
+                            ..query
+                        });
+
+                Ok((response.parts, next_query))
+            },
+        )
+    }
+
+    /// Streams every unfinished large file matching the query, following [next_file_id](B2ListUnfinishedLargeFilesResponse::next_file_id)
+    /// until the listing is done or the [ListingGuard] stops it.
+    pub fn list_all_unfinished_large_files(
+        &self,
+        query: B2ListUnfinishedLargeFilesQueryParameters,
+        guard: ListingGuard,
+    ) -> impl Stream<Item = Result<B2File, B2Error>> + '_ {
+        paginate(
+            query,
+            guard,
+            move |query: B2ListUnfinishedLargeFilesQueryParameters| async move {
+                let response = self.list_unfinished_large_files(query.clone()).await?;
+
+                let next_query = response.next_file_id.map(|next_file_id| {
+                    B2ListUnfinishedLargeFilesQueryParameters {
+                        start_file_id: Some(next_file_id),
+                        ..query
+                    }
+                });
+
+                Ok((response.files, next_query))
+            },
+        )
     }
 
     /// [b2_list_file_versions](https://www.backblaze.com/apidocs/b2-list-file-versions)
@@ -436,13 +1073,8 @@ impl B2SimpleClient {
     ) -> Result<B2ListFileVersionsResponse, B2Error> {
         self.has_capabilities(&[B2KeyCapability::ListFiles])?;
 
-        let response = self
-            .create_request_with_token(Method::GET, B2Endpoint::B2ListFileVersions)
-            .query(&request_body)
-            .send()
-            .await;
-
-        B2SimpleClient::handle_response(response).await
+        self.send_api_request(B2Endpoint::B2ListFileVersions, &request_body)
+            .await
     }
 
     /// [b2_list_keys](https://www.backblaze.com/apidocs/b2-list-keys)
@@ -452,13 +1084,8 @@ impl B2SimpleClient {
     ) -> Result<B2ListKeysResponse, B2Error> {
         self.has_capabilities(&[B2KeyCapability::ListKeys])?;
 
-        let response = self
-            .create_request_with_token(Method::GET, B2Endpoint::B2ListKeys)
-            .query(&request_body)
-            .send()
-            .await;
-
-        B2SimpleClient::handle_response(response).await
+        self.send_api_request(B2Endpoint::B2ListKeys, &request_body)
+            .await
     }
 
     /// [b2_list_parts](https://www.backblaze.com/apidocs/b2-list-parts)
@@ -468,13 +1095,8 @@ impl B2SimpleClient {
     ) -> Result<B2ListPartsResponse, B2Error> {
         self.has_capabilities(&[B2KeyCapability::WriteFiles])?;
 
-        let response = self
-            .create_request_with_token(Method::GET, B2Endpoint::B2ListParts)
-            .query(&request_body)
-            .send()
-            .await;
-
-        B2SimpleClient::handle_response(response).await
+        self.send_api_request(B2Endpoint::B2ListParts, &request_body)
+            .await
     }
 
     /// [b2_list_unfinished_large_files](https://www.backblaze.com/apidocs/b2-list-unfinished-large-files)
@@ -484,13 +1106,8 @@ impl B2SimpleClient {
     ) -> Result<B2ListUnfinishedLargeFilesResponse, B2Error> {
         self.has_capabilities(&[B2KeyCapability::ListFiles])?;
 
-        let response = self
-            .create_request_with_token(Method::GET, B2Endpoint::B2ListUnfinishedLargeFiles)
-            .query(&request_body)
-            .send()
-            .await;
-
-        B2SimpleClient::handle_response(response).await
+        self.send_api_request(B2Endpoint::B2ListUnfinishedLargeFiles, &request_body)
+            .await
     }
 
     /// [b2_set_bucket_notification_rules](https://www.backblaze.com/apidocs/b2-set-bucket-notification-rules)
@@ -500,13 +1117,8 @@ impl B2SimpleClient {
     ) -> Result<B2BucketNotificationRulesResponseBody, B2Error> {
         self.has_capabilities(&[B2KeyCapability::WriteBucketNotifications])?;
 
-        let response = self
-            .create_request_with_token(Method::POST, B2Endpoint::B2SetBucketNotificationRules)
-            .json(&request_body)
-            .send()
-            .await;
-
-        B2SimpleClient::handle_response(response).await
+        self.send_api_request(B2Endpoint::B2SetBucketNotificationRules, &request_body)
+            .await
     }
 
     /// [b2_start_large_file](https://www.backblaze.com/apidocs/b2-start-large-file)
@@ -514,13 +1126,8 @@ impl B2SimpleClient {
         &self,
         request_body: B2StartLargeFileUploadBody,
     ) -> Result<B2File, B2Error> {
-        let response = self
-            .create_request_with_token(Method::POST, B2Endpoint::B2StartLargeFile)
-            .json(&request_body)
-            .send()
-            .await;
-
-        B2SimpleClient::handle_response(response).await
+        self.send_api_request(B2Endpoint::B2StartLargeFile, &request_body)
+            .await
     }
 
     /// [b2_update_bucket](https://www.backblaze.com/apidocs/b2-update-bucket)
@@ -530,13 +1137,145 @@ impl B2SimpleClient {
     ) -> Result<B2Bucket, B2Error> {
         self.has_capabilities(&[B2KeyCapability::WriteBuckets])?;
 
-        let response = self
-            .create_request_with_token(Method::POST, B2Endpoint::B2UpdateBucket)
-            .json(&request_body)
-            .send()
-            .await;
+        self.send_api_request(B2Endpoint::B2UpdateBucket, &request_body)
+            .await
+    }
+
+    /// Adds a replication rule to a bucket that's already a replication source.
+    /// <br> The bucket's replication configuration is read, changed, and written back with `ifRevisionIs`,
+    /// starting over when another writer updated the bucket in between.
+    /// <br> Fails with [InvalidValue](B2Error::InvalidValue) if a rule with the same name exists,
+    /// or the bucket is a replication destination.
+    pub async fn add_replication_rule(
+        &self,
+        bucket_id: String,
+        rule: B2ReplicationRule,
+    ) -> Result<B2Bucket, B2Error> {
+        self.update_replication_rules(bucket_id, |replication_rules| {
+            if replication_rules
+                .iter()
+                .any(|existing| existing.replication_rule_name == rule.replication_rule_name)
+            {
+                return Err(B2Error::InvalidValue(InvalidValue {
+                    object_name: "B2ReplicationRule".into(),
+                    value_name: "replication_rule_name".into(),
+                    value_as_string: rule.replication_rule_name.clone(),
+                    expected: "a name no other rule of the bucket has".into(),
+                }));
+            }
+
+            replication_rules.push(rule.clone());
+
+            Ok(true)
+        })
+        .await
+    }
 
-        B2SimpleClient::handle_response(response).await
+    /// Pauses or resumes a bucket's replication rule, the bucket is updated the same way as [add_replication_rule](B2SimpleClient::add_replication_rule).
+    /// <br> Nothing is sent if the rule is already in the wanted state.
+    /// Fails with [InvalidValue](B2Error::InvalidValue) if the bucket has no rule with this name.
+    pub async fn set_rule_enabled(
+        &self,
+        bucket_id: String,
+        rule_name: &str,
+        enabled: bool,
+    ) -> Result<B2Bucket, B2Error> {
+        self.update_replication_rules(bucket_id, |replication_rules| {
+            let rule = replication_rules
+                .iter_mut()
+                .find(|rule| rule.replication_rule_name == rule_name)
+                .ok_or_else(|| {
+                    B2Error::InvalidValue(InvalidValue {
+                        object_name: "B2ReplicationRule".into(),
+                        value_name: "replication_rule_name".into(),
+                        value_as_string: rule_name.to_string(),
+                        expected: "the name of one of the bucket's rules".into(),
+                    })
+                })?;
+
+            let changed = rule.is_enabled != enabled;
+            rule.is_enabled = enabled;
+
+            Ok(changed)
+        })
+        .await
+    }
+
+    /// Updates a bucket without overwriting other writers' changes: fetches the bucket, lets `mutator` fill the update body,
+    /// and sends it with [if_revision_is](B2UpdateBucketBody::if_revision_is) set to the fetched revision.
+    /// When another writer updated the bucket in between, B2 rejects the update and it starts over from the fetch.
+    /// <br> The body is handed to `mutator` with the account and bucket IDs set, returning false skips the update
+    /// and returns the fetched bucket, an error stops it.
+    /// <br> Gives up with the conflict error after 5 tries.
+    pub async fn update_bucket_with_retry<F>(
+        &self,
+        bucket_id: String,
+        mut mutator: F,
+    ) -> Result<B2Bucket, B2Error>
+    where
+        F: FnMut(&B2Bucket, &mut B2UpdateBucketBody) -> Result<bool, B2Error>,
+    {
+        let mut attempt = 0;
+
+        loop {
+            attempt += 1;
+
+            let bucket = self
+                .get_bucket(bucket_id.clone())
+                .await?
+                .ok_or_else(|| bucket_not_found(&bucket_id))?;
+
+            let mut request_body = B2UpdateBucketBody::builder()
+                .account_id(bucket.account_id.clone())
+                .bucket_id(bucket.bucket_id.clone())
+                .build();
+
+            if !mutator(&bucket, &mut request_body)? {
+                return Ok(bucket);
+            }
+
+            request_body.if_revision_is = Some(bucket.revision);
+
+            match self.update_bucket(request_body).await {
+                Err(error) if error.is_conflict() && attempt < BUCKET_REVISION_ATTEMPTS => continue,
+                result => return result,
+            }
+        }
+    }
+
+    /// Applies `modify` to the bucket's replication rules through [update_bucket_with_retry](B2SimpleClient::update_bucket_with_retry).
+    async fn update_replication_rules<F>(
+        &self,
+        bucket_id: String,
+        mut modify: F,
+    ) -> Result<B2Bucket, B2Error>
+    where
+        F: FnMut(&mut Vec<B2ReplicationRule>) -> Result<bool, B2Error>,
+    {
+        self.update_bucket_with_retry(bucket_id, |bucket, request_body| {
+            let mut configuration = bucket.replication_configuration.clone();
+
+            let B2ReplicationConfig::AsReplicationSource {
+                replication_rules, ..
+            } = &mut configuration
+            else {
+                return Err(B2Error::InvalidValue(InvalidValue {
+                    object_name: "B2Bucket".into(),
+                    value_name: "replication_configuration".into(),
+                    value_as_string: "asReplicationDestination".into(),
+                    expected: "asReplicationSource".into(),
+                }));
+            };
+
+            if !modify(replication_rules)? {
+                return Ok(false);
+            }
+
+            request_body.replication_configuration = Some(configuration);
+
+            Ok(true)
+        })
+        .await
     }
 
     /// [b2_update_file_legal_hold](https://www.backblaze.com/apidocs/b2-update-file-legal-hold)
@@ -546,13 +1285,8 @@ impl B2SimpleClient {
     ) -> Result<B2UpdateFileLegalHoldBodyResponse, B2Error> {
         self.has_capabilities(&[B2KeyCapability::WriteFileLegalHolds])?;
 
-        let response = self
-            .create_request_with_token(Method::POST, B2Endpoint::B2UpdateFileLegalHold)
-            .json(&request_body)
-            .send()
-            .await;
-
-        B2SimpleClient::handle_response(response).await
+        self.send_api_request(B2Endpoint::B2UpdateFileLegalHold, &request_body)
+            .await
     }
 
     /// [b2_update_file_retention](https://www.backblaze.com/apidocs/b2-update-file-retention)
@@ -562,13 +1296,8 @@ impl B2SimpleClient {
     ) -> Result<B2UpdateFileRetentionResponse, B2Error> {
         self.has_capabilities(&[B2KeyCapability::WriteFileRetentions])?;
 
-        let response = self
-            .create_request_with_token(Method::POST, B2Endpoint::B2UpdateFileRetention)
-            .json(&request_body)
-            .send()
-            .await;
-
-        B2SimpleClient::handle_response(response).await
+        self.send_api_request(B2Endpoint::B2UpdateFileRetention, &request_body)
+            .await
     }
 
     /// [b2_upload_file](https://www.backblaze.com/apidocs/b2-upload-file)
@@ -579,10 +1308,7 @@ impl B2SimpleClient {
         request_headers: B2UploadFileHeaders,
         file_info: Option<HashMap<S, impl AsRef<str>>>,
     ) -> Result<B2File, B2Error> {
-        let file_info = match file_info {
-            Some(map) => map,
-            None => HashMap::new(),
-        };
+        let file_info = file_info.unwrap_or_default();
 
         let file_info: HashMap<_, _> = file_info
             .iter()
@@ -590,26 +1316,30 @@ impl B2SimpleClient {
                 let key_ref = key.as_ref();
                 (
                     format!("X-Bz-Info-{key_ref}"),
-                    utf8_percent_encode(value.as_ref(), ENCODE_SET).to_string(),
+                    encode_file_info_value(value),
                 )
             })
             .collect();
 
         let mut request_headers = request_headers;
+        let bytes_sent = request_headers.content_length;
 
-        request_headers.file_name =
-            utf8_percent_encode(&request_headers.file_name, ENCODE_SET).to_string();
+        request_headers.file_name = encode_header_value(&request_headers.file_name);
 
-        let response = self
-            .client
+        let request = self
+            .upload_client
             .request(Method::POST, upload_url.as_ref())
             .headers(request_headers.into_header_map()?)
             .headers(hash_map_to_headers(file_info))
-            .body(file)
-            .send()
-            .await;
+            .body(file);
+
+        self.endpoint_stats
+            .timed(B2Endpoint::B2UploadFile, self.clock.as_ref(), async {
+                endpoint_stats::note_bytes_sent(bytes_sent);
 
-        B2SimpleClient::handle_response(response).await
+                B2SimpleClient::handle_response(self.send(request).await, self.clock.as_ref()).await
+            })
+            .await
     }
 
     /// []()
@@ -619,21 +1349,170 @@ impl B2SimpleClient {
         part: F,
         upload_url: String,
     ) -> Result<B2FilePart, B2Error> {
-        let response = self
-            .client
+        #[cfg(feature = "tracing")]
+        let part_number = request_headers.part_number;
+        let bytes_sent = request_headers.content_length;
+
+        let request = self
+            .upload_client
             .request(Method::POST, upload_url)
             .headers(request_headers.into_header_map()?)
-            .body(part)
-            .send()
-            .await;
+            .body(part);
 
-        B2SimpleClient::handle_response(response).await
+        self.endpoint_stats
+            .timed(B2Endpoint::B2UploadPart, self.clock.as_ref(), async {
+                #[cfg(feature = "tracing")]
+                tracing::Span::current().record("part_number", part_number);
+                endpoint_stats::note_bytes_sent(bytes_sent);
+
+                B2SimpleClient::handle_response(self.send(request).await, self.clock.as_ref()).await
+            })
+            .await
+    }
+
+    /// Calls made by this client and its clones per endpoint, with their latencies, see [EndpointStatsSnapshot::transactions]
+    /// to estimate transaction costs.
+    pub fn endpoint_stats(&self) -> EndpointStatsSnapshot {
+        self.endpoint_stats.snapshot()
+    }
+
+    /// Zeroes the [endpoint stats](B2SimpleClient::endpoint_stats).
+    pub fn reset_endpoint_stats(&self) {
+        self.endpoint_stats.reset()
     }
 
     pub fn get_authorization_token(&self) -> &str {
         &self.auth_data.authorization_token
     }
 
+    /// The identifier of the authorized account.
+    pub fn account_id(&self) -> &str {
+        &self.auth_data.account_id
+    }
+
+    /// The part size in bytes recommended for large file uploads.
+    pub fn recommended_part_size(&self) -> u64 {
+        self.auth_data
+            .api_info
+            .storage_api
+            .recommended_part_size
+            .get()
+    }
+
+    /// The smallest part size in bytes allowed for every part of a large file except the last one.
+    pub fn min_part_size(&self) -> u64 {
+        self.auth_data
+            .api_info
+            .storage_api
+            .absolute_minimum_part_size
+            .get()
+    }
+
+    /// The host the account is authorized on, refer to [B2SimpleClientBuilder::with_api_base_url].
+    pub fn authorize_base_url(&self) -> &str {
+        &self.authorize_base_url
+    }
+
+    /// The base URL used for all API calls other than uploads and downloads.
+    pub fn api_base_url(&self) -> &str {
+        &self.auth_data.api_info.storage_api.api_url
+    }
+
+    /// The base URL used for downloading files.
+    pub fn download_base_url(&self) -> &str {
+        &self.auth_data.api_info.storage_api.download_url
+    }
+
+    /// The friendly URL [b2_download_file_by_name](B2SimpleClient::download_file_by_name) downloads the file from,
+    /// with the authorization's token as the `Authorization` query parameter, and the overrides as their query parameters.
+    /// <br> Anyone with the URL can download the file until the token expires, so it can be handed out as an expiring link.
+    /// The URL of a public bucket's file works without an authorization. The overrides' range isn't part of the URL, since it's sent as a header.
+    /// ```rust,no_run
+    /// # use backblaze_b2_client::{definitions::{bodies::B2GetDownloadAuthorizationBody, query_params::B2DownloadFileQueryParameters}, simple_client::B2SimpleClient};
+    /// # async fn example(client: B2SimpleClient, bucket_id: String) -> Result<(), backblaze_b2_client::error::B2Error> {
+    /// let auth = client
+    ///     .get_download_authorization(
+    ///         B2GetDownloadAuthorizationBody::builder()
+    ///             .bucket_id(bucket_id)
+    ///             .file_name_prefix("reports/".into())
+    ///             .valid_duration_in_seconds(3600)
+    ///             .build(),
+    ///     )
+    ///     .await?;
+    ///
+    /// let url = client.make_download_url(
+    ///     "my-bucket",
+    ///     "reports/march 2024.pdf",
+    ///     Some(&auth),
+    ///     Some(&B2DownloadFileQueryParameters::builder()
+    ///         .b2_content_disposition(Some("attachment".into()))
+    ///         .build()),
+    /// );
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn make_download_url(
+        &self,
+        bucket_name: &str,
+        file_name: &str,
+        auth: Option<&B2GetDownloadAuthorizationBodyResponse>,
+        query_overrides: Option<&B2DownloadFileQueryParameters>,
+    ) -> String {
+        let mut url = format!(
+            "{}/file/{}/{}",
+            self.download_base_url(),
+            encode_url_path_segment(bucket_name),
+            encode_url_path(file_name)
+        );
+
+        let mut query: Vec<(String, String)> = vec![];
+
+        if let Some(auth) = auth {
+            query.push(("Authorization".into(), auth.authorization_token.clone()));
+        }
+
+        if let Some(Ok(serde_json::Value::Object(overrides))) =
+            query_overrides.map(serde_json::to_value)
+        {
+            query.extend(
+                overrides
+                    .into_iter()
+                    .filter_map(|(name, value)| match value {
+                        serde_json::Value::String(value) => Some((name, value)),
+                        _ => None,
+                    }),
+            );
+        }
+
+        for (index, (name, value)) in query.iter().enumerate() {
+            url.push(if index == 0 { '?' } else { '&' });
+            url.push_str(&encode_query_value(name));
+            url.push('=');
+            url.push_str(&encode_query_value(value));
+        }
+
+        url
+    }
+
+    /// Sends an unauthenticated GET to the url, any response counts as the host being reachable.
+    /// <br> Returns how long the response took, and the server's `Date` header if it sent a valid one.
+    pub(crate) async fn probe_host(
+        &self,
+        url: &str,
+    ) -> Result<(Duration, Option<SystemTime>), reqwest::Error> {
+        let start = self.clock.now();
+        let response = self.send(self.client.get(url)).await?;
+        let latency = self.clock.now().saturating_duration_since(start);
+
+        let date = response
+            .headers()
+            .get("date")
+            .and_then(|date| date.to_str().ok())
+            .and_then(|date| httpdate::parse_http_date(date).ok());
+
+        Ok((latency, date))
+    }
+
     pub fn has_capability(&self, capability: &B2KeyCapability) -> bool {
         self.auth_data
             .api_info
@@ -642,6 +1521,9 @@ impl B2SimpleClient {
             .contains(capability)
     }
 
+    /// Checks the key has every capability before a call is made, so a missing one fails without a round trip.
+    /// <br> Without the `capability-checks` feature this is a no-op, and B2 rejects the call instead.
+    #[cfg(feature = "capability-checks")]
     pub fn has_capabilities(&self, capabilities: &[B2KeyCapability]) -> Result<(), B2Error> {
         for capability in capabilities {
             if !self.has_capability(capability) {
@@ -652,13 +1534,17 @@ impl B2SimpleClient {
         Ok(())
     }
 
+    /// Checks the key has every capability before a call is made, so a missing one fails without a round trip.
+    /// <br> Without the `capability-checks` feature this is a no-op, and B2 rejects the call instead.
+    #[cfg(not(feature = "capability-checks"))]
+    #[inline(always)]
+    pub fn has_capabilities(&self, _capabilities: &[B2KeyCapability]) -> Result<(), B2Error> {
+        Ok(())
+    }
+
     #[inline]
     fn create_request_url(&self, api_name: B2Endpoint) -> String {
-        format!(
-            "{}/b2api/v3/{}",
-            self.auth_data.api_info.storage_api.api_url,
-            api_name.to_string()
-        )
+        self.api_version.endpoint_url(self.api_base_url(), api_name)
     }
 
     #[inline]
@@ -670,9 +1556,179 @@ impl B2SimpleClient {
             .header("Authorization", self.get_authorization_token())
     }
 
+    /// Creates an API call request with the method picked by the [B2RequestMethodPolicy],
+    /// sending `params` as the query string for GET and as a JSON body otherwise.
+    fn create_api_request<T: Serialize + ?Sized>(
+        &self,
+        api_name: B2Endpoint,
+        params: &T,
+    ) -> RequestBuilder {
+        let documented_method = api_name.documented_method();
+        let method = match self.request_method_policy {
+            B2RequestMethodPolicy::Documented => documented_method.clone(),
+            B2RequestMethodPolicy::AlwaysPost => Method::POST,
+        };
+
+        let request = self.create_request_with_token(method.clone(), api_name);
+
+        if method == Method::GET {
+            return request.query(params);
+        }
+
+        if documented_method != Method::GET {
+            return request.json(params);
+        }
+
+        // Unset query parameters are left out of the query string, so they're left out of the body too
+        match serde_json::to_value(params) {
+            Ok(serde_json::Value::Object(mut body)) => {
+                body.retain(|_, value| !value.is_null());
+                request.json(&body)
+            }
+            _ => request.json(params),
+        }
+    }
+
+    /// Sends an API call made by [create_api_request](B2SimpleClient::create_api_request) and parses its response,
+    /// logging both when [debug logging](B2SimpleClient::with_debug_logging) is on.
+    async fn send_api_request<T: Serialize + ?Sized, R: DeserializeOwned>(
+        &self,
+        api_name: B2Endpoint,
+        params: &T,
+    ) -> Result<R, B2Error> {
+        let Some(retry_strategy) = self
+            .retry_strategy
+            .as_ref()
+            .filter(|_| api_name.is_idempotent())
+        else {
+            return self.send_timed_api_request(api_name, params).await;
+        };
+
+        let max_attempts = retry_strategy.max_attempts();
+        let endpoint = api_name.to_string();
+        let mut attempt = 0;
+
+        loop {
+            attempt += 1;
+
+            let error = match self.send_timed_api_request(api_name.clone(), params).await {
+                Ok(response) => return Ok(response),
+                Err(error) => error,
+            };
+
+            if !error.is_retryable() || attempt >= max_attempts {
+                return Err(error);
+            }
+
+            let wait = self.clock.sleep(retry_strategy.retry_wait(&RetryContext {
+                endpoint: &endpoint,
+                attempt: attempt + 1,
+                last_error: Some(&error),
+                retry_after: error.retry_after(),
+            }));
+
+            run_on(self.runtime.as_ref(), wait).await;
+        }
+    }
+
+    async fn send_timed_api_request<T: Serialize + ?Sized, R: DeserializeOwned>(
+        &self,
+        api_name: B2Endpoint,
+        params: &T,
+    ) -> Result<R, B2Error> {
+        self.endpoint_stats
+            .timed(
+                api_name.clone(),
+                self.clock.as_ref(),
+                self.send_logged_api_request(api_name, params),
+            )
+            .await
+    }
+
+    async fn send_logged_api_request<T: Serialize + ?Sized, R: DeserializeOwned>(
+        &self,
+        api_name: B2Endpoint,
+        params: &T,
+    ) -> Result<R, B2Error> {
+        let request = self.create_api_request(api_name.clone(), params);
+
+        let Some(debug_logging) = self.debug_logging else {
+            return B2SimpleClient::handle_response(
+                self.send_noted(request).await,
+                self.clock.as_ref(),
+            )
+            .await;
+        };
+
+        let request_id = format!("{:08x}", rand::random::<u32>());
+        let max_body_len = debug_logging.max_body_len;
+
+        tracing::debug!(
+            request_id,
+            endpoint = %api_name,
+            params = %serde_json::to_value(params)
+                .map(|value| loggable_json(value, max_body_len))
+                .unwrap_or_default(),
+            "B2 request"
+        );
+
+        let start = self.clock.now();
+        let response = B2SimpleClient::response_option_handling(
+            self.send_noted(request).await,
+            self.clock.as_ref(),
+        )
+        .await;
+
+        let response = match response {
+            Ok(response) => response,
+            Err(error) => {
+                tracing::debug!(
+                    request_id,
+                    endpoint = %api_name,
+                    elapsed = ?self.clock.now().saturating_duration_since(start),
+                    %error,
+                    "B2 request failed"
+                );
+                return Err(error);
+            }
+        };
+
+        let status = response.status().as_u16();
+        let text = response.text().await.map_err(B2Error::RequestSendError)?;
+
+        tracing::debug!(
+            request_id,
+            endpoint = %api_name,
+            status,
+            elapsed = ?self.clock.now().saturating_duration_since(start),
+            body = %loggable_body(&text, max_body_len),
+            "B2 response"
+        );
+
+        serde_json::from_str::<R>(&text).map_err(B2Error::JsonParseError)
+    }
+
+    /// Sends the request, noting its body's size for the [metrics](B2SimpleClientBuilder::with_metrics) first.
+    async fn send_noted(&self, request: RequestBuilder) -> Result<Response, reqwest::Error> {
+        let (client, request) = request.build_split();
+        let request = request?;
+
+        if let Some(body) = request.body().and_then(|body| body.as_bytes()) {
+            endpoint_stats::note_bytes_sent(body.len() as u64);
+        }
+
+        run_on(self.runtime.as_ref(), client.execute(request)).await
+    }
+
+    /// Sends the request on the [runtime](B2SimpleClient::with_runtime).
+    async fn send(&self, request: RequestBuilder) -> Result<Response, reqwest::Error> {
+        run_on(self.runtime.as_ref(), request.send()).await
+    }
+
     #[inline]
     async fn response_option_handling(
         response: Result<Response, reqwest::Error>,
+        clock: &dyn Clock,
     ) -> Result<Response, B2Error> {
         let response = match response {
             Ok(resp) => resp,
@@ -683,7 +1739,26 @@ impl B2SimpleClient {
 
         let response_code = response.status().as_u16();
 
+        endpoint_stats::note_response(response_code, response.content_length());
+
+        #[cfg(feature = "tracing")]
+        tracing::debug!(
+            status = response_code,
+            request_id = response
+                .headers()
+                .get("x-bz-request-id")
+                .and_then(|value| value.to_str().ok()),
+            bytes_received = response.content_length(),
+            "B2 response"
+        );
+
         if response_code >= 400 {
+            let retry_after = response
+                .headers()
+                .get(reqwest::header::RETRY_AFTER)
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| B2SimpleClient::parse_retry_after(value, clock.system_time()));
+
             let response = match response.bytes().await {
                 Ok(text) => text,
                 Err(_) => {
@@ -691,41 +1766,84 @@ impl B2SimpleClient {
                         status: NonZeroU16::new(response_code).expect("Response code cannot be 0"),
                         code: String::from(""),
                         message: Some(String::from("B2Client failed to collect")),
+                        retry_after,
                     }))
                 }
             };
 
             let error_json: B2RequestError = match serde_json::from_slice(&response) {
-                Ok(json) => json,
+                Ok(json) => B2RequestError {
+                    retry_after,
+                    ..json
+                },
                 Err(_) => B2RequestError {
                     status: NonZeroU16::new(response_code).expect("Response code cannot be 0"),
                     code: String::from(""),
-                    message: Some(String::from(format!(
+                    message: Some(format!(
                         "B2Client failed to parse response as json, returned string: {}",
                         String::from_utf8_lossy(&response)
-                    ))),
+                    )),
+                    retry_after,
                 },
             };
 
-            return Err(B2Error::RequestError(error_json));
+            return Err(B2Error::from_request_error(error_json));
         };
 
         Ok(response)
     }
 
+    /// Parses a `Retry-After` header, either a number of seconds or an HTTP date to wait until.
+    fn parse_retry_after(value: &str, now: SystemTime) -> Option<Duration> {
+        let value = value.trim();
+
+        if let Ok(seconds) = value.parse::<u64>() {
+            return Some(Duration::from_secs(seconds));
+        }
+
+        let until = httpdate::parse_http_date(value).ok()?;
+
+        Some(until.duration_since(now).unwrap_or(Duration::ZERO))
+    }
+
+    #[inline]
+    fn download_range_header(
+        request_query_params: &Option<B2DownloadFileQueryParameters>,
+    ) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+
+        let range = request_query_params
+            .as_ref()
+            .and_then(|params| params.range.as_deref())
+            .and_then(|range| HeaderValue::from_str(range).ok());
+
+        if let Some(range) = range {
+            headers.insert(reqwest::header::RANGE, range);
+        }
+
+        headers
+    }
+
+    #[inline]
+    fn file_not_found_as_none<T>(result: Result<T, B2Error>) -> Result<Option<T>, B2Error> {
+        match result {
+            Ok(value) => Ok(Some(value)),
+            Err(B2Error::FileNotFound(_)) => Ok(None),
+            Err(error) => Err(error),
+        }
+    }
+
     #[inline]
     async fn handle_response<T: DeserializeOwned>(
         response: Result<Response, reqwest::Error>,
+        clock: &dyn Clock,
     ) -> Result<T, B2Error> {
-        let response = match B2SimpleClient::response_option_handling(response).await {
+        let response = match B2SimpleClient::response_option_handling(response, clock).await {
             Ok(resp) => resp,
             Err(error) => return Err(error),
         };
 
-        let text = response
-            .text()
-            .await
-            .map_err(|err| B2Error::RequestSendError(err))?;
+        let text = response.text().await.map_err(B2Error::RequestSendError)?;
 
         match serde_json::from_str::<T>(&text) {
             Ok(json) => Ok(json),
@@ -736,51 +1854,12 @@ impl B2SimpleClient {
     #[inline]
     async fn handle_file_response(
         response: Result<Response, reqwest::Error>,
+        clock: &dyn Clock,
     ) -> Result<B2DownloadFileContent, B2Error> {
-        let response = match response {
-            Ok(resp) => resp,
-            Err(error) => return Err(B2Error::RequestSendError(error)),
-        };
+        let response = B2SimpleClient::response_option_handling(response, clock).await?;
 
         let mut headers = header_map_to_hashmap(response.headers());
-        let file_name = headers.remove("x-bz-file-name").expect("should exist");
-        let file_name = utf8_percent_encode(&file_name.replace("+", " "), ENCODE_SET).to_string();
-
-        let sha1 = headers.remove("x-bz-content-sha1").expect("should exist");
-
-        let mut file_details = B2FileDownloadDetails {
-            file_id: headers.remove("x-bz-file-id").expect("should exist"),
-            file_name,
-            content_length: headers
-                .remove("content-length")
-                .expect("should exist")
-                .parse()
-                .expect("valid number"),
-            content_type: headers.remove("content-type").expect("should exist"),
-            content_sha1: if sha1 != "none" { Some(sha1) } else { None },
-            upload_timestamp: headers
-                .remove("x-bz-upload-timestamp")
-                .expect("should exist")
-                .parse()
-                .expect("valid number"),
-            file_info: None,
-        };
-
-        let mut temp_file_info: HashMap<String, String> = HashMap::new();
-        let keys: Vec<String> = headers.keys().map(|e| e.clone()).collect();
-
-        for key in keys {
-            if key.starts_with("x-bz-info-") {
-                let value = headers.remove(&key).expect("key exists");
-                let value = utf8_percent_encode(&value.replace("+", " "), ENCODE_SET).to_string();
-
-                temp_file_info.insert(key.replace("x-bz-info-", ""), value);
-            }
-        }
-
-        if temp_file_info.len() > 0 {
-            file_details.file_info = Some(temp_file_info)
-        }
+        let file_details = B2FileDownloadDetails::from_headers(&mut headers)?;
 
         let body = response.bytes_stream();
 
@@ -792,6 +1871,16 @@ impl B2SimpleClient {
     }
 }
 
+/// The error B2 responds with for an unknown bucket ID, for lookups that come back empty instead.
+fn bucket_not_found(bucket_id: &str) -> B2Error {
+    B2Error::BucketNotFound(B2RequestError {
+        status: NonZeroU16::new(400).expect("400 is not zero"),
+        code: "bad_bucket_id".into(),
+        message: Some(format!("Invalid bucketId: {}", bucket_id)),
+        retry_after: None,
+    })
+}
+
 #[inline]
 fn hash_map_to_headers<S: AsRef<str>>(map: HashMap<S, impl AsRef<str>>) -> HeaderMap {
     map.iter()
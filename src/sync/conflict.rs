@@ -0,0 +1,83 @@
+use std::{
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+/// What a sync does with a file changed on both sides since the last sync, needs a [state file](super::SyncState) to tell.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ConflictPolicy {
+    /// The side modified last is kept, the remote side wins a tie.
+    #[default]
+    NewestWins,
+    RemoteWins,
+    LocalWins,
+    /// The losing side is kept next to the winner under a conflict name, see [conflict_copy_path].
+    /// <br> The remote side takes the file's name.
+    KeepBoth,
+}
+
+/// Which side a conflict was resolved with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConflictWinner {
+    Local,
+    Remote,
+    Both,
+}
+
+/// A file changed on both sides, and how it was resolved.
+#[derive(Debug, Clone)]
+pub struct SyncConflict {
+    /// The file's name relative to the synced prefix.
+    pub relative_name: String,
+    pub winner: ConflictWinner,
+    /// Where the losing copy was kept, with [ConflictPolicy::KeepBoth].
+    pub kept_copy: Option<PathBuf>,
+}
+
+impl ConflictPolicy {
+    /// Picks the winning side given when each side was last modified.
+    pub fn resolve(
+        &self,
+        local_modified: SystemTime,
+        remote_modified: SystemTime,
+    ) -> ConflictWinner {
+        match self {
+            Self::NewestWins if local_modified > remote_modified => ConflictWinner::Local,
+            Self::NewestWins | Self::RemoteWins => ConflictWinner::Remote,
+            Self::LocalWins => ConflictWinner::Local,
+            Self::KeepBoth => ConflictWinner::Both,
+        }
+    }
+}
+
+/// The name a conflicting copy is kept under, the modification time in millis is added before the extension.
+/// ```rust
+/// # use std::path::Path;
+/// # use backblaze_b2_client::sync::conflict_copy_path;
+/// assert_eq!(
+///     conflict_copy_path(Path::new("docs/report.txt"), 1700000000000),
+///     Path::new("docs/report.conflict-1700000000000.txt")
+/// );
+/// assert_eq!(
+///     conflict_copy_path(Path::new("Makefile"), 5),
+///     Path::new("Makefile.conflict-5")
+/// );
+/// ```
+pub fn conflict_copy_path(path: &Path, modified_millis: u64) -> PathBuf {
+    let stem = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default();
+
+    let name = match path.extension() {
+        Some(extension) => format!(
+            "{}.conflict-{}.{}",
+            stem,
+            modified_millis,
+            extension.to_string_lossy()
+        ),
+        None => format!("{}.conflict-{}", stem, modified_millis),
+    };
+
+    path.with_file_name(name)
+}
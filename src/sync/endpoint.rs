@@ -0,0 +1,255 @@
+use std::{
+    fmt,
+    num::NonZeroU16,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use crate::{
+    definitions::shared::B2Bucket,
+    error::{B2Error, B2RequestError},
+    simple_client::B2SimpleClient,
+    util::InvalidValue,
+};
+
+const B2_SCHEME: &str = "b2://";
+
+/// A directory on the local filesystem, one side of a sync.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct LocalPath(pub PathBuf);
+
+impl LocalPath {
+    pub fn new<P: Into<PathBuf>>(path: P) -> Self {
+        Self(path.into())
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl fmt::Display for LocalPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0.display())
+    }
+}
+
+/// Files under a prefix in a bucket, one side of a sync, written as `b2://bucket/prefix`.
+/// ```rust
+/// # use backblaze_b2_client::sync::B2Location;
+/// let location: B2Location = "b2://photos/2024/march/".parse().unwrap();
+///
+/// assert_eq!(location.bucket, "photos");
+/// assert_eq!(location.prefix, "2024/march/");
+/// assert_eq!(location.to_string(), "b2://photos/2024/march/");
+///
+/// let whole_bucket: B2Location = "b2://photos".parse().unwrap();
+/// assert_eq!(whole_bucket.prefix, "");
+///
+/// // The prefix is a folder, so `backups` doesn't take in `backups2/`
+/// let folder = B2Location::new("photos", "backups");
+/// assert_eq!(folder.prefix, "backups/");
+/// assert_eq!(folder.file_name("a.jpg"), "backups/a.jpg");
+///
+/// assert!("photos/2024".parse::<B2Location>().is_err());
+/// assert!("b2:///2024".parse::<B2Location>().is_err());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct B2Location {
+    /// The bucket's name.
+    pub bucket: String,
+    /// Prefix of the file names, empty for the whole bucket. A file's name relative to the location is the rest of its name.
+    /// <br> Treated as a folder, a prefix not ending in `/` is used with one, refer to [folder_prefix](B2Location::folder_prefix).
+    pub prefix: String,
+}
+
+impl B2Location {
+    /// Creates the location, adding a `/` to a prefix that doesn't end in one.
+    pub fn new<B: Into<String>, P: Into<String>>(bucket: B, prefix: P) -> Self {
+        let mut location = Self {
+            bucket: bucket.into(),
+            prefix: prefix.into(),
+        };
+        location.prefix = location.folder_prefix();
+
+        location
+    }
+
+    /// The prefix ending in `/`, or empty for the whole bucket, so only files inside the folder are listed.
+    pub fn folder_prefix(&self) -> String {
+        match self.prefix.is_empty() || self.prefix.ends_with('/') {
+            true => self.prefix.clone(),
+            false => format!("{}/", self.prefix),
+        }
+    }
+
+    /// The full B2 file name of a file at `relative_name` under the location.
+    pub fn file_name(&self, relative_name: &str) -> String {
+        format!("{}{}", self.folder_prefix(), relative_name)
+    }
+
+    /// Looks up the location's bucket by name, failing with [B2Error::BucketNotFound] when there's no such bucket.
+    pub async fn bucket(&self, client: &B2SimpleClient) -> Result<B2Bucket, B2Error> {
+        client
+            .get_bucket_by_name(self.bucket.clone())
+            .await?
+            .ok_or_else(|| {
+                B2Error::BucketNotFound(B2RequestError {
+                    status: NonZeroU16::new(400).expect("400 is not zero"),
+                    code: "bad_bucket_id".into(),
+                    message: Some(format!("No bucket named {}", self.bucket)),
+                    retry_after: None,
+                })
+            })
+    }
+}
+
+impl FromStr for B2Location {
+    type Err = InvalidValue;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let invalid = || InvalidValue {
+            object_name: "B2Location".into(),
+            value_name: "uri".into(),
+            value_as_string: value.into(),
+            expected: "b2://bucket/prefix".into(),
+        };
+
+        let rest = value.strip_prefix(B2_SCHEME).ok_or_else(invalid)?;
+        let (bucket, prefix) = rest.split_once('/').unwrap_or((rest, ""));
+
+        if bucket.is_empty() {
+            return Err(invalid());
+        }
+
+        Ok(Self::new(bucket, prefix))
+    }
+}
+
+impl fmt::Display for B2Location {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}/{}", B2_SCHEME, self.bucket, self.prefix)
+    }
+}
+
+/// Either side of a sync, parsed as a [B2Location] when it starts with `b2://` and as a [LocalPath] otherwise.
+/// ```rust
+/// # use backblaze_b2_client::sync::{B2Location, LocalPath, SyncEndpoint};
+/// let remote: SyncEndpoint = "b2://backups/laptop/".parse().unwrap();
+/// assert_eq!(remote, SyncEndpoint::B2(B2Location::new("backups", "laptop/")));
+///
+/// let local: SyncEndpoint = "./documents".parse().unwrap();
+/// assert_eq!(local, SyncEndpoint::Local(LocalPath::new("./documents")));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum SyncEndpoint {
+    Local(LocalPath),
+    B2(B2Location),
+}
+
+impl FromStr for SyncEndpoint {
+    type Err = InvalidValue;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.starts_with(B2_SCHEME) {
+            true => value.parse().map(Self::B2),
+            false => Ok(Self::Local(LocalPath::new(value))),
+        }
+    }
+}
+
+impl fmt::Display for SyncEndpoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Local(path) => write!(f, "{}", path),
+            Self::B2(location) => write!(f, "{}", location),
+        }
+    }
+}
+
+impl From<LocalPath> for SyncEndpoint {
+    fn from(path: LocalPath) -> Self {
+        Self::Local(path)
+    }
+}
+
+impl From<B2Location> for SyncEndpoint {
+    fn from(location: B2Location) -> Self {
+        Self::B2(location)
+    }
+}
+
+/// What a sync does, picked from its source and destination endpoints.
+/// ```rust
+/// # use backblaze_b2_client::sync::SyncMode;
+/// let mode = SyncMode::from_endpoints("./photos".parse().unwrap(), "b2://photos/".parse().unwrap());
+/// assert!(matches!(mode, Ok(SyncMode::Upload { .. })));
+///
+/// let mode = SyncMode::from_endpoints("b2://photos/".parse().unwrap(), "b2://archive/photos/".parse().unwrap());
+/// assert!(matches!(mode, Ok(SyncMode::Copy { .. })));
+///
+/// assert!(SyncMode::from_endpoints("./a".parse().unwrap(), "./b".parse().unwrap()).is_err());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SyncMode {
+    /// Pushes local files to B2.
+    Upload {
+        source: LocalPath,
+        destination: B2Location,
+    },
+    /// Pulls B2 files to the local filesystem.
+    Download {
+        source: B2Location,
+        destination: LocalPath,
+    },
+    /// Copies files between two B2 locations, server side.
+    Copy {
+        source: B2Location,
+        destination: B2Location,
+    },
+}
+
+impl SyncMode {
+    /// Fails when both endpoints are local, that's not something B2 is needed for.
+    pub fn from_endpoints(
+        source: SyncEndpoint,
+        destination: SyncEndpoint,
+    ) -> Result<Self, InvalidValue> {
+        match (source, destination) {
+            (SyncEndpoint::Local(source), SyncEndpoint::B2(destination)) => Ok(Self::Upload {
+                source,
+                destination,
+            }),
+            (SyncEndpoint::B2(source), SyncEndpoint::Local(destination)) => Ok(Self::Download {
+                source,
+                destination,
+            }),
+            (SyncEndpoint::B2(source), SyncEndpoint::B2(destination)) => Ok(Self::Copy {
+                source,
+                destination,
+            }),
+            (SyncEndpoint::Local(source), SyncEndpoint::Local(destination)) => Err(InvalidValue {
+                object_name: "SyncMode".into(),
+                value_name: "endpoints".into(),
+                value_as_string: format!("{} -> {}", source, destination),
+                expected: "at least one b2:// endpoint".into(),
+            }),
+        }
+    }
+
+    pub fn source(&self) -> SyncEndpoint {
+        match self {
+            Self::Upload { source, .. } => source.clone().into(),
+            Self::Download { source, .. } | Self::Copy { source, .. } => source.clone().into(),
+        }
+    }
+
+    pub fn destination(&self) -> SyncEndpoint {
+        match self {
+            Self::Download { destination, .. } => destination.clone().into(),
+            Self::Upload { destination, .. } | Self::Copy { destination, .. } => {
+                destination.clone().into()
+            }
+        }
+    }
+}
@@ -0,0 +1,13 @@
+pub mod conflict;
+pub mod endpoint;
+pub mod plan;
+pub mod state;
+pub mod sync_down;
+pub mod sync_up;
+
+pub use conflict::*;
+pub use endpoint::*;
+pub use plan::*;
+pub use state::*;
+pub use sync_down::*;
+pub use sync_up::*;
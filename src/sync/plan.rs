@@ -0,0 +1,662 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use futures::{stream, StreamExt, TryStreamExt};
+use serde::{Deserialize, Serialize};
+use tokio_util::sync::CancellationToken;
+
+use crate::{
+    batch::{BatchExecutor, BatchOptions, DeletePlan, Destruction},
+    definitions::{
+        bodies::B2DeleteFileVersionBody,
+        query_params::B2ListFileNamesQueryParameters,
+        shared::{B2Action, B2File},
+    },
+    error::B2Error,
+    pagination::ListingGuard,
+    simple_client::B2SimpleClient,
+    tasks::{
+        error::TransferError,
+        shared::file_sha1,
+        upload::{B2FileUploadSettings, FileUpload, FileUploadOptions},
+    },
+    util::InvalidValue,
+};
+
+use super::{
+    local_files, millis, relative_path_name, remote_modified_time, remote_sha1,
+    sync_up::call_progress, B2Location, LocalPath, SyncCompare, SyncProgress, SyncRemoteExtraneous,
+    SyncUpOptions, SyncUpReport, PARTIAL_SUFFIX,
+};
+
+/// Something a [SyncPlan] does to a single file.
+/// <br> `relative_name` is relative to the synced prefix, `file_name` is the full name in the bucket.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "action", rename_all = "camelCase")]
+pub enum SyncAction {
+    /// Uploads the local file, which is new or differs from the remote one.
+    #[serde(rename_all = "camelCase")]
+    Upload {
+        path: PathBuf,
+        relative_name: String,
+        file_name: String,
+        size: u64,
+        modified_millis: Option<u64>,
+        /// Set when comparing by [SyncCompare::Sha1], stored as the `large_file_sha1` of large files.
+        sha1: Option<String>,
+    },
+    /// Hides the remote file, which doesn't exist locally.
+    #[serde(rename_all = "camelCase")]
+    Hide {
+        relative_name: String,
+        file_name: String,
+        size: u64,
+    },
+    /// Deletes the versions of the remote file listed when planning, the file doesn't exist locally.
+    /// <br> Versions uploaded after the plan was made are kept.
+    #[serde(rename_all = "camelCase")]
+    Delete {
+        relative_name: String,
+        file_name: String,
+        size: u64,
+        versions: Vec<PlannedVersion>,
+    },
+}
+
+/// A version of a remote file a [SyncAction::Delete] deletes.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlannedVersion {
+    pub file_id: String,
+    /// An unfinished large file, which is canceled instead of deleted.
+    pub unfinished: bool,
+}
+
+impl From<&B2File> for PlannedVersion {
+    fn from(version: &B2File) -> Self {
+        Self {
+            file_id: version.file_id.clone(),
+            unfinished: version.action == B2Action::Start,
+        }
+    }
+}
+
+impl SyncAction {
+    pub fn relative_name(&self) -> &str {
+        match self {
+            Self::Upload { relative_name, .. }
+            | Self::Hide { relative_name, .. }
+            | Self::Delete { relative_name, .. } => relative_name,
+        }
+    }
+
+    pub fn file_name(&self) -> &str {
+        match self {
+            Self::Upload { file_name, .. }
+            | Self::Hide { file_name, .. }
+            | Self::Delete { file_name, .. } => file_name,
+        }
+    }
+
+    /// Bytes of the local file for uploads, of the latest remote version otherwise.
+    pub fn size(&self) -> u64 {
+        match self {
+            Self::Upload { size, .. } | Self::Hide { size, .. } | Self::Delete { size, .. } => {
+                *size
+            }
+        }
+    }
+
+    pub fn is_upload(&self) -> bool {
+        matches!(self, Self::Upload { .. })
+    }
+}
+
+/// What a [sync_up](super::sync_up) would do, compared without touching the bucket.
+/// <br> Can be inspected, serialized for review, filtered, and then [executed](SyncPlan::execute), which does exactly the planned actions.
+/// ```rust
+/// # use backblaze_b2_client::sync::{PlannedVersion, SyncAction, SyncPlan};
+/// let plan = SyncPlan {
+///     bucket_id: "4a48fe8875c6214145260818".into(),
+///     actions: vec![
+///         SyncAction::Upload {
+///             path: "photos/a.jpg".into(),
+///             relative_name: "a.jpg".into(),
+///             file_name: "photos/a.jpg".into(),
+///             size: 1024,
+///             modified_millis: Some(1_717_027_200_000),
+///             sha1: None,
+///         },
+///         SyncAction::Delete {
+///             relative_name: "old.jpg".into(),
+///             file_name: "photos/old.jpg".into(),
+///             size: 2048,
+///             versions: vec![PlannedVersion { file_id: "4_z4a48fe8875c6214145260818_f1".into(), unfinished: false }],
+///         },
+///     ],
+///     ..Default::default()
+/// };
+///
+/// let json = serde_json::to_string(&plan).unwrap();
+/// assert!(json.contains("\"action\":\"delete\""));
+/// assert_eq!(plan.deleted_versions(), 1);
+///
+/// // Approve the uploads only
+/// let approved = serde_json::from_str::<SyncPlan>(&json).unwrap().filter(SyncAction::is_upload);
+/// assert_eq!(approved.len(), 1);
+/// assert_eq!(approved.upload_bytes(), 1024);
+/// ```
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncPlan {
+    pub bucket_id: String,
+    /// Uploads first, in name order, then hides or deletes, which run only after every upload finished.
+    pub actions: Vec<SyncAction>,
+    /// Local files that already match the remote ones.
+    pub unchanged: u64,
+    /// Bytes of the unchanged files.
+    pub unchanged_bytes: u64,
+    /// Local files that couldn't be compared, they're carried into the report as failures.
+    /// <br> Not serialized.
+    #[serde(skip)]
+    pub failed: Vec<(String, TransferError)>,
+}
+
+impl SyncPlan {
+    /// Compares the local directory against the files under the prefix, like [sync_up](super::sync_up) does, without uploading,
+    /// hiding or deleting anything. Uses the options' [compare](SyncUpOptions::compare), [extraneous](SyncUpOptions::extraneous),
+    /// [concurrency](SyncUpOptions::concurrency) and [naming](SyncUpOptions::naming).
+    /// <br> Listing the remote files or reading the local directory failing fails the plan, files that can't be compared are collected in [failed](SyncPlan::failed).
+    pub async fn sync_up(
+        client: &B2SimpleClient,
+        source: &LocalPath,
+        destination: &B2Location,
+        options: &SyncUpOptions,
+    ) -> Result<Self, TransferError> {
+        let bucket = destination.bucket(client).await?;
+        let prefix = destination.folder_prefix();
+
+        let remote_files: HashMap<String, B2File> = client
+            .list_all_file_names(
+                B2ListFileNamesQueryParameters::builder()
+                    .bucket_id(bucket.bucket_id.clone())
+                    .prefix((!prefix.is_empty()).then(|| prefix.clone()))
+                    .build(),
+                ListingGuard::default(),
+            )
+            .try_filter(|file| std::future::ready(file.action == B2Action::Upload))
+            .map_ok(|file| (file.file_name[prefix.len()..].to_string(), file))
+            .try_collect()
+            .await?;
+
+        let mut plan = SyncPlan {
+            bucket_id: bucket.bucket_id,
+            ..Default::default()
+        };
+        let mut local_names: HashMap<String, PathBuf> = HashMap::new();
+        let mut to_compare = vec![];
+
+        for path in local_files(source.path())
+            .await
+            .map_err(TransferError::SourceIo)?
+        {
+            if path.as_os_str().to_string_lossy().ends_with(PARTIAL_SUFFIX) {
+                continue;
+            }
+
+            match relative_path_name(source.path(), &path) {
+                Some(relative_name) => {
+                    let remote_name = match &options.naming {
+                        Some(naming) => naming.file_name(&relative_name),
+                        None => relative_name.clone(),
+                    };
+
+                    // Two files under one name would overwrite each other on every sync
+                    if let Some(other) = local_names.insert(remote_name.clone(), path.clone()) {
+                        return Err(InvalidValue {
+                            object_name: "SyncUpOptions".into(),
+                            value_name: "naming".into(),
+                            value_as_string: remote_name,
+                            expected: format!(
+                                "different names for {} and {}",
+                                other.display(),
+                                path.display()
+                            ),
+                        }
+                        .into());
+                    }
+
+                    to_compare.push((relative_name, remote_name, path));
+                }
+                None => plan.failed.push((
+                    path.to_string_lossy().into_owned(),
+                    InvalidValue {
+                        object_name: "LocalPath".into(),
+                        value_name: "path".into(),
+                        value_as_string: path.to_string_lossy().into_owned(),
+                        expected: "a path that is valid UTF-8".into(),
+                    }
+                    .into(),
+                )),
+            }
+        }
+
+        let remote_ref = &remote_files;
+
+        let mut compared = stream::iter(to_compare)
+            .map(|(relative_name, remote_name, path)| async move {
+                let compared =
+                    compare_file(&path, remote_ref.get(&remote_name), options.compare).await;
+
+                (relative_name, remote_name, path, compared)
+            })
+            .buffer_unordered(options.concurrency.get());
+
+        let mut uploads = vec![];
+
+        while let Some((relative_name, remote_name, path, compared)) = compared.next().await {
+            match compared {
+                Ok(compared) if !compared.changed => {
+                    plan.unchanged += 1;
+                    plan.unchanged_bytes += compared.size;
+                }
+                Ok(compared) => uploads.push(SyncAction::Upload {
+                    path,
+                    relative_name,
+                    file_name: destination.file_name(&remote_name),
+                    size: compared.size,
+                    modified_millis: compared.modified_millis,
+                    sha1: compared.sha1,
+                }),
+                Err(error) => plan.failed.push((relative_name, error)),
+            }
+        }
+
+        drop(compared);
+
+        uploads.sort_by(|a, b| a.relative_name().cmp(b.relative_name()));
+        plan.actions = uploads;
+
+        if options.extraneous != SyncRemoteExtraneous::Keep {
+            let mut extraneous: Vec<(&String, &B2File)> = remote_files
+                .iter()
+                .filter(|(relative_name, _)| !local_names.contains_key(*relative_name))
+                .collect();
+            extraneous.sort_by_key(|(relative_name, _)| *relative_name);
+
+            // The versions are listed now, so executing deletes what was planned and nothing uploaded since
+            let mut versions: HashMap<String, Vec<PlannedVersion>> = HashMap::new();
+
+            if options.extraneous == SyncRemoteExtraneous::Delete && !extraneous.is_empty() {
+                let listed =
+                    DeletePlan::prefix(client, plan.bucket_id.clone(), prefix.clone()).await?;
+
+                for version in &listed.versions {
+                    versions
+                        .entry(version.file_name.clone())
+                        .or_default()
+                        .push(version.into());
+                }
+            }
+
+            plan.actions
+                .extend(extraneous.into_iter().map(|(relative_name, file)| {
+                    let (relative_name, file_name, size) = (
+                        relative_name.clone(),
+                        file.file_name.clone(),
+                        file.content_length,
+                    );
+
+                    match options.extraneous {
+                        SyncRemoteExtraneous::Delete => SyncAction::Delete {
+                            versions: versions.remove(&file_name).unwrap_or_default(),
+                            relative_name,
+                            file_name,
+                            size,
+                        },
+                        SyncRemoteExtraneous::Keep | SyncRemoteExtraneous::Hide => {
+                            SyncAction::Hide {
+                                relative_name,
+                                file_name,
+                                size,
+                            }
+                        }
+                    }
+                }));
+        }
+
+        Ok(plan)
+    }
+
+    pub fn len(&self) -> usize {
+        self.actions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.actions.is_empty()
+    }
+
+    /// Versions the planned deletes remove, what [execute](SyncPlan::execute) has to be confirmed with.
+    pub fn deleted_versions(&self) -> usize {
+        self.actions
+            .iter()
+            .map(|action| match action {
+                SyncAction::Delete { versions, .. } => versions.len(),
+                SyncAction::Upload { .. } | SyncAction::Hide { .. } => 0,
+            })
+            .sum()
+    }
+
+    /// Bytes the planned uploads send.
+    pub fn upload_bytes(&self) -> u64 {
+        self.actions
+            .iter()
+            .filter(|action| action.is_upload())
+            .map(SyncAction::size)
+            .sum()
+    }
+
+    /// Keeps only the actions the predicate approves of, the rest aren't done when executed.
+    pub fn filter<F: FnMut(&SyncAction) -> bool>(mut self, predicate: F) -> Self {
+        self.actions.retain(predicate);
+        self
+    }
+
+    /// Does the planned actions, uploads concurrently first, then the hides and deletes once every upload finished.
+    /// <br> Uses the options' [concurrency](SyncUpOptions::concurrency), [stall_timeout](SyncUpOptions::stall_timeout),
+    /// [shared_throttle](SyncUpOptions::shared_throttle), [usage](SyncUpOptions::usage) and [on_progress](SyncUpOptions::on_progress),
+    /// the rest was decided by the plan.
+    /// <br> Once the options' [cancellation](SyncUpOptions::cancellation) is cancelled, uploads not started yet fail with
+    /// [Aborted](TransferError::Aborted), and nothing is hidden or deleted.
+    /// <br> A local file whose size or modification time changed since it was planned fails instead of being uploaded,
+    /// so what's uploaded is what was reviewed. Failures of single files are collected in the report.
+    /// <br> Deletes only remove the planned versions, and have to be confirmed with the plan's [deleted_versions](SyncPlan::deleted_versions),
+    /// `Destruction::confirmed(0)` for a plan that deletes nothing. Nothing is done if the count differs.
+    pub async fn execute(
+        self,
+        client: Arc<B2SimpleClient>,
+        options: &SyncUpOptions,
+        confirmation: Destruction,
+    ) -> Result<SyncUpReport, TransferError> {
+        let deleted_versions = self.deleted_versions();
+
+        if confirmation.expected_count() != deleted_versions {
+            return Err(InvalidValue {
+                object_name: "Destruction".into(),
+                value_name: "expected_count".into(),
+                value_as_string: confirmation.expected_count().to_string(),
+                expected: format!("{}, the number of versions to delete", deleted_versions),
+            }
+            .into());
+        }
+
+        let mut report = SyncUpReport {
+            unchanged: self.unchanged,
+            failed: self.failed,
+            ..Default::default()
+        };
+
+        let (uploads, removals): (Vec<SyncAction>, Vec<SyncAction>) =
+            self.actions.into_iter().partition(SyncAction::is_upload);
+
+        let mut progress = SyncProgress {
+            total_files: self.unchanged + (uploads.len() + report.failed.len()) as u64,
+            finished_files: self.unchanged,
+            failed_files: report.failed.len() as u64,
+            total_bytes: self.unchanged_bytes + uploads.iter().map(SyncAction::size).sum::<u64>(),
+            finished_bytes: self.unchanged_bytes,
+        };
+
+        let (client_ref, bucket_id) = (&client, &self.bucket_id);
+
+        let mut outcomes = stream::iter(uploads)
+            .map(|action| async move {
+                let outcome = upload_planned(client_ref, bucket_id, &action, options).await;
+
+                (action, outcome)
+            })
+            .buffer_unordered(options.concurrency.get());
+
+        while let Some((action, outcome)) = outcomes.next().await {
+            match outcome {
+                Ok(_) => {
+                    progress.finished_files += 1;
+                    progress.finished_bytes += action.size();
+                    report.bytes_uploaded += action.size();
+                    report.uploaded.push(action.relative_name().to_string());
+                }
+                Err(error) => {
+                    progress.failed_files += 1;
+                    report
+                        .failed
+                        .push((action.relative_name().to_string(), error));
+                }
+            }
+
+            call_progress(&options.on_progress, progress).await;
+        }
+
+        drop(outcomes);
+
+        let cancelled = options
+            .cancellation
+            .as_ref()
+            .is_some_and(CancellationToken::is_cancelled);
+
+        for action in removals.into_iter().filter(|_| !cancelled) {
+            let result = match &action {
+                SyncAction::Upload { .. } => Ok(()),
+                SyncAction::Hide { file_name, .. } => client
+                    .hide_file(self.bucket_id.clone(), file_name.clone())
+                    .await
+                    .map(|_| ()),
+                SyncAction::Delete {
+                    file_name,
+                    versions,
+                    ..
+                } => delete_versions(&client, file_name, versions).await,
+            };
+
+            match result {
+                Ok(_) => report.removed.push(action.relative_name().to_string()),
+                Err(error) => report
+                    .failed
+                    .push((action.relative_name().to_string(), error.into())),
+            }
+        }
+
+        report.uploaded.sort();
+
+        Ok(report)
+    }
+
+    /// The report of a dry run, what executing the plan would do if every action succeeded.
+    pub fn dry_run_report(self) -> SyncUpReport {
+        let mut report = SyncUpReport {
+            unchanged: self.unchanged,
+            failed: self.failed,
+            ..Default::default()
+        };
+
+        for action in self.actions {
+            match action {
+                SyncAction::Upload {
+                    relative_name,
+                    size,
+                    ..
+                } => {
+                    report.bytes_uploaded += size;
+                    report.uploaded.push(relative_name);
+                }
+                SyncAction::Hide { relative_name, .. }
+                | SyncAction::Delete { relative_name, .. } => report.removed.push(relative_name),
+            }
+        }
+
+        report.uploaded.sort();
+
+        report
+    }
+}
+
+/// How a local file compares to the remote one.
+struct ComparedFile {
+    size: u64,
+    modified_millis: Option<u64>,
+    sha1: Option<String>,
+    changed: bool,
+}
+
+async fn compare_file(
+    path: &Path,
+    remote: Option<&B2File>,
+    compare: SyncCompare,
+) -> Result<ComparedFile, TransferError> {
+    let metadata = tokio::fs::metadata(path)
+        .await
+        .map_err(TransferError::SourceIo)?;
+    let size = metadata.len();
+    let modified_millis = metadata.modified().ok().and_then(millis);
+
+    // Only hashed when comparing by SHA1, it's read again for the upload
+    let sha1 = match compare {
+        SyncCompare::Sha1 => Some(local_sha1(path).await?),
+        SyncCompare::Size | SyncCompare::ModifiedTime => None,
+    };
+
+    let changed = match remote {
+        None => true,
+        Some(remote) if remote.content_length != size => true,
+        Some(remote) => {
+            let modified_differs = modified_millis != millis(remote_modified_time(remote));
+
+            match (compare, remote_sha1(remote), &sha1) {
+                (SyncCompare::Size, _, _) => false,
+                (SyncCompare::Sha1, Some(remote), Some(local)) => {
+                    !local.eq_ignore_ascii_case(remote)
+                }
+                _ => modified_differs,
+            }
+        }
+    };
+
+    Ok(ComparedFile {
+        size,
+        modified_millis,
+        sha1,
+        changed,
+    })
+}
+
+/// Uploads a planned file, failing if it changed since it was planned.
+async fn upload_planned(
+    client: &Arc<B2SimpleClient>,
+    bucket_id: &str,
+    action: &SyncAction,
+    options: &SyncUpOptions,
+) -> Result<(), TransferError> {
+    let SyncAction::Upload {
+        path,
+        file_name,
+        size,
+        modified_millis,
+        sha1,
+        ..
+    } = action
+    else {
+        return Ok(());
+    };
+
+    if options
+        .cancellation
+        .as_ref()
+        .is_some_and(CancellationToken::is_cancelled)
+    {
+        return Err(TransferError::Aborted);
+    }
+
+    let metadata = tokio::fs::metadata(path)
+        .await
+        .map_err(TransferError::SourceIo)?;
+
+    if metadata.len() != *size || metadata.modified().ok().and_then(millis) != *modified_millis {
+        return Err(InvalidValue {
+            object_name: "SyncPlan".into(),
+            value_name: "path".into(),
+            value_as_string: path.to_string_lossy().into_owned(),
+            expected: "a file that didn't change since it was planned".into(),
+        }
+        .into());
+    }
+
+    let file = tokio::fs::File::open(path)
+        .await
+        .map_err(TransferError::SourceIo)?;
+
+    let mut settings = B2FileUploadSettings {
+        src_last_modified_millis: *modified_millis,
+        ..Default::default()
+    };
+
+    if let Some(mime) = mime_guess::from_path(path).first() {
+        settings.content_type = mime.essence_str().to_string();
+    }
+
+    let upload = FileUpload::new(
+        file,
+        file_name.clone(),
+        bucket_id.to_string(),
+        sha1.clone()
+            .map(|sha1| HashMap::from([("large_file_sha1".to_string(), sha1)])),
+        *size,
+        FileUploadOptions {
+            stall_timeout: options.stall_timeout,
+            shared_throttle: options.shared_throttle.clone(),
+            usage: options.usage.clone(),
+            cancellation: options.cancellation.clone(),
+            options: settings,
+            ..Default::default()
+        },
+        client.clone(),
+    );
+
+    upload.start().await?;
+
+    Ok(())
+}
+
+/// Deletes the planned versions of a file, canceling unfinished large files, fails with the first error.
+async fn delete_versions(
+    client: &B2SimpleClient,
+    file_name: &str,
+    versions: &[PlannedVersion],
+) -> Result<(), B2Error> {
+    let results = BatchExecutor::new(BatchOptions::default())
+        .run(versions.to_vec(), |version: PlannedVersion| async move {
+            match version.unfinished {
+                true => client.cancel_large_file(version.file_id).await.map(|_| ()),
+                false => client
+                    .delete_file_version(
+                        B2DeleteFileVersionBody::builder()
+                            .file_name(file_name.to_string())
+                            .file_id(version.file_id)
+                            .build(),
+                    )
+                    .await
+                    .map(|_| ()),
+            }
+        })
+        .await;
+
+    results.into_iter().collect()
+}
+
+async fn local_sha1(path: &Path) -> Result<String, TransferError> {
+    file_sha1(path)
+        .await
+        .map(|(_, sha1)| sha1)
+        .map_err(TransferError::SourceIo)
+}
@@ -0,0 +1,73 @@
+use std::{collections::HashMap, io, path::Path};
+
+use serde::{Deserialize, Serialize};
+
+/// A suggested name for the state file, kept in the synced local directory, which syncs leave alone.
+pub const DEFAULT_SYNC_STATE_FILE_NAME: &str = ".b2sync-state.json";
+
+/// The current version of [SyncState], a state file of another version is ignored and starts over.
+pub const SYNC_STATE_VERSION: u32 = 1;
+
+/// What both sides of every file looked like right after the last sync, saved between syncs
+/// so a file changed on both sides can be told apart from a file changed on one side.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncState {
+    pub version: u32,
+    /// Synced files by their name relative to the synced prefix.
+    pub files: HashMap<String, SyncedFile>,
+}
+
+impl Default for SyncState {
+    fn default() -> Self {
+        Self {
+            version: SYNC_STATE_VERSION,
+            files: HashMap::new(),
+        }
+    }
+}
+
+/// A file as it was after the last sync.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncedFile {
+    pub size: u64,
+    /// The file's SHA1, if B2 has one.
+    pub sha1: Option<String>,
+    /// The local file's modification time.
+    pub local_modified_millis: Option<u64>,
+    /// The B2 file version it was synced with, a new upload gets a new ID.
+    pub remote_file_id: String,
+}
+
+impl SyncState {
+    /// Loads the state file, a missing file or one of another version gives an empty state.
+    pub async fn load<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let data = match tokio::fs::read(path).await {
+            Ok(data) => data,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(error) => return Err(error),
+        };
+
+        let state: Self = serde_json::from_slice(&data)
+            .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?;
+
+        match state.version == SYNC_STATE_VERSION {
+            true => Ok(state),
+            false => Ok(Self::default()),
+        }
+    }
+
+    /// Saves the state file, writing it next to the old one first so a crash can't leave it half written.
+    pub async fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let path = path.as_ref();
+        let data = serde_json::to_vec_pretty(self)
+            .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?;
+
+        let mut temp_path = path.as_os_str().to_owned();
+        temp_path.push(".tmp");
+
+        tokio::fs::write(&temp_path, data).await?;
+        tokio::fs::rename(&temp_path, path).await
+    }
+}
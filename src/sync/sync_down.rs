@@ -0,0 +1,598 @@
+use std::{
+    collections::HashSet,
+    fs::Metadata,
+    num::NonZeroUsize,
+    path::{Component, Path, PathBuf},
+    sync::Arc,
+    time::{Duration, SystemTime},
+};
+
+use futures::{stream, StreamExt, TryStreamExt};
+use tokio_util::sync::CancellationToken;
+
+use crate::{
+    definitions::{
+        query_params::B2ListFileNamesQueryParameters,
+        shared::{B2Action, B2File},
+    },
+    pagination::ListingGuard,
+    simple_client::B2SimpleClient,
+    tasks::{
+        download::{FileDownload, FileDownloadOptions, FileDownloadSource},
+        error::TransferError,
+        shared::file_sha1,
+    },
+    throttle::SharedThrottle,
+    usage::UsageTracker,
+    util::InvalidValue,
+};
+
+use super::{
+    conflict_copy_path, B2Location, ConflictPolicy, ConflictWinner, LocalPath, SyncConflict,
+    SyncState, SyncedFile,
+};
+
+/// Suffix of the file a download is written to before it replaces the local file.
+pub(crate) const PARTIAL_SUFFIX: &str = ".b2sync-partial";
+
+/// How a sync decides a local file differs from the remote one.
+/// <br> Files of different sizes always differ.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SyncCompare {
+    /// Only the size is compared.
+    Size,
+    /// The local modification time is compared with the remote `src_last_modified_millis`, or the upload time without one.
+    /// <br> Downloaded files get the remote time, so they match on the next sync.
+    #[default]
+    ModifiedTime,
+    /// The local file is hashed and compared with the remote SHA1, files without a remote SHA1 are compared by modification time.
+    Sha1,
+}
+
+/// Options for [sync_down].
+#[derive(Debug)]
+pub struct SyncDownOptions {
+    /// How changed files are found when there's no state for them, refer to [SyncCompare].
+    /// <br> Default is [SyncCompare::ModifiedTime].
+    pub compare: SyncCompare,
+    /// Max files downloading at once.
+    /// <br> Default is 4.
+    pub concurrency: NonZeroUsize,
+    /// Deletes local files that don't exist under the remote prefix.
+    /// <br> With a [state file](SyncDownOptions::state_file), only files synced before and unchanged locally since are deleted,
+    /// new local files and kept conflict copies stay.
+    /// <br> Default is false.
+    pub delete_extraneous: bool,
+    /// Passed to every file's [FileDownloadOptions::stall_timeout].
+    /// <br> Default is None.
+    pub stall_timeout: Option<Duration>,
+    /// Passed to every file's [FileDownloadOptions::shared_throttle].
+    /// <br> Default is None.
+    pub shared_throttle: Option<Arc<SharedThrottle>>,
+    /// Passed to every file's [FileDownloadOptions::usage].
+    /// <br> Default is None.
+    pub usage: Option<Arc<UsageTracker>>,
+    /// Where the [SyncState] is kept between syncs, like the local directory's [DEFAULT_SYNC_STATE_FILE_NAME](super::DEFAULT_SYNC_STATE_FILE_NAME).
+    /// <br> With it, a file changed only locally is left alone, and a file changed on both sides is resolved with the
+    /// [conflict policy](SyncDownOptions::conflict_policy). Without it, local changes are overwritten by the remote files.
+    /// <br> Default is None.
+    pub state_file: Option<PathBuf>,
+    /// How files changed on both sides since the last sync are resolved, refer to [ConflictPolicy].
+    /// <br> Default is [ConflictPolicy::NewestWins].
+    pub conflict_policy: ConflictPolicy,
+    /// Stops the sync once cancelled, passed to every file's [FileDownloadOptions::cancellation].
+    /// Files not started yet fail with [Aborted](TransferError::Aborted), and nothing is deleted.
+    /// <br> Default is None.
+    pub cancellation: Option<CancellationToken>,
+}
+
+impl Default for SyncDownOptions {
+    fn default() -> Self {
+        Self {
+            compare: SyncCompare::default(),
+            concurrency: NonZeroUsize::new(4).expect("4 is not zero"),
+            delete_extraneous: false,
+            stall_timeout: None,
+            shared_throttle: None,
+            usage: None,
+            state_file: None,
+            conflict_policy: ConflictPolicy::default(),
+            cancellation: None,
+        }
+    }
+}
+
+/// What a [sync_down] did, file names are relative to the synced prefix.
+#[derive(Debug, Default)]
+pub struct SyncDownReport {
+    /// Files that were new or changed, and downloaded.
+    pub downloaded: Vec<String>,
+    /// Files that already matched the remote ones.
+    pub unchanged: u64,
+    /// Local files deleted because they don't exist remotely.
+    pub deleted: Vec<PathBuf>,
+    /// Files changed on both sides, and how they were resolved.
+    pub conflicts: Vec<SyncConflict>,
+    /// Files that failed to download or delete, the rest of the sync carries on.
+    pub failed: Vec<(String, TransferError)>,
+    /// Bytes downloaded across all files.
+    pub bytes_downloaded: u64,
+}
+
+/// How a single file was synced, and its state afterwards.
+struct FileSync {
+    downloaded: Option<u64>,
+    conflict: Option<SyncConflict>,
+    record: SyncedFile,
+}
+
+/// Mirrors the files under a B2 prefix into a local directory, downloading new and changed files concurrently.
+/// <br> A file is downloaded next to its destination and renamed over it once complete, so an interrupted sync doesn't leave half written files.
+/// Failures of single files are collected in the report, listing the remote files or reading the local directory failing stops the sync.
+pub async fn sync_down(
+    client: Arc<B2SimpleClient>,
+    source: &B2Location,
+    destination: &LocalPath,
+    options: SyncDownOptions,
+) -> Result<SyncDownReport, TransferError> {
+    let bucket = source.bucket(&client).await?;
+    let prefix = source.folder_prefix();
+
+    let remote_files: Vec<B2File> = client
+        .list_all_file_names(
+            B2ListFileNamesQueryParameters::builder()
+                .bucket_id(bucket.bucket_id)
+                .prefix((!prefix.is_empty()).then(|| prefix.clone()))
+                .build(),
+            ListingGuard::default(),
+        )
+        .try_collect()
+        .await?;
+
+    tokio::fs::create_dir_all(destination.path())
+        .await
+        .map_err(TransferError::DestinationIo)?;
+
+    let state = match &options.state_file {
+        Some(state_file) => Some(
+            SyncState::load(state_file)
+                .await
+                .map_err(TransferError::DestinationIo)?,
+        ),
+        None => None,
+    };
+
+    let mut report = SyncDownReport::default();
+    let mut remote_names = HashSet::new();
+    let mut to_sync = vec![];
+
+    for file in remote_files {
+        if file.action != B2Action::Upload {
+            continue;
+        }
+
+        let relative_name = file.file_name[prefix.len()..].to_string();
+
+        match local_path(destination.path(), &relative_name) {
+            Ok(path) => {
+                remote_names.insert(path.clone());
+                to_sync.push((relative_name, path, file));
+            }
+            Err(error) => report.failed.push((relative_name, error.into())),
+        }
+    }
+
+    let (client, options_ref, state_ref) = (&client, &options, &state);
+
+    let outcomes: Vec<(String, Result<FileSync, TransferError>)> = stream::iter(to_sync)
+        .map(|(relative_name, path, file)| async move {
+            let record = state_ref
+                .as_ref()
+                .and_then(|state| state.files.get(&relative_name));
+            let outcome =
+                sync_file(client, &relative_name, &path, &file, record, options_ref).await;
+
+            (relative_name, outcome)
+        })
+        .buffer_unordered(options.concurrency.get())
+        .collect()
+        .await;
+
+    let mut new_state = SyncState::default();
+
+    for (relative_name, outcome) in outcomes {
+        match outcome {
+            Ok(synced) => {
+                match synced.downloaded {
+                    Some(bytes) => {
+                        report.bytes_downloaded += bytes;
+                        report.downloaded.push(relative_name.clone());
+                    }
+                    None => report.unchanged += 1,
+                }
+
+                report.conflicts.extend(synced.conflict);
+                new_state.files.insert(relative_name, synced.record);
+            }
+            Err(error) => {
+                // A failed file keeps its old state, so the next sync compares against the same thing
+                if let Some(record) = state
+                    .as_ref()
+                    .and_then(|state| state.files.get(&relative_name))
+                {
+                    new_state
+                        .files
+                        .insert(relative_name.clone(), record.clone());
+                }
+
+                report.failed.push((relative_name, error));
+            }
+        }
+    }
+
+    let cancelled = options
+        .cancellation
+        .as_ref()
+        .is_some_and(CancellationToken::is_cancelled);
+
+    if options.delete_extraneous && !cancelled {
+        for path in local_files(destination.path())
+            .await
+            .map_err(TransferError::DestinationIo)?
+        {
+            if remote_names.contains(&path) || is_state_file(&path, &options) {
+                continue;
+            }
+
+            if let Some(state) = &state {
+                let unchanged_since_sync = match relative_path_name(destination.path(), &path)
+                    .and_then(|name| state.files.get(&name))
+                {
+                    Some(record) => tokio::fs::metadata(&path)
+                        .await
+                        .is_ok_and(|metadata| !local_changed(&metadata, record)),
+                    None => false,
+                };
+
+                if !unchanged_since_sync {
+                    continue;
+                }
+            }
+
+            match tokio::fs::remove_file(&path).await {
+                Ok(_) => report.deleted.push(path),
+                Err(error) => report.failed.push((
+                    path.to_string_lossy().into_owned(),
+                    TransferError::DestinationIo(error),
+                )),
+            }
+        }
+    }
+
+    if let Some(state_file) = &options.state_file {
+        new_state
+            .save(state_file)
+            .await
+            .map_err(TransferError::DestinationIo)?;
+    }
+
+    report.downloaded.sort();
+
+    Ok(report)
+}
+
+async fn sync_file(
+    client: &Arc<B2SimpleClient>,
+    relative_name: &str,
+    path: &Path,
+    file: &B2File,
+    record: Option<&SyncedFile>,
+    options: &SyncDownOptions,
+) -> Result<FileSync, TransferError> {
+    let local = match tokio::fs::metadata(path).await {
+        Ok(metadata) => Some(metadata),
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => None,
+        Err(error) => return Err(TransferError::DestinationIo(error)),
+    };
+
+    let Some(local) = local else {
+        return download_file(client, path, file, options, None).await;
+    };
+
+    let Some(record) = record else {
+        return match is_changed(path, file, options.compare).await? {
+            true => download_file(client, path, file, options, None).await,
+            false => Ok(FileSync {
+                downloaded: None,
+                conflict: None,
+                record: local_record(&local, file),
+            }),
+        };
+    };
+
+    let remote_changed = record.remote_file_id != file.file_id;
+
+    if !remote_changed {
+        return Ok(FileSync {
+            downloaded: None,
+            conflict: None,
+            record: record.clone(),
+        });
+    }
+
+    if !local_changed(&local, record) {
+        return download_file(client, path, file, options, None).await;
+    }
+
+    let local_modified = local.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+    let winner = options
+        .conflict_policy
+        .resolve(local_modified, remote_modified_time(file));
+
+    let conflict = |kept_copy| SyncConflict {
+        relative_name: relative_name.to_string(),
+        winner,
+        kept_copy,
+    };
+
+    match winner {
+        // The remote version counts as synced, so it's only a conflict again if either side changes again
+        ConflictWinner::Local => Ok(FileSync {
+            downloaded: None,
+            conflict: Some(conflict(None)),
+            record: local_record(&local, file),
+        }),
+        ConflictWinner::Remote => {
+            let mut synced = download_file(client, path, file, options, None).await?;
+            synced.conflict = Some(conflict(None));
+            Ok(synced)
+        }
+        ConflictWinner::Both => {
+            let kept_copy = conflict_copy_path(path, millis(local_modified).unwrap_or_default());
+            let mut synced = download_file(client, path, file, options, Some(&kept_copy)).await?;
+            synced.conflict = Some(conflict(Some(kept_copy)));
+            Ok(synced)
+        }
+    }
+}
+
+/// Downloads the file over the local one, moving the local one to `keep_local_as` first when given.
+async fn download_file(
+    client: &Arc<B2SimpleClient>,
+    path: &Path,
+    file: &B2File,
+    options: &SyncDownOptions,
+    keep_local_as: Option<&Path>,
+) -> Result<FileSync, TransferError> {
+    if options
+        .cancellation
+        .as_ref()
+        .is_some_and(CancellationToken::is_cancelled)
+    {
+        return Err(TransferError::Aborted);
+    }
+
+    if let Some(parent) = path.parent() {
+        tokio::fs::create_dir_all(parent)
+            .await
+            .map_err(TransferError::DestinationIo)?;
+    }
+
+    let mut partial_path = path.as_os_str().to_owned();
+    partial_path.push(PARTIAL_SUFFIX);
+    let partial_path = PathBuf::from(partial_path);
+
+    let result = async {
+        let partial = tokio::fs::File::create(&partial_path)
+            .await
+            .map_err(TransferError::DestinationIo)?;
+
+        let download = FileDownload::new(
+            FileDownloadSource::FileId(file.file_id.clone()),
+            partial,
+            FileDownloadOptions {
+                stall_timeout: options.stall_timeout,
+                shared_throttle: options.shared_throttle.clone(),
+                usage: options.usage.clone(),
+                usage_bucket_id: Some(file.bucket_id.clone()),
+                cancellation: options.cancellation.clone(),
+                ..Default::default()
+            },
+            client.clone(),
+        );
+
+        let details = download.start().await?;
+        drop(download);
+
+        tokio::fs::OpenOptions::new()
+            .write(true)
+            .open(&partial_path)
+            .await
+            .map_err(TransferError::DestinationIo)?
+            .into_std()
+            .await
+            .set_modified(remote_modified_time(file))
+            .map_err(TransferError::DestinationIo)?;
+
+        if let Some(keep_local_as) = keep_local_as {
+            tokio::fs::rename(path, keep_local_as)
+                .await
+                .map_err(TransferError::DestinationIo)?;
+        }
+
+        tokio::fs::rename(&partial_path, path)
+            .await
+            .map_err(TransferError::DestinationIo)?;
+
+        // Read back, the filesystem might keep the time less precisely than it was set
+        let metadata = tokio::fs::metadata(path)
+            .await
+            .map_err(TransferError::DestinationIo)?;
+
+        Ok(FileSync {
+            downloaded: Some(details.content_length),
+            conflict: None,
+            record: local_record(&metadata, file),
+        })
+    }
+    .await;
+
+    if result.is_err() {
+        tokio::fs::remove_file(&partial_path).await.ok();
+    }
+
+    result
+}
+
+/// The state of a local file matching the remote one.
+fn local_record(local: &Metadata, file: &B2File) -> SyncedFile {
+    SyncedFile {
+        size: local.len(),
+        sha1: remote_sha1(file).map(String::from),
+        local_modified_millis: local.modified().ok().and_then(millis),
+        remote_file_id: file.file_id.clone(),
+    }
+}
+
+fn local_changed(local: &Metadata, record: &SyncedFile) -> bool {
+    local.len() != record.size
+        || local.modified().ok().and_then(millis) != record.local_modified_millis
+}
+
+fn is_state_file(path: &Path, options: &SyncDownOptions) -> bool {
+    options.state_file.as_ref().is_some_and(|state_file| {
+        let mut temp = state_file.as_os_str().to_owned();
+        temp.push(".tmp");
+
+        path == state_file || path.as_os_str() == temp
+    })
+}
+
+/// A local file's name relative to the directory, with `/` separators like the remote names.
+pub(crate) fn relative_path_name(directory: &Path, path: &Path) -> Option<String> {
+    let relative = path.strip_prefix(directory).ok()?;
+    let segments: Option<Vec<&str>> = relative
+        .components()
+        .map(|component| component.as_os_str().to_str())
+        .collect();
+
+    Some(segments?.join("/"))
+}
+
+async fn is_changed(
+    path: &Path,
+    file: &B2File,
+    compare: SyncCompare,
+) -> Result<bool, TransferError> {
+    let metadata = match tokio::fs::metadata(path).await {
+        Ok(metadata) => metadata,
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => return Ok(true),
+        Err(error) => return Err(TransferError::DestinationIo(error)),
+    };
+
+    if !metadata.is_file() || metadata.len() != file.content_length {
+        return Ok(true);
+    }
+
+    let modified_differs = || {
+        let local = metadata.modified().ok().and_then(millis);
+        local != millis(remote_modified_time(file))
+    };
+
+    match compare {
+        SyncCompare::Size => Ok(false),
+        SyncCompare::ModifiedTime => Ok(modified_differs()),
+        SyncCompare::Sha1 => match remote_sha1(file) {
+            Some(remote) => Ok(!local_sha1(path).await?.eq_ignore_ascii_case(remote)),
+            None => Ok(modified_differs()),
+        },
+    }
+}
+
+/// The remote file's `src_last_modified_millis`, or its upload time without one.
+pub(crate) fn remote_modified_time(file: &B2File) -> SystemTime {
+    let millis = file
+        .file_info_value("src_last_modified_millis")
+        .and_then(|millis| millis.parse().ok())
+        .unwrap_or(file.upload_timestamp);
+
+    SystemTime::UNIX_EPOCH + Duration::from_millis(millis)
+}
+
+pub(crate) fn millis(time: SystemTime) -> Option<u64> {
+    time.duration_since(SystemTime::UNIX_EPOCH)
+        .ok()
+        .map(|since_epoch| since_epoch.as_millis() as u64)
+}
+
+pub(crate) fn remote_sha1(file: &B2File) -> Option<&str> {
+    match file.content_sha1.as_deref() {
+        Some(sha1) if sha1 != "none" => Some(sha1.trim_start_matches("unverified:")),
+        _ => file.file_info_value("large_file_sha1"),
+    }
+}
+
+async fn local_sha1(path: &Path) -> Result<String, TransferError> {
+    file_sha1(path)
+        .await
+        .map(|(_, sha1)| sha1)
+        .map_err(TransferError::DestinationIo)
+}
+
+/// Where a remote file goes locally, refusing names that would land outside the directory.
+pub(crate) fn local_path(directory: &Path, relative_name: &str) -> Result<PathBuf, InvalidValue> {
+    let segments: Vec<&str> = relative_name.split('/').collect();
+
+    if segments.iter().any(|segment| !is_plain_segment(segment))
+        || relative_name.ends_with(PARTIAL_SUFFIX)
+    {
+        return Err(InvalidValue {
+            object_name: "B2File".into(),
+            value_name: "file_name".into(),
+            value_as_string: relative_name.into(),
+            expected: "a relative name without empty, `.` or `..` segments, `\\` or `:`".into(),
+        });
+    }
+
+    Ok(segments
+        .into_iter()
+        .fold(directory.to_path_buf(), |path, segment| path.join(segment)))
+}
+
+/// Whether the segment joins onto a path as a single file or directory name.
+/// <br> `\` and `:` are refused on every platform, Windows takes them as separators, drives or alternate data streams.
+fn is_plain_segment(segment: &str) -> bool {
+    if segment.contains(['\\', ':']) {
+        return false;
+    }
+
+    let mut components = Path::new(segment).components();
+
+    matches!(
+        (components.next(), components.next()),
+        (Some(Component::Normal(_)), None)
+    )
+}
+
+/// Every file under the directory, left over partial downloads included.
+pub(crate) async fn local_files(directory: &Path) -> std::io::Result<Vec<PathBuf>> {
+    let mut files = vec![];
+    let mut directories = vec![directory.to_path_buf()];
+
+    // Symlinks aren't followed, their file type is neither a directory nor a file
+    while let Some(directory) = directories.pop() {
+        let mut entries = tokio::fs::read_dir(&directory).await?;
+
+        while let Some(entry) = entries.next_entry().await? {
+            let file_type = entry.file_type().await?;
+
+            if file_type.is_dir() {
+                directories.push(entry.path());
+            } else if file_type.is_file() {
+                files.push(entry.path());
+            }
+        }
+    }
+
+    Ok(files)
+}
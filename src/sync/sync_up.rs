@@ -0,0 +1,167 @@
+use std::{num::NonZeroUsize, sync::Arc, time::Duration};
+
+use tokio_util::sync::CancellationToken;
+
+use crate::{
+    batch::Destruction,
+    naming::KeyNamingStrategy,
+    simple_client::B2SimpleClient,
+    tasks::{error::TransferError, shared::cancelled},
+    throttle::SharedThrottle,
+    usage::UsageTracker,
+    util::{B2Callback, InvalidValue},
+};
+
+use super::{B2Location, LocalPath, SyncCompare, SyncPlan};
+
+/// What [sync_up] does with remote files that don't exist in the local directory.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SyncRemoteExtraneous {
+    /// Remote files are left alone.
+    #[default]
+    Keep,
+    /// Remote files are [hidden](B2SimpleClient::hide_file), their versions are kept and can be restored.
+    Hide,
+    /// Every version of the remote files is deleted, up to [max_deleted_versions](SyncUpOptions::max_deleted_versions) in a [sync_up].
+    Delete,
+}
+
+/// Progress of a [sync_up], sent after every local file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SyncProgress {
+    pub total_files: u64,
+    /// Files uploaded, or skipped since they already matched the remote ones.
+    pub finished_files: u64,
+    pub failed_files: u64,
+    pub total_bytes: u64,
+    /// Bytes of the finished files.
+    pub finished_bytes: u64,
+}
+
+/// Options for [sync_up].
+#[derive(Debug)]
+pub struct SyncUpOptions {
+    /// How changed files are found, refer to [SyncCompare].
+    /// <br> Uploaded files get the local modification time as their `src_last_modified_millis`, and with [SyncCompare::Sha1]
+    /// large files get their `large_file_sha1`, so they match on the next sync.
+    /// <br> Default is [SyncCompare::ModifiedTime].
+    pub compare: SyncCompare,
+    /// Max files uploading at once.
+    /// <br> Default is 4.
+    pub concurrency: NonZeroUsize,
+    /// What's done with remote files that don't exist locally, refer to [SyncRemoteExtraneous].
+    /// <br> Default is [SyncRemoteExtraneous::Keep].
+    pub extraneous: SyncRemoteExtraneous,
+    /// Most versions a [sync_up] deletes with [SyncRemoteExtraneous::Delete], the sync fails before changing anything when it would delete more.
+    /// <br> A [SyncPlan] is executed with its own confirmation instead, so deletes can be reviewed first.
+    /// <br> Default is 0, so a sync that would delete anything fails.
+    pub max_deleted_versions: usize,
+    /// Compares the files without uploading, hiding or deleting anything, the report lists what the sync would do.
+    /// <br> Refer to [SyncPlan] for a plan that can be reviewed and then executed.
+    /// <br> Default is false.
+    pub dry_run: bool,
+    /// Passed to every file's [FileUploadOptions::stall_timeout](crate::tasks::upload::FileUploadOptions::stall_timeout).
+    /// <br> Default is None.
+    pub stall_timeout: Option<Duration>,
+    /// Passed to every file's [FileUploadOptions::shared_throttle](crate::tasks::upload::FileUploadOptions::shared_throttle).
+    /// <br> Default is None.
+    pub shared_throttle: Option<Arc<SharedThrottle>>,
+    /// Passed to every file's [FileUploadOptions::usage](crate::tasks::upload::FileUploadOptions::usage).
+    /// <br> Default is None.
+    pub usage: Option<Arc<UsageTracker>>,
+    /// Called every time a local file finishes, successfully or not.
+    /// <br> Default is None.
+    pub on_progress: Option<B2Callback<SyncProgress>>,
+    /// Names the files under the prefix, a local file is compared against, and uploaded as, the name the strategy gives its relative path.
+    /// The strategy has to give a file the same name every sync, refer to [KeyNamingStrategy], and the sync fails when it gives two files the same name.
+    /// <br> Default is None, which uses the relative path as is.
+    pub naming: Option<Arc<dyn KeyNamingStrategy>>,
+    /// Stops the sync once cancelled, passed to every file's [FileUploadOptions::cancellation](crate::tasks::upload::FileUploadOptions::cancellation).
+    /// Files not started yet fail with [Aborted](TransferError::Aborted), and nothing is hidden or deleted.
+    /// <br> Default is None.
+    pub cancellation: Option<CancellationToken>,
+}
+
+impl Default for SyncUpOptions {
+    fn default() -> Self {
+        Self {
+            compare: SyncCompare::default(),
+            concurrency: NonZeroUsize::new(4).expect("4 is not zero"),
+            extraneous: SyncRemoteExtraneous::default(),
+            max_deleted_versions: 0,
+            dry_run: false,
+            stall_timeout: None,
+            shared_throttle: None,
+            usage: None,
+            on_progress: None,
+            naming: None,
+            cancellation: None,
+        }
+    }
+}
+
+/// What a [sync_up] did, or would do in a dry run, file names are relative to the synced prefix.
+#[derive(Debug, Default)]
+pub struct SyncUpReport {
+    /// Files that were new or changed, and uploaded.
+    pub uploaded: Vec<String>,
+    /// Files that already matched the remote ones.
+    pub unchanged: u64,
+    /// Remote files hidden or deleted because they don't exist locally.
+    pub removed: Vec<String>,
+    /// Files that failed to upload, hide or delete, the rest of the sync carries on.
+    pub failed: Vec<(String, TransferError)>,
+    /// Bytes uploaded across all files.
+    pub bytes_uploaded: u64,
+}
+
+/// Mirrors a local directory into a B2 prefix, uploading new and changed files concurrently.
+/// <br> Remote files missing locally are kept, hidden or deleted depending on [SyncUpOptions::extraneous], only after every upload finished.
+/// Failures of single files are collected in the report, listing the remote files or reading the local directory failing stops the sync.
+/// <br> Same as [planning](SyncPlan::sync_up) and [executing](SyncPlan::execute) in one go, plan first to review what the sync does.
+pub async fn sync_up(
+    client: Arc<B2SimpleClient>,
+    source: &LocalPath,
+    destination: &B2Location,
+    options: SyncUpOptions,
+) -> Result<SyncUpReport, TransferError> {
+    let plan = tokio::select! {
+        plan = SyncPlan::sync_up(&client, source, destination, &options) => plan?,
+        _ = cancelled(options.cancellation.as_ref()) => return Err(TransferError::Aborted),
+    };
+
+    match options.dry_run {
+        true => Ok(plan.dry_run_report()),
+        false => {
+            let deleted_versions = plan.deleted_versions();
+
+            if deleted_versions > options.max_deleted_versions {
+                return Err(InvalidValue {
+                    object_name: "SyncUpOptions".into(),
+                    value_name: "max_deleted_versions".into(),
+                    value_as_string: options.max_deleted_versions.to_string(),
+                    expected: format!(
+                        "at least {}, the number of versions the sync deletes",
+                        deleted_versions
+                    ),
+                }
+                .into());
+            }
+
+            // Within the caller's limit, which stands in for reviewing the plan
+            plan.execute(client, &options, Destruction::confirmed(deleted_versions))
+                .await
+        }
+    }
+}
+
+pub(super) async fn call_progress(
+    callback: &Option<B2Callback<SyncProgress>>,
+    progress: SyncProgress,
+) {
+    match callback {
+        Some(B2Callback::Fn(fun)) => fun(progress),
+        Some(B2Callback::AsyncFn(fun)) => fun(progress).await,
+        None => {}
+    }
+}
@@ -0,0 +1,591 @@
+use std::{
+    collections::HashMap,
+    io::{self, Cursor},
+    num::NonZeroUsize,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::{Duration, SystemTime},
+};
+
+use futures::{stream, StreamExt, TryStreamExt};
+use serde::{Deserialize, Serialize};
+use sha1_smol::Sha1;
+use tokio::io::AsyncReadExt;
+
+use crate::{
+    definitions::{
+        bodies::B2DeleteFileVersionBody,
+        query_params::B2ListFileNamesQueryParameters,
+        shared::{B2Action, B2File},
+    },
+    error::B2Error,
+    pagination::ListingGuard,
+    simple_client::B2SimpleClient,
+    sync::{local_path, remote_sha1, B2Location, LocalPath, PARTIAL_SUFFIX},
+    throttle::SharedThrottle,
+    usage::UsageTracker,
+    util::{B2Callback, InvalidValue},
+};
+
+use super::{
+    download::{FileDownload, FileDownloadOptions, FileDownloadSource},
+    error::TransferError,
+    shared::file_sha1,
+    upload::{upload_stream, FileUpload, FileUploadOptions, PartProgress, StreamUploadOptions},
+};
+
+/// Current version of the [BackupManifest] format.
+pub const BACKUP_MANIFEST_VERSION: u32 = 1;
+
+/// Name of the manifest under a backup's location.
+pub const BACKUP_MANIFEST_NAME: &str = "manifest.json";
+
+/// Folder under a backup's location the backed up files are stored in.
+const BACKUP_DATA_FOLDER: &str = "data/";
+
+/// Describes a finished backup, stored as [BACKUP_MANIFEST_NAME] under the backup's location.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupManifest {
+    pub version: u32,
+    /// When the backup finished, in milliseconds since the epoch.
+    pub created_millis: u64,
+    /// The backed up files, sorted by path.
+    pub entries: Vec<BackupEntry>,
+}
+
+impl BackupManifest {
+    /// Total size of the backed up files.
+    pub fn total_size(&self) -> u64 {
+        self.entries.iter().map(|entry| entry.size).sum()
+    }
+}
+
+/// A file in a [BackupManifest].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupEntry {
+    /// The file's path inside the backup, `/` separated.
+    pub path: String,
+    pub size: u64,
+    /// SHA1 of the file's content, checked against the bytes that were uploaded.
+    pub sha1: String,
+    /// The uploaded file's name in the bucket.
+    pub file_name: String,
+    /// The uploaded file version, a restore downloads exactly this version.
+    pub file_id: String,
+}
+
+/// A local file to back up.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BackupFile {
+    /// Where the file is read from.
+    pub source: PathBuf,
+    /// The file's path inside the backup, `/` separated without empty, `.` or `..` segments.
+    pub path: String,
+}
+
+impl BackupFile {
+    pub fn new<S: Into<PathBuf>, P: Into<String>>(source: S, path: P) -> Self {
+        Self {
+            source: source.into(),
+            path: path.into(),
+        }
+    }
+}
+
+/// Progress of a [backup] or [restore], sent after every file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BackupProgress {
+    pub total_files: u64,
+    /// Files transferred, or skipped since they were already in place.
+    pub finished_files: u64,
+    pub failed_files: u64,
+    pub total_bytes: u64,
+    /// Bytes of the finished files.
+    pub finished_bytes: u64,
+}
+
+/// Options for [backup] and [restore].
+#[derive(Debug)]
+pub struct BackupOptions {
+    /// Max files transferring at once.
+    /// <br> Default is 4.
+    pub concurrency: NonZeroUsize,
+    /// Passed to every file's upload or download as its stall timeout.
+    /// <br> Default is None.
+    pub stall_timeout: Option<Duration>,
+    /// Passed to every file's upload or download as its shared throttle.
+    /// <br> Default is None.
+    pub shared_throttle: Option<Arc<SharedThrottle>>,
+    /// Passed to every file's upload or download to record the transferred bytes.
+    /// <br> Default is None.
+    pub usage: Option<Arc<UsageTracker>>,
+    /// Called every time a file finishes, successfully or not.
+    /// <br> Default is None.
+    pub on_progress: Option<B2Callback<BackupProgress>>,
+}
+
+impl Default for BackupOptions {
+    fn default() -> Self {
+        Self {
+            concurrency: NonZeroUsize::new(4).expect("4 is not zero"),
+            stall_timeout: None,
+            shared_throttle: None,
+            usage: None,
+            on_progress: None,
+        }
+    }
+}
+
+/// What a [backup] did.
+#[derive(Debug, Default)]
+pub struct BackupReport {
+    /// The uploaded manifest, None when any file failed, since the backup isn't complete.
+    pub manifest: Option<BackupManifest>,
+    /// Paths of the files that were uploaded.
+    pub uploaded: Vec<String>,
+    /// Files already uploaded by an earlier run with the same content.
+    pub skipped: u64,
+    pub failed: Vec<(String, TransferError)>,
+    pub bytes_uploaded: u64,
+}
+
+/// What a [restore] did.
+#[derive(Debug, Default)]
+pub struct RestoreReport {
+    /// Paths of the files that were downloaded.
+    pub downloaded: Vec<String>,
+    /// Files already in place with the right content.
+    pub skipped: u64,
+    pub failed: Vec<(String, TransferError)>,
+    pub bytes_downloaded: u64,
+}
+
+/// Uploads the files under `destination`, then a [BackupManifest] listing them.
+/// <br> Files are stored under `data/` and the manifest as [BACKUP_MANIFEST_NAME]. Running the backup again into the same
+/// location resumes it, files already uploaded with the same content aren't uploaded again.
+/// <br> The manifest is only uploaded once every file made it, failures of single files are collected in the report.
+pub async fn backup(
+    client: Arc<B2SimpleClient>,
+    files: Vec<BackupFile>,
+    destination: &B2Location,
+    options: BackupOptions,
+) -> Result<BackupReport, TransferError> {
+    for file in &files {
+        local_path(Path::new(""), &file.path).map_err(|error| InvalidValue {
+            object_name: "BackupFile".into(),
+            value_name: "path".into(),
+            ..error
+        })?;
+    }
+
+    let bucket = destination.bucket(&client).await?;
+    let data_prefix = destination.file_name(BACKUP_DATA_FOLDER);
+
+    let uploaded: HashMap<String, B2File> = client
+        .list_all_file_names(
+            B2ListFileNamesQueryParameters::builder()
+                .bucket_id(bucket.bucket_id.clone())
+                .prefix(Some(data_prefix.clone()))
+                .build(),
+            ListingGuard::default(),
+        )
+        .map_ok(|file| (file.file_name.clone(), file))
+        .try_filter(|(_, file)| std::future::ready(file.action == B2Action::Upload))
+        .try_collect()
+        .await?;
+
+    let mut progress = BackupProgress {
+        total_files: files.len() as u64,
+        finished_files: 0,
+        failed_files: 0,
+        total_bytes: 0,
+        finished_bytes: 0,
+    };
+
+    for file in &files {
+        if let Ok(metadata) = tokio::fs::metadata(&file.source).await {
+            progress.total_bytes += metadata.len();
+        }
+    }
+
+    let (client_ref, options_ref, uploaded_ref) = (&client, &options, &uploaded);
+    let (bucket_id, data_prefix) = (&bucket.bucket_id, &data_prefix);
+
+    let mut outcomes = stream::iter(files)
+        .map(|file| async move {
+            let file_name = format!("{}{}", data_prefix, file.path);
+            let outcome = backup_file(
+                client_ref,
+                &file,
+                file_name,
+                bucket_id,
+                uploaded_ref,
+                options_ref,
+            )
+            .await;
+
+            (file.path, outcome)
+        })
+        .buffer_unordered(options.concurrency.get());
+
+    let mut report = BackupReport::default();
+    let mut entries = vec![];
+
+    while let Some((path, outcome)) = outcomes.next().await {
+        match outcome {
+            Ok((entry, was_uploaded)) => {
+                progress.finished_files += 1;
+                progress.finished_bytes += entry.size;
+
+                match was_uploaded {
+                    true => {
+                        report.bytes_uploaded += entry.size;
+                        report.uploaded.push(path);
+                    }
+                    false => report.skipped += 1,
+                }
+
+                entries.push(entry);
+            }
+            Err(error) => {
+                progress.failed_files += 1;
+                report.failed.push((path, error));
+            }
+        }
+
+        call_progress(&options.on_progress, progress).await;
+    }
+
+    drop(outcomes);
+    report.uploaded.sort();
+
+    if !report.failed.is_empty() {
+        return Ok(report);
+    }
+
+    entries.sort_by(|a, b| a.path.cmp(&b.path));
+
+    let manifest = BackupManifest {
+        version: BACKUP_MANIFEST_VERSION,
+        created_millis: SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .map(|since_epoch| since_epoch.as_millis() as u64)
+            .unwrap_or_default(),
+        entries,
+    };
+
+    let manifest_json = serde_json::to_vec_pretty(&manifest).map_err(B2Error::JsonParseError)?;
+
+    upload_stream(
+        &client,
+        Cursor::new(manifest_json),
+        destination.file_name(BACKUP_MANIFEST_NAME),
+        bucket.bucket_id,
+        None,
+        StreamUploadOptions::default(),
+    )
+    .await?;
+
+    report.manifest = Some(manifest);
+
+    Ok(report)
+}
+
+/// Downloads every file in the backup at `source` into `destination`, verifying each file's SHA1 against the manifest.
+/// <br> Files already in place with the manifest's content are skipped, so running the restore again resumes it.
+/// A file is downloaded next to its destination and renamed over it once verified.
+pub async fn restore(
+    client: Arc<B2SimpleClient>,
+    source: &B2Location,
+    destination: &LocalPath,
+    options: BackupOptions,
+) -> Result<RestoreReport, TransferError> {
+    let manifest = read_manifest(&client, source).await?;
+
+    tokio::fs::create_dir_all(destination.path())
+        .await
+        .map_err(TransferError::DestinationIo)?;
+
+    let mut progress = BackupProgress {
+        total_files: manifest.entries.len() as u64,
+        finished_files: 0,
+        failed_files: 0,
+        total_bytes: manifest.total_size(),
+        finished_bytes: 0,
+    };
+
+    let (client_ref, options_ref) = (&client, &options);
+
+    let mut outcomes = stream::iter(manifest.entries)
+        .map(|entry| async move {
+            let outcome = async {
+                let path = local_path(destination.path(), &entry.path)?;
+                restore_file(client_ref, &entry, &path, options_ref).await
+            }
+            .await;
+
+            (entry, outcome)
+        })
+        .buffer_unordered(options.concurrency.get());
+
+    let mut report = RestoreReport::default();
+
+    while let Some((entry, outcome)) = outcomes.next().await {
+        match outcome {
+            Ok(was_downloaded) => {
+                progress.finished_files += 1;
+                progress.finished_bytes += entry.size;
+
+                match was_downloaded {
+                    true => {
+                        report.bytes_downloaded += entry.size;
+                        report.downloaded.push(entry.path);
+                    }
+                    false => report.skipped += 1,
+                }
+            }
+            Err(error) => {
+                progress.failed_files += 1;
+                report.failed.push((entry.path, error));
+            }
+        }
+
+        call_progress(&options.on_progress, progress).await;
+    }
+
+    drop(outcomes);
+    report.downloaded.sort();
+
+    Ok(report)
+}
+
+/// Downloads and parses the manifest of the backup at `location`.
+pub async fn read_manifest(
+    client: &B2SimpleClient,
+    location: &B2Location,
+) -> Result<BackupManifest, TransferError> {
+    let response = client
+        .download_file_by_name(
+            location.bucket.clone(),
+            location.file_name(BACKUP_MANIFEST_NAME),
+            None,
+        )
+        .await?;
+
+    let bytes = response.file.read_all().await?;
+    let manifest: BackupManifest =
+        serde_json::from_slice(&bytes).map_err(B2Error::JsonParseError)?;
+
+    if manifest.version != BACKUP_MANIFEST_VERSION {
+        return Err(InvalidValue {
+            object_name: "BackupManifest".into(),
+            value_name: "version".into(),
+            value_as_string: manifest.version.to_string(),
+            expected: BACKUP_MANIFEST_VERSION.to_string(),
+        }
+        .into());
+    }
+
+    Ok(manifest)
+}
+
+/// Uploads the file unless it's already uploaded with the same content, returning its entry and whether it was uploaded.
+async fn backup_file(
+    client: &Arc<B2SimpleClient>,
+    file: &BackupFile,
+    file_name: String,
+    bucket_id: &str,
+    uploaded: &HashMap<String, B2File>,
+    options: &BackupOptions,
+) -> Result<(BackupEntry, bool), TransferError> {
+    let (size, sha1) = file_sha1(&file.source)
+        .await
+        .map_err(TransferError::SourceIo)?;
+
+    let entry = |file_id: String| BackupEntry {
+        path: file.path.clone(),
+        size,
+        sha1: sha1.clone(),
+        file_name: file_name.clone(),
+        file_id,
+    };
+
+    if let Some(existing) = uploaded.get(&file_name) {
+        if existing.content_length == size && remote_sha1(existing) == Some(sha1.as_str()) {
+            return Ok((entry(existing.file_id.clone()), false));
+        }
+    }
+
+    let source = tokio::fs::File::open(&file.source)
+        .await
+        .map_err(TransferError::SourceIo)?;
+
+    // Lets later backups skip the file, only kept once it's checked against the sent bytes below
+    let upload = FileUpload::new(
+        source,
+        file_name.clone(),
+        bucket_id.to_string(),
+        Some(HashMap::from([(
+            "large_file_sha1".to_string(),
+            sha1.clone(),
+        )])),
+        size,
+        FileUploadOptions {
+            stall_timeout: options.stall_timeout,
+            shared_throttle: options.shared_throttle.clone(),
+            usage: options.usage.clone(),
+            fail_on_checksum_mismatch: true,
+            verify_parts: true,
+            ..Default::default()
+        },
+        client.clone(),
+    );
+
+    let uploaded = upload.start().await?;
+
+    // The file can change between hashing and uploading, the entry's SHA1 has to be of the bytes that were sent
+    let parts = upload.parts();
+    let sent_sha1 = match parts.is_empty() {
+        // A small file's SHA1 is sent with it and checked by B2
+        true => uploaded.content_sha1.clone(),
+        false => sent_parts_sha1(&file.source, &parts)
+            .await
+            .map_err(TransferError::SourceIo)?,
+    };
+
+    if sent_sha1.as_deref() != Some(sha1.as_str()) {
+        client
+            .delete_file_version(
+                B2DeleteFileVersionBody::builder()
+                    .file_name(uploaded.file_name)
+                    .file_id(uploaded.file_id)
+                    .build(),
+            )
+            .await
+            .ok();
+
+        return Err(TransferError::ChecksumMismatch {
+            expected: sha1,
+            actual: sent_sha1.unwrap_or_else(|| "changed during the upload".into()),
+        });
+    }
+
+    Ok((entry(uploaded.file_id), true))
+}
+
+/// The SHA1 of the large file the parts make up, hashing the source by the parts' ranges,
+/// None when a part doesn't have the SHA1 it was sent with, since the source changed since.
+/// <br> B2 checks every part against its SHA1, so a match ties the whole file's SHA1 to the sent bytes.
+async fn sent_parts_sha1(path: &Path, parts: &[PartProgress]) -> io::Result<Option<String>> {
+    let mut file = tokio::fs::File::open(path).await?;
+    let mut whole = Sha1::new();
+    let mut buffer = vec![0u8; 1024 * 1024];
+
+    for part in parts {
+        let mut part_sha1 = Sha1::new();
+        let mut remaining = part.size;
+
+        while remaining > 0 {
+            let length = remaining.min(buffer.len() as u64) as usize;
+            let read = file.read(&mut buffer[..length]).await?;
+
+            if read == 0 {
+                return Ok(None);
+            }
+
+            part_sha1.update(&buffer[..read]);
+            whole.update(&buffer[..read]);
+            remaining -= read as u64;
+        }
+
+        if part.sha1.as_deref() != Some(part_sha1.digest().to_string().as_str()) {
+            return Ok(None);
+        }
+    }
+
+    Ok(Some(whole.digest().to_string()))
+}
+
+/// Downloads the entry's file version to `path` unless it's already there, returning whether it was downloaded.
+async fn restore_file(
+    client: &Arc<B2SimpleClient>,
+    entry: &BackupEntry,
+    path: &Path,
+    options: &BackupOptions,
+) -> Result<bool, TransferError> {
+    if let Ok((size, sha1)) = file_sha1(path).await {
+        if size == entry.size && sha1 == entry.sha1 {
+            return Ok(false);
+        }
+    }
+
+    if let Some(parent) = path.parent() {
+        tokio::fs::create_dir_all(parent)
+            .await
+            .map_err(TransferError::DestinationIo)?;
+    }
+
+    let mut partial_path = path.as_os_str().to_owned();
+    partial_path.push(PARTIAL_SUFFIX);
+    let partial_path = PathBuf::from(partial_path);
+
+    let result = async {
+        let partial = tokio::fs::File::create(&partial_path)
+            .await
+            .map_err(TransferError::DestinationIo)?;
+
+        let download = FileDownload::new(
+            FileDownloadSource::FileId(entry.file_id.clone()),
+            partial,
+            FileDownloadOptions {
+                stall_timeout: options.stall_timeout,
+                shared_throttle: options.shared_throttle.clone(),
+                usage: options.usage.clone(),
+                ..Default::default()
+            },
+            client.clone(),
+        );
+
+        download.start().await?;
+        drop(download);
+
+        let (size, sha1) = file_sha1(&partial_path)
+            .await
+            .map_err(TransferError::DestinationIo)?;
+
+        if size != entry.size {
+            return Err(TransferError::LengthMismatch {
+                expected: entry.size,
+                actual: size,
+            });
+        }
+
+        if sha1 != entry.sha1 {
+            return Err(TransferError::ChecksumMismatch {
+                expected: entry.sha1.clone(),
+                actual: sha1,
+            });
+        }
+
+        tokio::fs::rename(&partial_path, path)
+            .await
+            .map_err(TransferError::DestinationIo)
+    }
+    .await;
+
+    if result.is_err() {
+        tokio::fs::remove_file(&partial_path).await.ok();
+    }
+
+    result.map(|_| true)
+}
+
+async fn call_progress(callback: &Option<B2Callback<BackupProgress>>, progress: BackupProgress) {
+    match callback {
+        Some(B2Callback::Fn(fun)) => fun(progress),
+        Some(B2Callback::AsyncFn(fun)) => fun(progress).await,
+        None => {}
+    }
+}
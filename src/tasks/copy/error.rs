@@ -0,0 +1,53 @@
+use core::fmt;
+use std::error::Error;
+
+use crate::error::B2Error;
+
+#[derive(Debug)]
+pub enum FileCopyError {
+    RequestError(B2Error),
+    /// The copy's [cancellation](super::CopyOptions::cancellation) token was cancelled.
+    Aborted,
+    /// The passed copy range isn't in the `bytes=start-end` format.
+    InvalidRange(String),
+    /// The copied file or part doesn't have the expected length.
+    LengthMismatch {
+        expected: u64,
+        actual: u64,
+    },
+    /// The copied file doesn't have the same SHA1 as the source.
+    ChecksumMismatch {
+        expected: String,
+        actual: String,
+    },
+}
+
+impl Error for FileCopyError {}
+
+impl fmt::Display for FileCopyError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "B2 copy failed, ")?;
+
+        match self {
+            Self::RequestError(err) => write!(f, "{}", err),
+            Self::Aborted => write!(f, "Copy was aborted."),
+            Self::InvalidRange(range) => write!(f, "[{}] is not a valid copy range.", range),
+            Self::LengthMismatch { expected, actual } => write!(
+                f,
+                "Copy length mismatch, expected {} bytes got {} bytes.",
+                expected, actual
+            ),
+            Self::ChecksumMismatch { expected, actual } => write!(
+                f,
+                "Copy SHA1 mismatch, expected [{}] got [{}].",
+                expected, actual
+            ),
+        }
+    }
+}
+
+impl From<B2Error> for FileCopyError {
+    fn from(value: B2Error) -> Self {
+        FileCopyError::RequestError(value)
+    }
+}
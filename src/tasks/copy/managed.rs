@@ -0,0 +1,338 @@
+use std::{collections::HashMap, num::NonZeroUsize};
+
+use tokio_util::sync::CancellationToken;
+
+use crate::{
+    batch::{BatchExecutor, BatchOptions},
+    definitions::{
+        bodies::{
+            B2CopyFileBody, B2CopyPartBody, B2DeleteFileVersionBody, B2FinishLargeFileBody,
+            B2StartLargeFileUploadBody,
+        },
+        shared::{B2File, B2MetadataDirective},
+    },
+    simple_client::B2SimpleClient,
+    tasks::shared::cancelled,
+    util::RetryStrategy,
+};
+
+use super::FileCopyError;
+
+/// The largest file [b2_copy_file](B2SimpleClient::copy_file) copies in one request, and the largest part, larger files are copied part by part.
+pub const MAX_COPY_FILE_SIZE: u64 = 5_000_000_000;
+
+/// The smallest part of a large file, other than the last one.
+const MIN_COPY_PART_SIZE: u64 = 5_000_000;
+
+/// The most parts a large file can have, the part size grows to stay under it.
+const MAX_COPY_PARTS: u64 = 10_000;
+
+/// Options for [copy].
+#[derive(Debug)]
+pub struct CopyOptions {
+    /// Bucket the new file is created in.
+    /// <br> Default is None, which is the source's bucket.
+    pub destination_bucket_id: Option<String>,
+    /// Content type of the new file.
+    /// <br> Default is None, which keeps the source's content type and file info, unless [file_info](CopyOptions::file_info) is set.
+    pub content_type: Option<String>,
+    /// File info of the new file, replacing the source's.
+    /// <br> Default is None, which keeps the source's file info and content type, unless [content_type](CopyOptions::content_type) is set.
+    pub file_info: Option<HashMap<String, String>>,
+    /// Size of the parts a large copy is split into, from 5 MB - 5 GB, made larger if the file would need more than 10,000 parts.
+    /// <br> Default is None, which is the account's recommended part size.
+    pub part_size: Option<u64>,
+    /// Max parts copying at once.
+    /// <br> Default is 4.
+    pub concurrency: NonZeroUsize,
+    /// Retry strategy for every copy request.
+    /// <br> A single request copy isn't retried after a failure it may have gone through with, like a lost response,
+    /// since repeating it would make a second copy. Large file parts are always retried.
+    /// <br> Defaults to RetryStrategy::Dynamic([crate::util::DefaultRetryStrategy]).
+    pub retry_strategy: RetryStrategy,
+    /// Stops the copy once cancelled, failing it with [Aborted](FileCopyError::Aborted). Requests in flight are dropped,
+    /// and a large file being copied into is canceled.
+    /// <br> Default is None.
+    pub cancellation: Option<CancellationToken>,
+}
+
+impl Default for CopyOptions {
+    fn default() -> Self {
+        Self {
+            destination_bucket_id: None,
+            content_type: None,
+            file_info: None,
+            part_size: None,
+            concurrency: NonZeroUsize::new(4).expect("4 is not zero"),
+            retry_strategy: RetryStrategy::default(),
+            cancellation: None,
+        }
+    }
+}
+
+/// Copies a whole file of any size to `file_name`.
+/// <br> Files up to [MAX_COPY_FILE_SIZE] take a single [b2_copy_file](B2SimpleClient::copy_file), larger files are copied
+/// into a new large file with concurrent [b2_copy_part](B2SimpleClient::copy_part) calls. The large file is canceled if any part fails.
+pub async fn copy(
+    client: &B2SimpleClient,
+    source_file_id: String,
+    file_name: String,
+    options: CopyOptions,
+) -> Result<B2File, FileCopyError> {
+    let source = client.get_file_info(source_file_id).await?;
+
+    copy_source(client, source, file_name, options).await
+}
+
+/// Options for [move_file].
+#[derive(Debug, Default)]
+pub struct MoveOptions {
+    /// How the file is copied, refer to [CopyOptions].
+    pub copy: CopyOptions,
+    /// Hides the original name instead of deleting the original version, so its versions stay restorable.
+    /// <br> Default is false.
+    pub keep_history: bool,
+}
+
+/// Moves a file version to `new_name`, possibly in another bucket, by [copying](copy) it then deleting the original version.
+/// <br> With [keep_history](MoveOptions::keep_history) the original name is hidden instead. The copy is kept if removing the original fails,
+/// so the file is never lost, but it can end up under both names.
+pub async fn move_file(
+    client: &B2SimpleClient,
+    source_file_id: String,
+    new_name: String,
+    options: MoveOptions,
+) -> Result<B2File, FileCopyError> {
+    let source = client.get_file_info(source_file_id).await?;
+    let (source_name, source_bucket_id) = (source.file_name.clone(), source.bucket_id.clone());
+
+    let moved = copy_source(client, source.clone(), new_name, options.copy).await?;
+
+    match options.keep_history {
+        // Hiding the name the copy went to would hide the copy
+        true if moved.file_name == source_name && moved.bucket_id == source_bucket_id => {}
+        true => {
+            client.hide_file(source_bucket_id, source_name).await?;
+        }
+        false => {
+            client
+                .delete_file_version(
+                    B2DeleteFileVersionBody::builder()
+                        .file_name(source_name)
+                        .file_id(source.file_id)
+                        .build(),
+                )
+                .await?;
+        }
+    }
+
+    Ok(moved)
+}
+
+/// Replaces a file's info and content type by copying the file onto its own name with the new metadata,
+/// since B2 has no call to edit them in place. Large files are copied part by part like in [copy].
+/// <br> `file_info` replaces all of the old info, except `large_file_sha1` which is kept unless given, since the content doesn't change.
+/// `content_type` None keeps the old content type. The old version is deleted when `delete_old_version` is set,
+/// otherwise it stays as the file's previous version.
+pub async fn replace_file_metadata(
+    client: &B2SimpleClient,
+    file_id: String,
+    mut file_info: HashMap<String, String>,
+    content_type: Option<String>,
+    delete_old_version: bool,
+) -> Result<B2File, FileCopyError> {
+    let source = client.get_file_info(file_id).await?;
+
+    if let Some(sha1) = source.file_info_value("large_file_sha1") {
+        file_info
+            .entry("large_file_sha1".into())
+            .or_insert_with(|| sha1.to_string());
+    }
+
+    let replaced = copy_source(
+        client,
+        source.clone(),
+        source.file_name.clone(),
+        CopyOptions {
+            content_type: Some(
+                content_type
+                    .or(source.content_type.clone())
+                    .unwrap_or_else(|| "b2/x-auto".into()),
+            ),
+            file_info: Some(file_info),
+            ..Default::default()
+        },
+    )
+    .await?;
+
+    if delete_old_version {
+        client
+            .delete_file_version(
+                B2DeleteFileVersionBody::builder()
+                    .file_name(source.file_name)
+                    .file_id(source.file_id)
+                    .build(),
+            )
+            .await?;
+    }
+
+    Ok(replaced)
+}
+
+async fn copy_source(
+    client: &B2SimpleClient,
+    source: B2File,
+    file_name: String,
+    mut options: CopyOptions,
+) -> Result<B2File, FileCopyError> {
+    let executor = BatchExecutor::new(BatchOptions {
+        concurrency: options.concurrency,
+        retry_strategy: std::mem::take(&mut options.retry_strategy),
+        // b2_copy_file makes a new version every time, b2_copy_part can be repeated
+        retry_ambiguous: source.content_length > MAX_COPY_FILE_SIZE,
+        ..Default::default()
+    });
+
+    match source.content_length > MAX_COPY_FILE_SIZE {
+        true => copy_large_file(client, source, file_name, options, executor).await,
+        false => {
+            let replace_metadata = options.content_type.is_some() || options.file_info.is_some();
+
+            let body = B2CopyFileBody::builder()
+                .source_file_id(source.file_id.clone())
+                .file_name(file_name)
+                .destination_bucket_id(options.destination_bucket_id.clone())
+                .metadata_directive(Some(match replace_metadata {
+                    true => B2MetadataDirective::Replace,
+                    false => B2MetadataDirective::Copy,
+                }))
+                .content_type(replace_metadata.then(|| {
+                    options
+                        .content_type
+                        .clone()
+                        .or_else(|| source.content_type.clone())
+                        .unwrap_or_else(|| "b2/x-auto".into())
+                }))
+                .file_info(replace_metadata.then(|| {
+                    options
+                        .file_info
+                        .clone()
+                        .unwrap_or(source.file_info.clone())
+                }))
+                .build();
+
+            let results = tokio::select! {
+                results = executor.run([body], |body| client.copy_file(body)) => results,
+                _ = cancelled(options.cancellation.as_ref()) => return Err(FileCopyError::Aborted),
+            };
+
+            Ok(results
+                .into_iter()
+                .next()
+                .expect("one body gives one result")?)
+        }
+    }
+}
+
+async fn copy_large_file(
+    client: &B2SimpleClient,
+    source: B2File,
+    file_name: String,
+    options: CopyOptions,
+    executor: BatchExecutor,
+) -> Result<B2File, FileCopyError> {
+    let part_size = options
+        .part_size
+        .unwrap_or(client.recommended_part_size())
+        .max(source.content_length.div_ceil(MAX_COPY_PARTS))
+        .clamp(MIN_COPY_PART_SIZE, MAX_COPY_FILE_SIZE);
+
+    let large_file = client
+        .start_large_file(
+            B2StartLargeFileUploadBody::builder()
+                .bucket_id(
+                    options
+                        .destination_bucket_id
+                        .clone()
+                        .unwrap_or(source.bucket_id.clone()),
+                )
+                .file_name(file_name)
+                .content_type(
+                    options
+                        .content_type
+                        .clone()
+                        .or(source.content_type.clone())
+                        .unwrap_or_else(|| "b2/x-auto".into()),
+                )
+                .file_info(Some(
+                    options
+                        .file_info
+                        .clone()
+                        .unwrap_or(source.file_info.clone()),
+                ))
+                .build(),
+        )
+        .await?;
+
+    let bodies: Vec<B2CopyPartBody> = (0..source.content_length.div_ceil(part_size))
+        .map(|index| {
+            let start = index * part_size;
+            let end = (start + part_size).min(source.content_length) - 1;
+
+            B2CopyPartBody::builder()
+                .source_file_id(source.file_id.clone())
+                .large_file_id(large_file.file_id.clone())
+                .part_number(index as u16 + 1)
+                .range(Some(format!("bytes={}-{}", start, end)))
+                .build()
+        })
+        .collect();
+
+    let expected_lengths: Vec<u64> = (0..bodies.len() as u64)
+        .map(|index| part_size.min(source.content_length - index * part_size))
+        .collect();
+
+    let parts = tokio::select! {
+        parts = executor.run(bodies, |body| client.copy_part(body)) => parts,
+        _ = cancelled(options.cancellation.as_ref()) => {
+            client.cancel_large_file(large_file.file_id).await.ok();
+
+            return Err(FileCopyError::Aborted);
+        }
+    };
+
+    let mut part_sha1_array = vec![];
+
+    for (part, expected) in parts.into_iter().zip(expected_lengths) {
+        let checked = match part {
+            Ok(part) if part.content_length == expected => Ok(part.content_sha1),
+            Ok(part) => Err(FileCopyError::LengthMismatch {
+                expected,
+                actual: part.content_length,
+            }),
+            Err(error) => Err(error.into()),
+        };
+
+        match checked {
+            Ok(sha1) => part_sha1_array.push(sha1),
+            Err(error) => {
+                client.cancel_large_file(large_file.file_id).await.ok();
+
+                return Err(error);
+            }
+        }
+    }
+
+    let file = client
+        .finish_large_file(B2FinishLargeFileBody {
+            file_id: large_file.file_id.clone(),
+            part_sha1_array,
+        })
+        .await;
+
+    if file.is_err() {
+        client.cancel_large_file(large_file.file_id).await.ok();
+    }
+
+    Ok(file?)
+}
@@ -0,0 +1,6 @@
+pub mod error;
+pub mod managed;
+pub mod verify;
+
+pub use error::*;
+pub use managed::*;
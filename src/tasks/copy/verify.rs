@@ -0,0 +1,119 @@
+use crate::{
+    definitions::{
+        bodies::{B2CopyFileBody, B2CopyPartBody, B2DeleteFileVersionBody},
+        responses::B2FilePart,
+        shared::B2File,
+    },
+    simple_client::B2SimpleClient,
+};
+
+use super::FileCopyError;
+
+/// Copies a file, then compares the new file's length and SHA1 against the source.
+/// <br> On mismatch the new file version is deleted before the error is returned.
+pub async fn copy_file_verified(
+    client: &B2SimpleClient,
+    body: B2CopyFileBody,
+) -> Result<B2File, FileCopyError> {
+    let source = client.get_file_info(body.source_file_id.clone()).await?;
+
+    let expected_length = match &body.range {
+        Some(range) => range_length(range)?,
+        None => source.content_length,
+    };
+
+    // The SHA1 only carries over when copying the whole file
+    let expected_sha1 = match body.range {
+        Some(_) => None,
+        None => file_sha1(&source),
+    };
+
+    let copied = client.copy_file(body).await?;
+
+    let mismatch = if copied.content_length != expected_length {
+        Some(FileCopyError::LengthMismatch {
+            expected: expected_length,
+            actual: copied.content_length,
+        })
+    } else {
+        match (expected_sha1, file_sha1(&copied)) {
+            (Some(expected), Some(actual)) if !expected.eq_ignore_ascii_case(&actual) => {
+                Some(FileCopyError::ChecksumMismatch { expected, actual })
+            }
+            _ => None,
+        }
+    };
+
+    match mismatch {
+        Some(error) => {
+            client
+                .delete_file_version(
+                    B2DeleteFileVersionBody::builder()
+                        .file_name(copied.file_name)
+                        .file_id(copied.file_id)
+                        .build(),
+                )
+                .await
+                .ok();
+
+            Err(error)
+        }
+        None => Ok(copied),
+    }
+}
+
+/// Copies a part, then compares the new part's length against the copied range.
+/// <br> A bad part doesn't need cleaning up, copying the same part number again replaces it.
+pub async fn copy_part_verified(
+    client: &B2SimpleClient,
+    body: B2CopyPartBody,
+) -> Result<B2FilePart, FileCopyError> {
+    let expected_length = match &body.range {
+        Some(range) => range_length(range)?,
+        None => {
+            client
+                .get_file_info(body.source_file_id.clone())
+                .await?
+                .content_length
+        }
+    };
+
+    let part = client.copy_part(body).await?;
+
+    if part.content_length != expected_length {
+        return Err(FileCopyError::LengthMismatch {
+            expected: expected_length,
+            actual: part.content_length,
+        });
+    }
+
+    Ok(part)
+}
+
+/// Returns the number of bytes in a `bytes=start-end` range, both ends are inclusive.
+pub(crate) fn range_length(range: &str) -> Result<u64, FileCopyError> {
+    let invalid = || FileCopyError::InvalidRange(range.to_owned());
+
+    let (start, end) = range
+        .trim()
+        .strip_prefix("bytes=")
+        .and_then(|range| range.split_once('-'))
+        .ok_or_else(invalid)?;
+
+    let start: u64 = start.trim().parse().map_err(|_| invalid())?;
+    let end: u64 = end.trim().parse().map_err(|_| invalid())?;
+
+    if end < start {
+        return Err(invalid());
+    }
+
+    Ok(end - start + 1)
+}
+
+/// The file's SHA1, falling back to the `large_file_sha1` file info for large files.
+fn file_sha1(file: &B2File) -> Option<String> {
+    match file.content_sha1.as_deref() {
+        Some(sha1) if sha1 != "none" => Some(sha1.trim_start_matches("unverified:").to_owned()),
+        _ => file.file_info_value("large_file_sha1").map(String::from),
+    }
+}
@@ -0,0 +1,94 @@
+use core::fmt;
+use std::{error::Error, time::Duration};
+
+use crate::error::B2Error;
+
+#[derive(Debug)]
+pub enum FileDownloadError {
+    Aborted,
+    AlreadyStarted,
+    FailedToWriteFile(std::io::Error),
+    RequestError(B2Error),
+    /// The download made no progress for the given [stall timeout](super::FileDownloadOptions::stall_timeout).
+    Stalled(Duration),
+    /// The bucket of a by-name download doesn't exist, or the key can't see it,
+    /// found by [check_bucket](super::FileDownloadOptions::check_bucket).
+    BucketNotVisible(String),
+    /// The bucket of a by-name download was checked and is visible, but has no file by that name.
+    FileMissing {
+        bucket_name: String,
+        file_name: String,
+    },
+    /// The downloaded data doesn't have the file's length.
+    LengthMismatch {
+        expected: u64,
+        actual: u64,
+    },
+}
+
+impl Error for FileDownloadError {}
+
+impl FileDownloadError {
+    /// The `Retry-After` the server responded with, if any.
+    pub fn retry_after(&self) -> Option<Duration> {
+        match self {
+            Self::RequestError(error) => error.retry_after(),
+            _ => None,
+        }
+    }
+
+    /// Whether trying again can succeed, a retry continues from the bytes already written.
+    /// <br> Failed writes aren't retried, since it's unknown how much of the data made it to the destination.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            Self::RequestError(error) => error.is_retryable(),
+            Self::Stalled(_) => true,
+            Self::LengthMismatch { expected, actual } => actual < expected,
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for FileDownloadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "B2 download failed, ")?;
+
+        match self {
+            Self::Aborted => write!(f, "Request was aborted."),
+            Self::AlreadyStarted => write!(f, "Already started file download."),
+            Self::FailedToWriteFile(err) => write!(f, "Failed to write downloaded file: {}", err),
+            Self::RequestError(err) => write!(f, "{}", err),
+            Self::Stalled(timeout) => write!(f, "No progress was made for {:?}.", timeout),
+            Self::BucketNotVisible(bucket_name) => write!(
+                f,
+                "Bucket [{}] doesn't exist or isn't visible to this key.",
+                bucket_name
+            ),
+            Self::FileMissing {
+                bucket_name,
+                file_name,
+            } => write!(
+                f,
+                "File [{}] doesn't exist in bucket [{}].",
+                file_name, bucket_name
+            ),
+            Self::LengthMismatch { expected, actual } => write!(
+                f,
+                "Download length mismatch, expected {} bytes got {} bytes.",
+                expected, actual
+            ),
+        }
+    }
+}
+
+impl From<B2Error> for FileDownloadError {
+    fn from(value: B2Error) -> Self {
+        FileDownloadError::RequestError(value)
+    }
+}
+
+impl From<std::io::Error> for FileDownloadError {
+    fn from(value: std::io::Error) -> Self {
+        FileDownloadError::FailedToWriteFile(value)
+    }
+}
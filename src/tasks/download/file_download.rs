@@ -0,0 +1,415 @@
+use std::{
+    sync::{atomic::Ordering, Arc},
+    time::Duration,
+};
+
+use futures::StreamExt;
+use tokio::{
+    io::AsyncWriteExt,
+    sync::{
+        mpsc::{self, Receiver, Sender},
+        Mutex, RwLock,
+    },
+};
+use tokio_util::sync::CancellationToken;
+
+use crate::{
+    definitions::{
+        query_params::B2DownloadFileQueryParameters,
+        shared::{B2FileDownloadDetails, B2KeyCapability},
+    },
+    error::B2Error,
+    simple_client::B2SimpleClient,
+    tasks::shared::{cancelled, AsyncFileWriter, FileNetworkStats, FileStatus, ProgressWatch},
+    throttle::SharedThrottle,
+    usage::UsageTracker,
+    util::{
+        run_callbacks, write_lock_arc::WriteLockArc, B2Callback, CallbackRunOptions, Clock,
+        RetryContext, RetryStrategy,
+    },
+};
+
+use super::error::FileDownloadError;
+
+/// Which file a [FileDownload] downloads.
+#[derive(Debug, Clone)]
+pub enum FileDownloadSource {
+    FileId(String),
+    /// The latest version of the file, a download that has to retry keeps downloading the version it started with.
+    FileName {
+        bucket_name: String,
+        file_name: String,
+    },
+}
+
+/// Options for [FileDownload].
+#[derive(Debug, Default)]
+pub struct FileDownloadOptions {
+    /// Retry strategy for failed downloads, a retry continues from the bytes already written.
+    /// <br> Defaults to RetryStrategy::Dynamic([crate::util::DefaultRetryStrategy]).
+    pub retry_strategy: RetryStrategy,
+    /// Aborts and retries the download request if it doesn't receive any bytes for this long.
+    /// <br> Default is None, which relies on the connection timing out on its own.
+    pub stall_timeout: Option<Duration>,
+    /// Download speed throttle shared with other transfers.
+    /// <br> Downloads created by a [B2Client](crate::client::B2Client) get its [shared throttle](crate::client::B2Client::with_shared_throttle) when this is None.
+    /// <br> Default is None.
+    pub shared_throttle: Option<Arc<SharedThrottle>>,
+    /// Records the downloaded bytes, under [usage_bucket_id](FileDownloadOptions::usage_bucket_id) if it's set.
+    /// <br> Downloads created by a [B2Client](crate::client::B2Client) get its [usage tracker](crate::client::B2Client::usage) when this is None.
+    /// <br> Default is None.
+    pub usage: Option<Arc<UsageTracker>>,
+    /// Bucket the [usage](FileDownloadOptions::usage) records the downloaded bytes under, since a download's response doesn't say
+    /// which bucket the file is in.
+    /// <br> Default is None, which records them in the session's totals only.
+    pub usage_bucket_id: Option<String>,
+    /// How [finish callbacks](FileDownload::add_finish_callback) are run, refer to [CallbackRunOptions].
+    /// <br> Default waits for every callback.
+    pub finish_callbacks: CallbackRunOptions,
+    /// Checks that the bucket of a by-name download exists and is visible to the key before downloading,
+    /// since B2 answers both a missing bucket and a missing file with `not_found`.
+    /// The download then fails with [BucketNotVisible](FileDownloadError::BucketNotVisible) or [FileMissing](FileDownloadError::FileMissing) instead.
+    /// <br> Takes a [b2_list_buckets](B2SimpleClient::list_buckets) call, skipped when the key can't list buckets,
+    /// a key restricted to another bucket fails without it. A missing file is only reported as [FileMissing](FileDownloadError::FileMissing)
+    /// when the bucket was found, a skipped check leaves B2's `not_found` error as is.
+    /// <br> Default is false.
+    pub check_bucket: bool,
+    /// Aborts the download once cancelled, like [abort](FileDownload::abort), so the download can be tied to the request
+    /// or job it's part of. The response stream is dropped right away.
+    /// <br> Default is None.
+    pub cancellation: Option<CancellationToken>,
+    /// Clock the download's stats and retry waits use, refer to [Clock].
+    /// <br> Default is None, which is the [system clock](crate::util::SystemClock).
+    pub clock: Option<Arc<dyn Clock>>,
+}
+
+pub struct FileDownload {
+    id: u64,
+    client: Arc<B2SimpleClient>,
+    source: RwLock<FileDownloadSource>,
+    options: FileDownloadOptions,
+    destination: Mutex<Box<dyn AsyncFileWriter>>,
+    status: WriteLockArc<FileStatus>,
+    stats: Arc<FileNetworkStats>,
+    details: RwLock<Option<B2FileDownloadDetails>>,
+    completion_callbacks: Arc<RwLock<Vec<B2Callback<()>>>>,
+    abort_channel: (WriteLockArc<Sender<()>>, WriteLockArc<Receiver<()>>),
+}
+
+impl FileDownload {
+    pub fn new<W: AsyncFileWriter + 'static>(
+        source: FileDownloadSource,
+        destination: W,
+        options: FileDownloadOptions,
+        client: Arc<B2SimpleClient>,
+    ) -> Arc<Self> {
+        let (tx, rx) = mpsc::channel::<()>(1);
+        let stats = Arc::new(FileNetworkStats::new(0, options.clock.clone()));
+
+        Arc::new(Self {
+            id: rand::random(),
+            client,
+            source: RwLock::new(source),
+            options,
+            destination: Mutex::new(Box::new(destination)),
+            status: WriteLockArc::new(FileStatus::Pending),
+            stats,
+            details: RwLock::new(None),
+            completion_callbacks: Arc::new(RwLock::new(vec![])),
+            abort_channel: (WriteLockArc::new(tx), WriteLockArc::new(rx)),
+        })
+    }
+
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    /// The download's progress, the total is 0 until B2 responds with the file's size.
+    pub fn stats(&self) -> &FileNetworkStats {
+        &self.stats
+    }
+
+    pub fn status(&self) -> FileStatus {
+        (*self.status).clone()
+    }
+
+    /// Returns true when the file has finished or has been aborted.
+    pub fn has_stopped(&self) -> bool {
+        *self.status == FileStatus::Finished || *self.status == FileStatus::Aborted
+    }
+
+    /// Whether it was started or not, will only start if status is [`Pending`](FileStatus::Pending)
+    /// <br> Writes the whole file to the destination and flushes it, returning the downloaded file's details.
+    pub async fn start(&self) -> Result<B2FileDownloadDetails, FileDownloadError> {
+        if *self.status != FileStatus::Pending {
+            return Err(FileDownloadError::AlreadyStarted);
+        }
+
+        self.status.set(FileStatus::Working).await;
+        self.stats.start_timer().await;
+
+        let max_attempts = self.options.retry_strategy.max_attempts();
+        let mut attempt = 0;
+        let abort_receiver = self.abort_channel.1.clone();
+        let mut receiver_lock = abort_receiver.lock_write().await;
+        let cancellation = self.options.cancellation.as_ref();
+
+        let result = loop {
+            attempt += 1;
+
+            let result = tokio::select! {
+                result = self.download_file() => result,
+                _ = receiver_lock.recv() => break Err(FileDownloadError::Aborted),
+                _ = cancelled(cancellation) => {
+                    self.abort().await;
+                    break Err(FileDownloadError::Aborted);
+                }
+            };
+
+            let error = match result {
+                Ok(details) => break Ok(details),
+                Err(error) => error,
+            };
+
+            if attempt >= max_attempts || !error.is_retryable() {
+                break Err(error);
+            }
+
+            let wait = self.options.retry_strategy.retry_wait(&RetryContext {
+                endpoint: "b2_download_file",
+                attempt: attempt + 1,
+                last_error: Some(&error),
+                retry_after: error.retry_after(),
+            });
+
+            self.set_status_if(FileStatus::Working, FileStatus::Retrying)
+                .await;
+
+            tokio::select! {
+                _ = self.stats.clock().sleep(wait) => {},
+                _ = receiver_lock.recv() => {
+                    break Err(FileDownloadError::Aborted)
+                }
+                _ = cancelled(cancellation) => {
+                    self.abort().await;
+                    break Err(FileDownloadError::Aborted);
+                }
+            };
+
+            self.set_status_if(FileStatus::Retrying, FileStatus::Working)
+                .await;
+        };
+
+        drop(receiver_lock);
+
+        self.set_status_if(FileStatus::Working, FileStatus::Finished)
+            .await;
+
+        self.call_finish_callbacks().await;
+
+        if *self.status == FileStatus::Aborted {
+            return Err(FileDownloadError::Aborted);
+        }
+
+        result
+    }
+
+    /// Will abort ongoing download if status is [`Working`](FileStatus::Working) or [`Retrying`](FileStatus::Retrying), does nothing otherwise.
+    /// <br> Bytes already written to the destination are left there.
+    pub async fn abort(&self) {
+        // If its not working there's nothing to do
+        if *self.status != FileStatus::Working && *self.status != FileStatus::Retrying {
+            return;
+        }
+
+        self.status.set(FileStatus::Aborted).await;
+
+        let sender = &self.abort_channel.0;
+        sender.send(()).await.ok();
+    }
+
+    pub async fn add_finish_callback(&self, callback: B2Callback<()>) {
+        let mut callbacks = self.completion_callbacks.write().await;
+        callbacks.push(callback);
+    }
+
+    /// Fails a by-name download early when its bucket can't be seen, if [check_bucket](FileDownloadOptions::check_bucket) is set.
+    /// <br> Only by-name downloads that haven't gotten a response yet are checked, later retries download by file ID.
+    /// <br> Returns whether the bucket was found, false when the check was skipped.
+    async fn check_bucket(&self) -> Result<bool, FileDownloadError> {
+        let FileDownloadSource::FileName { bucket_name, .. } = &*self.source.read().await else {
+            return Ok(false);
+        };
+
+        if !self.options.check_bucket {
+            return Ok(false);
+        }
+
+        let storage_api = self.client.auth_data().api_info.storage_api;
+
+        if storage_api
+            .bucket_name
+            .is_some_and(|allowed| &allowed != bucket_name)
+        {
+            return Err(FileDownloadError::BucketNotVisible(bucket_name.clone()));
+        }
+
+        if !self.client.has_capability(&B2KeyCapability::ListBuckets) {
+            return Ok(false);
+        }
+
+        match self.client.get_bucket_by_name(bucket_name.clone()).await? {
+            Some(_) => Ok(true),
+            None => Err(FileDownloadError::BucketNotVisible(bucket_name.clone())),
+        }
+    }
+
+    /// A single download request, continuing from the bytes already written.
+    async fn download_file(&self) -> Result<B2FileDownloadDetails, FileDownloadError> {
+        let written = self.stats.done.load(Ordering::Relaxed);
+
+        if written > 0 && written == self.stats.total_bytes() {
+            return self.finish_download(written).await;
+        }
+
+        let bucket_found = self.check_bucket().await?;
+
+        let progress = ProgressWatch::new(self.stats.clock().clone());
+        let stall_timeout = self.options.stall_timeout;
+
+        let download = async {
+            let query_params = (written > 0).then(|| {
+                B2DownloadFileQueryParameters::builder()
+                    .range(Some(format!("bytes={}-", written)))
+                    .build()
+            });
+
+            let source = self.source.read().await.clone();
+            let response = match source {
+                FileDownloadSource::FileId(file_id) => {
+                    self.client
+                        .download_file_by_id(file_id, query_params)
+                        .await?
+                }
+                FileDownloadSource::FileName {
+                    bucket_name,
+                    file_name,
+                } => match self
+                    .client
+                    .download_file_by_name(bucket_name.clone(), file_name.clone(), query_params)
+                    .await
+                {
+                    Err(error) if bucket_found && error.is_not_found() => {
+                        return Err(FileDownloadError::FileMissing {
+                            bucket_name,
+                            file_name,
+                        })
+                    }
+                    response => response?,
+                },
+            };
+
+            if written == 0 {
+                let details = response.file_details.clone();
+
+                self.stats.set_total(details.content_length);
+                // Retries have to get the same version, even if a newer one is uploaded meanwhile
+                *self.source.write().await = FileDownloadSource::FileId(details.file_id.clone());
+                *self.details.write().await = Some(details);
+            }
+
+            // Without a Content-Range the server sent the whole file, so the written bytes are skipped
+            let mut skip = match response.remaining_headers.contains_key("content-range") {
+                true => 0,
+                false => written,
+            };
+
+            let usage = self
+                .options
+                .usage
+                .as_ref()
+                .map(|usage| usage.recorder(self.options.usage_bucket_id.as_deref()));
+
+            let (_, mut stream) = response.file.into_stream();
+            let mut destination = self.destination.lock().await;
+
+            while let Some(chunk) = stream.next().await {
+                let mut chunk = chunk.map_err(B2Error::RequestSendError)?;
+                progress.touch().await;
+
+                if let Some(ref usage) = usage {
+                    usage.record_download(chunk.len() as u64);
+                }
+
+                if skip > 0 {
+                    let skipped = skip.min(chunk.len() as u64);
+                    chunk = chunk.slice(skipped as usize..);
+                    skip -= skipped;
+                }
+
+                if chunk.is_empty() {
+                    continue;
+                }
+
+                if let Some(ref throttle) = self.options.shared_throttle {
+                    throttle.advance_by(chunk.len() as u64).await;
+                }
+
+                destination.write_all(&chunk).await?;
+                self.stats.add_done_bytes(chunk.len() as u64).await;
+            }
+
+            drop(destination);
+
+            self.finish_download(self.stats.done.load(Ordering::Relaxed))
+                .await
+        };
+
+        match progress.guard(download, stall_timeout).await {
+            Some(result) => result,
+            None => Err(FileDownloadError::Stalled(
+                stall_timeout.expect("only stalls with a timeout"),
+            )),
+        }
+    }
+
+    async fn finish_download(
+        &self,
+        written: u64,
+    ) -> Result<B2FileDownloadDetails, FileDownloadError> {
+        let expected = self.stats.total_bytes();
+
+        if written != expected {
+            return Err(FileDownloadError::LengthMismatch {
+                expected,
+                actual: written,
+            });
+        }
+
+        self.destination.lock().await.flush().await?;
+
+        Ok(self
+            .details
+            .read()
+            .await
+            .clone()
+            .expect("set by the first response"))
+    }
+
+    async fn set_status_if(&self, current: FileStatus, new: FileStatus) {
+        let mut status = self.status.lock_write().await;
+        if *status == current {
+            *status = new;
+        }
+    }
+
+    async fn call_finish_callbacks(&self) {
+        run_callbacks(
+            self.completion_callbacks.clone(),
+            (),
+            self.options.finish_callbacks,
+            self.stats.clock().clone(),
+        )
+        .await;
+    }
+}
@@ -0,0 +1,8 @@
+pub mod error;
+pub mod file_download;
+pub mod remote_file;
+pub mod split;
+
+pub use file_download::*;
+pub use remote_file::*;
+pub use split::*;
@@ -0,0 +1,271 @@
+use std::{
+    collections::VecDeque,
+    io::{self, SeekFrom},
+    pin::Pin,
+    sync::Arc,
+    task::{ready, Context, Poll},
+};
+
+use bytes::Bytes;
+use futures::{future::BoxFuture, FutureExt};
+use tokio::io::{AsyncRead, AsyncSeek, ReadBuf};
+
+use crate::{
+    definitions::query_params::B2DownloadFileQueryParameters, error::B2Error,
+    simple_client::B2SimpleClient, util::SizeUnit,
+};
+
+/// Options for [B2RemoteFile].
+#[derive(Debug, Clone, Default)]
+pub struct B2RemoteFileOptions {
+    /// How much is fetched per ranged download and kept around, refer to [PrefetchPolicy].
+    /// <br> Default is [PrefetchPolicy::default].
+    pub prefetch: PrefetchPolicy,
+}
+
+/// How a [B2RemoteFile] fetches and caches data.
+/// <br> A read that continues right where the last fetch ended counts as sequential, anything after a seek elsewhere counts as random access.
+#[derive(Debug, Clone)]
+pub struct PrefetchPolicy {
+    /// Bytes fetched per ranged download when reading sequentially.
+    /// <br> Default is 4 MiB.
+    pub sequential_read_ahead: u64,
+    /// Bytes fetched per ranged download on random access.
+    /// <br> Default is 256 KiB.
+    pub random_access_chunk_size: u64,
+    /// Max bytes kept in fetched blocks, the oldest blocks are dropped first. The latest block is always kept.
+    /// <br> Default is 16 MiB.
+    pub max_cached_bytes: u64,
+}
+
+impl Default for PrefetchPolicy {
+    fn default() -> Self {
+        Self {
+            sequential_read_ahead: SizeUnit::MEBIBYTE * 4,
+            random_access_chunk_size: SizeUnit::KIBIBYTE * 256,
+            max_cached_bytes: SizeUnit::MEBIBYTE * 16,
+        }
+    }
+}
+
+impl PrefetchPolicy {
+    /// For reading whole files front to back, like CSV or log files.
+    pub fn sequential() -> Self {
+        Self {
+            sequential_read_ahead: SizeUnit::MEBIBYTE * 16,
+            random_access_chunk_size: SizeUnit::MEBIBYTE,
+            max_cached_bytes: SizeUnit::MEBIBYTE * 32,
+        }
+    }
+
+    /// For formats read in small scattered pieces, like a parquet footer followed by selected column chunks.
+    pub fn random_access() -> Self {
+        Self {
+            sequential_read_ahead: SizeUnit::MEBIBYTE,
+            random_access_chunk_size: SizeUnit::KIBIBYTE * 64,
+            max_cached_bytes: SizeUnit::MEBIBYTE * 8,
+        }
+    }
+}
+
+struct CachedBlock {
+    start: u64,
+    data: Bytes,
+}
+
+impl CachedBlock {
+    fn contains(&self, position: u64) -> bool {
+        position >= self.start && position < self.start + self.data.len() as u64
+    }
+}
+
+type PendingFetch = BoxFuture<'static, Result<CachedBlock, B2Error>>;
+
+/// A remote B2 file that can be read and seeked like a local one, backed by ranged downloads.
+/// <br> Lets parsers that only need parts of a file, like a zip's central directory or a parquet footer,
+/// read them without downloading the whole file.
+pub struct B2RemoteFile {
+    client: Arc<B2SimpleClient>,
+    file_id: String,
+    size: u64,
+    position: u64,
+    options: B2RemoteFileOptions,
+    blocks: VecDeque<CachedBlock>,
+    cached_bytes: u64,
+    last_fetch_end: u64,
+    pending: Option<PendingFetch>,
+}
+
+impl B2RemoteFile {
+    /// Opens the remote file, getting its size with [get_file_info](B2SimpleClient::get_file_info).
+    pub async fn open(
+        client: Arc<B2SimpleClient>,
+        file_id: String,
+        options: B2RemoteFileOptions,
+    ) -> Result<Self, B2Error> {
+        let size = client.get_file_info(file_id.clone()).await?.content_length;
+
+        Ok(Self::with_size(client, file_id, size, options))
+    }
+
+    /// Opens the remote file with an already known size, without any request.
+    pub fn with_size(
+        client: Arc<B2SimpleClient>,
+        file_id: String,
+        size: u64,
+        options: B2RemoteFileOptions,
+    ) -> Self {
+        Self {
+            client,
+            file_id,
+            size,
+            position: 0,
+            options,
+            blocks: VecDeque::new(),
+            cached_bytes: 0,
+            last_fetch_end: 0,
+            pending: None,
+        }
+    }
+
+    pub fn file_id(&self) -> &str {
+        &self.file_id
+    }
+
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+
+    pub fn position(&self) -> u64 {
+        self.position
+    }
+
+    fn fetch(&mut self, start: u64) -> PendingFetch {
+        let prefetch = &self.options.prefetch;
+        let fetch_size = match start == self.last_fetch_end {
+            true => prefetch.sequential_read_ahead,
+            false => prefetch.random_access_chunk_size,
+        };
+
+        let client = self.client.clone();
+        let file_id = self.file_id.clone();
+        let end = (start + fetch_size.max(1)).min(self.size) - 1;
+        let size = self.size;
+
+        self.last_fetch_end = end + 1;
+
+        async move {
+            let content = client
+                .download_file_by_id(
+                    file_id,
+                    Some(
+                        B2DownloadFileQueryParameters::builder()
+                            .range(Some(format!("bytes={}-{}", start, end)))
+                            .build(),
+                    ),
+                )
+                .await?;
+
+            let mut data = content.file.read_all().await?;
+
+            // The whole file came back, the range was ignored
+            if data.len() as u64 == size && end - start + 1 != size {
+                data = data.slice(start as usize..=end as usize);
+            }
+
+            Ok(CachedBlock { start, data })
+        }
+        .boxed()
+    }
+
+    fn cache_block(&mut self, block: CachedBlock) {
+        let block_size = block.data.len() as u64;
+
+        while !self.blocks.is_empty()
+            && self.cached_bytes + block_size > self.options.prefetch.max_cached_bytes
+        {
+            if let Some(evicted) = self.blocks.pop_front() {
+                self.cached_bytes -= evicted.data.len() as u64;
+            }
+        }
+
+        self.cached_bytes += block_size;
+        self.blocks.push_back(block);
+    }
+}
+
+impl AsyncRead for B2RemoteFile {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+
+        loop {
+            if this.position >= this.size || buf.remaining() == 0 {
+                return Poll::Ready(Ok(()));
+            }
+
+            let position = this.position;
+
+            if let Some(block) = this.blocks.iter().find(|block| block.contains(position)) {
+                let offset = (position - block.start) as usize;
+                let read = buf.remaining().min(block.data.len() - offset);
+
+                buf.put_slice(&block.data[offset..offset + read]);
+                this.position += read as u64;
+
+                return Poll::Ready(Ok(()));
+            }
+
+            if this.pending.is_none() {
+                this.pending = Some(this.fetch(position));
+            }
+
+            let pending = this.pending.as_mut().expect("fetch was just started");
+
+            let result = ready!(pending.as_mut().poll(cx));
+            this.pending = None;
+
+            let block = result.map_err(io::Error::other)?;
+
+            if block.data.is_empty() {
+                return Poll::Ready(Err(io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    "Ranged download returned no data",
+                )));
+            }
+
+            this.cache_block(block);
+        }
+    }
+}
+
+impl AsyncSeek for B2RemoteFile {
+    fn start_seek(self: Pin<&mut Self>, position: SeekFrom) -> io::Result<()> {
+        let this = self.get_mut();
+
+        let target = match position {
+            SeekFrom::Start(target) => Some(target),
+            SeekFrom::Current(delta) => this.position.checked_add_signed(delta),
+            SeekFrom::End(delta) => this.size.checked_add_signed(delta),
+        };
+
+        this.position = target.ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Invalid seek to a negative or overflowing position",
+            )
+        })?;
+
+        // A fetch started for the old position isn't needed anymore
+        this.pending = None;
+
+        Ok(())
+    }
+
+    fn poll_complete(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<u64>> {
+        Poll::Ready(Ok(self.position))
+    }
+}
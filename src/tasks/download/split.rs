@@ -0,0 +1,222 @@
+use std::{
+    num::{NonZeroU64, NonZeroUsize},
+    path::Path,
+};
+
+use futures::{stream, StreamExt, TryStreamExt};
+use serde::{Deserialize, Serialize};
+use sha1_smol::Sha1;
+use tokio::{fs::File, io::AsyncWriteExt};
+
+use crate::{
+    definitions::query_params::B2DownloadFileQueryParameters,
+    error::B2Error,
+    simple_client::B2SimpleClient,
+    util::{RetryContext, RetryStrategy, SizeUnit},
+};
+
+use super::error::FileDownloadError;
+
+/// Options for [split_download].
+#[derive(Debug)]
+pub struct SplitDownloadOptions {
+    /// Size of every chunk file, the last chunk holds whatever is left.
+    /// <br> Default is 1 GiB.
+    pub chunk_size: NonZeroU64,
+    /// Max chunks downloading at once.
+    /// <br> Default is 4.
+    pub concurrency: NonZeroUsize,
+    /// Retry strategy for a failed chunk, a retry downloads the chunk again from its start.
+    /// <br> Defaults to RetryStrategy::Dynamic([crate::util::DefaultRetryStrategy]).
+    pub retry_strategy: RetryStrategy,
+}
+
+impl Default for SplitDownloadOptions {
+    fn default() -> Self {
+        Self {
+            chunk_size: NonZeroU64::new(SizeUnit::GIBIBYTE).expect("1 GiB is not zero"),
+            concurrency: NonZeroUsize::new(4).expect("4 is not zero"),
+            retry_strategy: RetryStrategy::default(),
+        }
+    }
+}
+
+/// Describes the chunk files a [split_download] wrote, saved next to them as `<file name>.manifest.json`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SplitManifest {
+    pub file_id: String,
+    pub file_name: String,
+    pub content_length: u64,
+    /// The whole file's SHA1 as B2 reports it, if it has one.
+    pub content_sha1: Option<String>,
+    pub chunk_size: u64,
+    /// The chunks in file order.
+    pub chunks: Vec<SplitChunk>,
+}
+
+/// A chunk file written by [split_download].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SplitChunk {
+    pub index: u64,
+    /// The chunk file's name inside the output directory.
+    pub file_name: String,
+    /// Where the chunk starts in the original file.
+    pub offset: u64,
+    pub length: u64,
+    /// SHA1 of the chunk's bytes.
+    pub sha1: String,
+}
+
+/// Downloads a file into chunk files of [chunk_size](SplitDownloadOptions::chunk_size) in `directory`, using parallel ranged downloads.
+/// <br> Chunk files are named after the last segment of the B2 file name, as `<name>.part0000`, `<name>.part0001`, and so on,
+/// existing files with those names are overwritten. The returned [SplitManifest] is also written to the directory.
+pub async fn split_download<P: AsRef<Path>>(
+    client: &B2SimpleClient,
+    file_id: String,
+    directory: P,
+    options: SplitDownloadOptions,
+) -> Result<SplitManifest, FileDownloadError> {
+    let directory = directory.as_ref();
+
+    let file = client.get_file_info(file_id.clone()).await?;
+    let base_name = file
+        .file_name
+        .rsplit('/')
+        .next()
+        .filter(|name| !name.is_empty())
+        .unwrap_or(&file.file_id)
+        .to_string();
+
+    tokio::fs::create_dir_all(directory).await?;
+
+    let chunk_size = options.chunk_size.get();
+    let chunk_count = file.content_length.div_ceil(chunk_size);
+    let (base_name, file_id, options) = (&base_name, &file_id, &options);
+
+    let chunks: Vec<SplitChunk> = stream::iter(0..chunk_count)
+        .map(|index| async move {
+            let offset = index * chunk_size;
+            let length = chunk_size.min(file.content_length - offset);
+
+            let chunk = SplitChunk {
+                index,
+                file_name: format!("{}.part{:04}", base_name, index),
+                offset,
+                length,
+                sha1: String::new(),
+            };
+
+            download_chunk_with_retry(client, file_id, directory, chunk, options).await
+        })
+        .buffered(options.concurrency.get())
+        .try_collect()
+        .await?;
+
+    let manifest = SplitManifest {
+        file_id: file.file_id.clone(),
+        file_name: file.file_name.clone(),
+        content_length: file.content_length,
+        content_sha1: file
+            .content_sha1
+            .clone()
+            .filter(|sha1| sha1 != "none")
+            .or_else(|| file.file_info_value("large_file_sha1").map(String::from)),
+        chunk_size,
+        chunks,
+    };
+
+    let manifest_json = serde_json::to_vec_pretty(&manifest).map_err(B2Error::JsonParseError)?;
+    tokio::fs::write(
+        directory.join(format!("{}.manifest.json", base_name)),
+        manifest_json,
+    )
+    .await?;
+
+    Ok(manifest)
+}
+
+async fn download_chunk_with_retry(
+    client: &B2SimpleClient,
+    file_id: &str,
+    directory: &Path,
+    mut chunk: SplitChunk,
+    options: &SplitDownloadOptions,
+) -> Result<SplitChunk, FileDownloadError> {
+    let path = directory.join(&chunk.file_name);
+    let max_attempts = options.retry_strategy.max_attempts();
+    let mut attempt = 0;
+
+    loop {
+        attempt += 1;
+
+        let error = match download_chunk(client, file_id, &path, &chunk).await {
+            Ok(sha1) => {
+                chunk.sha1 = sha1;
+                return Ok(chunk);
+            }
+            Err(error) => error,
+        };
+
+        if !error.is_retryable() || attempt >= max_attempts {
+            return Err(error);
+        }
+
+        client
+            .clock()
+            .sleep(options.retry_strategy.retry_wait(&RetryContext {
+                endpoint: "b2_download_file_by_id",
+                attempt: attempt + 1,
+                last_error: Some(&error),
+                retry_after: error.retry_after(),
+            }))
+            .await;
+    }
+}
+
+/// Downloads a chunk into its file from the start, returning the chunk's SHA1.
+async fn download_chunk(
+    client: &B2SimpleClient,
+    file_id: &str,
+    path: &Path,
+    chunk: &SplitChunk,
+) -> Result<String, FileDownloadError> {
+    let mut destination = File::create(path).await?;
+    let mut sha1 = Sha1::new();
+    let mut written = 0u64;
+
+    if chunk.length > 0 {
+        let query_params = B2DownloadFileQueryParameters::builder()
+            .range(Some(format!(
+                "bytes={}-{}",
+                chunk.offset,
+                chunk.offset + chunk.length - 1
+            )))
+            .build();
+
+        let response = client
+            .download_file_by_id(file_id.to_string(), Some(query_params))
+            .await?;
+        let (_, mut stream) = response.file.into_stream();
+
+        while let Some(bytes) = stream.next().await {
+            let bytes = bytes.map_err(B2Error::RequestSendError)?;
+
+            destination.write_all(&bytes).await?;
+            sha1.update(&bytes);
+            written += bytes.len() as u64;
+        }
+    }
+
+    destination.flush().await?;
+
+    if written != chunk.length {
+        return Err(FileDownloadError::LengthMismatch {
+            expected: chunk.length,
+            actual: written,
+        });
+    }
+
+    Ok(sha1.digest().to_string())
+}
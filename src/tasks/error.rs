@@ -0,0 +1,196 @@
+use core::fmt;
+use std::{error::Error, io, time::Duration};
+
+use crate::{error::B2Error, util::InvalidValue};
+
+use super::{
+    copy::FileCopyError,
+    download::error::FileDownloadError,
+    upload::{error::FileUploadError, UploadLockHolder},
+};
+
+/// An error any transfer task can fail with, so every task type can be handled the same way.
+/// <br> Every task's own error converts into it.
+#[derive(Debug)]
+pub enum TransferError {
+    Aborted,
+    /// Failed to read the data being transferred.
+    SourceIo(io::Error),
+    /// Failed to write the transferred data.
+    DestinationIo(io::Error),
+    Api(B2Error),
+    Validation(InvalidValue),
+    /// The transferred data doesn't have the expected length.
+    LengthMismatch {
+        expected: u64,
+        actual: u64,
+    },
+    /// The transferred data's checksum doesn't match the source's.
+    ChecksumMismatch {
+        expected: String,
+        actual: String,
+    },
+    /// A sampled range of the transferred data doesn't match the source.
+    SampleMismatch {
+        offset: u64,
+        length: u64,
+    },
+    /// The bucket doesn't exist, or the key can't see it.
+    BucketNotVisible(String),
+    /// The bucket is visible, but has no file by that name.
+    FileMissing {
+        bucket_name: String,
+        file_name: String,
+    },
+    /// No progress was made for the given duration.
+    TimedOut(Duration),
+    /// The task went over its retry budget, holds the last error.
+    RetryBudgetExhausted(Box<TransferError>),
+    /// Another writer holds the destination's lock.
+    Locked(UploadLockHolder),
+}
+
+impl Error for TransferError {}
+
+impl fmt::Display for TransferError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "B2 transfer failed, ")?;
+
+        match self {
+            Self::Aborted => write!(f, "Transfer was aborted."),
+            Self::SourceIo(err) => write!(f, "Failed to read source: {}", err),
+            Self::DestinationIo(err) => write!(f, "Failed to write destination: {}", err),
+            Self::Api(err) => write!(f, "{}", err),
+            Self::Validation(err) => write!(f, "{}", err),
+            Self::LengthMismatch { expected, actual } => write!(
+                f,
+                "Length mismatch, expected {} bytes got {} bytes.",
+                expected, actual
+            ),
+            Self::ChecksumMismatch { expected, actual } => write!(
+                f,
+                "Checksum mismatch, expected [{}] got [{}].",
+                expected, actual
+            ),
+            Self::SampleMismatch { offset, length } => write!(
+                f,
+                "Content mismatch in the {} bytes at offset {}.",
+                length, offset
+            ),
+            Self::BucketNotVisible(bucket_name) => write!(
+                f,
+                "Bucket [{}] doesn't exist or isn't visible to this key.",
+                bucket_name
+            ),
+            Self::FileMissing {
+                bucket_name,
+                file_name,
+            } => write!(
+                f,
+                "File [{}] doesn't exist in bucket [{}].",
+                file_name, bucket_name
+            ),
+            Self::TimedOut(timeout) => write!(f, "No progress was made for {:?}.", timeout),
+            Self::RetryBudgetExhausted(err) => {
+                write!(f, "Retry budget exhausted, last error: {}", err)
+            }
+            Self::Locked(holder) => write!(f, "Destination is locked by {}.", holder),
+        }
+    }
+}
+
+impl From<B2Error> for TransferError {
+    fn from(value: B2Error) -> Self {
+        TransferError::Api(value)
+    }
+}
+
+impl From<InvalidValue> for TransferError {
+    fn from(value: InvalidValue) -> Self {
+        TransferError::Validation(value)
+    }
+}
+
+impl From<FileUploadError> for TransferError {
+    fn from(value: FileUploadError) -> Self {
+        match value {
+            FileUploadError::Aborted => TransferError::Aborted,
+            FileUploadError::AlreadyStarted => TransferError::Validation(InvalidValue {
+                object_name: "FileUpload".into(),
+                value_name: "status".into(),
+                value_as_string: "started".into(),
+                expected: "pending".into(),
+            }),
+            FileUploadError::FailedToReadFile(err) => TransferError::SourceIo(err),
+            FileUploadError::RequestError(err) => TransferError::Api(err),
+            FileUploadError::InvalidOptions(err) => TransferError::Validation(err),
+            FileUploadError::Stalled(timeout) => TransferError::TimedOut(timeout),
+            FileUploadError::RetryBudgetExhausted(err) => {
+                TransferError::RetryBudgetExhausted(Box::new((*err).into()))
+            }
+            FileUploadError::LengthMismatch { expected, actual } => {
+                TransferError::LengthMismatch { expected, actual }
+            }
+            FileUploadError::ChecksumMismatch { expected, actual }
+            | FileUploadError::PartChecksumMismatch {
+                expected, actual, ..
+            } => TransferError::ChecksumMismatch { expected, actual },
+            FileUploadError::SampleMismatch { offset, length } => {
+                TransferError::SampleMismatch { offset, length }
+            }
+            FileUploadError::Locked(holder) => TransferError::Locked(holder),
+            FileUploadError::LockFailed(err) => TransferError::DestinationIo(err),
+        }
+    }
+}
+
+impl From<FileDownloadError> for TransferError {
+    fn from(value: FileDownloadError) -> Self {
+        match value {
+            FileDownloadError::Aborted => TransferError::Aborted,
+            FileDownloadError::AlreadyStarted => TransferError::Validation(InvalidValue {
+                object_name: "FileDownload".into(),
+                value_name: "status".into(),
+                value_as_string: "started".into(),
+                expected: "pending".into(),
+            }),
+            FileDownloadError::FailedToWriteFile(err) => TransferError::DestinationIo(err),
+            FileDownloadError::RequestError(err) => TransferError::Api(err),
+            FileDownloadError::Stalled(timeout) => TransferError::TimedOut(timeout),
+            FileDownloadError::BucketNotVisible(bucket_name) => {
+                TransferError::BucketNotVisible(bucket_name)
+            }
+            FileDownloadError::FileMissing {
+                bucket_name,
+                file_name,
+            } => TransferError::FileMissing {
+                bucket_name,
+                file_name,
+            },
+            FileDownloadError::LengthMismatch { expected, actual } => {
+                TransferError::LengthMismatch { expected, actual }
+            }
+        }
+    }
+}
+
+impl From<FileCopyError> for TransferError {
+    fn from(value: FileCopyError) -> Self {
+        match value {
+            FileCopyError::RequestError(err) => TransferError::Api(err),
+            FileCopyError::Aborted => TransferError::Aborted,
+            FileCopyError::InvalidRange(range) => TransferError::Validation(InvalidValue {
+                object_name: "B2CopyFileBody".into(),
+                value_name: "range".into(),
+                value_as_string: range,
+                expected: "bytes=start-end".into(),
+            }),
+            FileCopyError::LengthMismatch { expected, actual } => {
+                TransferError::LengthMismatch { expected, actual }
+            }
+            FileCopyError::ChecksumMismatch { expected, actual } => {
+                TransferError::ChecksumMismatch { expected, actual }
+            }
+        }
+    }
+}
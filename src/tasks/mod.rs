@@ -1,2 +1,6 @@
+pub mod backup;
+pub mod copy;
+pub mod download;
+pub mod error;
 pub mod shared;
 pub mod upload;
@@ -1,5 +1,9 @@
 use std::{
     fmt::Display,
+    future::{pending, Future},
+    io,
+    num::NonZeroU64,
+    path::{Path, PathBuf},
     sync::{
         atomic::{AtomicU64, Ordering},
         Arc,
@@ -7,13 +11,45 @@ use std::{
     time::{Duration, Instant},
 };
 
-use tokio::io::{AsyncRead, AsyncSeek};
+use futures::future::BoxFuture;
+use sha1_smol::Sha1;
+use tokio::{
+    io::{AsyncRead, AsyncReadExt, AsyncSeek, AsyncWrite},
+    sync::{oneshot, watch, Notify, RwLock},
+};
+use tokio_util::sync::CancellationToken;
 
-use crate::util::{write_lock_arc::WriteLockArc, RollingTimeSeries, SizeUnit};
+use crate::util::{
+    run_callbacks, write_lock_arc::WriteLockArc, B2Callback, CallbackRunOptions, Clock,
+    RollingTimeSeries, SizeUnit, SystemClock,
+};
 
+/// A source an upload reads from, any reader that can seek, like a local file or a
+/// [RemoteFileReader](super::upload::RemoteFileReader) over a remote file.
 pub trait AsyncFileReader: AsyncRead + AsyncSeek + Unpin + Send + Sync {}
 impl<T: AsyncRead + AsyncSeek + Unpin + Send + Sync> AsyncFileReader for T {}
 
+pub trait AsyncFileWriter: AsyncWrite + Unpin + Send + Sync {}
+impl<T: AsyncWrite + Unpin + Send + Sync> AsyncFileWriter for T {}
+
+/// Opens new independent readers over the same file.
+pub trait AsyncFileOpener: std::fmt::Debug + Send + Sync {
+    fn open(&self) -> BoxFuture<'_, Result<Box<dyn AsyncFileReader>, io::Error>>;
+}
+
+/// Opens the file at the given path.
+#[derive(Debug, Clone)]
+pub struct PathFileOpener(pub PathBuf);
+
+impl AsyncFileOpener for PathFileOpener {
+    fn open(&self) -> BoxFuture<'_, Result<Box<dyn AsyncFileReader>, io::Error>> {
+        Box::pin(async move {
+            let file = tokio::fs::File::open(&self.0).await?;
+            Ok(Box::new(file) as Box<dyn AsyncFileReader>)
+        })
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct CurrentFileNetworkStats {
     /// Bytes per seconds
@@ -60,20 +96,36 @@ pub enum FileStatus {
 pub struct FileNetworkStats {
     pub(super) done: Arc<AtomicU64>,
     pub(super) speed_buffer: WriteLockArc<RollingTimeSeries<u64, 5000>>,
-    pub(super) total: f64,
+    pub(super) total: AtomicU64,
     pub(super) start_time: WriteLockArc<Instant>,
+    progress: Notify,
+    clock: Arc<dyn Clock>,
 }
 
 impl FileNetworkStats {
-    pub(super) fn new(total: f64) -> Self {
+    pub(super) fn new(total: u64, clock: Option<Arc<dyn Clock>>) -> Self {
+        let clock = clock.unwrap_or_else(SystemClock::shared);
+
         Self {
-            total,
+            total: AtomicU64::new(total),
             done: Arc::new(AtomicU64::new(0)),
             speed_buffer: WriteLockArc::new(RollingTimeSeries::new(Duration::from_secs(10))),
-            start_time: WriteLockArc::new(Instant::now()),
+            start_time: WriteLockArc::new(clock.now()),
+            progress: Notify::new(),
+            clock,
         }
     }
 
+    /// The clock the transfer's stats and retry waits use.
+    pub(super) fn clock(&self) -> &Arc<dyn Clock> {
+        &self.clock
+    }
+
+    /// Restarts the elapsed time, called when the transfer starts.
+    pub(super) async fn start_timer(&self) {
+        self.start_time.set(self.clock.now()).await;
+    }
+
     /// Returns estimated download/upload speed in bytes per second
     pub fn bytes_per_second(&self) -> f64 {
         self.inner_bytes_per_second()
@@ -90,7 +142,7 @@ impl FileNetworkStats {
     pub fn percentage(&self) -> f64 {
         let done = self.done.load(Ordering::Relaxed) as f64;
 
-        done / self.total
+        done / self.total()
     }
 
     /// Returns file stats at this point of time
@@ -100,32 +152,55 @@ impl FileNetworkStats {
         CurrentFileNetworkStats {
             bps: self.inner_bytes_per_second().into(),
             eta: Duration::from_secs_f64(self.inner_estimated_time(done).max(0.0)),
-            percentage: done / self.total,
+            percentage: done / self.total(),
             done: done.into(),
-            total: self.total.into(),
-            elapsed: self.start_time.elapsed(),
+            total: self.total().into(),
+            elapsed: self.clock.now().saturating_duration_since(*self.start_time),
         }
     }
 
+    /// Total bytes to transfer, 0 until a download learns the file's size.
+    pub fn total_bytes(&self) -> u64 {
+        self.total.load(Ordering::Relaxed)
+    }
+
+    /// Bytes transferred so far.
+    pub fn done_bytes(&self) -> u64 {
+        self.done.load(Ordering::Relaxed)
+    }
+
+    pub(super) fn set_total(&self, total: u64) {
+        self.total.store(total, Ordering::Relaxed);
+    }
+
+    fn total(&self) -> f64 {
+        self.total.load(Ordering::Relaxed) as f64
+    }
+
     pub(super) async fn add_done_bytes(&self, bytes: u64) {
         self.done.fetch_add(bytes, Ordering::Relaxed);
         let mut buffer = self.speed_buffer.lock_write().await;
-        buffer.add_value(bytes);
+        buffer.add_value_at(bytes, self.clock.now());
+        drop(buffer);
+
+        self.progress.notify_waiters();
     }
 
     fn inner_bytes_per_second(&self) -> f64 {
-        let dps = self.speed_buffer.get_valid_points();
+        let now = self.clock.now();
+        let dps = self.speed_buffer.get_valid_points_at(now);
         let mut total = 0.0;
         let oldest_time = dps
             .iter()
             .map(|dp| {
                 total += dp.data as f64;
-                dp.time.elapsed()
+                now.saturating_duration_since(dp.time)
             })
             .max();
 
         match oldest_time {
-            Some(dur) => total / dur.as_secs_f64(),
+            Some(dur) if !dur.is_zero() => total / dur.as_secs_f64(),
+            Some(_) => 0.0,
             None => 0.0,
         }
     }
@@ -137,6 +212,207 @@ impl FileNetworkStats {
             bytes_per_sec = 1.0;
         }
 
-        (self.total - done) / bytes_per_sec
+        (self.total() - done) / bytes_per_sec
+    }
+}
+
+/// Reads the whole file, returning its size and SHA1.
+pub(crate) async fn file_sha1(path: &Path) -> io::Result<(u64, String)> {
+    let mut file = tokio::fs::File::open(path).await?;
+    let mut sha1 = Sha1::new();
+    let mut buffer = vec![0u8; 1024 * 1024];
+    let mut size = 0u64;
+
+    loop {
+        let read = file.read(&mut buffer).await?;
+
+        if read == 0 {
+            break;
+        }
+
+        sha1.update(&buffer[..read]);
+        size += read as u64;
+    }
+
+    Ok((size, sha1.digest().to_string()))
+}
+
+/// When a task calls its progress callbacks while it runs, they're also called once when it stops.
+/// <br> With both set, callbacks are called on whichever comes first.
+#[derive(Debug, Clone, Copy)]
+pub struct ProgressCallbackOptions {
+    /// Calls the progress callbacks this often.
+    /// <br> Default is 1 second.
+    pub interval: Option<Duration>,
+    /// Calls the progress callbacks every time this many more bytes are transferred.
+    /// <br> Default is None.
+    pub every_bytes: Option<NonZeroU64>,
+    /// How the progress callbacks are run, refer to [CallbackRunOptions].
+    /// <br> Default waits for every callback before the next report.
+    pub run: CallbackRunOptions,
+}
+
+impl Default for ProgressCallbackOptions {
+    fn default() -> Self {
+        Self {
+            interval: Some(Duration::from_secs(1)),
+            every_bytes: None,
+            run: CallbackRunOptions::default(),
+        }
+    }
+}
+
+/// Calls the callbacks with the current stats as the options say, until `stop` is sent or dropped,
+/// then calls them a last time with the final stats.
+pub(crate) async fn report_progress(
+    stats: Arc<FileNetworkStats>,
+    callbacks: Arc<RwLock<Vec<B2Callback<CurrentFileNetworkStats>>>>,
+    options: ProgressCallbackOptions,
+    mut stop: oneshot::Receiver<()>,
+) {
+    let clock = stats.clock().clone();
+    let interval = options.interval.filter(|interval| !interval.is_zero());
+    let next_tick = || -> BoxFuture<'static, ()> {
+        match interval {
+            Some(interval) => clock.sleep(interval),
+            None => Box::pin(pending()),
+        }
+    };
+    let mut tick = next_tick();
+    let mut reported_bytes = stats.done_bytes();
+
+    loop {
+        let progressed = stats.progress.notified();
+
+        tokio::select! {
+            _ = &mut stop => break,
+            _ = &mut tick => tick = next_tick(),
+            _ = progressed, if options.every_bytes.is_some() => {
+                let every_bytes = options.every_bytes.map_or(0, NonZeroU64::get);
+                // A retried attempt starts counting from zero again
+                reported_bytes = reported_bytes.min(stats.done_bytes());
+
+                if stats.done_bytes() - reported_bytes < every_bytes {
+                    continue;
+                }
+            },
+        }
+
+        reported_bytes = stats.done_bytes();
+        run_callbacks(
+            callbacks.clone(),
+            stats.current_stats(),
+            options.run,
+            clock.clone(),
+        )
+        .await;
+    }
+
+    run_callbacks(callbacks, stats.current_stats(), options.run, clock).await;
+}
+
+/// Tracks the last time a single request made byte progress, used to detect stalled transfers.
+#[derive(Debug, Clone)]
+pub(crate) struct ProgressWatch {
+    last_progress: WriteLockArc<Instant>,
+    clock: Arc<dyn Clock>,
+}
+
+impl ProgressWatch {
+    pub(crate) fn new(clock: Arc<dyn Clock>) -> Self {
+        Self {
+            last_progress: WriteLockArc::new(clock.now()),
+            clock,
+        }
+    }
+
+    /// Marks that bytes were transferred just now.
+    pub(crate) async fn touch(&self) {
+        self.last_progress.set(self.clock.now()).await;
+    }
+
+    /// Time since the last byte progress.
+    pub(crate) fn idle_time(&self) -> Duration {
+        self.clock
+            .now()
+            .saturating_duration_since(*self.last_progress)
+    }
+
+    /// Runs the future to completion, unless no progress is made for `stall_timeout`,
+    /// in which case the future is dropped and `None` is returned.
+    pub(crate) async fn guard<F: Future>(
+        &self,
+        future: F,
+        stall_timeout: Option<Duration>,
+    ) -> Option<F::Output> {
+        let stall_timeout = match stall_timeout {
+            Some(timeout) => timeout,
+            None => return Some(future.await),
+        };
+
+        tokio::pin!(future);
+
+        loop {
+            let wait = stall_timeout.saturating_sub(self.idle_time());
+
+            if wait.is_zero() {
+                return None;
+            }
+
+            tokio::select! {
+                output = &mut future => return Some(output),
+                _ = self.clock.sleep(wait) => {},
+            }
+        }
+    }
+}
+
+/// Holds a transfer's requests back while paused, shared by every task of the transfer.
+#[derive(Debug, Clone)]
+pub(crate) struct PauseGate(Arc<watch::Sender<bool>>);
+
+impl PauseGate {
+    pub(crate) fn new() -> Self {
+        Self(Arc::new(watch::Sender::new(false)))
+    }
+
+    pub(crate) fn set_paused(&self, paused: bool) {
+        self.0.send_replace(paused);
+    }
+
+    pub(crate) fn is_paused(&self) -> bool {
+        *self.0.borrow()
+    }
+
+    /// Waits until the gate isn't paused.
+    /// <br> A request's progress watch is touched while waiting, so a pause doesn't count as a stall.
+    pub(crate) async fn wait(&self, progress: Option<&ProgressWatch>) {
+        let mut paused = self.0.subscribe();
+
+        while *paused.borrow_and_update() {
+            match progress {
+                Some(progress) => {
+                    progress.touch().await;
+
+                    tokio::select! {
+                        _ = paused.changed() => {},
+                        _ = progress.clock.sleep(Duration::from_secs(1)) => {},
+                    }
+                }
+                None => drop(paused.changed().await),
+            }
+        }
+
+        if let Some(progress) = progress {
+            progress.touch().await;
+        }
+    }
+}
+
+/// Finishes once the token is cancelled, never without a token.
+pub(crate) async fn cancelled(token: Option<&CancellationToken>) {
+    match token {
+        Some(token) => token.cancelled().await,
+        None => pending().await,
     }
 }
@@ -0,0 +1,112 @@
+use std::{collections::HashMap, num::NonZeroUsize, path::PathBuf, time::Duration};
+
+use serde::{Deserialize, Serialize};
+
+use crate::util::RetryBudget;
+
+use super::{
+    B2FileUploadSettings, ContentEncodingCheck, FileUploadOptions, LargeFileCutoff,
+    SampleVerificationOptions,
+};
+
+/// Version of the [UploadTaskDescriptor] format written by this crate.
+pub const UPLOAD_TASK_DESCRIPTOR_VERSION: u32 = 1;
+
+/// A serializable description of an upload task, enough for another worker to recreate it and resume where it left off,
+/// see [B2Client::create_upload_from_descriptor](crate::client::B2Client::create_upload_from_descriptor).
+/// <br> Downloads have no descriptor, there's no API to resume a download from a checkpoint for one to carry.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UploadTaskDescriptor {
+    /// Format version, descriptors with a different version are rejected.
+    pub version: u32,
+    pub source: UploadSource,
+    pub bucket_id: String,
+    pub file_name: String,
+    pub file_info: Option<HashMap<String, String>>,
+    pub options: UploadTaskOptions,
+    /// Set once a large file was started, None means the upload starts from the beginning.
+    pub checkpoint: Option<UploadCheckpoint>,
+}
+
+/// Where the upload reads its data from, `length` bytes of the file at `path` starting at `offset`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UploadSource {
+    pub path: PathBuf,
+    pub offset: u64,
+    pub length: u64,
+}
+
+/// The serializable part of [FileUploadOptions].
+/// <br> Load strategy, throttle, retry strategy and part file handles can't be serialized, and are left at their defaults.
+/// <br> The upload lock isn't carried over either, since every worker has its own owner ID.
+/// <br> Note that [B2FileUploadSettings] holds the SSE-C customer key if one is used.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UploadTaskOptions {
+    pub large_file_cutoff: LargeFileCutoff,
+    pub retry_budget: RetryBudget,
+    pub stall_timeout: Option<Duration>,
+    pub fail_on_checksum_mismatch: bool,
+    #[serde(default)]
+    pub verify_parts: bool,
+    #[serde(default)]
+    pub sha1_at_end: bool,
+    #[serde(default)]
+    pub large_file_sha1: bool,
+    #[serde(default)]
+    pub max_concurrent_parts: Option<NonZeroUsize>,
+    #[serde(default)]
+    pub sample_verification: Option<SampleVerificationOptions>,
+    #[serde(default)]
+    pub content_encoding_check: ContentEncodingCheck,
+    pub settings: B2FileUploadSettings,
+}
+
+impl From<&FileUploadOptions> for UploadTaskOptions {
+    fn from(options: &FileUploadOptions) -> Self {
+        Self {
+            large_file_cutoff: options.large_file_cutoff.clone(),
+            retry_budget: options.retry_budget.clone(),
+            stall_timeout: options.stall_timeout,
+            fail_on_checksum_mismatch: options.fail_on_checksum_mismatch,
+            verify_parts: options.verify_parts,
+            sha1_at_end: options.sha1_at_end,
+            large_file_sha1: options.large_file_sha1,
+            max_concurrent_parts: options.max_concurrent_parts,
+            sample_verification: options.sample_verification,
+            content_encoding_check: options.content_encoding_check,
+            settings: options.options.clone(),
+        }
+    }
+}
+
+impl From<UploadTaskOptions> for FileUploadOptions {
+    fn from(options: UploadTaskOptions) -> Self {
+        Self {
+            large_file_cutoff: options.large_file_cutoff,
+            retry_budget: options.retry_budget,
+            stall_timeout: options.stall_timeout,
+            fail_on_checksum_mismatch: options.fail_on_checksum_mismatch,
+            verify_parts: options.verify_parts,
+            sha1_at_end: options.sha1_at_end,
+            large_file_sha1: options.large_file_sha1,
+            max_concurrent_parts: options.max_concurrent_parts,
+            sample_verification: options.sample_verification,
+            content_encoding_check: options.content_encoding_check,
+            options: options.settings,
+            ..Default::default()
+        }
+    }
+}
+
+/// Progress of a started large file, parts already stored in B2 are skipped on resume.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UploadCheckpoint {
+    /// The unfinished large file's ID.
+    pub large_file_id: String,
+    /// The part size the large file was split with, a resumed upload has to use the same size.
+    pub part_size: u64,
+}
@@ -1,8 +1,10 @@
 use core::fmt;
-use std::error::Error;
+use std::{error::Error, time::Duration};
 
 use crate::{error::B2Error, util::InvalidValue};
 
+use super::UploadLockHolder;
+
 #[derive(Debug)]
 pub enum FileUploadError {
     Aborted,
@@ -10,10 +12,83 @@ pub enum FileUploadError {
     FailedToReadFile(std::io::Error),
     RequestError(B2Error),
     InvalidOptions(InvalidValue),
+    /// The upload made no progress for the given [stall timeout](super::FileUploadOptions::stall_timeout).
+    Stalled(Duration),
+    /// The task went over its [retry budget](super::FileUploadOptions::retry_budget), holds the last error.
+    RetryBudgetExhausted(Box<FileUploadError>),
+    /// The uploaded file doesn't have the expected length.
+    LengthMismatch {
+        expected: u64,
+        actual: u64,
+    },
+    /// The uploaded file's SHA1 doesn't match the locally computed one.
+    ChecksumMismatch {
+        expected: String,
+        actual: String,
+    },
+    /// A large file part B2 stored doesn't have the SHA1 of the bytes sent for it.
+    PartChecksumMismatch {
+        part_number: u16,
+        expected: String,
+        actual: String,
+    },
+    /// A range downloaded by [sample verification](super::FileUploadOptions::sample_verification) doesn't match the source.
+    SampleMismatch {
+        offset: u64,
+        length: u64,
+    },
+    /// Another writer holds the destination's [upload lock](super::FileUploadOptions::lock).
+    Locked(UploadLockHolder),
+    /// Failed to create or lock the local lock file of the [upload lock](super::FileUploadOptions::lock).
+    LockFailed(std::io::Error),
 }
 
 impl Error for FileUploadError {}
 
+/// Sent to [on_part_failure](super::FileUploadOptions::on_part_failure) when a large file part gives up,
+/// right before the error fails the upload attempt.
+#[derive(Debug, Clone)]
+pub struct PartUploadFailure {
+    pub part_number: u16,
+    /// Requests made for the part, including the last one.
+    pub attempts: u64,
+    /// HTTP status of the last response, None if the request didn't get one.
+    pub status: Option<u16>,
+    /// The last error's message.
+    pub last_error: String,
+}
+
+impl PartUploadFailure {
+    pub(crate) fn new(part_number: u16, attempts: u64, error: &FileUploadError) -> Self {
+        let status = match error {
+            FileUploadError::RequestError(error) => error.request_error(),
+            FileUploadError::RetryBudgetExhausted(error) => match error.as_ref() {
+                FileUploadError::RequestError(error) => error.request_error(),
+                _ => None,
+            },
+            _ => None,
+        };
+
+        Self {
+            part_number,
+            attempts,
+            status: status.map(|error| error.status.get()),
+            last_error: error.to_string(),
+        }
+    }
+}
+
+impl FileUploadError {
+    /// The `Retry-After` the server responded with, if any.
+    pub fn retry_after(&self) -> Option<Duration> {
+        match self {
+            Self::RequestError(error) => error.retry_after(),
+            Self::RetryBudgetExhausted(error) => error.retry_after(),
+            _ => None,
+        }
+    }
+}
+
 impl fmt::Display for FileUploadError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "B2 upload failed, ")?;
@@ -24,6 +99,36 @@ impl fmt::Display for FileUploadError {
             Self::FailedToReadFile(err) => write!(f, "Failed to read file to upload: {}", err),
             Self::RequestError(err) => write!(f, "{}", err),
             Self::InvalidOptions(err) => write!(f, "{}", err),
+            Self::Stalled(timeout) => write!(f, "No progress was made for {:?}.", timeout),
+            Self::RetryBudgetExhausted(err) => {
+                write!(f, "Retry budget exhausted, last error: {}", err)
+            }
+            Self::LengthMismatch { expected, actual } => write!(
+                f,
+                "Upload length mismatch, expected {} bytes got {} bytes.",
+                expected, actual
+            ),
+            Self::ChecksumMismatch { expected, actual } => write!(
+                f,
+                "Upload SHA1 mismatch, expected [{}] got [{}].",
+                expected, actual
+            ),
+            Self::PartChecksumMismatch {
+                part_number,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "Upload SHA1 mismatch in part {}, expected [{}] got [{}].",
+                part_number, expected, actual
+            ),
+            Self::SampleMismatch { offset, length } => write!(
+                f,
+                "Uploaded file differs from the source in the {} bytes at offset {}.",
+                length, offset
+            ),
+            Self::Locked(holder) => write!(f, "Destination is locked by {}.", holder),
+            Self::LockFailed(err) => write!(f, "Failed to take the local upload lock: {}", err),
         }
     }
 }
@@ -0,0 +1,287 @@
+use std::collections::HashMap;
+
+use bytes::Bytes;
+use futures::future::join_all;
+use sha1_smol::Sha1;
+use tokio::io::AsyncRead;
+
+use crate::{
+    definitions::{
+        bodies::{B2FinishLargeFileBody, B2StartLargeFileUploadBody},
+        responses::B2GetUploadPartUrlResponse,
+        shared::B2File,
+    },
+    simple_client::B2SimpleClient,
+    util::{InvalidValue, IsValid, SizeUnit},
+};
+
+use super::{
+    error::FileUploadError,
+    stream_upload::{read_part, upload_part_with_retry, upload_whole_hashed, MAX_PART_COUNT},
+    upload_cache::UploadCache,
+    StreamUploadOptions,
+};
+
+/// A bucket [upload_fan_out] uploads a copy to, the client can be of another account than the other destinations'.
+#[derive(Debug, Clone)]
+pub struct FanOutDestination {
+    pub client: B2SimpleClient,
+    pub bucket_id: String,
+}
+
+/// A destination's large file while the parts are uploaded, until it finishes or fails.
+struct FanOutLargeFile<'a> {
+    client: &'a B2SimpleClient,
+    file_id: String,
+    upload_url: Option<B2GetUploadPartUrlResponse>,
+    error: Option<FileUploadError>,
+}
+
+/// Uploads everything read from `reader` to every destination in a single pass, for redundancy across accounts or regions.
+/// <br> Works like [upload_stream](super::upload_stream), every part is read and hashed once and uploaded to all destinations at once,
+/// so the slowest destination sets the pace. A destination that fails drops out, its unfinished large file is canceled,
+/// and the others carry on.
+/// <br> Returns a result per destination, in the order they were given. Failing to read `reader` fails every destination,
+/// and is returned as the error instead.
+/// <br> The part size defaults to the first destination's recommended part size.
+/// <br> A [cache](StreamUploadOptions::cache) is kept when at least one destination has the file.
+pub async fn upload_fan_out<R: AsyncRead + Unpin + Send>(
+    destinations: &[FanOutDestination],
+    reader: R,
+    file_name: String,
+    optional_info: Option<HashMap<String, String>>,
+    options: StreamUploadOptions,
+) -> Result<Vec<Result<B2File, FileUploadError>>, FileUploadError> {
+    options.is_valid()?;
+
+    let mut cache = UploadCache::open(options.cache.as_ref()).await;
+
+    let result = upload_fan_out_cached(
+        destinations,
+        reader,
+        file_name,
+        optional_info,
+        &options,
+        &mut cache,
+    )
+    .await;
+
+    if let Some(cache) = cache {
+        let uploaded = result
+            .as_ref()
+            .is_ok_and(|results| results.iter().any(Result::is_ok));
+
+        cache.finish(uploaded).await;
+    }
+
+    result
+}
+
+/// [upload_fan_out], with every part read also written to the cache.
+async fn upload_fan_out_cached<R: AsyncRead + Unpin + Send>(
+    destinations: &[FanOutDestination],
+    mut reader: R,
+    file_name: String,
+    optional_info: Option<HashMap<String, String>>,
+    options: &StreamUploadOptions,
+    cache: &mut Option<UploadCache<'_>>,
+) -> Result<Vec<Result<B2File, FileUploadError>>, FileUploadError> {
+    let Some(first) = destinations.first() else {
+        return Ok(vec![]);
+    };
+
+    let part_size = options
+        .part_size
+        .unwrap_or_else(|| first.client.recommended_part_size());
+
+    let first_part = read_part(&mut reader, part_size, cache).await?;
+    let second_part = match (first_part.len() as u64) < part_size {
+        true => vec![],
+        false => read_part(&mut reader, part_size, cache).await?,
+    };
+
+    // A stream that fits in one part is a regular file, B2 needs at least two parts for a large file
+    if second_part.is_empty() {
+        let sha1 = Sha1::from(&first_part).digest().to_string();
+        let data = Bytes::from(first_part);
+
+        return Ok(join_all(destinations.iter().map(|destination| {
+            upload_whole_hashed(
+                &destination.client,
+                data.clone(),
+                sha1.clone(),
+                file_name.clone(),
+                destination.bucket_id.clone(),
+                optional_info.clone(),
+                options,
+            )
+        }))
+        .await);
+    }
+
+    let started = join_all(destinations.iter().map(|destination| {
+        let start_body = B2StartLargeFileUploadBody::builder()
+            .bucket_id(destination.bucket_id.clone())
+            .file_name(file_name.clone())
+            .content_type("b2/x-auto".into())
+            .file_info(optional_info.clone())
+            .build();
+
+        let start_body = options.options.clone().apply_large_file_upload(start_body);
+
+        destination.client.start_large_file(start_body)
+    }))
+    .await;
+
+    let mut large_files: Vec<FanOutLargeFile> = destinations
+        .iter()
+        .zip(started)
+        .map(|(destination, started)| match started {
+            Ok(file) => FanOutLargeFile {
+                client: &destination.client,
+                file_id: file.file_id,
+                upload_url: None,
+                error: None,
+            },
+            Err(error) => FanOutLargeFile {
+                client: &destination.client,
+                file_id: String::new(),
+                upload_url: None,
+                error: Some(error.into()),
+            },
+        })
+        .collect();
+
+    let result = upload_fan_out_parts(
+        &mut large_files,
+        &mut reader,
+        part_size,
+        [first_part, second_part],
+        options,
+        cache,
+    )
+    .await;
+
+    let sha1s = match result {
+        Ok(sha1s) => sha1s,
+        Err(error) => {
+            join_all(
+                large_files
+                    .iter()
+                    .filter(|large_file| large_file.error.is_none())
+                    .map(|large_file| {
+                        large_file
+                            .client
+                            .cancel_large_file(large_file.file_id.clone())
+                    }),
+            )
+            .await;
+
+            return Err(error);
+        }
+    };
+
+    Ok(join_all(large_files.into_iter().map(|large_file| {
+        let sha1s = sha1s.clone();
+
+        async move {
+            if let Some(error) = large_file.error {
+                return Err(error);
+            }
+
+            Ok(large_file
+                .client
+                .finish_large_file(
+                    B2FinishLargeFileBody::builder()
+                        .file_id(large_file.file_id)
+                        .part_sha1_array(sha1s)
+                        .build(),
+                )
+                .await?)
+        }
+    }))
+    .await)
+}
+
+/// Uploads the parts in order to every large file that hasn't failed, returning the parts' SHA1s.
+/// <br> Only fails when reading the stream does, or it has too many parts.
+async fn upload_fan_out_parts<R: AsyncRead + Unpin + Send>(
+    large_files: &mut [FanOutLargeFile<'_>],
+    reader: &mut R,
+    part_size: u64,
+    [first_part, second_part]: [Vec<u8>; 2],
+    options: &StreamUploadOptions,
+    cache: &mut Option<UploadCache<'_>>,
+) -> Result<Vec<String>, FileUploadError> {
+    let mut sha1s = vec![];
+    let mut part_number: u16 = 0;
+    let mut current = first_part;
+    let mut next = Some(second_part);
+
+    while !current.is_empty() {
+        // Nothing left to upload to, the rest of the stream isn't read
+        if large_files
+            .iter()
+            .all(|large_file| large_file.error.is_some())
+        {
+            break;
+        }
+
+        part_number += 1;
+
+        if part_number > MAX_PART_COUNT {
+            return Err(FileUploadError::InvalidOptions(InvalidValue {
+                object_name: "StreamUploadOptions".into(),
+                value_name: "part_size".into(),
+                value_as_string: SizeUnit::from(part_size as f64).to_string(),
+                expected: format!("large enough to fit the stream in {} parts", MAX_PART_COUNT),
+            }));
+        }
+
+        let sha1 = Sha1::from(&current).digest().to_string();
+        let part = Bytes::from(current);
+
+        let uploads = join_all(
+            large_files
+                .iter_mut()
+                .filter(|large_file| large_file.error.is_none())
+                .map(|large_file| {
+                    let (part, sha1) = (part.clone(), sha1.clone());
+
+                    async move {
+                        let uploaded = upload_part_with_retry(
+                            large_file.client,
+                            &large_file.file_id,
+                            part_number,
+                            part,
+                            sha1,
+                            &mut large_file.upload_url,
+                            options,
+                        )
+                        .await;
+
+                        if let Err(error) = uploaded {
+                            large_file
+                                .client
+                                .cancel_large_file(large_file.file_id.clone())
+                                .await
+                                .ok();
+                            large_file.error = Some(error);
+                        }
+                    }
+                }),
+        );
+
+        let (_, read) = tokio::join!(uploads, async {
+            match next.take() {
+                Some(next) => Ok(next),
+                None => read_part(reader, part_size, &mut *cache).await,
+            }
+        });
+
+        sha1s.push(sha1);
+        current = read?;
+    }
+
+    Ok(sha1s)
+}
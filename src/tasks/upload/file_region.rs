@@ -0,0 +1,139 @@
+use std::{
+    io::{self, SeekFrom},
+    pin::Pin,
+    sync::Arc,
+    task::{ready, Context, Poll},
+};
+
+use futures::future::BoxFuture;
+use tokio::io::{AsyncRead, AsyncSeek, AsyncSeekExt, ReadBuf};
+
+use crate::tasks::shared::{AsyncFileOpener, AsyncFileReader};
+
+/// A byte range of a reader, read and seeked as if it was the whole file.
+/// <br> Position 0 of the region is `offset` in the inner reader, and reads stop after `length` bytes.
+#[derive(Debug)]
+pub struct FileRegion<R> {
+    inner: R,
+    offset: u64,
+    length: u64,
+    position: u64,
+}
+
+impl<R: AsyncRead + AsyncSeek + Unpin> FileRegion<R> {
+    /// Creates the region and seeks the inner reader to its start.
+    /// <br> Fails if the region goes past the end of the inner reader.
+    pub async fn new(mut inner: R, offset: u64, length: u64) -> Result<Self, io::Error> {
+        let inner_length = inner.seek(SeekFrom::End(0)).await?;
+
+        if offset
+            .checked_add(length)
+            .is_none_or(|end| end > inner_length)
+        {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "Region of {} bytes at offset {} goes past the end of a {} bytes file",
+                    length, offset, inner_length
+                ),
+            ));
+        }
+
+        inner.seek(SeekFrom::Start(offset)).await?;
+
+        Ok(Self {
+            inner,
+            offset,
+            length,
+            position: 0,
+        })
+    }
+
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+
+    pub fn length(&self) -> u64 {
+        self.length
+    }
+
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
+
+impl<R: AsyncRead + Unpin> AsyncRead for FileRegion<R> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        let remaining = this.length.saturating_sub(this.position);
+
+        if remaining == 0 {
+            return Poll::Ready(Ok(()));
+        }
+
+        let max = buf.remaining().min(remaining as usize);
+        let mut limited = ReadBuf::new(buf.initialize_unfilled_to(max));
+
+        ready!(Pin::new(&mut this.inner).poll_read(cx, &mut limited))?;
+
+        let read = limited.filled().len();
+        buf.advance(read);
+        this.position += read as u64;
+
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl<R: AsyncSeek + Unpin> AsyncSeek for FileRegion<R> {
+    fn start_seek(self: Pin<&mut Self>, position: SeekFrom) -> io::Result<()> {
+        let this = self.get_mut();
+
+        let target = match position {
+            SeekFrom::Start(target) => Some(target),
+            SeekFrom::Current(delta) => this.position.checked_add_signed(delta),
+            SeekFrom::End(delta) => this.length.checked_add_signed(delta),
+        };
+
+        let target = target.ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Invalid seek to a negative or overflowing position",
+            )
+        })?;
+
+        Pin::new(&mut this.inner).start_seek(SeekFrom::Start(this.offset + target))
+    }
+
+    fn poll_complete(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<u64>> {
+        let this = self.get_mut();
+        let inner_position = ready!(Pin::new(&mut this.inner).poll_complete(cx))?;
+
+        this.position = inner_position.saturating_sub(this.offset);
+
+        Poll::Ready(Ok(this.position))
+    }
+}
+
+/// Opens the same [FileRegion] over every reader the inner opener opens,
+/// for using [PartFileHandles::PerTask](super::PartFileHandles::PerTask) with a region upload.
+#[derive(Debug, Clone)]
+pub struct FileRegionOpener {
+    pub opener: Arc<dyn AsyncFileOpener>,
+    pub offset: u64,
+    pub length: u64,
+}
+
+impl AsyncFileOpener for FileRegionOpener {
+    fn open(&self) -> BoxFuture<'_, Result<Box<dyn AsyncFileReader>, io::Error>> {
+        Box::pin(async move {
+            let file = self.opener.open().await?;
+            let region = FileRegion::new(file, self.offset, self.length).await?;
+
+            Ok(Box::new(region) as Box<dyn AsyncFileReader>)
+        })
+    }
+}
@@ -1,43 +1,63 @@
 use std::{
     collections::HashMap,
     convert::Infallible,
-    io::SeekFrom,
-    ops::Deref,
-    sync::{atomic::Ordering, Arc},
-    time::{Duration, Instant},
+    io::{self, SeekFrom},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
 };
 
 use async_stream::stream;
 use bytes::Bytes;
+use futures::{
+    stream::{self, BoxStream},
+    StreamExt,
+};
 use sha1_smol::Sha1;
 use tokio::{
     io::{AsyncReadExt, AsyncSeekExt},
     sync::{
         mpsc::{self, Receiver, Sender},
-        Mutex, RwLock,
+        oneshot, Mutex, RwLock,
     },
     task::{AbortHandle, JoinHandle},
-    time::sleep,
 };
 
 use crate::{
     definitions::{
-        bodies::{B2FinishLargeFileBody, B2StartLargeFileUploadBody},
+        bodies::{B2DeleteFileVersionBody, B2FinishLargeFileBody, B2StartLargeFileUploadBody},
         headers::{B2UploadFileHeaders, B2UploadPartHeaders},
+        query_params::{B2DownloadFileQueryParameters, B2ListPartsQueryParameters},
+        responses::B2FilePart,
         shared::B2File,
     },
-    error::B2Error,
     simple_client::B2SimpleClient,
-    tasks::upload::{large_file_sha1::LargeFileSha1, upload_buffer::UploadBuffer},
-    throttle::Throttle,
-    util::{write_lock_arc::WriteLockArc, B2Callback, IsValid, SizeUnit},
+    tasks::upload::{
+        large_file_sha1::LargeFileSha1, part_progress::PartTracker, part_queue::PartQueue,
+        upload_buffer::UploadBuffer,
+    },
+    throttle::{SharedThrottle, Throttle},
+    usage::UsageRecorder,
+    util::{
+        append_sha1_hex, content_encoding_matches, retry_strategy::trace_retry, run_callbacks,
+        sniff_content_encoding, write_lock_arc::WriteLockArc, B2Callback, FileInfoExt,
+        InvalidValue, IsValid, RetryBudgetTracker, RetryContext, SizeUnit,
+        CONTENT_ENCODING_HEAD_LENGTH, HEX_DIGITS_AT_END, SHA1_HEX_LENGTH,
+    },
 };
 
-use crate::tasks::shared::{AsyncFileReader, FileNetworkStats, FileStatus};
+use crate::tasks::shared::{
+    cancelled, report_progress, AsyncFileReader, CurrentFileNetworkStats, FileNetworkStats,
+    FileStatus, PauseGate, ProgressWatch,
+};
 
 use super::{
-    error::FileUploadError, upload_details::UploadFileDetails, FileUploadOptions,
-    LargeFileLoadStrategy,
+    error::{FileUploadError, PartUploadFailure},
+    upload_details::UploadFileDetails,
+    ConstantLargeFileLoadStrategy, ContentEncodingCheck, FileUploadOptions, LargeFileLoadStrategy,
+    PartFileHandles, PartProgress, PartSizes, SampleVerificationOptions, UploadCheckpoint,
+    UploadLock, UploadSource, UploadTaskDescriptor, UPLOAD_TASK_DESCRIPTOR_VERSION,
 };
 pub struct FileUpload {
     id: u64,
@@ -46,9 +66,15 @@ pub struct FileUpload {
     status: WriteLockArc<FileStatus>,
     file: Arc<RwLock<dyn AsyncFileReader>>,
     stats: Arc<FileNetworkStats>,
-    large_file_id: Arc<RwLock<Option<String>>>,
+    /// The started large file, kept across retries so they carry on with it instead of starting over.
+    large_file: RwLock<Option<UploadCheckpoint>>,
+    retry_budget: Arc<RetryBudgetTracker>,
+    verified: WriteLockArc<Option<bool>>,
     completion_callbacks: Arc<RwLock<Vec<B2Callback<()>>>>,
+    progress_callbacks: Arc<RwLock<Vec<B2Callback<CurrentFileNetworkStats>>>>,
     abort_channel: (WriteLockArc<Sender<()>>, WriteLockArc<Receiver<()>>),
+    pause_gate: PauseGate,
+    part_tracker: Arc<PartTracker>,
 }
 
 impl FileUpload {
@@ -62,6 +88,8 @@ impl FileUpload {
         client: Arc<B2SimpleClient>,
     ) -> Arc<Self> {
         let (tx, rx) = mpsc::channel::<()>(1);
+        let retry_budget = Arc::new(RetryBudgetTracker::new(options.retry_budget.clone()));
+        let stats = Arc::new(FileNetworkStats::new(file_size, options.clock.clone()));
 
         Arc::new(Self {
             id: rand::random(),
@@ -73,12 +101,17 @@ impl FileUpload {
                 optional_info,
                 options: Arc::new(options),
             },
-            large_file_id: Arc::new(RwLock::new(None)),
+            large_file: RwLock::new(None),
+            retry_budget,
+            verified: WriteLockArc::new(None),
             status: WriteLockArc::new(FileStatus::Pending),
             file: Arc::new(RwLock::new(file)),
-            stats: Arc::new(FileNetworkStats::new(file_size as f64)),
+            stats,
             completion_callbacks: Arc::new(RwLock::new(vec![])),
+            progress_callbacks: Arc::new(RwLock::new(vec![])),
             abort_channel: (WriteLockArc::new(tx), WriteLockArc::new(rx)),
+            pause_gate: PauseGate::new(),
+            part_tracker: Arc::new(PartTracker::default()),
         })
     }
 
@@ -94,6 +127,20 @@ impl FileUpload {
         (*self.status).clone()
     }
 
+    /// Whether the finished file was checked against what was uploaded, None until the upload finishes.
+    /// <br> True when the length and SHA1s both matched. Small files compare the SHA1 B2 computed with the one of the bytes sent,
+    /// large files compare the SHA1 of every part B2 stored with the one of the part's bytes sent if [verify_parts](FileUploadOptions::verify_parts) is set.
+    /// <br> False when something didn't match, or when there was no SHA1 to compare.
+    pub fn verified(&self) -> Option<bool> {
+        *self.verified
+    }
+
+    /// Every large file part's state, bytes sent and SHA1, by part number, for showing progress per part.
+    /// <br> Empty until the large file's parts are laid out, and for files uploaded in a single request.
+    pub fn parts(&self) -> Vec<PartProgress> {
+        self.part_tracker.snapshot()
+    }
+
     /// Returns true when the file has finished or has been aborted.
     pub fn has_stopped(&self) -> bool {
         *self.status == FileStatus::Finished || *self.status == FileStatus::Aborted
@@ -101,89 +148,193 @@ impl FileUpload {
 
     /// Whether it was started or not, will only start if status is [`Pending`](FileStatus::Pending)
     pub async fn start(&self) -> Result<B2File, FileUploadError> {
-        if *self.status != FileStatus::Pending {
+        // Checked and set under one lock, so only one of two concurrent calls starts the upload
+        let mut status = self.status.lock_write().await;
+        if *status != FileStatus::Pending {
             return Err(FileUploadError::AlreadyStarted);
         }
+        *status = FileStatus::Working;
+        drop(status);
+
+        let (stop_progress, progress_stopped) = oneshot::channel();
+        let progress_reporter = tokio::spawn(report_progress(
+            self.stats.clone(),
+            self.progress_callbacks.clone(),
+            self.details.options.progress_callbacks,
+            progress_stopped,
+        ));
+
+        // Invalid options and lock failures end the upload like any other error, with its status and finish callbacks
+        let prepared = async {
+            self.details.options.is_valid()?;
+            self.check_content_encoding().await?;
+            self.acquire_lock().await
+        };
+
+        let result = match prepared.await {
+            Ok(lock) => {
+                let result = self.upload_with_retries().await;
+
+                if let Some(lock) = lock {
+                    lock.release(&self.client).await;
+                }
+
+                result
+            }
+            Err(error) => Err(error),
+        };
+
+        let mut status = self.status.lock_write().await;
+        if *status == FileStatus::Working {
+            *status = FileStatus::Finished;
+        }
+        drop(status);
+
+        drop(stop_progress);
+        progress_reporter.await.ok();
+
+        self.call_finish_callbacks().await;
+
+        if *self.status == FileStatus::Aborted {
+            return Err(FileUploadError::Aborted);
+        }
+
+        result
+    }
 
-        self.details.options.is_valid()?;
+    /// Takes the destination's lock when the upload has [lock options](FileUploadOptions::lock).
+    async fn acquire_lock(&self) -> Result<Option<UploadLock>, FileUploadError> {
+        let Some(lock_options) = &self.details.options.lock else {
+            return Ok(None);
+        };
 
-        self.status.set(FileStatus::Working).await;
+        let large_file_cutoff = self
+            .details
+            .options
+            .large_file_cutoff
+            .resolve(self.client.recommended_part_size());
+
+        UploadLock::acquire(
+            &self.client,
+            &self.details.bucket_id,
+            &self.details.file_name,
+            self.details.file_size > large_file_cutoff,
+            lock_options,
+        )
+        .await
+        .map(Some)
+    }
 
-        let retry_count = self.details.options.retry_strategy.count();
-        let mut curr_retry_count = 1;
+    /// Uploads the file, retrying failed attempts as the [retry strategy](FileUploadOptions::retry_strategy) allows.
+    async fn upload_with_retries(&self) -> Result<B2File, FileUploadError> {
+        let max_attempts = self.details.options.retry_strategy.max_attempts();
+        let mut attempt = 0;
         let abort_receiver = self.abort_channel.1.clone();
+        let cancellation = self.details.options.cancellation.as_ref();
 
-        let result = loop {
-            curr_retry_count += 1;
+        loop {
+            attempt += 1;
 
-            let result = match self.details.file_size {
-                size if size <= self.details.options.large_file_cutoff => {
-                    self.upload_small_file().await
-                }
-                _ => {
-                    let file_strat = match &self.details.options.file_load_strategy {
-                        LargeFileLoadStrategy::Constant(strat) => strat,
-                        LargeFileLoadStrategy::Dynamic(strat) => {
-                            &strat.get_load_strategy(self.details.file_size)
-                        }
-                    };
+            let attempt_start = self.stats.clock().now();
+            let done_before = self.stats.done.load(Ordering::Relaxed);
+
+            let large_file_cutoff = self
+                .details
+                .options
+                .large_file_cutoff
+                .resolve(self.client.recommended_part_size());
 
-                    file_strat.is_valid()?;
+            let is_small_file = self.details.file_size <= large_file_cutoff;
+
+            let upload = async {
+                match is_small_file {
+                    true => self.upload_small_file().await,
+                    false => {
+                        self.large_file_load_strategy().is_valid()?;
+
+                        self.upload_large_file().await
+                    }
+                }
+            };
 
-                    self.upload_large_file().await
+            let result = tokio::select! {
+                result = upload => result,
+                _ = cancelled(cancellation) => {
+                    self.abort().await;
+                    return Err(FileUploadError::Aborted);
                 }
             };
 
             if *self.status == FileStatus::Aborted {
-                break Err(FileUploadError::Aborted);
+                return Err(FileUploadError::Aborted);
             }
 
-            if result.is_err() && curr_retry_count <= retry_count.get() {
-                let wait = self.details.options.retry_strategy.wait(curr_retry_count);
-                let mut receiver_lock = abort_receiver.lock_write().await;
-
-                let mut status = self.status.lock_write().await;
-                if *status == FileStatus::Working {
-                    *status = FileStatus::Retrying;
-                }
-                drop(status);
+            let error = match result {
+                Ok(file) => return Ok(file),
+                Err(error) => error,
+            };
 
-                tokio::select! {
-                    _ = sleep(wait) => {},
-                    _ = receiver_lock.recv() => {
-                        break Err(FileUploadError::Aborted)
-                    }
-                };
+            if attempt >= max_attempts || matches!(error, FileUploadError::RetryBudgetExhausted(_))
+            {
+                return Err(error);
+            }
 
-                continue;
+            let retry_context = RetryContext {
+                endpoint: match is_small_file {
+                    true => "b2_upload_file",
+                    false => "b2_upload_part",
+                },
+                attempt: attempt + 1,
+                last_error: Some(&error),
+                retry_after: error.retry_after(),
+            };
+            let wait = self.details.options.retry_strategy.wait(&retry_context);
+            let wasted_bytes = self
+                .stats
+                .done
+                .load(Ordering::Relaxed)
+                .saturating_sub(done_before);
+
+            if !self.retry_budget.spend(
+                wasted_bytes,
+                self.stats.clock().now() - attempt_start + wait,
+            ) {
+                return Err(FileUploadError::RetryBudgetExhausted(Box::new(error)));
             }
 
-            break result;
-        };
+            trace_retry(&retry_context, wait);
 
-        let mut status = self.status.lock_write().await;
-        if *status == FileStatus::Working {
-            *status = FileStatus::Finished;
-        }
-        drop(status);
+            let mut receiver_lock = abort_receiver.lock_write().await;
 
-        self.call_finish_callbacks().await;
+            let mut status = self.status.lock_write().await;
+            if *status == FileStatus::Working {
+                *status = FileStatus::Retrying;
+            }
+            drop(status);
 
-        if *self.status == FileStatus::Aborted {
-            return Err(FileUploadError::Aborted);
+            tokio::select! {
+                _ = self.stats.clock().sleep(wait) => {},
+                _ = receiver_lock.recv() => {
+                    return Err(FileUploadError::Aborted)
+                }
+                _ = cancelled(cancellation) => {
+                    self.abort().await;
+                    return Err(FileUploadError::Aborted);
+                }
+            };
         }
-
-        return result;
     }
 
     /// Will abort ongoing upload if status is [`Working`](FileStatus::Working) or [`Retrying`](FileStatus::Retrying), does nothing otherwise.
     pub async fn abort(&self) {
         // If its not working there's nothing to do
-        if *self.status != FileStatus::Working || *self.status != FileStatus::Retrying {
+        if *self.status != FileStatus::Working && *self.status != FileStatus::Retrying {
             return;
         }
 
         self.status.set(FileStatus::Aborted).await;
+        // Paused tasks have to wake up to see the abort
+        self.pause_gate.set_paused(false);
 
         let sender = &self.abort_channel.0;
         sender.send(()).await.ok();
@@ -191,53 +342,148 @@ impl FileUpload {
         self.cancel_large_file().await;
     }
 
+    /// Holds the upload back until [resumed](FileUpload::resume), to yield bandwidth for a while.
+    /// <br> No new large file parts are started, and the bodies already being sent stop streaming until resumed,
+    /// the pause doesn't count towards the [stall_timeout](FileUploadOptions::stall_timeout).
+    /// B2 can still drop a request that was paused for long, it's retried like any other failure.
+    /// <br> Can be called before the upload starts, it then waits for the resume before sending any of the file.
+    pub fn pause(&self) {
+        self.pause_gate.set_paused(true);
+    }
+
+    /// Carries on a [paused](FileUpload::pause) upload from where it stopped, does nothing if it isn't paused.
+    pub fn resume(&self) {
+        self.pause_gate.set_paused(false);
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.pause_gate.is_paused()
+    }
+
     pub async fn add_finish_callback(&self, callback: B2Callback<()>) {
         let mut callbacks = self.completion_callbacks.write().await;
         callbacks.push(callback);
     }
 
+    /// Adds a callback that gets the upload's [current stats](FileNetworkStats::current_stats) while it runs,
+    /// as often as [progress_callbacks](FileUploadOptions::progress_callbacks) says, and once more when it stops.
+    pub async fn add_progress_callback(&self, callback: B2Callback<CurrentFileNetworkStats>) {
+        let mut callbacks = self.progress_callbacks.write().await;
+        callbacks.push(callback);
+    }
+
+    /// Makes the upload continue an unfinished large file instead of starting a new one,
+    /// parts already stored in B2 are skipped. Only takes effect before the upload is started.
+    /// <br> A stored part is only skipped when its SHA1 matches the same range of the source, which is read and hashed for it.
+    /// <br> The file is split with the checkpoint's part size, not the [load strategy](FileUploadOptions::file_load_strategy)'s.
+    pub async fn resume_from(&self, checkpoint: UploadCheckpoint) {
+        if *self.status != FileStatus::Pending {
+            return;
+        }
+
+        *self.large_file.write().await = Some(checkpoint);
+    }
+
+    /// The large file's progress so far, None if no large file was started or resumed.
+    pub async fn checkpoint(&self) -> Option<UploadCheckpoint> {
+        self.large_file.read().await.clone()
+    }
+
+    /// Describes this upload so it can be recreated elsewhere, see [UploadTaskDescriptor].
+    pub async fn descriptor(&self, source: UploadSource) -> UploadTaskDescriptor {
+        UploadTaskDescriptor {
+            version: UPLOAD_TASK_DESCRIPTOR_VERSION,
+            source,
+            bucket_id: self.details.bucket_id.clone(),
+            file_name: self.details.file_name.clone(),
+            file_info: self.details.optional_info.clone(),
+            options: self.details.options.as_ref().into(),
+            checkpoint: self.checkpoint().await,
+        }
+    }
+
+    fn large_file_load_strategy(&self) -> ConstantLargeFileLoadStrategy {
+        match &self.details.options.file_load_strategy {
+            LargeFileLoadStrategy::Constant(strat) => strat.clone(),
+            LargeFileLoadStrategy::Dynamic(strat) => strat.get_load_strategy_with_part_sizes(
+                self.details.file_size,
+                PartSizes::of(&self.client),
+            ),
+        }
+    }
+
     async fn upload_large_file(&self) -> Result<B2File, FileUploadError> {
         let file = self.file.clone();
+        let file_strat = self.large_file_load_strategy();
+        // Set by resume_from, or by an earlier attempt that failed
+        let resumed_from = self.large_file.read().await.clone();
+        let resumed = resumed_from.is_some();
+
+        let checkpoint = match resumed_from {
+            Some(checkpoint) => checkpoint,
+            None => {
+                let given_sha1 = self
+                    .details
+                    .optional_info
+                    .as_ref()
+                    .and_then(|info| info.get_info("large_file_sha1"))
+                    .map(String::from);
+
+                let local_sha1 = match (given_sha1, self.details.options.large_file_sha1) {
+                    (Some(sha1), _) => Some(sha1),
+                    (None, true) => Some(self.whole_file_sha1().await?),
+                    (None, false) => None,
+                };
 
-        let start_large_upload_body = B2StartLargeFileUploadBody::builder()
-            .bucket_id(self.details.bucket_id.clone())
-            .file_name(self.details.file_name.clone())
-            .content_type("b2/x-auto".into())
-            .file_info(self.details.optional_info.clone())
-            .build();
+                let mut file_info = self.details.optional_info.clone();
 
-        let start_large_upload_body = self
-            .details
-            .options
-            .options
-            .clone()
-            .apply_large_file_upload(start_large_upload_body);
+                if let Some(sha1) = &local_sha1 {
+                    file_info
+                        .get_or_insert_with(HashMap::new)
+                        .entry("large_file_sha1".into())
+                        .or_insert_with(|| sha1.clone());
+                }
 
-        let start_large_file_response = self
-            .client
-            .start_large_file(start_large_upload_body)
-            .await?;
+                let start_large_upload_body = B2StartLargeFileUploadBody::builder()
+                    .bucket_id(self.details.bucket_id.clone())
+                    .file_name(self.details.file_name.clone())
+                    .content_type("b2/x-auto".into())
+                    .file_info(file_info)
+                    .build();
 
-        let file_id = start_large_file_response.file_id;
-        let total_uploaded = self.stats.clone();
+                let start_large_upload_body = self
+                    .details
+                    .options
+                    .options
+                    .clone()
+                    .apply_large_file_upload(start_large_upload_body);
 
-        let mut large_file = self.large_file_id.write().await;
-        *large_file = Some(file_id.clone());
-        drop(large_file);
+                let file_id = self
+                    .client
+                    .start_large_file(start_large_upload_body)
+                    .await?
+                    .file_id;
 
-        let file_strat = match &self.details.options.file_load_strategy {
-            LargeFileLoadStrategy::Constant(strat) => strat,
-            LargeFileLoadStrategy::Dynamic(strat) => {
-                &strat.get_load_strategy(self.details.file_size)
+                let checkpoint = UploadCheckpoint {
+                    large_file_id: file_id,
+                    part_size: file_strat.part_size,
+                };
+                *self.large_file.write().await = Some(checkpoint.clone());
+
+                checkpoint
             }
         };
 
+        let file_id = checkpoint.large_file_id;
+        let part_size = checkpoint.part_size;
+        let total_uploaded = self.stats.clone();
+
         let mut parts: Vec<((u64, u64), u16)> = vec![];
         let mut current_range_start: u16 = 0;
 
         loop {
-            let start = file_strat.part_size * u64::from(current_range_start);
-            let end = file_strat.part_size * (u64::from(current_range_start) + 1);
+            let start = part_size * u64::from(current_range_start);
+            let end = part_size * (u64::from(current_range_start) + 1);
 
             current_range_start += 1;
 
@@ -250,6 +496,43 @@ impl FileUpload {
         }
 
         let sha1s = Arc::new(LargeFileSha1::new(parts.len()));
+        self.part_tracker.reset(&parts);
+
+        if resumed {
+            let uploaded_parts = self.list_uploaded_parts(file_id.clone()).await?;
+            // What an earlier attempt sent is counted again as its parts are skipped
+            total_uploaded.done.store(0, Ordering::Relaxed);
+            let mut remaining_parts = Vec::with_capacity(parts.len());
+
+            for part in parts {
+                let ((start, end), part_number) = part;
+
+                let uploaded = match uploaded_parts.get(&part_number) {
+                    Some(uploaded) if uploaded.content_length == end - start => uploaded,
+                    _ => {
+                        remaining_parts.push(part);
+                        continue;
+                    }
+                };
+
+                // The source might have changed since the part was uploaded, only the same bytes are skipped
+                let local_sha1 = self.range_sha1(start, end).await?;
+
+                if !local_sha1.eq_ignore_ascii_case(&uploaded.content_sha1) {
+                    remaining_parts.push(part);
+                    continue;
+                }
+
+                self.part_tracker.finish(part_number, &local_sha1);
+                sha1s.set_sha1((part_number - 1) as usize, local_sha1);
+                total_uploaded
+                    .done
+                    .fetch_add(uploaded.content_length, Ordering::Relaxed);
+            }
+
+            parts = remaining_parts;
+        }
+
         let mut join_handles: Vec<JoinHandle<Result<(), FileUploadError>>> = vec![];
         let abort_handles: Arc<RwLock<Vec<AbortHandle>>> = Arc::new(RwLock::new(vec![]));
         self.start_timer().await;
@@ -264,8 +547,21 @@ impl FileUpload {
 
         let status = self.status.clone();
 
-        for chunk in parts.chunks(file_strat.chunk_size as usize) {
-            let task_chunk = chunk.to_owned();
+        // A pool of workers taking parts from one queue, or a task per chunk of parts
+        let part_queues: Vec<Arc<PartQueue>> = match self.details.options.max_concurrent_parts {
+            Some(max_concurrent_parts) => {
+                let workers = max_concurrent_parts.get().min(parts.len());
+                let queue = Arc::new(PartQueue::new(parts));
+
+                (0..workers).map(|_| queue.clone()).collect()
+            }
+            None => parts
+                .chunks(file_strat.chunk_size as usize)
+                .map(|chunk| Arc::new(PartQueue::new(chunk.to_owned())))
+                .collect(),
+        };
+
+        for part_queue in part_queues {
             let file_id = file_id.clone();
             let sha1s = sha1s.clone();
             let task_abort_handles = abort_handles.clone();
@@ -277,21 +573,31 @@ impl FileUpload {
             }
 
             let upload_throttle = upload_throttle.clone();
+            let shared_throttle = self.details.options.shared_throttle.clone();
+            let usage = self.usage_recorder();
             let file = file.clone();
             let client = self.client.clone();
 
             let options = self.details.options.clone();
+            let retry_budget = self.retry_budget.clone();
+            let pause_gate = self.pause_gate.clone();
+            let part_tracker = self.part_tracker.clone();
 
             let task_func = FileUpload::part_upload(
                 client,
                 file_id,
                 status,
-                task_chunk,
+                part_queue,
                 file,
                 sha1s,
                 total_uploaded,
                 upload_throttle,
+                shared_throttle,
+                usage,
                 options,
+                retry_budget,
+                pause_gate,
+                part_tracker,
             );
 
             let join_handle = tokio::spawn(async move {
@@ -324,25 +630,264 @@ impl FileUpload {
             }?;
         }
 
-        Ok(self
+        let part_sha1_array: Vec<String> = Arc::into_inner(sha1s)
+            .expect("sha1s shouldn't be referenced any where else")
+            .into();
+
+        // B2 only echoes back the large_file_sha1 it was given, so the parts it stored are checked instead
+        let checksum = match self.details.options.verify_parts {
+            true => self.verify_parts(&file_id, &part_sha1_array).await,
+            false => ChecksumCheck::Unchecked,
+        };
+
+        let checksum = match checksum {
+            ChecksumCheck::Mismatched(error) if self.details.options.fail_on_checksum_mismatch => {
+                // A retry starts a new large file
+                self.cancel_large_file().await;
+
+                return Err(error);
+            }
+            checksum => checksum,
+        };
+
+        let file = self
             .client
             .finish_large_file(B2FinishLargeFileBody {
                 file_id: file_id.clone(),
-                part_sha1_array: Arc::into_inner(sha1s)
-                    .expect("sha1s shouldn't be referenced any where else")
-                    .into(),
+                part_sha1_array,
             })
-            .await?)
+            .await?;
+
+        self.verify_upload(file, checksum).await
     }
 
-    async fn upload_small_file(&self) -> Result<B2File, FileUploadError> {
-        let mut buffer = Vec::with_capacity(self.details.file_size as usize);
+    /// Compares the SHA1 of every part B2 stored with the SHA1 of the bytes sent for it, unchecked if the parts couldn't be listed.
+    async fn verify_parts(&self, file_id: &str, sent_sha1s: &[String]) -> ChecksumCheck {
+        let stored_parts = match self.list_uploaded_parts(file_id.to_string()).await {
+            Ok(stored_parts) => stored_parts,
+            Err(error) => {
+                #[cfg(feature = "tracing")]
+                tracing::warn!(file_id, %error, "Couldn't list the large file's parts to verify them");
+                #[cfg(not(feature = "tracing"))]
+                let _ = error;
+
+                return ChecksumCheck::Unchecked;
+            }
+        };
+
+        for (index, sent_sha1) in sent_sha1s.iter().enumerate() {
+            let part_number = index as u16 + 1;
+            let stored_sha1 = stored_parts
+                .get(&part_number)
+                .map_or("", |part| part.content_sha1.as_str());
+
+            if !sent_sha1.eq_ignore_ascii_case(stored_sha1) {
+                return ChecksumCheck::Mismatched(FileUploadError::PartChecksumMismatch {
+                    part_number,
+                    expected: sent_sha1.clone(),
+                    actual: stored_sha1.to_string(),
+                });
+            }
+        }
+
+        ChecksumCheck::Matched
+    }
+
+    /// Hashes the whole file for its `large_file_sha1`, refer to [large_file_sha1](FileUploadOptions::large_file_sha1).
+    async fn whole_file_sha1(&self) -> Result<String, FileUploadError> {
+        self.range_sha1(0, self.details.file_size).await
+    }
+
+    /// Hashes the bytes of the file from `start` up to `end`.
+    async fn range_sha1(&self, start: u64, end: u64) -> Result<String, FileUploadError> {
+        let mut sha1 = Sha1::new();
+        let mut buffer = vec![0u8; SizeUnit::MEBIBYTE as usize];
+        let mut remaining = end - start;
         let mut file = self.file.write().await;
+
+        file.seek(SeekFrom::Start(start)).await?;
+
+        while remaining > 0 {
+            let to_read = remaining.min(buffer.len() as u64) as usize;
+            file.read_exact(&mut buffer[..to_read]).await?;
+            sha1.update(&buffer[..to_read]);
+            remaining -= to_read as u64;
+        }
+
         file.seek(SeekFrom::Start(0)).await?;
-        file.read_to_end(&mut buffer).await?;
-        drop(file);
 
-        let sha1 = Sha1::from(&buffer).digest().to_string();
+        Ok(sha1.digest().to_string())
+    }
+
+    /// Compares the finished file against what was uploaded and records the result in [verified](FileUpload::verified),
+    /// failing on mismatch if [fail_on_checksum_mismatch](FileUploadOptions::fail_on_checksum_mismatch) is set.
+    /// <br> `checksum` is the result of comparing the SHA1s of the sent bytes with B2's.
+    async fn verify_upload(
+        &self,
+        file: B2File,
+        checksum: ChecksumCheck,
+    ) -> Result<B2File, FileUploadError> {
+        let length_matches = file.content_length == self.details.file_size;
+        let verified = length_matches && matches!(checksum, ChecksumCheck::Matched);
+        self.verified.set(Some(verified)).await;
+
+        let mismatch = match length_matches {
+            false => Some(FileUploadError::LengthMismatch {
+                expected: self.details.file_size,
+                actual: file.content_length,
+            }),
+            true => match checksum {
+                ChecksumCheck::Mismatched(error) => Some(error),
+                ChecksumCheck::Matched | ChecksumCheck::Unchecked => None,
+            },
+        };
+
+        let mismatch = match mismatch {
+            Some(error) if self.details.options.fail_on_checksum_mismatch => Some(error),
+            _ => match self.details.options.sample_verification {
+                Some(samples) => self.verify_samples(&file, samples).await.err(),
+                None => None,
+            },
+        };
+
+        match mismatch {
+            Some(error) => {
+                self.client
+                    .delete_file_version(
+                        B2DeleteFileVersionBody::builder()
+                            .file_name(file.file_name)
+                            .file_id(file.file_id)
+                            .build(),
+                    )
+                    .await
+                    .ok();
+
+                Err(error)
+            }
+            None => Ok(file),
+        }
+    }
+
+    /// Downloads random ranges of the finished file and compares them with the same ranges of the source.
+    async fn verify_samples(
+        &self,
+        file: &B2File,
+        options: SampleVerificationOptions,
+    ) -> Result<(), FileUploadError> {
+        let size = self.details.file_size;
+
+        if size == 0 {
+            return Ok(());
+        }
+
+        let length = options.sample_size.get().min(size);
+        let mut offsets: Vec<u64> = (0..options.samples.get())
+            .map(|_| rand::random_range(0..=size - length))
+            .collect();
+        offsets.sort_unstable();
+
+        let usage = self.usage_recorder();
+
+        for offset in offsets {
+            let end = offset + length - 1;
+
+            let content = self
+                .client
+                .download_file_by_id(
+                    file.file_id.clone(),
+                    Some(
+                        B2DownloadFileQueryParameters::builder()
+                            .range(Some(format!("bytes={}-{}", offset, end)))
+                            .build(),
+                    ),
+                )
+                .await?;
+
+            let mut remote = content.file.read_all().await?;
+
+            if let Some(usage) = &usage {
+                usage.record_download(remote.len() as u64);
+            }
+
+            // The whole file came back, the range was ignored
+            if remote.len() as u64 == size && length != size {
+                remote = remote.slice(offset as usize..=end as usize);
+            }
+
+            let mut local = vec![0; length as usize];
+            let mut source = self.file.write().await;
+            source.seek(SeekFrom::Start(offset)).await?;
+            source.read_exact(&mut local).await?;
+            drop(source);
+
+            if remote.as_ref() != local.as_slice() {
+                return Err(FileUploadError::SampleMismatch { offset, length });
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn list_uploaded_parts(
+        &self,
+        file_id: String,
+    ) -> Result<HashMap<u16, B2FilePart>, FileUploadError> {
+        let mut uploaded_parts = HashMap::new();
+        let mut start_part_number = None;
+
+        loop {
+            let response = self
+                .client
+                .list_parts(
+                    B2ListPartsQueryParameters::builder()
+                        .file_id(file_id.clone())
+                        .start_part_number(start_part_number)
+                        .max_part_count(Some(1000))
+                        .build(),
+                )
+                .await?;
+
+            for part in response.parts {
+                uploaded_parts.insert(part.part_number, part);
+            }
+
+            match response.next_part_number {
+                Some(next) => start_part_number = Some(next),
+                None => break,
+            }
+        }
+
+        Ok(uploaded_parts)
+    }
+
+    async fn upload_small_file(&self) -> Result<B2File, FileUploadError> {
+        let sha1_at_end = self.details.options.sha1_at_end;
+
+        let (chunks, content_sha1, content_length) = match sha1_at_end {
+            true => (
+                self.small_file_chunks(),
+                HEX_DIGITS_AT_END.to_string(),
+                self.details.file_size + SHA1_HEX_LENGTH,
+            ),
+            false => {
+                let mut buffer = Vec::with_capacity(self.details.file_size as usize);
+                let mut file = self.file.write().await;
+                file.seek(SeekFrom::Start(0)).await?;
+                file.read_to_end(&mut buffer).await?;
+                drop(file);
+
+                let sha1 = Sha1::from(&buffer).digest().to_string();
+                let buffer = UploadBuffer::new(buffer);
+                let chunks = stream::iter(
+                    buffer
+                        .chunks((SizeUnit::KIBIBYTE * 80) as usize)
+                        .map(Ok::<Bytes, io::Error>),
+                )
+                .boxed();
+
+                (chunks, sha1, self.details.file_size)
+            }
+        };
 
         let upload_url_response = self
             .client
@@ -353,8 +898,8 @@ impl FileUpload {
             .authorization(upload_url_response.authorization_token)
             .file_name(self.details.file_name.clone())
             .content_type("b2/x-auto".into())
-            .content_length(self.details.file_size)
-            .content_sha1(sha1)
+            .content_length(content_length)
+            .content_sha1(content_sha1.clone())
             .build();
 
         let b2_upload_headers = self
@@ -364,9 +909,10 @@ impl FileUpload {
             .clone()
             .apply_file_upload(b2_upload_headers);
 
-        let buffer = UploadBuffer::new(buffer);
         let uploaded = self.stats.clone();
         let status = self.status.clone();
+        let progress = ProgressWatch::new(self.stats.clock().clone());
+        let stream_progress = progress.clone();
         let upload_throttle = Arc::new(
             self.details
                 .options
@@ -374,85 +920,255 @@ impl FileUpload {
                 .clone()
                 .map(|t| Mutex::new(t)),
         );
+        let shared_throttle = self.details.options.shared_throttle.clone();
+        let usage = self.usage_recorder();
+        let pause_gate = self.pause_gate.clone();
 
         let stream = stream! {
-            for chunk in buffer.chunks((SizeUnit::KIBIBYTE * 80) as usize) {
+            let mut chunks = chunks;
+
+            while let Some(chunk) = chunks.next().await {
+                let chunk = match chunk {
+                    Ok(chunk) => chunk,
+                    Err(error) => {
+                        yield Err(error);
+                        break;
+                    }
+                };
+
+                pause_gate.wait(Some(&stream_progress)).await;
+
                 if let Some(ref throttle) = upload_throttle.as_ref() {
                     let mut throttle = throttle.lock().await;
                     throttle.advance_by(chunk.len() as u64).await;
                     drop(throttle);
                 }
 
+                if let Some(ref throttle) = shared_throttle {
+                    throttle.advance_by(chunk.len() as u64).await;
+                }
+
 
                 if *status == FileStatus::Aborted {
                     break;
                 }
 
+                if let Some(ref usage) = usage {
+                    usage.record_upload(chunk.len() as u64);
+                }
+
                 uploaded.add_done_bytes(chunk.len() as u64).await;
+                stream_progress.touch().await;
 
-                yield Ok::<Bytes, Infallible>(chunk);
+                yield Ok::<Bytes, io::Error>(chunk);
             }
         };
 
+        let (body, local_sha1) = match sha1_at_end {
+            true => {
+                let (body, sha1) = append_sha1_hex(stream);
+                (reqwest::Body::wrap_stream(body), Some(sha1))
+            }
+            false => (reqwest::Body::wrap_stream(stream), None),
+        };
+
         self.start_timer().await;
 
-        let file = self
-            .client
-            .upload_file(
-                reqwest::Body::wrap_stream(stream),
-                upload_url_response.upload_url,
-                b2_upload_headers,
-                self.details.optional_info.clone(),
-            )
-            .await?;
+        let stall_timeout = self.details.options.stall_timeout;
+        let upload = self.client.upload_file(
+            body,
+            upload_url_response.upload_url,
+            b2_upload_headers,
+            self.details.optional_info.clone(),
+        );
+
+        match progress.guard(upload, stall_timeout).await {
+            Some(file) => {
+                let local_sha1 = match local_sha1 {
+                    Some(sha1) => sha1.get().map(String::from),
+                    None => Some(content_sha1),
+                };
+                let file = file?;
+                let checksum = compare_sha1(local_sha1.as_deref(), &file);
+
+                self.verify_upload(file, checksum).await
+            }
+            None => {
+                self.stats.done.store(0, Ordering::Relaxed);
+
+                Err(FileUploadError::Stalled(
+                    stall_timeout.expect("only stalls with a timeout"),
+                ))
+            }
+        }
+    }
+
+    /// Reads the small file from the start in chunks as they're polled, instead of into memory upfront.
+    fn small_file_chunks(&self) -> BoxStream<'static, io::Result<Bytes>> {
+        let file = self.file.clone();
+        let chunk_size = (SizeUnit::KIBIBYTE * 80) as usize;
+
+        stream! {
+            let mut file = file.write().await;
+
+            if let Err(error) = file.seek(SeekFrom::Start(0)).await {
+                yield Err(error);
+                return;
+            }
+
+            loop {
+                let mut chunk = Vec::with_capacity(chunk_size);
+
+                match (&mut *file).take(chunk_size as u64).read_to_end(&mut chunk).await {
+                    Ok(0) => break,
+                    Ok(_) => yield Ok(Bytes::from(chunk)),
+                    Err(error) => {
+                        yield Err(error);
+                        break;
+                    }
+                }
+            }
+        }
+        .boxed()
+    }
+
+    fn usage_recorder(&self) -> Option<UsageRecorder> {
+        self.details
+            .options
+            .usage
+            .as_ref()
+            .map(|usage| usage.recorder(Some(&self.details.bucket_id)))
+    }
+
+    /// Compares the start of the file with the declared content encoding, refer to [ContentEncodingCheck].
+    async fn check_content_encoding(&self) -> Result<(), FileUploadError> {
+        let check = self.details.options.content_encoding_check;
+
+        if check == ContentEncodingCheck::Off {
+            return Ok(());
+        }
+
+        let mut head = [0u8; CONTENT_ENCODING_HEAD_LENGTH];
+        let mut read = 0;
+        let mut file = self.file.write().await;
+
+        file.seek(SeekFrom::Start(0)).await?;
+
+        while read < head.len() {
+            match file.read(&mut head[read..]).await? {
+                0 => break,
+                count => read += count,
+            }
+        }
+
+        file.seek(SeekFrom::Start(0)).await?;
+        drop(file);
 
-        Ok(file)
+        let settings = &self.details.options.options;
+        let declared = settings.b2_content_encoding.as_deref();
+
+        if content_encoding_matches(declared, Some(&settings.content_type), &head[..read]) {
+            return Ok(());
+        }
+
+        let mismatch = InvalidValue {
+            object_name: "B2FileUploadSettings".into(),
+            value_name: "b2_content_encoding".into(),
+            value_as_string: declared.unwrap_or("none").into(),
+            expected: match sniff_content_encoding(&head[..read]) {
+                Some(encoding) => format!("{}, the data starts like {} data", encoding, encoding),
+                None => format!(
+                    "no encoding, the data doesn't start like {} data",
+                    declared.unwrap_or("compressed")
+                ),
+            },
+        };
+
+        match check {
+            ContentEncodingCheck::Error => Err(FileUploadError::InvalidOptions(mismatch)),
+            ContentEncodingCheck::Warn | ContentEncodingCheck::Off => {
+                #[cfg(feature = "tracing")]
+                tracing::warn!(file_name = %self.details.file_name, "{}", mismatch);
+
+                #[cfg(not(feature = "tracing"))]
+                let _ = mismatch;
+
+                Ok(())
+            }
+        }
     }
 
     async fn start_timer(&self) {
-        self.stats.start_time.set(Instant::now()).await;
+        self.stats.start_timer().await;
     }
 
+    /// Cancels the started large file, a later attempt or [checkpoint](FileUpload::checkpoint) won't refer to it anymore.
     async fn cancel_large_file(&self) {
-        let large_file = self.large_file_id.read().await;
+        let large_file = self.large_file.write().await.take();
 
-        if let Some(id) = large_file.deref() {
-            self.client.cancel_large_file(id.clone()).await.ok();
+        if let Some(large_file) = large_file {
+            self.client
+                .cancel_large_file(large_file.large_file_id)
+                .await
+                .ok();
         }
     }
 
     async fn call_finish_callbacks(&self) {
-        let callbacks = self.completion_callbacks.read().await;
-
-        for callback in callbacks.deref() {
-            match callback {
-                B2Callback::Fn(fun) => fun(()),
-                B2Callback::AsyncFn(fun) => fun(()).await,
-            }
-        }
+        run_callbacks(
+            self.completion_callbacks.clone(),
+            (),
+            self.details.options.finish_callbacks,
+            self.stats.clock().clone(),
+        )
+        .await;
     }
 
+    #[allow(clippy::too_many_arguments)]
     async fn part_upload(
         client: Arc<B2SimpleClient>,
         file_id: String,
         status: WriteLockArc<FileStatus>,
-        task_chunk: Vec<((u64, u64), u16)>,
+        part_queue: Arc<PartQueue>,
         file: Arc<RwLock<dyn AsyncFileReader>>,
         sha1s: Arc<LargeFileSha1>,
         total_uploaded: Arc<FileNetworkStats>,
         upload_throttle: Arc<Option<Mutex<Throttle<u64>>>>,
+        shared_throttle: Option<Arc<SharedThrottle>>,
+        usage: Option<UsageRecorder>,
         options: Arc<FileUploadOptions>,
+        retry_budget: Arc<RetryBudgetTracker>,
+        pause_gate: PauseGate,
+        part_tracker: Arc<PartTracker>,
     ) -> Result<(), FileUploadError> {
         let mut upload_part_url_response = client.get_upload_part_url(file_id.clone()).await?;
 
-        for ((start, end), part_number) in task_chunk {
+        let mut task_file = match &options.part_file_handles {
+            PartFileHandles::Shared => None,
+            PartFileHandles::PerTask(opener) => Some(opener.open().await?),
+        };
+
+        while let Some(((start, end), part_number)) = part_queue.next_part() {
+            pause_gate.wait(None).await;
+
+            if *status == FileStatus::Aborted {
+                break;
+            }
+
             let status = status.clone();
             let mut buffer = vec![0u8; (end - start) as usize];
 
-            let mut file = file.write().await;
-            file.seek(std::io::SeekFrom::Start(start)).await?;
-            file.read_exact(&mut buffer).await?;
-            drop(file);
+            match task_file.as_mut() {
+                Some(task_file) => {
+                    task_file.seek(std::io::SeekFrom::Start(start)).await?;
+                    task_file.read_exact(&mut buffer).await?;
+                }
+                None => {
+                    let mut file = file.write().await;
+                    file.seek(std::io::SeekFrom::Start(start)).await?;
+                    file.read_exact(&mut buffer).await?;
+                }
+            }
 
             let sha1 = Sha1::from(&buffer).digest().to_string();
 
@@ -464,11 +1180,15 @@ impl FileUpload {
                 break;
             }
 
-            loop {
+            let max_attempts = options.retry_strategy.max_attempts();
+            let mut attempts = 0;
+
+            let part_result = loop {
                 let status = status.clone();
+                attempts += 1;
 
                 if *status == FileStatus::Aborted {
-                    break;
+                    break Ok(());
                 }
 
                 let total_uploaded = total_uploaded.clone();
@@ -486,13 +1206,25 @@ impl FileUpload {
                     .apply_file_part_upload(upload_part_headers);
 
                 let upload_throttle = upload_throttle.clone();
+                let shared_throttle = shared_throttle.clone();
+                let usage = usage.clone();
 
-                let mut total_uploaded_here: u64 = 0;
+                let total_uploaded_here = Arc::new(AtomicU64::new(0));
+                let stream_uploaded_here = total_uploaded_here.clone();
                 let total_uploaded_other = total_uploaded.clone();
                 let buffer = buffer.chunks((SizeUnit::KIBIBYTE * 160) as usize);
+                let progress = ProgressWatch::new(total_uploaded_other.clock().clone());
+                let stream_progress = progress.clone();
+                let attempt_start = total_uploaded_other.clock().now();
+                let pause_gate = pause_gate.clone();
+                let stream_part_tracker = part_tracker.clone();
+
+                part_tracker.start(part_number, &sha1);
 
                 let stream = stream! {
                     for chunk in buffer {
+                        pause_gate.wait(Some(&stream_progress)).await;
+
                         if *status == FileStatus::Aborted {
                             break;
                         }
@@ -503,8 +1235,18 @@ impl FileUpload {
                             drop(throttle);
                         }
 
+                        if let Some(ref throttle) = shared_throttle {
+                            throttle.advance_by(chunk.len() as u64).await;
+                        }
+
+                        if let Some(ref usage) = usage {
+                            usage.record_upload(chunk.len() as u64);
+                        }
+
                         total_uploaded.add_done_bytes(chunk.len() as u64).await;
-                        *(&mut total_uploaded_here) += chunk.len() as u64;
+                        stream_uploaded_here.fetch_add(chunk.len() as u64, Ordering::Relaxed);
+                        stream_part_tracker.add_sent(part_number, chunk.len() as u64);
+                        stream_progress.touch().await;
 
                         yield Ok::<_, Infallible>(chunk);
                     }
@@ -513,39 +1255,132 @@ impl FileUpload {
 
                 let stream = reqwest::Body::wrap_stream(stream);
 
-                let result = client
-                    .upload_part(
-                        upload_part_headers,
-                        stream,
-                        upload_part_url_response.upload_url.clone(),
-                    )
-                    .await;
+                let upload = client.upload_part(
+                    upload_part_headers,
+                    stream,
+                    upload_part_url_response.upload_url.clone(),
+                );
+
+                let result = progress.guard(upload, options.stall_timeout).await;
 
                 match result {
-                    Ok(_) => break,
-                    Err(error) => match error {
-                        B2Error::RequestError(error) => match error.status.get() {
-                            503 => {
-                                upload_part_url_response =
-                                    match client.get_upload_part_url(file_id.clone()).await {
-                                        Ok(resp) => resp,
-                                        Err(err) => return Err(err.into()),
-                                    };
-
-                                total_uploaded_other
-                                    .done
-                                    .fetch_sub(total_uploaded_here, Ordering::Relaxed);
-
-                                sleep(Duration::from_millis(200)).await;
-                            }
-                            _ => return Err(B2Error::RequestError(error).into()),
-                        },
-                        err => return Err(err.into()),
-                    },
+                    Some(Ok(_)) => {
+                        part_tracker.finish(part_number, &sha1);
+                        break Ok(());
+                    }
+                    Some(Err(error))
+                        if error.is_too_many_requests() || error.is_service_unavailable() =>
+                    {
+                        if attempts >= max_attempts {
+                            break Err(error.into());
+                        }
+
+                        if !retry_budget.spend(
+                            total_uploaded_here.load(Ordering::Relaxed),
+                            total_uploaded_other.clock().now() - attempt_start,
+                        ) {
+                            break Err(FileUploadError::RetryBudgetExhausted(Box::new(
+                                error.into(),
+                            )));
+                        }
+
+                        let wait = options.retry_strategy.retry_wait(&RetryContext {
+                            endpoint: "b2_upload_part",
+                            attempt: attempts + 1,
+                            last_error: Some(&error),
+                            retry_after: error.retry_after(),
+                        });
+
+                        // A 429 is the account going too fast, the upload URL is still good
+                        if error.is_service_unavailable() {
+                            upload_part_url_response =
+                                match client.get_upload_part_url(file_id.clone()).await {
+                                    Ok(resp) => resp,
+                                    Err(err) => break Err(err.into()),
+                                };
+                        }
+
+                        total_uploaded_other.done.fetch_sub(
+                            total_uploaded_here.load(Ordering::Relaxed),
+                            Ordering::Relaxed,
+                        );
+
+                        total_uploaded_other.clock().sleep(wait).await;
+                    }
+                    Some(Err(error)) => break Err(error.into()),
+                    // Stalled, the upload url might be stuck so get a fresh one before retrying
+                    None => {
+                        let stalled = FileUploadError::Stalled(
+                            options.stall_timeout.expect("only stalls with a timeout"),
+                        );
+
+                        if attempts >= max_attempts {
+                            break Err(stalled);
+                        }
+
+                        if !retry_budget.spend(
+                            total_uploaded_here.load(Ordering::Relaxed),
+                            total_uploaded_other.clock().now() - attempt_start,
+                        ) {
+                            break Err(FileUploadError::RetryBudgetExhausted(Box::new(stalled)));
+                        }
+
+                        upload_part_url_response =
+                            match client.get_upload_part_url(file_id.clone()).await {
+                                Ok(resp) => resp,
+                                Err(err) => break Err(err.into()),
+                            };
+
+                        total_uploaded_other.done.fetch_sub(
+                            total_uploaded_here.load(Ordering::Relaxed),
+                            Ordering::Relaxed,
+                        );
+                    }
                 };
+            };
+
+            if let Err(error) = part_result {
+                part_tracker.fail(part_number);
+
+                let failure = PartUploadFailure::new(part_number, attempts, &error);
+
+                match &options.on_part_failure {
+                    Some(B2Callback::Fn(fun)) => fun(failure),
+                    Some(B2Callback::AsyncFn(fun)) => fun(failure).await,
+                    None => {}
+                }
+
+                return Err(error);
             }
         }
 
         Ok(())
     }
 }
+
+/// Compares the SHA1 of the sent bytes with the one B2 computed for the file, None when either is missing.
+fn compare_sha1(local_sha1: Option<&str>, file: &B2File) -> ChecksumCheck {
+    let Some(local_sha1) = local_sha1 else {
+        return ChecksumCheck::Unchecked;
+    };
+    let remote_sha1 = match file.content_sha1.as_deref() {
+        Some(sha1) if sha1 != "none" => sha1.trim_start_matches("unverified:"),
+        _ => return ChecksumCheck::Unchecked,
+    };
+
+    match local_sha1.eq_ignore_ascii_case(remote_sha1) {
+        true => ChecksumCheck::Matched,
+        false => ChecksumCheck::Mismatched(FileUploadError::ChecksumMismatch {
+            expected: local_sha1.to_owned(),
+            actual: remote_sha1.to_owned(),
+        }),
+    }
+}
+
+/// Outcome of comparing the SHA1s of the sent bytes with the ones B2 stored.
+enum ChecksumCheck {
+    Matched,
+    Mismatched(FileUploadError),
+    /// There was nothing to compare, or B2's SHA1s couldn't be fetched.
+    Unchecked,
+}
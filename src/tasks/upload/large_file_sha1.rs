@@ -1,21 +1,25 @@
-use std::cell::RefCell;
+use std::sync::Mutex;
 
-pub(super) struct LargeFileSha1(RefCell<Vec<String>>);
-unsafe impl Send for LargeFileSha1 {}
-unsafe impl Sync for LargeFileSha1 {}
+pub(super) struct LargeFileSha1(Mutex<Vec<String>>);
 
 impl LargeFileSha1 {
     pub fn new(num_of_parts: usize) -> Self {
-        Self(RefCell::new(vec![String::new(); num_of_parts]))
+        Self(Mutex::new(vec![String::new(); num_of_parts]))
     }
 
     pub fn set_sha1(&self, index: usize, sha1: String) {
-        self.0.borrow_mut()[index] = sha1;
+        match self.0.lock() {
+            Ok(mut sha1s) => sha1s[index] = sha1,
+            Err(poisoned) => poisoned.into_inner()[index] = sha1,
+        }
     }
 }
 
-impl Into<Vec<String>> for LargeFileSha1 {
-    fn into(self) -> Vec<String> {
-        self.0.into_inner()
+impl From<LargeFileSha1> for Vec<String> {
+    fn from(value: LargeFileSha1) -> Self {
+        match value.0.into_inner() {
+            Ok(sha1s) => sha1s,
+            Err(poisoned) => poisoned.into_inner(),
+        }
     }
 }
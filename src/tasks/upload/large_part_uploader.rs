@@ -0,0 +1,386 @@
+use std::{
+    convert::Infallible,
+    sync::{Arc, Mutex},
+};
+
+use async_stream::stream;
+use bytes::Bytes;
+use sha1_smol::Sha1;
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+use crate::{
+    definitions::{
+        bodies::B2FinishLargeFileBody,
+        headers::B2UploadPartHeaders,
+        query_params::B2ListPartsQueryParameters,
+        responses::{B2FilePart, B2GetUploadPartUrlResponse},
+        shared::B2File,
+    },
+    error::B2Error,
+    simple_client::B2SimpleClient,
+    throttle::{SharedThrottle, Throttle},
+    util::{InvalidValue, RetryContext, RetryStrategy, SizeUnit},
+};
+
+use super::{error::FileUploadError, stream_upload::MAX_PART_COUNT, B2FileUploadSettings};
+
+/// How a large file is cut into parts, the same on every machine given the same file size and part size.
+/// <br> Part `n` covers bytes `(n - 1) * part_size` up to `n * part_size`, the last part holds what's left.
+/// ```rust
+/// # use backblaze_b2_client::{tasks::upload::PartLayout, util::SizeUnit};
+/// let layout = PartLayout::new(SizeUnit::MEBIBYTE * 25, SizeUnit::MEBIBYTE * 10).unwrap();
+///
+/// assert_eq!(layout.part_count(), 3);
+/// assert_eq!(layout.range(3), Some((SizeUnit::MEBIBYTE * 20, SizeUnit::MEBIBYTE * 25)));
+/// assert_eq!(layout.range(4), None);
+///
+/// // A file that fits in one part isn't a large file
+/// assert!(PartLayout::new(SizeUnit::MEBIBYTE * 8, SizeUnit::MEBIBYTE * 10).is_err());
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PartLayout {
+    file_size: u64,
+    part_size: u64,
+}
+
+impl PartLayout {
+    /// Fails if the part size isn't 5 MiB - 5 GiB, or the file doesn't take 2 - 10,000 parts of it.
+    /// <br> B2 needs at least two parts for a large file, a file that fits in one part is uploaded as a regular file.
+    pub fn new(file_size: u64, part_size: u64) -> Result<Self, InvalidValue> {
+        if !(SizeUnit::MEBIBYTE * 5..=SizeUnit::GIBIBYTE * 5).contains(&part_size) {
+            return Err(InvalidValue {
+                object_name: "PartLayout".into(),
+                value_name: "part_size".into(),
+                value_as_string: SizeUnit::from(part_size as f64).to_string(),
+                expected: "5 MiB - 5 GiB".into(),
+            });
+        }
+
+        let layout = Self {
+            file_size,
+            part_size,
+        };
+
+        if layout.part_count_u64() < 2 {
+            return Err(InvalidValue {
+                object_name: "PartLayout".into(),
+                value_name: "part_size".into(),
+                value_as_string: SizeUnit::from(part_size as f64).to_string(),
+                expected: format!(
+                    "smaller than the {} file, to cut it into at least 2 parts",
+                    SizeUnit::from(file_size as f64)
+                ),
+            });
+        }
+
+        if layout.part_count_u64() > u64::from(MAX_PART_COUNT) {
+            return Err(InvalidValue {
+                object_name: "PartLayout".into(),
+                value_name: "part_size".into(),
+                value_as_string: SizeUnit::from(part_size as f64).to_string(),
+                expected: format!("large enough to fit the file in {} parts", MAX_PART_COUNT),
+            });
+        }
+
+        Ok(layout)
+    }
+
+    pub fn file_size(&self) -> u64 {
+        self.file_size
+    }
+
+    pub fn part_size(&self) -> u64 {
+        self.part_size
+    }
+
+    pub fn part_count(&self) -> u16 {
+        self.part_count_u64() as u16
+    }
+
+    /// The part's byte range, start inclusive and end exclusive, None for a part number past the last part.
+    pub fn range(&self, part_number: u16) -> Option<(u64, u64)> {
+        if part_number == 0 || part_number > self.part_count() {
+            return None;
+        }
+
+        let start = self.part_size * u64::from(part_number - 1);
+
+        Some((start, (start + self.part_size).min(self.file_size)))
+    }
+
+    /// Every part number with its byte range, in order.
+    pub fn parts(&self) -> impl Iterator<Item = (u16, (u64, u64))> + '_ {
+        (1..=self.part_count())
+            .filter_map(|part_number| Some((part_number, self.range(part_number)?)))
+    }
+
+    fn part_count_u64(&self) -> u64 {
+        self.file_size.div_ceil(self.part_size)
+    }
+}
+
+/// Options for a [LargePartUploader].
+#[derive(Debug, Default)]
+pub struct LargePartUploaderOptions {
+    /// Retry strategy for a failed part upload, a retry sends the same bytes again.
+    /// <br> Defaults to RetryStrategy::Dynamic([crate::util::DefaultRetryStrategy]).
+    pub retry_strategy: RetryStrategy,
+    /// Speed throttle for the parts this uploader uploads, every part uploading at once shares it.
+    /// <br> Default is None.
+    pub speed_throttle: Option<Throttle<u64>>,
+    /// A throttle shared with other transfers, applies on top of `speed_throttle`.
+    /// <br> Default is None.
+    pub shared_throttle: Option<Arc<SharedThrottle>>,
+    /// The extra part upload options B2 provides, like the SSE-C key the large file was started with.
+    /// <br> Check default for [B2FileUploadSettings]
+    pub options: B2FileUploadSettings,
+}
+
+/// Uploads parts of a large file started elsewhere, for splitting one file's upload between machines.
+/// <br> Every machine uploads the parts it was given with its own uploader, for the same file id, and one of them
+/// [finishes](LargePartUploader::finish) the file with the parts the others report back, or with
+/// [finish_uploaded](LargePartUploader::finish_uploaded) once they're all done. A [PartLayout] gives every machine the same part numbers and ranges.
+/// <br> Every part's SHA1 is computed from its bytes before it's sent, and is in the returned [B2FilePart].
+/// Upload URLs are kept between parts, so parts can be uploaded concurrently from the same uploader.
+#[derive(Debug)]
+pub struct LargePartUploader {
+    client: Arc<B2SimpleClient>,
+    file_id: String,
+    options: LargePartUploaderOptions,
+    speed_throttle: Option<Arc<SharedThrottle>>,
+    upload_urls: Mutex<Vec<B2GetUploadPartUrlResponse>>,
+}
+
+impl LargePartUploader {
+    pub fn new(
+        client: Arc<B2SimpleClient>,
+        file_id: String,
+        options: LargePartUploaderOptions,
+    ) -> Self {
+        Self {
+            client,
+            file_id,
+            speed_throttle: options
+                .speed_throttle
+                .clone()
+                .map(|throttle| Arc::new(SharedThrottle::new(throttle))),
+            options,
+            upload_urls: Mutex::new(vec![]),
+        }
+    }
+
+    pub fn file_id(&self) -> &str {
+        &self.file_id
+    }
+
+    /// Uploads the data as the given part, an earlier upload of the same part number is replaced.
+    pub async fn upload_part(
+        &self,
+        part_number: u16,
+        data: Bytes,
+    ) -> Result<B2FilePart, FileUploadError> {
+        if part_number == 0 || part_number > MAX_PART_COUNT {
+            return Err(FileUploadError::InvalidOptions(InvalidValue {
+                object_name: "LargePartUploader".into(),
+                value_name: "part_number".into(),
+                value_as_string: part_number.to_string(),
+                expected: format!("1 - {}", MAX_PART_COUNT),
+            }));
+        }
+
+        let sha1 = Sha1::from(&data).digest().to_string();
+        let max_attempts = self.options.retry_strategy.max_attempts();
+        let mut attempt = 0;
+
+        loop {
+            attempt += 1;
+
+            let result = async {
+                let url = match self.take_upload_url() {
+                    Some(url) => url,
+                    None => {
+                        self.client
+                            .get_upload_part_url(self.file_id.clone())
+                            .await?
+                    }
+                };
+
+                let headers = B2UploadPartHeaders::builder()
+                    .authorization(url.authorization_token.clone())
+                    .part_number(part_number)
+                    .content_length(data.len() as u64)
+                    .content_sha1(sha1.clone())
+                    .build();
+
+                let headers = self.options.options.clone().apply_file_part_upload(headers);
+                let body = reqwest::Body::wrap_stream(self.throttled(data.clone()));
+
+                let uploaded = self
+                    .client
+                    .upload_part(headers, body, url.upload_url.clone())
+                    .await?;
+
+                // Only a URL that worked is reused, a failed one gets replaced
+                self.return_upload_url(url);
+
+                Ok::<_, B2Error>(uploaded)
+            }
+            .await;
+
+            let error = match result {
+                Ok(uploaded) => return Ok(uploaded),
+                Err(error) => error,
+            };
+
+            if !error.is_retryable() || attempt >= max_attempts {
+                return Err(error.into());
+            }
+
+            self.client
+                .clock()
+                .sleep(self.options.retry_strategy.retry_wait(&RetryContext {
+                    endpoint: "b2_upload_part",
+                    attempt: attempt + 1,
+                    last_error: Some(&error),
+                    retry_after: error.retry_after(),
+                }))
+                .await;
+        }
+    }
+
+    /// Reads exactly `length` bytes from the reader and uploads them as the given part, refer to [upload_part](LargePartUploader::upload_part).
+    /// <br> The part is held in memory, so it can be hashed before it's sent and sent again on a retry.
+    pub async fn upload_part_from<R: AsyncRead + Unpin>(
+        &self,
+        part_number: u16,
+        reader: &mut R,
+        length: u64,
+    ) -> Result<B2FilePart, FileUploadError> {
+        let mut part = vec![0u8; length as usize];
+
+        reader
+            .read_exact(&mut part)
+            .await
+            .map_err(FileUploadError::FailedToReadFile)?;
+
+        self.upload_part(part_number, Bytes::from(part)).await
+    }
+
+    /// Finishes the large file with the given parts, in any order, from whichever machine uploaded them.
+    /// <br> Fails without calling B2 if there are fewer than 2 parts, or the part numbers don't go from 1 up without gaps or repeats.
+    pub async fn finish(&self, mut parts: Vec<B2FilePart>) -> Result<B2File, FileUploadError> {
+        if parts.len() < 2 {
+            return Err(FileUploadError::InvalidOptions(InvalidValue {
+                object_name: "LargePartUploader".into(),
+                value_name: "parts".into(),
+                value_as_string: format!("{} parts", parts.len()),
+                expected: "at least 2 parts".into(),
+            }));
+        }
+
+        parts.sort_by_key(|part| part.part_number);
+
+        let misnumbered = parts
+            .iter()
+            .zip(1..)
+            .find(|(part, part_number)| part.part_number != *part_number);
+
+        if let Some((part, part_number)) = misnumbered {
+            return Err(FileUploadError::InvalidOptions(InvalidValue {
+                object_name: "LargePartUploader".into(),
+                value_name: "parts".into(),
+                value_as_string: format!("part {} at position {}", part.part_number, part_number),
+                expected: "parts numbered from 1 without gaps or repeats".into(),
+            }));
+        }
+
+        Ok(self
+            .client
+            .finish_large_file(
+                B2FinishLargeFileBody::builder()
+                    .file_id(self.file_id.clone())
+                    .part_sha1_array(parts.into_iter().map(|part| part.content_sha1).collect())
+                    .build(),
+            )
+            .await?)
+    }
+
+    /// Lists the parts B2 has for the file and finishes it with them, for when the machines uploading parts don't report back.
+    /// <br> Fails like [finish](LargePartUploader::finish) if a part is missing.
+    pub async fn finish_uploaded(&self) -> Result<B2File, FileUploadError> {
+        let mut parts = vec![];
+        let mut start_part_number = None;
+
+        loop {
+            let response = self
+                .client
+                .list_parts(
+                    B2ListPartsQueryParameters::builder()
+                        .file_id(self.file_id.clone())
+                        .start_part_number(start_part_number)
+                        .max_part_count(Some(1000))
+                        .build(),
+                )
+                .await?;
+
+            parts.extend(response.parts);
+
+            match response.next_part_number {
+                Some(next) => start_part_number = Some(next),
+                None => break,
+            }
+        }
+
+        self.finish(parts).await
+    }
+
+    /// Cancels the large file, dropping every part uploaded to it.
+    pub async fn cancel(&self) -> Result<(), FileUploadError> {
+        self.client.cancel_large_file(self.file_id.clone()).await?;
+
+        Ok(())
+    }
+
+    /// The data as a body stream, advancing the throttles as it's sent.
+    fn throttled(
+        &self,
+        data: Bytes,
+    ) -> impl futures::Stream<Item = Result<Bytes, Infallible>> + Send + 'static {
+        let speed_throttle = self.speed_throttle.clone();
+        let shared_throttle = self.options.shared_throttle.clone();
+        let chunk_size = (SizeUnit::KIBIBYTE * 160) as usize;
+
+        stream! {
+            let mut offset = 0;
+
+            while offset < data.len() {
+                let chunk = data.slice(offset..(offset + chunk_size).min(data.len()));
+                offset += chunk.len();
+
+                if let Some(ref throttle) = speed_throttle {
+                    throttle.advance_by(chunk.len() as u64).await;
+                }
+
+                if let Some(ref throttle) = shared_throttle {
+                    throttle.advance_by(chunk.len() as u64).await;
+                }
+
+                yield Ok(chunk);
+            }
+        }
+    }
+
+    fn take_upload_url(&self) -> Option<B2GetUploadPartUrlResponse> {
+        match self.upload_urls.lock() {
+            Ok(mut urls) => urls.pop(),
+            Err(poisoned) => poisoned.into_inner().pop(),
+        }
+    }
+
+    fn return_upload_url(&self, url: B2GetUploadPartUrlResponse) {
+        match self.upload_urls.lock() {
+            Ok(mut urls) => urls.push(url),
+            Err(poisoned) => poisoned.into_inner().push(url),
+        }
+    }
+}
@@ -0,0 +1,326 @@
+use std::{
+    collections::HashMap,
+    fmt,
+    fs::{File, OpenOptions},
+    path::PathBuf,
+    time::{Duration, SystemTime},
+};
+
+use fs4::fs_std::FileExt;
+use sha1_smol::Sha1;
+
+use crate::{
+    definitions::{
+        bodies::B2StartLargeFileUploadBody,
+        query_params::B2ListUnfinishedLargeFilesQueryParameters, shared::B2File,
+    },
+    error::B2Error,
+    simple_client::B2SimpleClient,
+};
+
+use super::error::FileUploadError;
+
+/// File info key of the sentinel an [UploadLock] leaves in B2, holds the owner.
+/// <br> The sentinel is an unfinished large file of its own, so the key never ends up in the uploaded file's file info.
+pub const UPLOAD_LOCK_INFO_KEY: &str = "upload-lock-owner";
+
+/// Makes only one writer upload to a destination at a time, see [FileUploadOptions::lock](super::FileUploadOptions::lock).
+/// <br> Writers on the same machine are coordinated with a file lock, writers elsewhere through a sentinel:
+/// an empty unfinished large file of the destination's name with the owner in its file info, when another writer's sentinel
+/// is found the upload waits or fails. The sentinel is cancelled once the upload finishes, fails or is aborted.
+/// <br> Small files are uploaded in one request, so they only check for other writers' sentinels and don't leave one.
+/// <br> A writer that crashes leaves its sentinel behind until it's [stale](UploadLockOptions::stale_after),
+/// a writer taking over with the same [owner](UploadLockOptions::owner) isn't held up by it.
+#[derive(Debug, Clone)]
+pub struct UploadLockOptions {
+    /// Identifies this writer in the sentinel, must be different for every writer.
+    /// <br> Default is a random ID.
+    pub owner: String,
+    /// Directory the local lock files are created in, None skips the local lock.
+    /// <br> Default is [std::env::temp_dir].
+    pub local_lock_dir: Option<PathBuf>,
+    /// Whether to coordinate through the sentinel in B2.
+    /// <br> Default is true.
+    pub remote_sentinel: bool,
+    /// What to do when another writer holds the lock, refer to [LockContention].
+    /// <br> Default is [LockContention::FailFast].
+    pub contention: LockContention,
+    /// Sentinels started longer ago than this are treated as abandoned.
+    /// <br> Default is 24 hours.
+    pub stale_after: Duration,
+}
+
+impl Default for UploadLockOptions {
+    fn default() -> Self {
+        Self {
+            owner: format!("{:016x}", rand::random::<u64>()),
+            local_lock_dir: Some(std::env::temp_dir()),
+            remote_sentinel: true,
+            contention: LockContention::default(),
+            stale_after: Duration::from_secs(60 * 60 * 24),
+        }
+    }
+}
+
+/// What an upload does when another writer holds its destination's lock.
+#[derive(Debug, Clone, Default)]
+pub enum LockContention {
+    /// Fails with [FileUploadError::Locked].
+    #[default]
+    FailFast,
+    /// Checks again every `poll_interval`, failing with [FileUploadError::Locked] once `timeout` runs out.
+    Wait {
+        poll_interval: Duration,
+        timeout: Option<Duration>,
+    },
+}
+
+/// Who holds a destination's lock.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UploadLockHolder {
+    /// Another process on this machine holds the local lock.
+    LocalProcess,
+    /// Another writer left a sentinel, an unfinished large file.
+    Remote {
+        owner: String,
+        large_file_id: String,
+    },
+}
+
+impl fmt::Display for UploadLockHolder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::LocalProcess => write!(f, "another process on this machine"),
+            Self::Remote {
+                owner,
+                large_file_id,
+            } => write!(f, "writer {} with large file {}", owner, large_file_id),
+        }
+    }
+}
+
+/// A held lock on an upload destination, give it back with [release](UploadLock::release).
+/// <br> Dropping it only releases the local lock, its sentinel stays until it's stale.
+#[derive(Debug)]
+pub struct UploadLock {
+    local: Option<File>,
+    /// The sentinel's large file ID.
+    sentinel: Option<String>,
+}
+
+impl UploadLock {
+    /// Takes the destination's lock, waiting or failing as the [contention](UploadLockOptions::contention) says.
+    /// <br> `leave_sentinel` starts a sentinel for the upload's duration, large files do so and small files only check for others.
+    pub async fn acquire(
+        client: &B2SimpleClient,
+        bucket_id: &str,
+        file_name: &str,
+        leave_sentinel: bool,
+        options: &UploadLockOptions,
+    ) -> Result<Self, FileUploadError> {
+        let clock = client.clock();
+        let start = clock.now();
+
+        loop {
+            let holder =
+                match Self::try_acquire(client, bucket_id, file_name, leave_sentinel, options)
+                    .await?
+                {
+                    Ok(lock) => return Ok(lock),
+                    Err(holder) => holder,
+                };
+
+            match &options.contention {
+                LockContention::Wait {
+                    poll_interval,
+                    timeout,
+                } if timeout
+                    .is_none_or(|timeout| clock.now() - start + *poll_interval < timeout) =>
+                {
+                    clock.sleep(*poll_interval).await
+                }
+                _ => return Err(FileUploadError::Locked(holder)),
+            }
+        }
+    }
+
+    /// Cancels the sentinel and releases the local lock.
+    pub async fn release(mut self, client: &B2SimpleClient) {
+        if let Some(sentinel) = self.sentinel.take() {
+            client.cancel_large_file(sentinel).await.ok();
+        }
+
+        if let Some(local) = self.local.take() {
+            FileExt::unlock(&local).ok();
+        }
+    }
+
+    async fn try_acquire(
+        client: &B2SimpleClient,
+        bucket_id: &str,
+        file_name: &str,
+        leave_sentinel: bool,
+        options: &UploadLockOptions,
+    ) -> Result<Result<Self, UploadLockHolder>, FileUploadError> {
+        let local = match &options.local_lock_dir {
+            Some(dir) => {
+                let path = dir.join(format!(
+                    "b2-upload-{}.lock",
+                    Sha1::from(format!("{}/{}", bucket_id, file_name)).hexdigest()
+                ));
+
+                let file = OpenOptions::new()
+                    .create(true)
+                    .truncate(false)
+                    .write(true)
+                    .open(path)
+                    .map_err(FileUploadError::LockFailed)?;
+
+                match file.try_lock_exclusive() {
+                    Ok(true) => Some(file),
+                    Ok(false) => return Ok(Err(UploadLockHolder::LocalProcess)),
+                    Err(error) => return Err(FileUploadError::LockFailed(error)),
+                }
+            }
+            None => None,
+        };
+
+        if options.remote_sentinel {
+            let sentinels =
+                live_sentinels(client, bucket_id, file_name, options.stale_after).await?;
+
+            let other = sentinels
+                .into_iter()
+                .find(|file| sentinel_owner(file) != Some(options.owner.as_str()));
+
+            if let Some(file) = other {
+                return Ok(Err(UploadLockHolder::Remote {
+                    owner: sentinel_owner(&file).unwrap_or_default().to_string(),
+                    large_file_id: file.file_id,
+                }));
+            }
+        }
+
+        let mut lock = Self {
+            local,
+            sentinel: None,
+        };
+
+        if options.remote_sentinel && leave_sentinel {
+            let sentinel = client
+                .start_large_file(
+                    B2StartLargeFileUploadBody::builder()
+                        .bucket_id(bucket_id.to_string())
+                        .file_name(file_name.to_string())
+                        .content_type("b2/x-auto".into())
+                        .file_info(Some(HashMap::from([(
+                            UPLOAD_LOCK_INFO_KEY.to_string(),
+                            options.owner.clone(),
+                        )])))
+                        .build(),
+                )
+                .await?
+                .file_id;
+
+            lock.sentinel = Some(sentinel.clone());
+
+            let confirmed =
+                confirm_sentinel(client, bucket_id, file_name, &sentinel, options).await;
+
+            // The lock gives the sentinel back, on an error too
+            match confirmed {
+                Ok(Ok(())) => {}
+                Ok(Err(holder)) => {
+                    lock.release(client).await;
+                    return Ok(Err(holder));
+                }
+                Err(error) => {
+                    lock.release(client).await;
+                    return Err(error.into());
+                }
+            }
+        }
+
+        Ok(Ok(lock))
+    }
+}
+
+/// Checks that the sentinel just started is the oldest live one of its destination,
+/// two writers can get past the check for other sentinels at the same time, and the one that started later backs off.
+async fn confirm_sentinel(
+    client: &B2SimpleClient,
+    bucket_id: &str,
+    file_name: &str,
+    large_file_id: &str,
+    options: &UploadLockOptions,
+) -> Result<Result<(), UploadLockHolder>, B2Error> {
+    let sentinels = live_sentinels(client, bucket_id, file_name, options.stale_after).await?;
+
+    let Some(own) = sentinels.iter().find(|file| file.file_id == large_file_id) else {
+        return Ok(Ok(()));
+    };
+
+    let own_order = (own.upload_timestamp, &own.file_id);
+
+    // Only other writers count, an older sentinel of this writer is one it left behind
+    let older = sentinels.iter().find(|file| {
+        sentinel_owner(file) != Some(options.owner.as_str())
+            && (file.upload_timestamp, &file.file_id) < own_order
+    });
+
+    match older {
+        Some(file) => Ok(Err(UploadLockHolder::Remote {
+            owner: sentinel_owner(file).unwrap_or_default().to_string(),
+            large_file_id: file.file_id.clone(),
+        })),
+        None => Ok(Ok(())),
+    }
+}
+
+fn sentinel_owner(file: &B2File) -> Option<&str> {
+    file.file_info_value(UPLOAD_LOCK_INFO_KEY)
+}
+
+/// Unfinished large files of the destination that have a sentinel and aren't stale.
+async fn live_sentinels(
+    client: &B2SimpleClient,
+    bucket_id: &str,
+    file_name: &str,
+    stale_after: Duration,
+) -> Result<Vec<B2File>, B2Error> {
+    let now = client
+        .clock()
+        .system_time()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default();
+    let stale_before = now.saturating_sub(stale_after).as_millis() as u64;
+
+    let mut sentinels = vec![];
+    let mut start_file_id = None;
+
+    loop {
+        let response = client
+            .list_unfinished_large_files(
+                B2ListUnfinishedLargeFilesQueryParameters::builder()
+                    .bucket_id(bucket_id.to_string())
+                    .name_prefix(Some(file_name.to_string()))
+                    .start_file_id(start_file_id)
+                    .max_file_count(Some(100))
+                    .build(),
+            )
+            .await?;
+
+        sentinels.extend(response.files.into_iter().filter(|file| {
+            file.file_name == file_name
+                && file.upload_timestamp >= stale_before
+                && sentinel_owner(file).is_some()
+        }));
+
+        match response.next_file_id {
+            Some(next_file_id) => start_file_id = Some(next_file_id),
+            None => break,
+        }
+    }
+
+    Ok(sentinels)
+}
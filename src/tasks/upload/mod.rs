@@ -1,9 +1,36 @@
+pub mod descriptor;
 pub mod error;
+pub mod fan_out;
+pub mod file_region;
 pub mod file_upload;
 pub mod large_file_sha1;
+pub mod large_part_uploader;
+pub mod lock;
 pub mod options;
+pub mod part_progress;
+pub mod part_queue;
+pub mod queue;
+pub mod remote_reader;
+pub mod staging;
+pub mod stream_upload;
+// Only public for the benches
+#[cfg(feature = "test-util")]
 pub mod upload_buffer;
+#[cfg(not(feature = "test-util"))]
+pub(crate) mod upload_buffer;
+pub mod upload_cache;
 pub mod upload_details;
 
+pub use descriptor::*;
+pub use fan_out::*;
+pub use file_region::*;
 pub use file_upload::*;
+pub use large_part_uploader::*;
+pub use lock::*;
 pub use options::*;
+pub use part_progress::*;
+pub use queue::*;
+pub use remote_reader::*;
+pub use staging::*;
+pub use stream_upload::*;
+pub use upload_cache::*;
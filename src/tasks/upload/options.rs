@@ -1,63 +1,222 @@
+use std::{
+    num::{NonZeroU32, NonZeroU64, NonZeroUsize},
+    sync::Arc,
+    time::Duration,
+};
+
+use serde::{Deserialize, Serialize};
+use tokio_util::sync::CancellationToken;
+
 use crate::{
     definitions::{
         bodies::B2StartLargeFileUploadBody,
         headers::{B2UploadFileHeaders, B2UploadPartHeaders},
         shared::{B2BucketFileRetention, B2FileLegalHold, B2ServerSideEncryption},
     },
-    throttle::Throttle,
-    util::{InvalidValue, IsValid, RetryStrategy, SizeUnit},
+    simple_client::B2SimpleClient,
+    tasks::shared::{AsyncFileOpener, ProgressCallbackOptions},
+    throttle::{SharedThrottle, Throttle},
+    usage::UsageTracker,
+    util::{
+        B2Callback, CallbackRunOptions, Clock, InvalidValue, IsValid, RetryBudget, RetryStrategy,
+        SizeUnit,
+    },
 };
 
+use super::{error::PartUploadFailure, stream_upload::MAX_PART_COUNT, UploadLockOptions};
+
 /// File upload options
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub struct FileUploadOptions {
-    /// Cut off point for the file to count as a big file, from 5 Mib - 5 Gib.
-    /// <br> Default is 200 Mib.
-    pub large_file_cutoff: u64,
+    /// Cut off point for the file to count as a big file, refer to [LargeFileCutoff] to find how they work.
+    /// <br> Default is [LargeFileCutoff::Auto].
+    pub large_file_cutoff: LargeFileCutoff,
     /// The large file load strategy, refer to [ConstantLargeFileLoadStrategy] to find how they work.
-    /// <br> Defaults to LargeFileLoadStrategy::Dynamic([DefaultLargeFileLoadStrategy])
+    /// <br> Defaults to LargeFileLoadStrategy::Dynamic([RecommendedLargeFileLoadStrategy])
     pub file_load_strategy: LargeFileLoadStrategy,
     /// Upload speed throttle, can be used as
     /// ```rust
+    /// # use backblaze_b2_client::{throttle::Throttle, util::SizeUnit};
     /// // Translates to a MiBPS upload speed limit
     /// let throttle = Throttle::per_second(SizeUnit::MEBIBYTE * 5);
     /// ```
     /// <br> Default is None.
     pub speed_throttle: Option<Throttle<u64>>,
+    /// Upload speed throttle shared with other transfers, applied on top of [speed_throttle](FileUploadOptions::speed_throttle).
+    /// <br> Uploads created by a [B2Client](crate::client::B2Client) get its [shared throttle](crate::client::B2Client::with_shared_throttle) when this is None.
+    /// <br> Default is None.
+    pub shared_throttle: Option<Arc<SharedThrottle>>,
+    /// Records the uploaded bytes under the upload's bucket.
+    /// <br> Uploads created by a [B2Client](crate::client::B2Client) get its [usage tracker](crate::client::B2Client::usage) when this is None.
+    /// <br> Default is None.
+    pub usage: Option<Arc<UsageTracker>>,
     /// Retry strategy on request failure.
     /// <br> Defaults to RetryStrategy::Dynamic([crate::util::DefaultRetryStrategy]).
     pub retry_strategy: RetryStrategy,
+    /// Limits retries across the whole upload, including every part of a large file.
+    /// <br> Default is unlimited, leaving it to [retry_strategy](FileUploadOptions::retry_strategy).
+    pub retry_budget: RetryBudget,
+    /// Aborts and retries a file or part upload request if it doesn't send any bytes for this long.
+    /// <br> Time spent waiting for B2 to respond after the whole body was sent counts as no progress,
+    /// so this should be comfortably larger than the expected response time.
+    /// <br> Default is None, which relies on the connection timing out on its own.
+    pub stall_timeout: Option<Duration>,
+    /// Max large file parts uploading at once, independent of the [load strategy](FileUploadOptions::file_load_strategy)'s `chunk_size`.
+    /// <br> A pool of this many tasks uploads the parts, each taking the next part once it's done with its last,
+    /// instead of a task for every `chunk_size` parts.
+    /// <br> Default is None, which starts a task for every `chunk_size` parts.
+    pub max_concurrent_parts: Option<NonZeroUsize>,
+    /// How large file part tasks read the file, refer to [PartFileHandles].
+    /// <br> Default is [PartFileHandles::Shared].
+    pub part_file_handles: PartFileHandles,
+    /// Fails the upload when the finished file's length or SHA1 doesn't match what was uploaded, instead of only reporting it
+    /// through [verified](super::FileUpload::verified). The bad version is deleted, and the upload retried like any other error.
+    /// <br> Default is false.
+    pub fail_on_checksum_mismatch: bool,
+    /// Lists the large file's parts before finishing it, and compares the SHA1 B2 stored for every part with the one of the bytes sent,
+    /// for [verified](super::FileUpload::verified). A part that doesn't match cancels the large file when
+    /// [fail_on_checksum_mismatch](FileUploadOptions::fail_on_checksum_mismatch) is set.
+    /// <br> The listing is a Class C call per 1000 parts. If it fails the file is still finished, only reported as not verified.
+    /// <br> Small files aren't affected, B2 returns their SHA1 with the upload.
+    /// <br> Default is false, which leaves large files not verified.
+    pub verify_parts: bool,
+    /// Downloads random ranges of the finished file and compares them byte for byte with the source, refer to [SampleVerificationOptions].
+    /// <br> Catches corruption a SHA1 can't, like the source being read wrong, at the cost of the downloads.
+    /// A mismatch, or a range failing to download, deletes the uploaded version and retries the upload like any other error.
+    /// <br> Default is None.
+    pub sample_verification: Option<SampleVerificationOptions>,
+    /// Checks the first bytes of the file against the declared [b2_content_encoding](B2FileUploadSettings::b2_content_encoding) before uploading,
+    /// so gzip data declared as plain, or the other way around, is caught before a download fails to decode it. Refer to [ContentEncodingCheck].
+    /// <br> Default is [ContentEncodingCheck::Off].
+    pub content_encoding_check: ContentEncodingCheck,
+    /// Small files are streamed straight from the file with their SHA1 sent after the content, see [append_sha1_hex](crate::util::append_sha1_hex),
+    /// instead of reading the whole file into memory to hash it before the upload.
+    /// <br> Large file parts aren't affected.
+    /// <br> Default is false.
+    pub sha1_at_end: bool,
+    /// Hashes the whole file before a large file is started, and stores the SHA1 in its file info as `large_file_sha1`
+    /// like the official CLI does, so the finished file has a whole file checksum downloads can check against.
+    /// <br> B2 only stores the given SHA1, the upload itself is [verified](super::FileUpload::verified) with the parts' SHA1s.
+    /// <br> Costs an extra read of the file. Skipped when the file info already has a `large_file_sha1`, small files aren't affected.
+    /// <br> Default is false.
+    pub large_file_sha1: bool,
+    /// Makes only one writer upload to this file name at a time, refer to [UploadLockOptions].
+    /// <br> Default is None.
+    pub lock: Option<UploadLockOptions>,
+    /// Called when a large file part gives up, out of retries or on an error that isn't retried,
+    /// before the upload attempt fails. Gives early warning that a transfer is in trouble.
+    /// <br> A part is retried up to [retry_strategy](FileUploadOptions::retry_strategy)'s count on a 429, a 503 or a stall, waiting as the strategy says.
+    /// <br> Default is None.
+    pub on_part_failure: Option<B2Callback<PartUploadFailure>>,
+    /// How [finish callbacks](super::FileUpload::add_finish_callback) are run, refer to [CallbackRunOptions].
+    /// <br> Default waits for every callback.
+    pub finish_callbacks: CallbackRunOptions,
+    /// When [progress callbacks](super::FileUpload::add_progress_callback) are called, refer to [ProgressCallbackOptions].
+    /// <br> Default is every second.
+    pub progress_callbacks: ProgressCallbackOptions,
+    /// Aborts the upload once cancelled, like [abort](super::FileUpload::abort), so the upload can be tied to the request
+    /// or job it's part of. An unfinished large file is canceled, and requests in flight are dropped.
+    /// <br> Default is None.
+    pub cancellation: Option<CancellationToken>,
+    /// Clock the upload's stats and retry waits use, refer to [Clock].
+    /// <br> Default is None, which is the [system clock](crate::util::SystemClock).
+    pub clock: Option<Arc<dyn Clock>>,
     /// The extra file upload options B2 provides
     /// <br> Check default for [B2FileUploadSettings]
     pub options: B2FileUploadSettings,
 }
 
-impl Default for FileUploadOptions {
+impl IsValid for FileUploadOptions {
+    fn is_valid(&self) -> Result<(), InvalidValue> {
+        if let LargeFileCutoff::Constant(cutoff) = self.large_file_cutoff {
+            if !(SizeUnit::MEBIBYTE * 5..=SizeUnit::GIBIBYTE * 5).contains(&cutoff) {
+                return Err(InvalidValue {
+                    object_name: "FileUploadOptions".into(),
+                    value_name: "large_file_cutoff".into(),
+                    value_as_string: SizeUnit::from(cutoff as f64).to_string(),
+                    expected: "5 MiB - 5 GiB".into(),
+                });
+            }
+        }
+
+        if let Some(timeout) = self.stall_timeout {
+            if timeout.is_zero() {
+                return Err(InvalidValue {
+                    object_name: "FileUploadOptions".into(),
+                    value_name: "stall_timeout".into(),
+                    value_as_string: format!("{:?}", timeout),
+                    expected: "a non zero duration".into(),
+                });
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// What an upload does when its data doesn't look like its declared content encoding, see [content_encoding_matches](crate::util::content_encoding_matches).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ContentEncodingCheck {
+    /// The data isn't checked.
+    #[default]
+    Off,
+    /// A mismatch is logged as a warning through [tracing] with the `tracing` feature, the upload carries on.
+    Warn,
+    /// A mismatch fails the upload with [InvalidOptions](super::error::FileUploadError::InvalidOptions) before anything is sent.
+    Error,
+}
+
+/// How many ranges [sample verification](FileUploadOptions::sample_verification) downloads, and how long they are.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SampleVerificationOptions {
+    /// Ranges downloaded, at random offsets.
+    /// <br> Default is 4.
+    pub samples: NonZeroU32,
+    /// Length of every range, smaller files are compared whole.
+    /// <br> Default is 64 KiB.
+    pub sample_size: NonZeroU64,
+}
+
+impl Default for SampleVerificationOptions {
     fn default() -> Self {
         Self {
-            large_file_cutoff: SizeUnit::MEBIBYTE * 200,
-            file_load_strategy: Default::default(),
-            speed_throttle: None,
-            retry_strategy: Default::default(),
-            options: Default::default(),
+            samples: NonZeroU32::new(4).expect("4 is not zero"),
+            sample_size: NonZeroU64::new(SizeUnit::KIBIBYTE * 64).expect("64 KiB is not zero"),
         }
     }
 }
 
-impl IsValid for FileUploadOptions {
-    fn is_valid(&self) -> Result<(), InvalidValue> {
-        if self.large_file_cutoff < SizeUnit::MEBIBYTE * 5
-            && self.large_file_cutoff > SizeUnit::GIBIBYTE * 5
-        {
-            return Err(InvalidValue {
-                object_name: "FileUploadOptions".into(),
-                value_name: "large_file_cutoff".into(),
-                value_as_string: SizeUnit::from(self.large_file_cutoff as f64).to_string(),
-                expected: "5 MiB - 5 GiB".into(),
-            });
-        }
+/// How the concurrent part tasks of a large file read the file.
+#[derive(Debug, Clone, Default)]
+pub enum PartFileHandles {
+    /// Every task seeks and reads the file handle given to the upload, one task at a time.
+    #[default]
+    Shared,
+    /// Every task opens its own handle with the opener, so reads don't wait on each other.
+    /// <br> Helps on fast disks and network filesystems, where the shared handle is the bottleneck.
+    PerTask(Arc<dyn AsyncFileOpener>),
+}
 
-        Ok(())
+/// Cut off point for the file to count as a big file.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub enum LargeFileCutoff {
+    /// A fixed size, from 5 Mib - 5 Gib.
+    Constant(u64),
+    #[default]
+    /// Picks the cutoff from the account's auth data, which is the recommended part size but at least 100 Mib.
+    Auto,
+}
+
+impl LargeFileCutoff {
+    /// Returns the cutoff in bytes, given the recommended part size from the account's auth data.
+    pub fn resolve(&self, recommended_part_size: u64) -> u64 {
+        match self {
+            Self::Constant(cutoff) => *cutoff,
+            Self::Auto => {
+                recommended_part_size.clamp(SizeUnit::MEBIBYTE * 100, SizeUnit::GIBIBYTE * 5)
+            }
+        }
     }
 }
 
@@ -70,7 +229,7 @@ pub enum LargeFileLoadStrategy {
 
 impl Default for LargeFileLoadStrategy {
     fn default() -> Self {
-        Self::Dynamic(Box::new(DefaultLargeFileLoadStrategy))
+        Self::Dynamic(Box::new(RecommendedLargeFileLoadStrategy::default()))
     }
 }
 
@@ -101,7 +260,7 @@ impl IsValid for ConstantLargeFileLoadStrategy {
             });
         }
 
-        if self.part_size < SizeUnit::MEBIBYTE * 5 && self.part_size > SizeUnit::GIBIBYTE * 5 {
+        if self.part_size < SizeUnit::MEBIBYTE * 5 || self.part_size > SizeUnit::GIBIBYTE * 5 {
             return Err(InvalidValue {
                 object_name: "ConstantLargeFileLoadStrategy".into(),
                 value_name: "part_size".into(),
@@ -126,8 +285,109 @@ impl Default for ConstantLargeFileLoadStrategy {
 /// A dynamic file load strategy, refer to [ConstantLargeFileLoadStrategy] to find how they work.
 pub trait DynamicLargeFileLoadStrategy: std::fmt::Debug {
     fn get_load_strategy(&self, file_size: u64) -> ConstantLargeFileLoadStrategy;
+
+    /// Same as [get_load_strategy](DynamicLargeFileLoadStrategy::get_load_strategy), knowing the account's part sizes.
+    /// <br> Uploads call this one, by default it ignores the part sizes.
+    fn get_load_strategy_with_part_sizes(
+        &self,
+        file_size: u64,
+        _part_sizes: PartSizes,
+    ) -> ConstantLargeFileLoadStrategy {
+        self.get_load_strategy(file_size)
+    }
+}
+
+/// The part sizes from the account's auth data, see [recommended_part_size](B2SimpleClient::recommended_part_size)
+/// and [min_part_size](B2SimpleClient::min_part_size).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PartSizes {
+    pub recommended: u64,
+    pub absolute_minimum: u64,
+}
+
+impl PartSizes {
+    /// The part sizes the client was authorized with.
+    pub fn of(client: &B2SimpleClient) -> Self {
+        Self {
+            recommended: client.recommended_part_size(),
+            absolute_minimum: client.min_part_size(),
+        }
+    }
+}
+
+impl Default for PartSizes {
+    /// What B2 currently gives every account, 100 MB recommended and 5 MB minimum.
+    fn default() -> Self {
+        Self {
+            recommended: 100_000_000,
+            absolute_minimum: 5_000_000,
+        }
+    }
+}
+
+/// Uses the account's recommended part size, growing the parts of files too big to fit in 10,000 of them,
+/// and shrinking them to half the file for files that would otherwise fit in a single part.
+/// <br> Parts are kept within the 5 MiB - 5 GiB a [ConstantLargeFileLoadStrategy] allows, and at least the account's minimum.
+/// ```rust
+/// # use backblaze_b2_client::{tasks::upload::*, util::SizeUnit};
+/// let strategy = RecommendedLargeFileLoadStrategy::default();
+///
+/// let small = strategy.get_load_strategy_with_part_sizes(SizeUnit::GIBIBYTE, PartSizes::default());
+/// assert_eq!(small.part_size, 100_000_000);
+///
+/// // Split in two instead of one part of 150 MB
+/// let split = strategy.get_load_strategy_with_part_sizes(150_000_000, PartSizes::default());
+/// assert_eq!(split.part_size, 75_000_000);
+///
+/// // 10,000 parts of 100 MB are only 1 TB
+/// let huge = strategy.get_load_strategy_with_part_sizes(SizeUnit::GIBIBYTE * 2048, PartSizes::default());
+/// assert!(huge.part_size * 10_000 >= SizeUnit::GIBIBYTE * 2048);
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct RecommendedLargeFileLoadStrategy {
+    /// Rough cap on the bytes loaded at once, decides how many parts every task handles.
+    /// <br> Default is 1 GiB.
+    pub max_loaded_bytes: u64,
 }
 
+impl Default for RecommendedLargeFileLoadStrategy {
+    fn default() -> Self {
+        Self {
+            max_loaded_bytes: SizeUnit::GIBIBYTE,
+        }
+    }
+}
+
+impl DynamicLargeFileLoadStrategy for RecommendedLargeFileLoadStrategy {
+    fn get_load_strategy(&self, file_size: u64) -> ConstantLargeFileLoadStrategy {
+        self.get_load_strategy_with_part_sizes(file_size, PartSizes::default())
+    }
+
+    fn get_load_strategy_with_part_sizes(
+        &self,
+        file_size: u64,
+        part_sizes: PartSizes,
+    ) -> ConstantLargeFileLoadStrategy {
+        let part_size = part_sizes
+            .recommended
+            .max(file_size.div_ceil(u64::from(MAX_PART_COUNT)))
+            // A large file needs at least two parts
+            .min(file_size.div_ceil(2))
+            .max(part_sizes.absolute_minimum)
+            .clamp(SizeUnit::MEBIBYTE * 5, SizeUnit::GIBIBYTE * 5);
+
+        let part_count = file_size.div_ceil(part_size).max(1);
+        let tasks = (self.max_loaded_bytes / part_size).max(1);
+        let chunk_size = part_count.div_ceil(tasks).clamp(1, u16::MAX as u64) as u16;
+
+        ConstantLargeFileLoadStrategy {
+            part_size,
+            chunk_size,
+        }
+    }
+}
+
+/// Fixed 5 MiB parts, with more parts per task the bigger the file.
 #[derive(Debug)]
 pub struct DefaultLargeFileLoadStrategy;
 
@@ -145,7 +405,7 @@ impl DynamicLargeFileLoadStrategy for DefaultLargeFileLoadStrategy {
 }
 
 /// File upload settings, check [file upload](crate::simple_client::B2SimpleClient::upload_file) to learn mode
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct B2FileUploadSettings {
     /// Default to `b2/x-auto`
     pub content_type: String,
@@ -193,29 +453,23 @@ impl B2FileUploadSettings {
         u.file_retention = self.file_retention;
         u.server_side_encryption = self.server_side_encryption;
 
-        if let Some(ref mut info) = u.file_info {
-            if let Some(v) = self.src_last_modified_millis {
-                info.insert("src_last_modified_millis".into(), v.to_string());
-            }
-
-            if let Some(v) = self.b2_content_disposition {
-                info.insert("b2-content-disposition".into(), v);
-            }
-
-            if let Some(v) = self.b2_content_language {
-                info.insert("b2-content-language".into(), v);
-            }
-
-            if let Some(v) = self.b2_expires {
-                info.insert("b2-expires".into(), v);
-            }
-
-            if let Some(v) = self.b2_cache_control {
-                info.insert("b2-cache-control".into(), v);
-            }
-
-            if let Some(v) = self.b2_content_encoding {
-                info.insert("b2-content-encoding".into(), v);
+        let settings_info = [
+            (
+                "src_last_modified_millis",
+                self.src_last_modified_millis.map(|v| v.to_string()),
+            ),
+            ("b2-content-disposition", self.b2_content_disposition),
+            ("b2-content-language", self.b2_content_language),
+            ("b2-expires", self.b2_expires),
+            ("b2-cache-control", self.b2_cache_control),
+            ("b2-content-encoding", self.b2_content_encoding),
+        ];
+
+        for (key, value) in settings_info {
+            if let Some(value) = value {
+                u.file_info
+                    .get_or_insert_with(Default::default)
+                    .insert(key.into(), value);
             }
         }
 
@@ -0,0 +1,87 @@
+use std::sync::{Mutex, MutexGuard};
+
+use super::part_queue::PartRange;
+
+/// Where a large file part is in its upload.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PartState {
+    Pending,
+    Uploading,
+    Done,
+    Failed,
+}
+
+/// A large file part's upload progress, refer to [parts](super::FileUpload::parts).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PartProgress {
+    pub part_number: u16,
+    /// Bytes in the part.
+    pub size: u64,
+    /// Bytes of the part sent so far, goes back to 0 when the part is retried.
+    pub bytes_sent: u64,
+    pub state: PartState,
+    /// The part's SHA1, None until the part is read.
+    pub sha1: Option<String>,
+}
+
+/// Keeps every part's [PartProgress], by part number.
+#[derive(Debug, Default)]
+pub(super) struct PartTracker(Mutex<Vec<PartProgress>>);
+
+impl PartTracker {
+    /// Starts tracking the parts, all pending.
+    pub fn reset(&self, parts: &[PartRange]) {
+        *self.parts() = parts
+            .iter()
+            .map(|((start, end), part_number)| PartProgress {
+                part_number: *part_number,
+                size: end - start,
+                bytes_sent: 0,
+                state: PartState::Pending,
+                sha1: None,
+            })
+            .collect();
+    }
+
+    pub fn snapshot(&self) -> Vec<PartProgress> {
+        self.parts().clone()
+    }
+
+    /// The part was read and is being sent, or sent again.
+    pub fn start(&self, part_number: u16, sha1: &str) {
+        self.update(part_number, |part| {
+            part.state = PartState::Uploading;
+            part.bytes_sent = 0;
+            part.sha1 = Some(sha1.to_string());
+        });
+    }
+
+    pub fn add_sent(&self, part_number: u16, bytes: u64) {
+        self.update(part_number, |part| part.bytes_sent += bytes);
+    }
+
+    pub fn finish(&self, part_number: u16, sha1: &str) {
+        self.update(part_number, |part| {
+            part.state = PartState::Done;
+            part.bytes_sent = part.size;
+            part.sha1 = Some(sha1.to_string());
+        });
+    }
+
+    pub fn fail(&self, part_number: u16) {
+        self.update(part_number, |part| part.state = PartState::Failed);
+    }
+
+    fn update(&self, part_number: u16, update: impl FnOnce(&mut PartProgress)) {
+        if let Some(part) = self.parts().get_mut(part_number as usize - 1) {
+            update(part);
+        }
+    }
+
+    fn parts(&self) -> MutexGuard<'_, Vec<PartProgress>> {
+        match self.0.lock() {
+            Ok(parts) => parts,
+            Err(poisoned) => poisoned.into_inner(),
+        }
+    }
+}
@@ -0,0 +1,20 @@
+use std::{collections::VecDeque, sync::Mutex};
+
+/// A part's byte range and part number.
+pub(super) type PartRange = ((u64, u64), u16);
+
+/// Large file parts waiting to be uploaded, every task uploading parts takes the next one until none are left.
+pub(super) struct PartQueue(Mutex<VecDeque<PartRange>>);
+
+impl PartQueue {
+    pub fn new(parts: Vec<PartRange>) -> Self {
+        Self(Mutex::new(parts.into()))
+    }
+
+    pub fn next_part(&self) -> Option<PartRange> {
+        match self.0.lock() {
+            Ok(mut parts) => parts.pop_front(),
+            Err(poisoned) => poisoned.into_inner().pop_front(),
+        }
+    }
+}
@@ -0,0 +1,189 @@
+use std::{
+    collections::VecDeque,
+    fmt,
+    num::NonZeroUsize,
+    sync::{Arc, Mutex},
+};
+
+use tokio::sync::oneshot;
+
+use crate::definitions::shared::B2File;
+
+use super::{error::FileUploadError, file_upload::FileUpload};
+
+/// Receives the result of an upload's [start](FileUpload::start) once the queue runs it.
+pub type QueuedUploadResult = oneshot::Receiver<Result<B2File, FileUploadError>>;
+
+/// Runs enqueued uploads with at most `max_concurrent` at once, starting queued uploads in order as others finish.
+/// <br> Cloning the queue gives another handle to the same queue.
+#[derive(Clone)]
+pub struct UploadQueue {
+    state: Arc<Mutex<QueueState>>,
+}
+
+struct QueueState {
+    queued: VecDeque<QueuedUpload>,
+    running: usize,
+    max_concurrent: usize,
+    paused: bool,
+}
+
+struct QueuedUpload {
+    upload: Arc<FileUpload>,
+    result: oneshot::Sender<Result<B2File, FileUploadError>>,
+}
+
+impl UploadQueue {
+    pub fn new(max_concurrent: NonZeroUsize) -> Self {
+        Self {
+            state: Arc::new(Mutex::new(QueueState {
+                queued: VecDeque::new(),
+                running: 0,
+                max_concurrent: max_concurrent.get(),
+                paused: false,
+            })),
+        }
+    }
+
+    /// Adds the upload to the end of the queue, it's started when its turn comes.
+    /// <br> The upload should still be [Pending](crate::tasks::shared::FileStatus::Pending),
+    /// otherwise its result is [AlreadyStarted](FileUploadError::AlreadyStarted).
+    pub fn enqueue(&self, upload: Arc<FileUpload>) -> QueuedUploadResult {
+        let (sender, receiver) = oneshot::channel();
+
+        self.lock_state().queued.push_back(QueuedUpload {
+            upload,
+            result: sender,
+        });
+        self.start_queued();
+
+        receiver
+    }
+
+    /// Stops starting queued uploads, running uploads carry on.
+    pub fn pause(&self) {
+        self.lock_state().paused = true;
+    }
+
+    /// Starts queued uploads again, up to the concurrency limit.
+    pub fn resume(&self) {
+        self.lock_state().paused = false;
+        self.start_queued();
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.lock_state().paused
+    }
+
+    /// Removes the upload with the given [ID](FileUpload::id) from the queue without starting it, its result is [Aborted](FileUploadError::Aborted).
+    /// <br> Returns false if it isn't queued, like when it already started, [abort](FileUpload::abort) stops it then.
+    pub fn cancel(&self, upload_id: u64) -> bool {
+        let mut state = self.lock_state();
+        let Some(index) = state
+            .queued
+            .iter()
+            .position(|queued| queued.upload.id() == upload_id)
+        else {
+            return false;
+        };
+
+        let queued = state.queued.remove(index);
+        drop(state);
+
+        if let Some(queued) = queued {
+            queued.result.send(Err(FileUploadError::Aborted)).ok();
+        }
+
+        true
+    }
+
+    /// Removes every upload waiting for its turn without starting it, their results are [Aborted](FileUploadError::Aborted).
+    /// <br> Returns how many were removed, running uploads carry on.
+    pub fn clear(&self) -> usize {
+        let queued: Vec<QueuedUpload> = self.lock_state().queued.drain(..).collect();
+        let removed = queued.len();
+
+        for queued_upload in queued {
+            queued_upload
+                .result
+                .send(Err(FileUploadError::Aborted))
+                .ok();
+        }
+
+        removed
+    }
+
+    /// Changes how many uploads run at once, lowering it doesn't stop running uploads,
+    /// queued ones just wait until enough finish.
+    pub fn set_max_concurrent(&self, max_concurrent: NonZeroUsize) {
+        self.lock_state().max_concurrent = max_concurrent.get();
+        self.start_queued();
+    }
+
+    pub fn max_concurrent(&self) -> usize {
+        self.lock_state().max_concurrent
+    }
+
+    /// Uploads waiting for their turn.
+    pub fn queued(&self) -> usize {
+        self.lock_state().queued.len()
+    }
+
+    /// Uploads started by the queue that haven't finished yet.
+    pub fn running(&self) -> usize {
+        self.lock_state().running
+    }
+
+    fn lock_state(&self) -> std::sync::MutexGuard<'_, QueueState> {
+        self.state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn start_queued(&self) {
+        let mut state = self.lock_state();
+
+        while !state.paused && state.running < state.max_concurrent {
+            let Some(queued) = state.queued.pop_front() else {
+                break;
+            };
+
+            state.running += 1;
+
+            let slot = RunningSlot {
+                queue: self.clone(),
+            };
+
+            tokio::spawn(async move {
+                let _slot = slot;
+                let result = queued.upload.start().await;
+                queued.result.send(result).ok();
+            });
+        }
+    }
+}
+
+/// A running upload's place in the queue, freed when dropped so the next upload starts even if the running one panicked.
+struct RunningSlot {
+    queue: UploadQueue,
+}
+
+impl Drop for RunningSlot {
+    fn drop(&mut self) {
+        self.queue.lock_state().running -= 1;
+        self.queue.start_queued();
+    }
+}
+
+impl fmt::Debug for UploadQueue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let state = self.lock_state();
+
+        f.debug_struct("UploadQueue")
+            .field("queued", &state.queued.len())
+            .field("running", &state.running)
+            .field("max_concurrent", &state.max_concurrent)
+            .field("paused", &state.paused)
+            .finish()
+    }
+}
@@ -0,0 +1,287 @@
+use std::{
+    fmt::Debug,
+    future::Future,
+    io::{self, SeekFrom},
+    pin::Pin,
+    sync::Arc,
+    task::{ready, Context, Poll},
+};
+
+use bytes::Bytes;
+use futures::future::BoxFuture;
+use tokio::{
+    io::{AsyncRead, AsyncSeek, ReadBuf},
+    task::JoinHandle,
+};
+
+use crate::tasks::shared::{AsyncFileOpener, AsyncFileReader};
+
+/// A remote file read by byte ranges, like an HTTP server supporting range requests, an SFTP file or an object store.
+/// <br> Wrap it in a [RemoteFileReader] to upload it like a local file.
+/// ```rust,no_run
+/// # use std::{io, sync::Arc};
+/// # use bytes::Bytes;
+/// # use futures::future::BoxFuture;
+/// # use backblaze_b2_client::tasks::upload::*;
+/// #[derive(Debug)]
+/// struct HttpRangeSource {
+///     client: reqwest::Client,
+///     url: String,
+/// }
+///
+/// impl RangeSource for HttpRangeSource {
+///     fn size(&self) -> BoxFuture<'_, io::Result<u64>> {
+///         Box::pin(async move {
+///             let response = self.client.head(&self.url).send().await.map_err(io::Error::other)?;
+///
+///             response
+///                 .headers()
+///                 .get(reqwest::header::CONTENT_LENGTH)
+///                 .and_then(|length| length.to_str().ok()?.parse().ok())
+///                 .ok_or_else(|| io::Error::other("no Content-Length"))
+///         })
+///     }
+///
+///     fn read_range(&self, start: u64, length: u64) -> BoxFuture<'_, io::Result<Bytes>> {
+///         Box::pin(async move {
+///             let range = format!("bytes={}-{}", start, start + length - 1);
+///
+///             let response = self.client
+///                 .get(&self.url)
+///                 .header(reqwest::header::RANGE, range)
+///                 .send()
+///                 .await
+///                 .map_err(io::Error::other)?;
+///
+///             // A server ignoring the range answers with the whole file and a 200
+///             if response.status() != reqwest::StatusCode::PARTIAL_CONTENT {
+///                 return Err(io::Error::other(format!("Range not served, got {}", response.status())));
+///             }
+///
+///             response.bytes().await.map_err(io::Error::other)
+///         })
+///     }
+/// }
+///
+/// # tokio::runtime::Runtime::new().unwrap().block_on(async {
+/// let source = Arc::new(HttpRangeSource {
+///     client: reqwest::Client::new(),
+///     url: "https://example.com/backup.tar".into(),
+/// });
+///
+/// // Fetching a part at a time lines requests up with the part reads of a large file upload
+/// let part_size = 100 * 1000 * 1000;
+/// let reader = RemoteFileReader::new(source.clone(), part_size).await.unwrap();
+/// let size = reader.size();
+///
+/// // Every part task gets its own reader, so parts are fetched in parallel
+/// let options = FileUploadOptions {
+///     part_file_handles: PartFileHandles::PerTask(Arc::new(RemoteFileOpener { source, fetch_size: part_size })),
+///     ..Default::default()
+/// };
+///
+/// // client.create_upload(reader, "backup.tar".into(), bucket_id, None, size, Some(options)).await
+/// # });
+/// ```
+pub trait RangeSource: Debug + Send + Sync + 'static {
+    /// Size of the file in bytes.
+    fn size(&self) -> BoxFuture<'_, io::Result<u64>>;
+    /// Reads `length` bytes from `start`, the range is always within the file and never empty.
+    /// <br> Returning fewer bytes than asked for is treated as the file ending early, returning more fails the read.
+    fn read_range(&self, start: u64, length: u64) -> BoxFuture<'_, io::Result<Bytes>>;
+}
+
+/// Reads a [RangeSource] as an [AsyncFileReader], fetching `fetch_size` bytes at a time and serving reads from the last fetch.
+/// <br> Uploads read their source in these patterns, which a `fetch_size` equal to the part size serves with a request per read:
+/// - A large file part is a seek to its start and a read of the whole part, with parts read concurrently in any order.
+///   A [shared](super::PartFileHandles::Shared) reader serves one part at a time, use a [RemoteFileOpener] with
+///   [PartFileHandles::PerTask](super::PartFileHandles::PerTask) to fetch parts in parallel.
+/// - A small file is read sequentially from the start, in 80 KiB reads.
+/// - Hashing a large file's whole [SHA1](super::FileUploadOptions::large_file_sha1) reads it sequentially, in 1 MiB reads.
+#[derive(Debug)]
+pub struct RemoteFileReader<S> {
+    source: Arc<S>,
+    size: u64,
+    fetch_size: u64,
+    position: u64,
+    /// Last fetched bytes and where they start in the file.
+    fetched: (u64, Bytes),
+    fetching: Option<(u64, JoinHandle<io::Result<Bytes>>)>,
+}
+
+impl<S: RangeSource> RemoteFileReader<S> {
+    /// Gets the source's size, nothing is fetched until the first read.
+    pub async fn new(source: Arc<S>, fetch_size: u64) -> Result<Self, io::Error> {
+        if fetch_size == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Fetch size has to be at least 1 byte",
+            ));
+        }
+
+        let size = source.size().await?;
+
+        Ok(Self {
+            source,
+            size,
+            fetch_size,
+            position: 0,
+            fetched: (0, Bytes::new()),
+            fetching: None,
+        })
+    }
+
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+
+    pub fn source(&self) -> &Arc<S> {
+        &self.source
+    }
+
+    /// Fetched bytes from the current position on, empty when the position isn't in the last fetch.
+    fn fetched_from_position(&self) -> &[u8] {
+        let (start, bytes) = &self.fetched;
+
+        match self.position.checked_sub(*start) {
+            Some(offset) if offset < bytes.len() as u64 => &bytes[offset as usize..],
+            _ => &[],
+        }
+    }
+}
+
+impl<S: RangeSource> AsyncRead for RemoteFileReader<S> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+
+        loop {
+            let fetched = this.fetched_from_position();
+
+            if !fetched.is_empty() {
+                let read = fetched.len().min(buf.remaining());
+                buf.put_slice(&fetched[..read]);
+                this.position += read as u64;
+
+                return Poll::Ready(Ok(()));
+            }
+
+            if this.position >= this.size {
+                return Poll::Ready(Ok(()));
+            }
+
+            let position = this.position;
+            let (start, fetching) = this.fetching.get_or_insert_with(|| {
+                let source = this.source.clone();
+                let length = this.fetch_size.min(this.size - position);
+
+                (
+                    position,
+                    tokio::spawn(async move {
+                        let bytes = source.read_range(position, length).await?;
+
+                        // Likely the whole file or another range, none of it can be trusted to start at the position
+                        match bytes.len() as u64 > length {
+                            true => Err(io::Error::new(
+                                io::ErrorKind::InvalidData,
+                                format!(
+                                    "Source returned {} bytes for a {} byte range at {}",
+                                    bytes.len(),
+                                    length,
+                                    position
+                                ),
+                            )),
+                            false => Ok(bytes),
+                        }
+                    }),
+                )
+            });
+            let start = *start;
+
+            let fetched = ready!(Pin::new(fetching).poll(cx));
+            this.fetching = None;
+
+            let bytes = fetched.map_err(io::Error::other)??;
+
+            if bytes.is_empty() {
+                return Poll::Ready(Err(io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    format!("Source ended at {} bytes, expected {}", start, this.size),
+                )));
+            }
+
+            this.fetched = (start, bytes);
+        }
+    }
+}
+
+impl<S: RangeSource> AsyncSeek for RemoteFileReader<S> {
+    fn start_seek(self: Pin<&mut Self>, position: SeekFrom) -> io::Result<()> {
+        let this = self.get_mut();
+
+        let target = match position {
+            SeekFrom::Start(target) => Some(target),
+            SeekFrom::Current(delta) => this.position.checked_add_signed(delta),
+            SeekFrom::End(delta) => this.size.checked_add_signed(delta),
+        };
+
+        this.position = target.ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Invalid seek to a negative or overflowing position",
+            )
+        })?;
+
+        // A fetch for where the reader was isn't needed anymore
+        if let Some((start, fetching)) = this.fetching.take() {
+            match start == this.position {
+                true => this.fetching = Some((start, fetching)),
+                false => fetching.abort(),
+            }
+        }
+
+        Ok(())
+    }
+
+    fn poll_complete(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<u64>> {
+        Poll::Ready(Ok(self.position))
+    }
+}
+
+impl<S> Drop for RemoteFileReader<S> {
+    fn drop(&mut self) {
+        if let Some((_, fetching)) = self.fetching.take() {
+            fetching.abort();
+        }
+    }
+}
+
+/// Opens a new [RemoteFileReader] over the same source for every task,
+/// for using [PartFileHandles::PerTask](super::PartFileHandles::PerTask) with a remote file.
+#[derive(Debug)]
+pub struct RemoteFileOpener<S> {
+    pub source: Arc<S>,
+    pub fetch_size: u64,
+}
+
+impl<S> Clone for RemoteFileOpener<S> {
+    fn clone(&self) -> Self {
+        Self {
+            source: self.source.clone(),
+            fetch_size: self.fetch_size,
+        }
+    }
+}
+
+impl<S: RangeSource> AsyncFileOpener for RemoteFileOpener<S> {
+    fn open(&self) -> BoxFuture<'_, Result<Box<dyn AsyncFileReader>, io::Error>> {
+        Box::pin(async move {
+            let reader = RemoteFileReader::new(self.source.clone(), self.fetch_size).await?;
+
+            Ok(Box::new(reader) as Box<dyn AsyncFileReader>)
+        })
+    }
+}
@@ -0,0 +1,321 @@
+use std::{
+    io::{self, SeekFrom},
+    path::PathBuf,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    task::{Context, Poll},
+};
+
+use futures::future::BoxFuture;
+use tokio::{
+    fs::OpenOptions,
+    io::{AsyncRead, AsyncReadExt, AsyncSeek, AsyncSeekExt, AsyncWriteExt, ReadBuf},
+};
+
+use crate::tasks::shared::AsyncFileReader;
+
+const STAGING_CHUNK_SIZE: usize = 64 * 1024;
+
+/// Where a stream of unknown length is staged before it's uploaded, so its size is known
+/// and it can be read again on retries.
+#[derive(Debug)]
+pub enum StagingPolicy {
+    Memory(MemoryStaging),
+    TempDir(TempDirStaging),
+    Custom(Box<dyn StagingArea + Send + Sync>),
+}
+
+impl Default for StagingPolicy {
+    fn default() -> Self {
+        Self::TempDir(TempDirStaging::default())
+    }
+}
+
+impl StagingPolicy {
+    pub async fn stage(
+        &self,
+        stream: &mut (dyn AsyncRead + Unpin + Send),
+    ) -> Result<StagedFile, io::Error> {
+        match self {
+            Self::Memory(staging) => staging.stage(stream).await,
+            Self::TempDir(staging) => staging.stage(stream).await,
+            Self::Custom(staging) => staging.stage(stream).await,
+        }
+    }
+}
+
+/// A place streams can be staged in.
+pub trait StagingArea: std::fmt::Debug {
+    /// Reads the whole stream into the staging area, returning a reader over the staged data.
+    /// <br> Anything staged should be released by the [StagedFile]'s cleanup, see [StagedFile::with_cleanup].
+    fn stage<'a>(
+        &'a self,
+        stream: &'a mut (dyn AsyncRead + Unpin + Send),
+    ) -> BoxFuture<'a, Result<StagedFile, io::Error>>;
+}
+
+/// Stages streams in memory.
+#[derive(Debug, Clone, Default)]
+pub struct MemoryStaging {
+    /// Max size of a single staged stream, staging fails if the stream is bigger.
+    /// <br> Default is None.
+    pub max_size: Option<u64>,
+}
+
+impl StagingArea for MemoryStaging {
+    fn stage<'a>(
+        &'a self,
+        stream: &'a mut (dyn AsyncRead + Unpin + Send),
+    ) -> BoxFuture<'a, Result<StagedFile, io::Error>> {
+        Box::pin(async move {
+            let mut data = vec![];
+            let mut buffer = vec![0; STAGING_CHUNK_SIZE];
+
+            loop {
+                let read = stream.read(&mut buffer).await?;
+
+                if read == 0 {
+                    break;
+                }
+
+                if let Some(max_size) = self.max_size {
+                    if (data.len() + read) as u64 > max_size {
+                        return Err(io::Error::other(format!(
+                            "Stream is bigger than the memory staging limit of {} bytes",
+                            max_size
+                        )));
+                    }
+                }
+
+                data.extend_from_slice(&buffer[..read]);
+            }
+
+            let size = data.len() as u64;
+
+            Ok(StagedFile::new(io::Cursor::new(data), size))
+        })
+    }
+}
+
+/// Stages streams in temporary files, which are deleted once the [StagedFile] is cleaned up.
+/// <br> Clones share the same disk usage, so a single policy cloned into many uploads caps their total.
+#[derive(Debug, Clone)]
+pub struct TempDirStaging {
+    /// Directory the temporary files are created in.
+    /// <br> Default is [std::env::temp_dir].
+    pub dir: PathBuf,
+    /// Max bytes staged on disk at once, across every upload sharing this policy.
+    /// <br> Default is None.
+    pub max_disk_usage: Option<u64>,
+    disk_usage: Arc<AtomicU64>,
+}
+
+impl Default for TempDirStaging {
+    fn default() -> Self {
+        Self::new(std::env::temp_dir(), None)
+    }
+}
+
+impl TempDirStaging {
+    pub fn new(dir: PathBuf, max_disk_usage: Option<u64>) -> Self {
+        Self {
+            dir,
+            max_disk_usage,
+            disk_usage: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    /// Bytes currently staged on disk by this policy and its clones.
+    pub fn disk_usage(&self) -> u64 {
+        self.disk_usage.load(Ordering::Relaxed)
+    }
+}
+
+impl StagingArea for TempDirStaging {
+    fn stage<'a>(
+        &'a self,
+        stream: &'a mut (dyn AsyncRead + Unpin + Send),
+    ) -> BoxFuture<'a, Result<StagedFile, io::Error>> {
+        Box::pin(async move {
+            let path = self
+                .dir
+                .join(format!("b2-staging-{:016x}.tmp", rand::random::<u64>()));
+
+            // Declared before the file so it's dropped after it, a file can't be removed while it's open on Windows
+            let guard;
+            let mut file = OpenOptions::new()
+                .read(true)
+                .write(true)
+                .create_new(true)
+                .open(&path)
+                .await?;
+
+            let staged = Arc::new(AtomicU64::new(0));
+
+            // Created before writing anything, so a failed or dropped stage is cleaned up when this is dropped
+            guard = StagingGuard::new({
+                let path = path.clone();
+                let staged = staged.clone();
+                let disk_usage = self.disk_usage.clone();
+
+                move || {
+                    disk_usage.fetch_sub(staged.load(Ordering::Relaxed), Ordering::Relaxed);
+
+                    // Runs from async code, a finish callback or a drop, so the removal is moved off the runtime's threads
+                    match tokio::runtime::Handle::try_current() {
+                        Ok(runtime) => {
+                            runtime.spawn_blocking(move || std::fs::remove_file(&path).ok());
+                        }
+                        Err(_) => {
+                            std::fs::remove_file(&path).ok();
+                        }
+                    }
+                }
+            });
+
+            let mut buffer = vec![0; STAGING_CHUNK_SIZE];
+
+            loop {
+                let read = stream.read(&mut buffer).await?;
+
+                if read == 0 {
+                    break;
+                }
+
+                staged.fetch_add(read as u64, Ordering::Relaxed);
+                let usage = self.disk_usage.fetch_add(read as u64, Ordering::Relaxed) + read as u64;
+
+                if let Some(max_disk_usage) = self.max_disk_usage {
+                    if usage > max_disk_usage {
+                        return Err(io::Error::other(format!(
+                            "Staging went over the disk usage limit of {} bytes",
+                            max_disk_usage
+                        )));
+                    }
+                }
+
+                file.write_all(&buffer[..read]).await?;
+            }
+
+            file.flush().await?;
+            file.seek(SeekFrom::Start(0)).await?;
+
+            let size = staged.load(Ordering::Relaxed);
+
+            Ok(StagedFile {
+                reader: Box::new(file),
+                size,
+                guard: Some(guard),
+            })
+        })
+    }
+}
+
+/// Runs a staging cleanup once, either when [cleanup](StagingGuard::cleanup) is called or when the last clone is dropped.
+#[derive(Clone)]
+pub struct StagingGuard(Arc<StagingGuardInner>);
+
+struct StagingGuardInner(Mutex<Option<Box<dyn FnOnce() + Send>>>);
+
+impl StagingGuard {
+    pub fn new<F: FnOnce() + Send + 'static>(cleanup: F) -> Self {
+        Self(Arc::new(StagingGuardInner(Mutex::new(Some(Box::new(
+            cleanup,
+        ))))))
+    }
+
+    pub fn cleanup(&self) {
+        self.0.cleanup();
+    }
+}
+
+impl StagingGuardInner {
+    fn cleanup(&self) {
+        let cleanup = match self.0.lock() {
+            Ok(mut cleanup) => cleanup.take(),
+            Err(poisoned) => poisoned.into_inner().take(),
+        };
+
+        if let Some(cleanup) = cleanup {
+            cleanup();
+        }
+    }
+}
+
+impl Drop for StagingGuardInner {
+    fn drop(&mut self) {
+        self.cleanup();
+    }
+}
+
+impl std::fmt::Debug for StagingGuard {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("StagingGuard").finish_non_exhaustive()
+    }
+}
+
+/// A fully staged stream, readable and seekable like a file.
+/// <br> Its cleanup runs when it's dropped, or earlier through its [guard](StagedFile::guard).
+pub struct StagedFile {
+    reader: Box<dyn AsyncFileReader>,
+    size: u64,
+    guard: Option<StagingGuard>,
+}
+
+impl StagedFile {
+    pub fn new<R: AsyncFileReader + 'static>(reader: R, size: u64) -> Self {
+        Self {
+            reader: Box::new(reader),
+            size,
+            guard: None,
+        }
+    }
+
+    /// Sets the cleanup that releases the staged data.
+    pub fn with_cleanup<F: FnOnce() + Send + 'static>(mut self, cleanup: F) -> Self {
+        self.guard = Some(StagingGuard::new(cleanup));
+        self
+    }
+
+    /// Size of the staged data in bytes.
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+
+    /// A handle to run the cleanup early, if there is one.
+    pub fn guard(&self) -> Option<StagingGuard> {
+        self.guard.clone()
+    }
+}
+
+impl std::fmt::Debug for StagedFile {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("StagedFile")
+            .field("size", &self.size)
+            .field("guard", &self.guard)
+            .finish_non_exhaustive()
+    }
+}
+
+impl AsyncRead for StagedFile {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.reader).poll_read(cx, buf)
+    }
+}
+
+impl AsyncSeek for StagedFile {
+    fn start_seek(mut self: Pin<&mut Self>, position: SeekFrom) -> io::Result<()> {
+        Pin::new(&mut self.reader).start_seek(position)
+    }
+
+    fn poll_complete(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<u64>> {
+        Pin::new(&mut self.reader).poll_complete(cx)
+    }
+}
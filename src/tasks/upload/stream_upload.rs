@@ -0,0 +1,402 @@
+use std::collections::HashMap;
+
+use bytes::Bytes;
+use sha1_smol::Sha1;
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+use crate::{
+    definitions::{
+        bodies::{B2FinishLargeFileBody, B2StartLargeFileUploadBody},
+        headers::{B2UploadFileHeaders, B2UploadPartHeaders},
+        responses::{B2FilePart, B2GetUploadPartUrlResponse},
+        shared::B2File,
+    },
+    error::B2Error,
+    simple_client::B2SimpleClient,
+    util::{InvalidValue, IsValid, RetryContext, RetryStrategy, SizeUnit},
+};
+
+use super::{
+    error::FileUploadError, upload_cache::UploadCache, B2FileUploadSettings, UploadCacheOptions,
+};
+
+/// B2 doesn't accept more parts than this for a large file.
+pub(super) const MAX_PART_COUNT: u16 = 10_000;
+
+/// Options for [upload_stream].
+#[derive(Debug, Default)]
+pub struct StreamUploadOptions {
+    /// Size of the parts the stream is cut into, at most two parts are held in memory at once.
+    /// A stream shorter than a part is uploaded as a regular file.
+    /// <br> Default is None, which uses the account's recommended part size.
+    pub part_size: Option<u64>,
+    /// Retry strategy for a failed file or part upload, parts are kept in memory so a retry sends the same bytes again.
+    /// <br> Defaults to RetryStrategy::Dynamic([crate::util::DefaultRetryStrategy]).
+    pub retry_strategy: RetryStrategy,
+    /// The extra file upload options B2 provides
+    /// <br> Check default for [B2FileUploadSettings]
+    pub options: B2FileUploadSettings,
+    /// Writes the uploaded bytes to a local file as they're read, so later reads can be served without downloading them.
+    /// <br> Failing to write the cache doesn't fail the upload, refer to [UploadCacheOptions].
+    /// <br> Default is None.
+    pub cache: Option<UploadCacheOptions>,
+}
+
+impl IsValid for StreamUploadOptions {
+    fn is_valid(&self) -> Result<(), InvalidValue> {
+        if let Some(part_size) = self.part_size {
+            if !(SizeUnit::MEBIBYTE * 5..=SizeUnit::GIBIBYTE * 5).contains(&part_size) {
+                return Err(InvalidValue {
+                    object_name: "StreamUploadOptions".into(),
+                    value_name: "part_size".into(),
+                    value_as_string: SizeUnit::from(part_size as f64).to_string(),
+                    expected: "5 MiB - 5 GiB".into(),
+                });
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Uploads everything read from `reader` without seeking, for sources like network streams or stdin.
+/// <br> The stream is read one part at a time, the next part is read while the current one uploads, and every part's SHA1
+/// is computed from memory in the same pass. Unlike [create_stream_upload](crate::client::B2Client::create_stream_upload)
+/// nothing is staged, so a failed upload can't be resumed, the unfinished large file is canceled.
+pub async fn upload_stream<R: AsyncRead + Unpin + Send>(
+    client: &B2SimpleClient,
+    reader: R,
+    file_name: String,
+    bucket_id: String,
+    optional_info: Option<HashMap<String, String>>,
+    options: StreamUploadOptions,
+) -> Result<B2File, FileUploadError> {
+    options.is_valid()?;
+
+    let mut cache = UploadCache::open(options.cache.as_ref()).await;
+
+    let result = upload_stream_cached(
+        client,
+        reader,
+        file_name,
+        bucket_id,
+        optional_info,
+        &options,
+        &mut cache,
+    )
+    .await;
+
+    if let Some(cache) = cache {
+        cache.finish(result.is_ok()).await;
+    }
+
+    result
+}
+
+/// [upload_stream], with every part read also written to the cache.
+async fn upload_stream_cached<R: AsyncRead + Unpin + Send>(
+    client: &B2SimpleClient,
+    mut reader: R,
+    file_name: String,
+    bucket_id: String,
+    optional_info: Option<HashMap<String, String>>,
+    options: &StreamUploadOptions,
+    cache: &mut Option<UploadCache<'_>>,
+) -> Result<B2File, FileUploadError> {
+    let part_size = options
+        .part_size
+        .unwrap_or_else(|| client.recommended_part_size());
+
+    let first_part = read_part(&mut reader, part_size, cache).await?;
+
+    // A stream that fits in one part is a regular file, B2 needs at least two parts for a large file
+    if (first_part.len() as u64) < part_size {
+        return upload_whole(
+            client,
+            first_part,
+            file_name,
+            bucket_id,
+            optional_info,
+            options,
+        )
+        .await;
+    }
+
+    let second_part = read_part(&mut reader, part_size, cache).await?;
+
+    if second_part.is_empty() {
+        return upload_whole(
+            client,
+            first_part,
+            file_name,
+            bucket_id,
+            optional_info,
+            options,
+        )
+        .await;
+    }
+
+    let start_body = B2StartLargeFileUploadBody::builder()
+        .bucket_id(bucket_id)
+        .file_name(file_name)
+        .content_type("b2/x-auto".into())
+        .file_info(optional_info)
+        .build();
+
+    let start_body = options.options.clone().apply_large_file_upload(start_body);
+
+    let file_id = client.start_large_file(start_body).await?.file_id;
+
+    let result = upload_parts(
+        client,
+        &file_id,
+        &mut reader,
+        part_size,
+        [first_part, second_part],
+        options,
+        cache,
+    )
+    .await;
+
+    let sha1s = match result {
+        Ok(sha1s) => sha1s,
+        Err(error) => {
+            client.cancel_large_file(file_id).await.ok();
+            return Err(error);
+        }
+    };
+
+    Ok(client
+        .finish_large_file(
+            B2FinishLargeFileBody::builder()
+                .file_id(file_id)
+                .part_sha1_array(sha1s)
+                .build(),
+        )
+        .await?)
+}
+
+/// Uploads the large file's parts in order, returning their SHA1s.
+async fn upload_parts<R: AsyncRead + Unpin + Send>(
+    client: &B2SimpleClient,
+    file_id: &str,
+    reader: &mut R,
+    part_size: u64,
+    [first_part, second_part]: [Vec<u8>; 2],
+    options: &StreamUploadOptions,
+    cache: &mut Option<UploadCache<'_>>,
+) -> Result<Vec<String>, FileUploadError> {
+    let mut sha1s = vec![];
+    let mut upload_url = None;
+    let mut part_number: u16 = 1;
+
+    let first_sha1 = Sha1::from(&first_part).digest().to_string();
+    upload_part_with_retry(
+        client,
+        file_id,
+        part_number,
+        Bytes::from(first_part),
+        first_sha1.clone(),
+        &mut upload_url,
+        options,
+    )
+    .await?;
+    sha1s.push(first_sha1);
+
+    let mut current = second_part;
+
+    while !current.is_empty() {
+        part_number += 1;
+
+        if part_number > MAX_PART_COUNT {
+            return Err(FileUploadError::InvalidOptions(InvalidValue {
+                object_name: "StreamUploadOptions".into(),
+                value_name: "part_size".into(),
+                value_as_string: SizeUnit::from(part_size as f64).to_string(),
+                expected: format!("large enough to fit the stream in {} parts", MAX_PART_COUNT),
+            }));
+        }
+
+        let sha1 = Sha1::from(&current).digest().to_string();
+
+        let (uploaded, next) = tokio::join!(
+            upload_part_with_retry(
+                client,
+                file_id,
+                part_number,
+                Bytes::from(current),
+                sha1.clone(),
+                &mut upload_url,
+                options,
+            ),
+            read_part(reader, part_size, cache),
+        );
+
+        uploaded?;
+        sha1s.push(sha1);
+        current = next?;
+    }
+
+    Ok(sha1s)
+}
+
+pub(super) async fn upload_part_with_retry(
+    client: &B2SimpleClient,
+    file_id: &str,
+    part_number: u16,
+    part: Bytes,
+    sha1: String,
+    upload_url: &mut Option<B2GetUploadPartUrlResponse>,
+    options: &StreamUploadOptions,
+) -> Result<B2FilePart, FileUploadError> {
+    let max_attempts = options.retry_strategy.max_attempts();
+    let mut attempt = 0;
+
+    loop {
+        attempt += 1;
+
+        let result = async {
+            let url = match upload_url.take() {
+                Some(url) => url,
+                None => client.get_upload_part_url(file_id.to_string()).await?,
+            };
+
+            let headers = B2UploadPartHeaders::builder()
+                .authorization(url.authorization_token.clone())
+                .part_number(part_number)
+                .content_length(part.len() as u64)
+                .content_sha1(sha1.clone())
+                .build();
+
+            let headers = options.options.clone().apply_file_part_upload(headers);
+            let uploaded = client
+                .upload_part(headers, part.clone(), url.upload_url.clone())
+                .await?;
+
+            // Only a URL that worked is reused, a failed one gets replaced
+            *upload_url = Some(url);
+
+            Ok::<_, B2Error>(uploaded)
+        }
+        .await;
+
+        let error = match result {
+            Ok(uploaded) => return Ok(uploaded),
+            Err(error) => error,
+        };
+
+        if !error.is_retryable() || attempt >= max_attempts {
+            return Err(error.into());
+        }
+
+        client
+            .clock()
+            .sleep(options.retry_strategy.retry_wait(&RetryContext {
+                endpoint: "b2_upload_part",
+                attempt: attempt + 1,
+                last_error: Some(&error),
+                retry_after: error.retry_after(),
+            }))
+            .await;
+    }
+}
+
+/// Uploads a stream that fit in a single part as a regular file.
+async fn upload_whole(
+    client: &B2SimpleClient,
+    data: Vec<u8>,
+    file_name: String,
+    bucket_id: String,
+    optional_info: Option<HashMap<String, String>>,
+    options: &StreamUploadOptions,
+) -> Result<B2File, FileUploadError> {
+    let sha1 = Sha1::from(&data).digest().to_string();
+
+    upload_whole_hashed(
+        client,
+        Bytes::from(data),
+        sha1,
+        file_name,
+        bucket_id,
+        optional_info,
+        options,
+    )
+    .await
+}
+
+/// Like [upload_whole], with the data's SHA1 already computed.
+pub(super) async fn upload_whole_hashed(
+    client: &B2SimpleClient,
+    data: Bytes,
+    sha1: String,
+    file_name: String,
+    bucket_id: String,
+    optional_info: Option<HashMap<String, String>>,
+    options: &StreamUploadOptions,
+) -> Result<B2File, FileUploadError> {
+    let max_attempts = options.retry_strategy.max_attempts();
+    let mut attempt = 0;
+
+    loop {
+        attempt += 1;
+
+        let result = async {
+            let url = client.get_upload_url(bucket_id.clone()).await?;
+
+            let headers = B2UploadFileHeaders::builder()
+                .authorization(url.authorization_token)
+                .file_name(file_name.clone())
+                .content_type("b2/x-auto".into())
+                .content_length(data.len() as u64)
+                .content_sha1(sha1.clone())
+                .build();
+
+            let headers = options.options.clone().apply_file_upload(headers);
+
+            client
+                .upload_file(data.clone(), url.upload_url, headers, optional_info.clone())
+                .await
+        }
+        .await;
+
+        let error = match result {
+            Ok(file) => return Ok(file),
+            Err(error) => error,
+        };
+
+        if !error.is_retryable() || attempt >= max_attempts {
+            return Err(error.into());
+        }
+
+        client
+            .clock()
+            .sleep(options.retry_strategy.retry_wait(&RetryContext {
+                endpoint: "b2_upload_file",
+                attempt: attempt + 1,
+                last_error: Some(&error),
+                retry_after: error.retry_after(),
+            }))
+            .await;
+    }
+}
+
+/// Reads up to `part_size` bytes, less only when the stream ends, and writes them to the cache if there's one.
+pub(super) async fn read_part<R: AsyncRead + Unpin>(
+    reader: &mut R,
+    part_size: u64,
+    cache: &mut Option<UploadCache<'_>>,
+) -> Result<Vec<u8>, FileUploadError> {
+    let mut part = Vec::with_capacity(part_size as usize);
+
+    (&mut *reader)
+        .take(part_size)
+        .read_to_end(&mut part)
+        .await
+        .map_err(FileUploadError::FailedToReadFile)?;
+
+    if let Some(cache) = cache {
+        cache.write(&part).await;
+    }
+
+    Ok(part)
+}
@@ -1,7 +1,8 @@
 use bytes::Bytes;
 
+/// An in memory upload body, split into chunks as it's streamed so progress and throttling can be tracked per chunk.
 #[derive(Debug, Clone)]
-pub(super) struct UploadBuffer(Bytes);
+pub struct UploadBuffer(Bytes);
 
 impl UploadBuffer {
     pub fn new<B>(bytes: B) -> Self
@@ -16,8 +17,9 @@ impl UploadBuffer {
     }
 }
 
+/// Iterates an [UploadBuffer] in chunks, the chunks share the buffer's memory.
 #[derive(Debug, Clone)]
-pub(super) struct UploadBufferChunks {
+pub struct UploadBufferChunks {
     data: Bytes,
     chunk_size: usize,
     offset: usize,
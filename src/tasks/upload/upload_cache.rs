@@ -0,0 +1,123 @@
+use std::{ffi::OsString, io, path::PathBuf};
+
+use tokio::{fs::File, io::AsyncWriteExt};
+
+use crate::util::B2Callback;
+
+/// Suffix of a cache file while it's written, it's renamed to its path once the upload finishes.
+const PARTIAL_CACHE_SUFFIX: &str = ".b2cache-partial";
+
+/// Where an upload writes a copy of the bytes it uploads, so they can be read back locally later.
+/// <br> The copy is written next to `path` and moved over it only once the upload finishes, a failed upload leaves no cache.
+#[derive(Debug)]
+pub struct UploadCacheOptions {
+    /// File the uploaded bytes end up in, replaced if it exists.
+    pub path: PathBuf,
+    /// Called when writing the cache fails, the upload carries on without it and the incomplete copy is removed.
+    /// <br> Default is None.
+    pub on_error: Option<B2Callback<UploadCacheError>>,
+}
+
+impl UploadCacheOptions {
+    pub fn new<P: Into<PathBuf>>(path: P) -> Self {
+        Self {
+            path: path.into(),
+            on_error: None,
+        }
+    }
+
+    pub fn with_on_error(mut self, on_error: B2Callback<UploadCacheError>) -> Self {
+        self.on_error = Some(on_error);
+        self
+    }
+}
+
+/// Writing an upload's [cache](UploadCacheOptions) failed.
+#[derive(Debug)]
+pub struct UploadCacheError {
+    pub path: PathBuf,
+    pub error: io::Error,
+}
+
+/// Writes the uploaded bytes to the cache, in the order they're read.
+pub(super) struct UploadCache<'a> {
+    options: &'a UploadCacheOptions,
+    partial_path: PathBuf,
+    file: Option<File>,
+}
+
+impl<'a> UploadCache<'a> {
+    /// Creates the partial cache file, None without cache options.
+    pub async fn open(options: Option<&'a UploadCacheOptions>) -> Option<Self> {
+        let options = options?;
+
+        let mut partial_path = OsString::from(options.path.as_os_str());
+        partial_path.push(PARTIAL_CACHE_SUFFIX);
+
+        let mut cache = Self {
+            options,
+            partial_path: partial_path.into(),
+            file: None,
+        };
+
+        match File::create(&cache.partial_path).await {
+            Ok(file) => cache.file = Some(file),
+            Err(error) => cache.fail(error).await,
+        }
+
+        Some(cache)
+    }
+
+    pub async fn write(&mut self, data: &[u8]) {
+        let Some(file) = self.file.as_mut() else {
+            return;
+        };
+
+        if let Err(error) = file.write_all(data).await {
+            self.fail(error).await;
+        }
+    }
+
+    /// Moves the cache to its path if the upload succeeded, removes it otherwise.
+    pub async fn finish(mut self, uploaded: bool) {
+        let Some(mut file) = self.file.take() else {
+            return;
+        };
+
+        if !uploaded {
+            drop(file);
+            tokio::fs::remove_file(&self.partial_path).await.ok();
+            return;
+        }
+
+        let finished = async {
+            file.flush().await?;
+            file.sync_all().await?;
+            drop(file);
+
+            tokio::fs::rename(&self.partial_path, &self.options.path).await
+        }
+        .await;
+
+        if let Err(error) = finished {
+            self.fail(error).await;
+        }
+    }
+
+    /// Stops caching, removing the incomplete copy, and reports the error.
+    async fn fail(&mut self, error: io::Error) {
+        self.file.take();
+        tokio::fs::remove_file(&self.partial_path).await.ok();
+
+        let error = UploadCacheError {
+            path: self.options.path.clone(),
+            error,
+        };
+
+        match &self.options.on_error {
+            Some(B2Callback::Fn(fun)) => fun(error),
+            Some(B2Callback::AsyncFn(fun)) => fun(error).await,
+            None => {}
+        }
+    }
+}
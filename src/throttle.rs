@@ -1,10 +1,13 @@
 use std::{
     ops::AddAssign,
+    sync::Arc,
     time::{Duration, Instant},
 };
 
 use num::Unsigned;
-use tokio::time::sleep;
+use tokio::sync::Mutex;
+
+use crate::util::{Clock, SystemClock};
 
 #[derive(Debug)]
 pub struct Throttle<T: Unsigned + AddAssign + Copy + PartialOrd> {
@@ -12,6 +15,7 @@ pub struct Throttle<T: Unsigned + AddAssign + Copy + PartialOrd> {
     count_start: Instant,
     period: Duration,
     current_count: T,
+    clock: Arc<dyn Clock>,
 }
 
 impl<T: Unsigned + AddAssign + Copy + PartialOrd> Throttle<T> {
@@ -21,12 +25,23 @@ impl<T: Unsigned + AddAssign + Copy + PartialOrd> Throttle<T> {
             period,
             count_start: Instant::now(),
             current_count: T::zero(),
+            clock: SystemClock::shared(),
         }
     }
 
+    /// Measures periods and waits on the given clock instead of the system's, refer to [Clock].
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.count_start = clock.now();
+        self.clock = clock;
+        self
+    }
+
     /// Equivalent to
     /// ```rust
-    /// Throttle::new(max_per_period, Duration::from_secs(1))
+    /// # use std::time::Duration;
+    /// # use backblaze_b2_client::throttle::Throttle;
+    /// # let max_per_period = 1u64;
+    /// Throttle::new(max_per_period, Duration::from_secs(1));
     /// ```
     pub fn per_second(max_per_period: T) -> Self {
         Self::new(max_per_period, Duration::from_secs(1))
@@ -34,7 +49,10 @@ impl<T: Unsigned + AddAssign + Copy + PartialOrd> Throttle<T> {
 
     /// Equivalent to
     /// ```rust
-    /// Throttle::new(max_per_period, Duration::from_secs(60))
+    /// # use std::time::Duration;
+    /// # use backblaze_b2_client::throttle::Throttle;
+    /// # let max_per_period = 1u64;
+    /// Throttle::new(max_per_period, Duration::from_secs(60));
     /// ```
     pub fn per_minute(max_per_period: T) -> Self {
         Self::new(max_per_period, Duration::from_secs(60))
@@ -47,49 +65,57 @@ impl<T: Unsigned + AddAssign + Copy + PartialOrd> Throttle<T> {
 
     /// Advances the throttle by the given amount, waiting if the throttle has been exhausted
     pub async fn advance_by(&mut self, by: T) -> T {
-        if self.count_start.elapsed() >= self.period {
+        if self.elapsed() >= self.period {
             self.current_count = T::zero();
-            self.count_start = Instant::now();
+            self.count_start = self.clock.now();
         }
 
         if self.current_count >= self.max_per_period {
-            sleep(self.period - self.count_start.elapsed()).await;
+            self.clock
+                .sleep(self.period.saturating_sub(self.elapsed()))
+                .await;
             self.current_count = T::zero();
-            self.count_start = Instant::now();
+            self.count_start = self.clock.now();
         }
 
         self.current_count += by;
 
-        return if self.current_count > self.max_per_period {
+        if self.current_count > self.max_per_period {
             T::zero()
         } else {
             self.max_per_period - self.current_count
-        };
+        }
     }
 
     /// If throttle period has been exhausted, waits for the period to end <br>
     /// otherwise returns immediately
     pub async fn wait_if_exhausted(&self) {
-        if self.count_start.elapsed() >= self.period {
+        if self.elapsed() >= self.period {
             return;
         }
 
         if self.current_count >= self.max_per_period {
-            sleep(self.period - self.count_start.elapsed()).await;
+            self.clock
+                .sleep(self.period.saturating_sub(self.elapsed()))
+                .await;
         }
     }
 
     /// Returns the remaining count for the current period
     pub fn remaining(&self) -> T {
-        if self.count_start.elapsed() >= self.period {
+        if self.elapsed() >= self.period {
             return self.max_per_period;
         }
 
-        return if self.current_count > self.max_per_period {
+        if self.current_count > self.max_per_period {
             T::zero()
         } else {
             self.max_per_period - self.current_count
-        };
+        }
+    }
+
+    fn elapsed(&self) -> Duration {
+        self.clock.now().saturating_duration_since(self.count_start)
     }
 }
 
@@ -97,9 +123,44 @@ impl<T: Unsigned + AddAssign + Copy + PartialOrd> Clone for Throttle<T> {
     fn clone(&self) -> Self {
         Self {
             max_per_period: self.max_per_period,
-            period: self.period.clone(),
-            count_start: Instant::now(),
+            period: self.period,
+            count_start: self.clock.now(),
             current_count: T::zero(),
+            clock: self.clock.clone(),
         }
     }
 }
+
+/// A byte [Throttle] shared by many transfers, so all of them together stay under one limit
+/// instead of each getting its own budget.
+/// <br> Attached to a [B2Client](crate::client::B2Client) with [with_shared_throttle](crate::client::B2Client::with_shared_throttle),
+/// or to single transfers through their options.
+/// ```rust
+/// # use std::sync::Arc;
+/// # use backblaze_b2_client::{throttle::{SharedThrottle, Throttle}, util::SizeUnit};
+/// // Every upload and download together at most 10 MiB a second
+/// let throttle = Arc::new(SharedThrottle::new(Throttle::per_second(SizeUnit::MEBIBYTE * 10)));
+/// ```
+#[derive(Debug)]
+pub struct SharedThrottle {
+    throttle: Mutex<Throttle<u64>>,
+}
+
+impl SharedThrottle {
+    pub fn new(throttle: Throttle<u64>) -> Self {
+        Self {
+            throttle: Mutex::new(throttle),
+        }
+    }
+
+    /// Advances the throttle by the given amount, waiting if the throttle has been exhausted.
+    /// <br> Callers wait in turn, so a transfer waiting for the next period holds the others back too.
+    pub async fn advance_by(&self, by: u64) -> u64 {
+        self.throttle.lock().await.advance_by(by).await
+    }
+
+    /// Returns the remaining count for the current period
+    pub async fn remaining(&self) -> u64 {
+        self.throttle.lock().await.remaining()
+    }
+}
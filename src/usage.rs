@@ -0,0 +1,242 @@
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex, RwLock, Weak,
+    },
+    time::{Duration, SystemTime},
+};
+
+use tokio::task::JoinHandle;
+
+use crate::util::{B2Callback, Clock, SystemClock};
+
+/// Bytes transferred, retries and aborted attempts included, since that's what's sent over the network.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Usage {
+    pub uploaded: u64,
+    pub downloaded: u64,
+}
+
+impl Usage {
+    pub fn total(&self) -> u64 {
+        self.uploaded + self.downloaded
+    }
+}
+
+/// The usage recorded by a [UsageTracker] since it was created or last reset.
+#[derive(Debug, Clone)]
+pub struct UsageSnapshot {
+    /// Everything the tracker recorded.
+    pub session: Usage,
+    /// Usage per bucket ID, only buckets with recorded bytes are included.
+    pub buckets: HashMap<String, Usage>,
+    /// When the tracker was created or last reset.
+    pub since: SystemTime,
+}
+
+#[derive(Debug, Default)]
+struct Counters {
+    uploaded: AtomicU64,
+    downloaded: AtomicU64,
+}
+
+impl Counters {
+    fn usage(&self) -> Usage {
+        Usage {
+            uploaded: self.uploaded.load(Ordering::Relaxed),
+            downloaded: self.downloaded.load(Ordering::Relaxed),
+        }
+    }
+
+    fn take(&self) -> Usage {
+        Usage {
+            uploaded: self.uploaded.swap(0, Ordering::Relaxed),
+            downloaded: self.downloaded.swap(0, Ordering::Relaxed),
+        }
+    }
+}
+
+/// Counts uploaded and downloaded bytes, in total and per bucket, for apps that bill or budget their transfers.
+/// <br> Uploads and downloads created by a [B2Client](crate::client::B2Client) record into [its tracker](crate::client::B2Client::usage),
+/// other transfers can be recorded with [record_upload](UsageTracker::record_upload) and [record_download](UsageTracker::record_download).
+#[derive(Debug)]
+pub struct UsageTracker {
+    session: Counters,
+    buckets: RwLock<HashMap<String, Arc<Counters>>>,
+    since: Mutex<SystemTime>,
+    clock: Arc<dyn Clock>,
+}
+
+impl Default for UsageTracker {
+    fn default() -> Self {
+        Self::with_clock(SystemClock::shared())
+    }
+}
+
+impl UsageTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Uses the clock for the [since](UsageSnapshot::since) times and the [reporter](UsageTracker::spawn_reporter)'s interval, refer to [Clock].
+    pub fn with_clock(clock: Arc<dyn Clock>) -> Self {
+        Self {
+            session: Counters::default(),
+            buckets: RwLock::new(HashMap::new()),
+            since: Mutex::new(clock.system_time()),
+            clock,
+        }
+    }
+
+    pub fn record_upload(&self, bucket_id: &str, bytes: u64) {
+        self.session.uploaded.fetch_add(bytes, Ordering::Relaxed);
+        self.bucket_counters(bucket_id)
+            .uploaded
+            .fetch_add(bytes, Ordering::Relaxed);
+    }
+
+    /// Records downloaded bytes, only in the session total when the bucket isn't known.
+    pub fn record_download(&self, bucket_id: Option<&str>, bytes: u64) {
+        self.session.downloaded.fetch_add(bytes, Ordering::Relaxed);
+
+        if let Some(bucket_id) = bucket_id {
+            self.bucket_counters(bucket_id)
+                .downloaded
+                .fetch_add(bytes, Ordering::Relaxed);
+        }
+    }
+
+    pub fn session(&self) -> Usage {
+        self.session.usage()
+    }
+
+    pub fn bucket(&self, bucket_id: &str) -> Usage {
+        self.read_buckets()
+            .get(bucket_id)
+            .map(|counters| counters.usage())
+            .unwrap_or_default()
+    }
+
+    pub fn snapshot(&self) -> UsageSnapshot {
+        UsageSnapshot {
+            session: self.session.usage(),
+            buckets: self
+                .read_buckets()
+                .iter()
+                .map(|(bucket_id, counters)| (bucket_id.clone(), counters.usage()))
+                .collect(),
+            since: *self
+                .since
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner()),
+        }
+    }
+
+    /// Zeroes every counter, returning the usage up to the reset.
+    pub fn reset(&self) -> UsageSnapshot {
+        let mut since = self
+            .since
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+
+        let snapshot = UsageSnapshot {
+            session: self.session.take(),
+            buckets: self
+                .read_buckets()
+                .iter()
+                .map(|(bucket_id, counters)| (bucket_id.clone(), counters.take()))
+                .filter(|(_, usage)| usage.total() > 0)
+                .collect(),
+            since: *since,
+        };
+
+        *since = self.clock.system_time();
+
+        snapshot
+    }
+
+    /// Calls the callback with a [snapshot](UsageTracker::snapshot) every `interval`, until the tracker is dropped
+    /// or the returned handle is aborted.
+    pub fn spawn_reporter(
+        self: &Arc<Self>,
+        interval: Duration,
+        callback: B2Callback<UsageSnapshot>,
+    ) -> JoinHandle<()> {
+        let tracker = Arc::downgrade(self);
+        let clock = self.clock.clone();
+
+        tokio::spawn(async move {
+            loop {
+                clock.sleep(interval).await;
+
+                let Some(snapshot) = Weak::upgrade(&tracker).map(|tracker| tracker.snapshot())
+                else {
+                    break;
+                };
+
+                match &callback {
+                    B2Callback::Fn(fun) => fun(snapshot),
+                    B2Callback::AsyncFn(fun) => fun(snapshot).await,
+                }
+            }
+        })
+    }
+
+    /// Records into the tracker for a single transfer, the bucket's counters are looked up once.
+    pub(crate) fn recorder(self: &Arc<Self>, bucket_id: Option<&str>) -> UsageRecorder {
+        UsageRecorder {
+            tracker: self.clone(),
+            bucket: bucket_id.map(|bucket_id| self.bucket_counters(bucket_id)),
+        }
+    }
+
+    fn bucket_counters(&self, bucket_id: &str) -> Arc<Counters> {
+        if let Some(counters) = self.read_buckets().get(bucket_id) {
+            return counters.clone();
+        }
+
+        self.buckets
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .entry(bucket_id.to_string())
+            .or_default()
+            .clone()
+    }
+
+    fn read_buckets(&self) -> std::sync::RwLockReadGuard<'_, HashMap<String, Arc<Counters>>> {
+        self.buckets
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct UsageRecorder {
+    tracker: Arc<UsageTracker>,
+    bucket: Option<Arc<Counters>>,
+}
+
+impl UsageRecorder {
+    pub(crate) fn record_upload(&self, bytes: u64) {
+        self.tracker
+            .session
+            .uploaded
+            .fetch_add(bytes, Ordering::Relaxed);
+
+        if let Some(bucket) = &self.bucket {
+            bucket.uploaded.fetch_add(bytes, Ordering::Relaxed);
+        }
+    }
+
+    pub(crate) fn record_download(&self, bytes: u64) {
+        self.tracker
+            .session
+            .downloaded
+            .fetch_add(bytes, Ordering::Relaxed);
+
+        if let Some(bucket) = &self.bucket {
+            bucket.downloaded.fetch_add(bytes, Ordering::Relaxed);
+        }
+    }
+}
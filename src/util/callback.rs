@@ -1,6 +1,17 @@
-use std::{future::Future, sync::Arc};
+use std::{
+    future::Future,
+    panic::{catch_unwind, AssertUnwindSafe},
+    sync::Arc,
+    time::Duration,
+};
 
-use futures::{future::BoxFuture, FutureExt};
+use futures::{
+    future::{select, BoxFuture, Either},
+    FutureExt,
+};
+use tokio::sync::RwLock;
+
+use super::Clock;
 
 pub enum B2Callback<T: Sync + Send + 'static> {
     Fn(Box<dyn Fn(T) + Send + Sync>),
@@ -33,3 +44,72 @@ impl<T: Sync + Send + 'static> B2Callback<T> {
         }))
     }
 }
+
+impl<T: Sync + Send + 'static> std::fmt::Debug for B2Callback<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Fn(_) => write!(f, "B2Callback::Fn"),
+            Self::AsyncFn(_) => write!(f, "B2Callback::AsyncFn"),
+        }
+    }
+}
+
+/// How a task runs its finish callbacks, so a hanging or panicking callback can't keep the task from finishing.
+/// <br> Callbacks that time out or panic are reported as warnings through [tracing] with the `tracing` feature.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CallbackRunOptions {
+    /// An async callback still running after this long on the task's clock is dropped, and the next one is called.
+    /// <br> Default is None, which waits for every callback.
+    pub timeout: Option<Duration>,
+    /// Runs the callbacks on a spawned task, the task finishes without waiting for them.
+    /// <br> Default is false.
+    pub detached: bool,
+}
+
+/// Calls every callback in order with a clone of the value, following the run options, timeouts are waited on the clock.
+pub(crate) async fn run_callbacks<T: Clone + Sync + Send + 'static>(
+    callbacks: Arc<RwLock<Vec<B2Callback<T>>>>,
+    value: T,
+    options: CallbackRunOptions,
+    clock: Arc<dyn Clock>,
+) {
+    let run = async move {
+        let callbacks = callbacks.read().await;
+
+        for (index, callback) in callbacks.iter().enumerate() {
+            let outcome = match callback {
+                B2Callback::Fn(fun) => {
+                    catch_unwind(AssertUnwindSafe(|| fun(value.clone()))).map_err(|_| "panicked")
+                }
+                B2Callback::AsyncFn(fun) => {
+                    let call = AssertUnwindSafe(fun(value.clone())).catch_unwind();
+
+                    match options.timeout {
+                        Some(timeout) => match select(call, clock.sleep(timeout)).await {
+                            Either::Left((result, _)) => result.map_err(|_| "panicked"),
+                            Either::Right(_) => Err("timed out"),
+                        },
+                        None => call.await.map_err(|_| "panicked"),
+                    }
+                }
+            };
+
+            if let Err(reason) = outcome {
+                #[cfg(feature = "tracing")]
+                tracing::warn!(
+                    callback = index,
+                    timeout = ?options.timeout,
+                    "B2 callback {}",
+                    reason
+                );
+                #[cfg(not(feature = "tracing"))]
+                let _ = (index, reason);
+            }
+        }
+    };
+
+    match options.detached {
+        true => drop(tokio::spawn(run)),
+        false => run.await,
+    }
+}
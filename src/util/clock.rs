@@ -0,0 +1,124 @@
+use std::{
+    fmt::Debug,
+    sync::Arc,
+    time::{Duration, Instant, SystemTime},
+};
+
+use futures::future::BoxFuture;
+use tokio::sync::watch;
+
+/// Source of time for throttles, retry waits, transfer stats and the client's reauth loop.
+/// <br> [SystemClock] is used by default, a [ManualClock] makes the time controllable in tests.
+pub trait Clock: Debug + Send + Sync {
+    /// Current monotonic time.
+    fn now(&self) -> Instant;
+    /// Current wall clock time, used against B2's timestamps like key expiration.
+    fn system_time(&self) -> SystemTime;
+    /// Waits for the duration to pass on this clock.
+    fn sleep(&self, duration: Duration) -> BoxFuture<'static, ()>;
+}
+
+/// The real clock, backed by [Instant::now], [SystemTime::now] and tokio's sleep.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+
+    fn system_time(&self) -> SystemTime {
+        SystemTime::now()
+    }
+
+    fn sleep(&self, duration: Duration) -> BoxFuture<'static, ()> {
+        Box::pin(tokio::time::sleep(duration))
+    }
+}
+
+impl SystemClock {
+    /// The system clock as a shared [Clock].
+    pub fn shared() -> Arc<dyn Clock> {
+        Arc::new(Self)
+    }
+}
+
+/// A clock that only moves when [advanced](ManualClock::advance), sleeps finish once enough time was advanced.
+/// ```rust
+/// # use std::{sync::Arc, time::Duration};
+/// # use backblaze_b2_client::util::{Clock, ManualClock};
+/// # tokio::runtime::Runtime::new().unwrap().block_on(async {
+/// let clock = Arc::new(ManualClock::new());
+/// let start = clock.now();
+/// let sleep = tokio::spawn(clock.sleep(Duration::from_secs(60)));
+///
+/// clock.advance(Duration::from_secs(60));
+/// sleep.await.unwrap();
+///
+/// assert_eq!(clock.now() - start, Duration::from_secs(60));
+/// # });
+/// ```
+#[derive(Debug)]
+pub struct ManualClock {
+    start: Instant,
+    start_system_time: SystemTime,
+    elapsed: watch::Sender<Duration>,
+}
+
+impl Default for ManualClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ManualClock {
+    /// A clock starting at the current time.
+    pub fn new() -> Self {
+        Self::starting_at(SystemTime::now())
+    }
+
+    /// A clock whose [system_time](Clock::system_time) starts at the given time.
+    pub fn starting_at(system_time: SystemTime) -> Self {
+        Self {
+            start: Instant::now(),
+            start_system_time: system_time,
+            elapsed: watch::Sender::new(Duration::ZERO),
+        }
+    }
+
+    /// Moves the clock forward, finishing every sleep whose time has come.
+    pub fn advance(&self, by: Duration) {
+        self.elapsed.send_modify(|elapsed| *elapsed += by);
+    }
+
+    /// Time advanced since the clock was created.
+    pub fn elapsed(&self) -> Duration {
+        *self.elapsed.borrow()
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> Instant {
+        self.start + self.elapsed()
+    }
+
+    fn system_time(&self) -> SystemTime {
+        self.start_system_time + self.elapsed()
+    }
+
+    fn sleep(&self, duration: Duration) -> BoxFuture<'static, ()> {
+        let mut elapsed = self.elapsed.subscribe();
+        let deadline = *elapsed.borrow() + duration;
+
+        Box::pin(async move {
+            // The sender lives as long as the clock, a dropped clock never wakes its sleepers
+            if elapsed
+                .wait_for(|elapsed| *elapsed >= deadline)
+                .await
+                .is_err()
+            {
+                std::future::pending::<()>().await;
+            }
+        })
+    }
+}
@@ -0,0 +1,95 @@
+/// Compression formats recognized by the magic bytes they start with.
+const MAGIC_BYTES: [(&str, &[u8]); 2] =
+    [("gzip", &[0x1f, 0x8b]), ("zstd", &[0x28, 0xb5, 0x2f, 0xfd])];
+
+/// Content types of data that is itself compressed, so it starts with magic bytes without a `Content-Encoding`.
+const COMPRESSED_CONTENT_TYPES: [&str; 8] = [
+    "application/gzip",
+    "application/x-gzip",
+    "application/x-gtar",
+    "application/x-compressed-tar",
+    "application/zstd",
+    "application/x-zstd",
+    "application/x-bzip",
+    "application/x-bzip2",
+];
+
+/// Bytes needed to recognize every format [sniff_content_encoding] knows.
+pub const CONTENT_ENCODING_HEAD_LENGTH: usize = 10;
+
+/// The compression format data starts with, recognized by its magic bytes, None for anything else.
+/// <br> bzip2 starts with the printable `BZh`, so its block size digit and first block's magic have to follow too,
+/// which takes [CONTENT_ENCODING_HEAD_LENGTH] bytes.
+/// ```rust
+/// # use backblaze_b2_client::util::sniff_content_encoding;
+/// assert_eq!(sniff_content_encoding(b"BZh91AY&SY"), Some("bzip2"));
+/// assert_eq!(sniff_content_encoding(b"BZh is how it starts"), None);
+/// ```
+pub fn sniff_content_encoding(head: &[u8]) -> Option<&'static str> {
+    if let Some(magic) = MAGIC_BYTES
+        .iter()
+        .find(|(_, magic)| head.starts_with(magic))
+    {
+        return Some(magic.0);
+    }
+
+    // A first block, or the end of stream marker of empty data
+    let bzip2 = head.len() >= 10
+        && head.starts_with(b"BZh")
+        && (b'1'..=b'9').contains(&head[3])
+        && (head[4..10] == [0x31, 0x41, 0x59, 0x26, 0x53, 0x59]
+            || head[4..10] == [0x17, 0x72, 0x45, 0x38, 0x50, 0x90]);
+
+    bzip2.then_some("bzip2")
+}
+
+/// Whether the content type is of compressed data, like `application/gzip`, ignoring its parameters and case.
+pub fn is_compressed_content_type(content_type: &str) -> bool {
+    let essence = content_type.split(';').next().unwrap_or_default().trim();
+
+    COMPRESSED_CONTENT_TYPES
+        .iter()
+        .any(|compressed| compressed.eq_ignore_ascii_case(essence))
+}
+
+/// Whether data starting with `head` matches the declared `Content-Encoding`, only the first few bytes are looked at.
+/// <br> Data with no encoding declared must not start like gzip, zstd or bzip2 data, unless its `content_type` is a
+/// [compressed](is_compressed_content_type) one like `application/gzip`. Brotli has no magic bytes,
+/// so it and other unknown or stacked encodings always match, as does empty data.
+/// ```rust
+/// # use backblaze_b2_client::util::content_encoding_matches;
+/// let gzipped = [0x1f, 0x8b, 0x08, 0x00];
+///
+/// assert!(content_encoding_matches(Some("gzip"), None, &gzipped));
+/// assert!(!content_encoding_matches(Some("gzip"), None, b"plain text"));
+/// assert!(!content_encoding_matches(None, None, &gzipped));
+/// assert!(content_encoding_matches(None, Some("application/gzip"), &gzipped));
+/// assert!(content_encoding_matches(Some("br"), None, b"anything"));
+/// ```
+pub fn content_encoding_matches(
+    declared: Option<&str>,
+    content_type: Option<&str>,
+    head: &[u8],
+) -> bool {
+    if head.is_empty() {
+        return true;
+    }
+
+    let sniffed = sniff_content_encoding(head);
+    let declared = declared.map(|declared| declared.trim().to_ascii_lowercase());
+
+    match declared.as_deref() {
+        None | Some("") | Some("identity") => {
+            sniffed.is_none() || content_type.is_some_and(is_compressed_content_type)
+        }
+        Some("gzip") | Some("x-gzip") => sniffed == Some("gzip"),
+        Some("zstd") => sniffed == Some("zstd"),
+        // HTTP's deflate is zlib wrapped, its 2 byte header has a check value
+        Some("deflate") => {
+            head.len() >= 2
+                && head[0] & 0x0f == 8
+                && ((u16::from(head[0]) << 8) | u16::from(head[1])) % 31 == 0
+        }
+        Some(_) => true,
+    }
+}
@@ -0,0 +1,59 @@
+use serde_json::Value;
+
+/// JSON fields whose values are replaced before a body is logged.
+const SECRET_FIELDS: [&str; 5] = [
+    "applicationKey",
+    "authorizationToken",
+    "customerKey",
+    "customHeaders",
+    "hmacSha256SigningSecret",
+];
+
+const REDACTED: &str = "<redacted>";
+
+/// Formats a JSON value for a debug log, with secrets redacted and cut to at most `max_len` bytes.
+pub(crate) fn loggable_json(mut value: Value, max_len: usize) -> String {
+    redact_secrets(&mut value);
+    truncate_for_log(value.to_string(), max_len)
+}
+
+/// Formats a response body for a debug log like [loggable_json], bodies that aren't JSON are only cut.
+pub(crate) fn loggable_body(body: &str, max_len: usize) -> String {
+    match serde_json::from_str::<Value>(body) {
+        Ok(value) => loggable_json(value, max_len),
+        Err(_) => truncate_for_log(body.to_string(), max_len),
+    }
+}
+
+fn redact_secrets(value: &mut Value) {
+    match value {
+        Value::Object(fields) => {
+            for (name, field) in fields.iter_mut() {
+                if SECRET_FIELDS.contains(&name.as_str()) && !field.is_null() {
+                    *field = Value::String(REDACTED.into());
+                } else {
+                    redact_secrets(field);
+                }
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(redact_secrets),
+        _ => {}
+    }
+}
+
+fn truncate_for_log(mut text: String, max_len: usize) -> String {
+    if text.len() <= max_len {
+        return text;
+    }
+
+    let total_len = text.len();
+    let mut cut = max_len;
+
+    while !text.is_char_boundary(cut) {
+        cut -= 1;
+    }
+
+    text.truncate(cut);
+    text.push_str(&format!("... ({} bytes total)", total_len));
+    text
+}
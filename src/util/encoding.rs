@@ -0,0 +1,80 @@
+//! Percent-encoding for the places B2 expects encoded strings, following the
+//! [B2 string encoding rules](https://www.backblaze.com/docs/cloud-storage-native-api-string-encoding).
+//!
+//! <br> B2 applies the same rules to file names in headers and URL paths and to file info values, so the header, file info
+//! and URL path encoders encode the same characters. They're kept apart so each call site names what it encodes.
+//! Only a [path segment](encode_url_path_segment) differs, where `/` is encoded too.
+//! ```
+//! use backblaze_b2_client::util::encoding::*;
+//!
+//! assert_eq!(encode_url_path("photos/a cat+1?.jpg"), "photos/a%20cat%2B1%3F.jpg");
+//! assert_eq!(encode_url_path_segment("photos/cat.jpg"), "photos%2Fcat.jpg");
+//! assert_eq!(decode_header_value("a+cat%2B1"), "a cat+1");
+//! ```
+
+use percent_encoding::{percent_decode_str, utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+
+/// Characters B2 requires to be encoded, everything except letters, digits and `-._~!$'()*;=:@/`.
+const B2_ENCODE_SET: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'.')
+    .remove(b'_')
+    .remove(b'~')
+    .remove(b'!')
+    .remove(b'$')
+    .remove(b'\'')
+    .remove(b'(')
+    .remove(b')')
+    .remove(b'*')
+    .remove(b';')
+    .remove(b'=')
+    .remove(b':')
+    .remove(b'@')
+    .remove(b'/');
+
+/// Same as [B2_ENCODE_SET], but also encodes `/`.
+const B2_SEGMENT_ENCODE_SET: &AsciiSet = &B2_ENCODE_SET.add(b'/');
+
+/// Characters left alone in query parameters, everything else is encoded so `&`, `=` and `+` can't change the query.
+const QUERY_ENCODE_SET: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'.')
+    .remove(b'_')
+    .remove(b'~');
+
+/// Encodes a header value, like the file name sent as `X-Bz-File-Name`.
+/// <br> `+` is encoded since B2 decodes it as a space.
+pub fn encode_header_value<S: AsRef<str>>(value: S) -> String {
+    utf8_percent_encode(value.as_ref(), B2_ENCODE_SET).to_string()
+}
+
+/// Encodes a file info value, sent as an `X-Bz-Info-*` header on uploads.
+/// <br> B2 decodes these like any other header value, so it's the same as [encode_header_value].
+pub fn encode_file_info_value<S: AsRef<str>>(value: S) -> String {
+    encode_header_value(value)
+}
+
+/// Encodes a URL path, keeping `/` so a file name like `photos/cat.jpg` stays a path.
+/// <br> Encodes the same as [encode_header_value], B2 matches the decoded path against the file name it decoded from the header,
+/// so `?` and `#` are encoded to stay out of the query and fragment, and `+` so it isn't taken for a space.
+pub fn encode_url_path<S: AsRef<str>>(path: S) -> String {
+    utf8_percent_encode(path.as_ref(), B2_ENCODE_SET).to_string()
+}
+
+/// Encodes a single URL path segment, where `/` is encoded too.
+pub fn encode_url_path_segment<S: AsRef<str>>(segment: S) -> String {
+    utf8_percent_encode(segment.as_ref(), B2_SEGMENT_ENCODE_SET).to_string()
+}
+
+/// Encodes a query parameter name or value, like the `Authorization` of a [download URL](crate::simple_client::B2SimpleClient::make_download_url).
+pub fn encode_query_value<S: AsRef<str>>(value: S) -> String {
+    utf8_percent_encode(value.as_ref(), QUERY_ENCODE_SET).to_string()
+}
+
+/// Decodes a header value sent by B2, like `x-bz-file-name` or `x-bz-info-*` on downloads.
+/// <br> `+` is decoded as a space, invalid UTF-8 sequences are replaced with `U+FFFD`.
+pub fn decode_header_value<S: AsRef<str>>(value: S) -> String {
+    percent_decode_str(&value.as_ref().replace('+', " "))
+        .decode_utf8_lossy()
+        .into_owned()
+}
@@ -0,0 +1,146 @@
+use std::collections::HashMap;
+
+use super::InvalidValue;
+
+/// Prefix of the file info keys used to store tags, a tag `env=prod` is stored as `tag-env: prod`.
+pub const FILE_INFO_TAG_PREFIX: &str = "tag-";
+
+/// Max number of file info keys B2 allows on a single file.
+pub const MAX_FILE_INFO_KEYS: usize = 10;
+
+/// Max length of a single file info key.
+pub const MAX_FILE_INFO_KEY_LENGTH: usize = 50;
+
+/// Returns the canonical form of a file info key.
+/// <br> B2 treats file info keys case-insensitively and always returns them lowercased,
+/// so a key uploaded as `Src_Last_Modified_Millis` comes back as `src_last_modified_millis`.
+pub fn canonical_file_info_key<S: AsRef<str>>(key: S) -> String {
+    key.as_ref().to_ascii_lowercase()
+}
+
+/// Canonicalizes every key of the given file info map, see [canonical_file_info_key].
+/// <br> Fails if two keys only differ by case, since B2 would keep only one of them.
+/// ```
+/// # use std::collections::HashMap;
+/// # use backblaze_b2_client::util::canonicalize_file_info;
+/// let file_info = HashMap::from([("Author", "a")]);
+/// assert_eq!(canonicalize_file_info(file_info).unwrap()["author"], "a");
+///
+/// let file_info = HashMap::from([("Author", "a"), ("author", "b")]);
+/// assert!(canonicalize_file_info(file_info).is_err());
+/// ```
+pub fn canonicalize_file_info<K: AsRef<str>, V>(
+    file_info: HashMap<K, V>,
+) -> Result<HashMap<String, V>, InvalidValue> {
+    let mut canonical = HashMap::with_capacity(file_info.len());
+
+    for (key, value) in file_info {
+        let canonical_key = canonical_file_info_key(&key);
+
+        if canonical.contains_key(&canonical_key) {
+            return Err(InvalidValue {
+                object_name: "FileInfo".into(),
+                value_name: "key".into(),
+                value_as_string: key.as_ref().to_owned(),
+                expected: "keys that don't only differ by case".into(),
+            });
+        }
+
+        canonical.insert(canonical_key, value);
+    }
+
+    Ok(canonical)
+}
+
+/// Case-insensitive access to a parsed file info map.
+pub trait FileInfoExt {
+    /// Gets a file info value ignoring the key's case.
+    fn get_info(&self, key: &str) -> Option<&str>;
+
+    /// Whether a file info key exists, ignoring the key's case.
+    fn contains_info(&self, key: &str) -> bool {
+        self.get_info(key).is_some()
+    }
+}
+
+impl<S: std::hash::BuildHasher> FileInfoExt for HashMap<String, String, S> {
+    fn get_info(&self, key: &str) -> Option<&str> {
+        if let Some(value) = self.get(&canonical_file_info_key(key)) {
+            return Some(value);
+        }
+
+        self.iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(key))
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+/// Gets the tags stored in a file info map, keys are returned without the [FILE_INFO_TAG_PREFIX].
+pub fn file_info_tags<S: std::hash::BuildHasher>(
+    file_info: &HashMap<String, String, S>,
+) -> HashMap<String, String> {
+    file_info
+        .iter()
+        .filter_map(|(key, value)| {
+            let key = canonical_file_info_key(key);
+
+            key.strip_prefix(FILE_INFO_TAG_PREFIX)
+                .map(|tag| (tag.to_owned(), value.clone()))
+        })
+        .collect()
+}
+
+/// Replaces every tag in a file info map with the given tags, other file info is kept as is.
+/// <br> Fails if a tag key isn't a valid file info key, if two file info keys only differ by case, or if the result goes over [MAX_FILE_INFO_KEYS].
+pub fn file_info_with_tags(
+    file_info: HashMap<String, String>,
+    tags: HashMap<String, String>,
+) -> Result<HashMap<String, String>, InvalidValue> {
+    let mut file_info: HashMap<String, String> = canonicalize_file_info(file_info)?
+        .into_iter()
+        .filter(|(key, _)| !key.starts_with(FILE_INFO_TAG_PREFIX))
+        .collect();
+
+    for (tag, value) in tags {
+        let key = canonical_file_info_key(format!("{}{}", FILE_INFO_TAG_PREFIX, tag));
+
+        if key.len() > MAX_FILE_INFO_KEY_LENGTH
+            || !key
+                .chars()
+                .all(|char| char.is_ascii_alphanumeric() || char == '-' || char == '_')
+        {
+            return Err(InvalidValue {
+                object_name: "Tags".into(),
+                value_name: "key".into(),
+                value_as_string: tag,
+                expected: format!(
+                    "letters, numbers, '-' and '_' only, at most {} characters",
+                    MAX_FILE_INFO_KEY_LENGTH - FILE_INFO_TAG_PREFIX.len()
+                ),
+            });
+        }
+
+        file_info.insert(key, value);
+    }
+
+    if file_info.len() > MAX_FILE_INFO_KEYS {
+        return Err(InvalidValue {
+            object_name: "Tags".into(),
+            value_name: "file_info".into(),
+            value_as_string: format!("{} keys", file_info.len()),
+            expected: format!("at most {} keys, including tags", MAX_FILE_INFO_KEYS),
+        });
+    }
+
+    Ok(file_info)
+}
+
+/// Whether a file info map has every one of the given tags, with the same values.
+pub fn file_info_has_tags<S: std::hash::BuildHasher>(
+    file_info: &HashMap<String, String, S>,
+    tags: &HashMap<String, String>,
+) -> bool {
+    tags.iter().all(|(tag, value)| {
+        file_info.get_info(&format!("{}{}", FILE_INFO_TAG_PREFIX, tag)) == Some(value.as_str())
+    })
+}
@@ -1,13 +1,19 @@
-use std::pin::Pin;
+use std::{
+    pin::Pin,
+    task::{ready, Context, Poll},
+};
 
 use bytes::Bytes;
-use futures::StreamExt;
+use futures::{future::BoxFuture, FutureExt, StreamExt};
 use futures_core::Stream;
 
 use crate::error::B2Error;
 
 use super::B2Callback;
 
+/// The boxed byte stream backing a [B2FileStream].
+pub type B2ByteStream = Pin<Box<dyn Stream<Item = Result<Bytes, reqwest::Error>> + Send>>;
+
 /// A file stream for the B2File, you're most likely gonna only use it as the following:
 ///
 /// ```rs
@@ -18,10 +24,20 @@ use super::B2Callback;
 ///
 /// let data = response.file.read_all().await;
 /// ```
+/// It's also a [Stream] of the file's chunks, for piping the body elsewhere without holding all of it in memory.
+/// Middlewares run on every chunk either way, before the chunk is returned.
 pub struct B2FileStream {
-    stream: Pin<Box<dyn Stream<Item = Result<Bytes, reqwest::Error>> + Send>>,
+    stream: B2ByteStream,
     size: usize,
     middlewares: Vec<B2Callback<Bytes>>,
+    pending: Option<PendingChunk>,
+}
+
+/// A chunk read from the stream that's still going through the middlewares.
+struct PendingChunk {
+    bytes: Bytes,
+    next_middleware: usize,
+    running: Option<BoxFuture<'static, ()>>,
 }
 
 impl B2FileStream {
@@ -33,6 +49,7 @@ impl B2FileStream {
             stream: Box::pin(stream),
             size,
             middlewares: vec![],
+            pending: None,
         }
     }
 
@@ -40,34 +57,21 @@ impl B2FileStream {
     pub async fn read_all(mut self) -> Result<Bytes, B2Error> {
         let mut buffer: Vec<u8> = Vec::with_capacity(self.size);
 
-        loop {
-            match self.stream.next().await {
-                Some(value) => {
-                    let value = value.map_err(|err| B2Error::RequestSendError(err))?;
-
-                    for middleware in &mut self.middlewares {
-                        match middleware {
-                            B2Callback::Fn(fun) => fun(value.clone()),
-                            B2Callback::AsyncFn(fun) => fun(value.clone()).await,
-                        }
-                    }
-
-                    buffer.extend_from_slice(value.as_ref());
-                }
-                None => break,
-            }
+        while let Some(value) = self.next().await {
+            buffer.extend_from_slice(value?.as_ref());
         }
 
         Ok(Bytes::from(buffer))
     }
 
-    /// Consumes self, then returns the underlying stream and file size
-    pub fn into_stream(
-        self,
-    ) -> (
-        usize,
-        Pin<Box<dyn Stream<Item = Result<Bytes, reqwest::Error>> + Send>>,
-    ) {
+    /// The file size reported by B2.
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Consumes self, then returns the underlying stream and file size.
+    /// <br> The middlewares don't run on the returned stream, use the [B2FileStream] as a [Stream] to keep them.
+    pub fn into_stream(self) -> (usize, B2ByteStream) {
         (self.size, self.stream)
     }
 
@@ -78,3 +82,57 @@ impl B2FileStream {
         self
     }
 }
+
+impl Stream for B2FileStream {
+    type Item = Result<Bytes, B2Error>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+
+        loop {
+            if let Some(pending) = &mut this.pending {
+                if let Some(running) = &mut pending.running {
+                    ready!(running.poll_unpin(cx));
+                    pending.running = None;
+                }
+
+                while let Some(middleware) = this.middlewares.get(pending.next_middleware) {
+                    pending.next_middleware += 1;
+
+                    match middleware {
+                        B2Callback::Fn(fun) => fun(pending.bytes.clone()),
+                        B2Callback::AsyncFn(fun) => {
+                            pending.running = Some(fun(pending.bytes.clone()));
+                            break;
+                        }
+                    }
+                }
+
+                if pending.running.is_some() {
+                    continue;
+                }
+
+                let bytes = this.pending.take().map(|pending| pending.bytes);
+                return Poll::Ready(bytes.map(Ok));
+            }
+
+            match ready!(this.stream.poll_next_unpin(cx)) {
+                Some(Ok(bytes)) => {
+                    this.pending = Some(PendingChunk {
+                        bytes,
+                        next_middleware: 0,
+                        running: None,
+                    })
+                }
+                Some(Err(error)) => {
+                    return Poll::Ready(Some(Err(B2Error::RequestSendError(error))))
+                }
+                None => return Poll::Ready(None),
+            }
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.stream.size_hint()
+    }
+}
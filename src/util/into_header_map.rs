@@ -5,37 +5,86 @@ use serde::Serialize;
 
 use crate::error::IntoHeaderMapError;
 
+/// Turns a headers object into a [HeaderMap].
+/// <br> The default implementation goes through [serialize_into_header_map], types on hot paths,
+/// like the upload headers, build the map directly instead.
 pub trait IntoHeaderMap: Sized + Serialize {
     fn into_header_map(self) -> Result<HeaderMap, IntoHeaderMapError> {
-        let serialized_object = serde_json::to_value(self)
-            .map_err(|err| IntoHeaderMapError::SerializationFailed(err))?;
-
-        match serialized_object {
-            serde_json::Value::Object(object) => object
-                .into_iter()
-                .filter_map(|(key, value)| {
-                    let value = match value {
-                        serde_json::Value::Null => return None,
-                        serde_json::Value::String(value) => value,
-                        val => val.to_string(),
-                    };
-
-                    let header_name = match HeaderName::from_str(&key) {
-                        Ok(header_name) => header_name,
-                        Err(_) => return Some(Err(IntoHeaderMapError::InvalidHeaderName(key))),
-                    };
-
-                    let header_value = match HeaderValue::from_str(&value) {
-                        Ok(header_value) => header_value,
-                        Err(_) => return Some(Err(IntoHeaderMapError::InvalidHeaderValue(value))),
-                    };
-
-                    Some(Ok((header_name, header_value)))
-                })
-                .collect(),
-            _ => return Err(IntoHeaderMapError::InvalidObject),
-        }
+        serialize_into_header_map(&self)
     }
 }
 
 impl<A: Sized + Serialize, B: Sized + Serialize> IntoHeaderMap for HashMap<A, B> {}
+
+/// Builds a [HeaderMap] by serializing the value to a JSON object, every non null field becomes a header.
+pub fn serialize_into_header_map<T: Serialize + ?Sized>(
+    value: &T,
+) -> Result<HeaderMap, IntoHeaderMapError> {
+    let serialized_object =
+        serde_json::to_value(value).map_err(IntoHeaderMapError::SerializationFailed)?;
+
+    match serialized_object {
+        serde_json::Value::Object(object) => object
+            .into_iter()
+            .filter_map(|(key, value)| {
+                let value = match value {
+                    serde_json::Value::Null => return None,
+                    serde_json::Value::String(value) => value,
+                    val => val.to_string(),
+                };
+
+                let header_name = match HeaderName::from_str(&key) {
+                    Ok(header_name) => header_name,
+                    Err(_) => return Some(Err(IntoHeaderMapError::InvalidHeaderName(key))),
+                };
+
+                let header_value = match HeaderValue::from_str(&value) {
+                    Ok(header_value) => header_value,
+                    Err(_) => return Some(Err(IntoHeaderMapError::InvalidHeaderValue(value))),
+                };
+
+                Some(Ok((header_name, header_value)))
+            })
+            .collect(),
+        _ => Err(IntoHeaderMapError::InvalidObject),
+    }
+}
+
+/// Inserts a string header, reusing the string's allocation for the value.
+pub(crate) fn insert_string_header(
+    headers: &mut HeaderMap,
+    name: &'static str,
+    value: String,
+) -> Result<(), IntoHeaderMapError> {
+    // Same rule HeaderValue checks, done first so the error can hold the value
+    let is_valid = value
+        .bytes()
+        .all(|byte| byte == b'\t' || (byte >= b' ' && byte != 0x7f));
+
+    if !is_valid {
+        return Err(IntoHeaderMapError::InvalidHeaderValue(value));
+    }
+
+    let value = HeaderValue::try_from(value).expect("value was checked to be valid");
+
+    headers.insert(HeaderName::from_static(name), value);
+
+    Ok(())
+}
+
+/// Inserts a number header, numbers are always valid header values.
+pub(crate) fn insert_number_header(headers: &mut HeaderMap, name: &'static str, value: u64) {
+    headers.insert(HeaderName::from_static(name), HeaderValue::from(value));
+}
+
+/// Inserts a header with a value known at compile time.
+pub(crate) fn insert_static_header(
+    headers: &mut HeaderMap,
+    name: &'static str,
+    value: &'static str,
+) {
+    headers.insert(
+        HeaderName::from_static(name),
+        HeaderValue::from_static(value),
+    );
+}
@@ -1,17 +1,29 @@
 pub mod callback;
+pub mod clock;
+pub mod content_encoding;
+pub(crate) mod debug_log;
+pub mod encoding;
+pub mod file_info;
 pub mod file_stream;
 pub mod into_header_map;
 pub mod is_valid;
 pub mod retry_strategy;
+pub mod runtime;
+pub mod sha1_at_end;
 pub mod size_unit;
 pub mod time_series;
 pub mod write_lock_arc;
 
 pub use callback::*;
+pub use clock::*;
+pub use content_encoding::*;
+pub use file_info::*;
 pub use file_stream::*;
 pub use into_header_map::*;
 pub use is_valid::*;
 pub use retry_strategy::*;
+pub use runtime::*;
+pub use sha1_at_end::*;
 pub use size_unit::*;
 pub use time_series::*;
 pub(crate) use write_lock_arc::*;
@@ -1,4 +1,11 @@
-use std::{num::NonZeroU64, time::Duration};
+use std::{
+    error::Error,
+    num::NonZeroU64,
+    sync::atomic::{AtomicU64, Ordering},
+    time::Duration,
+};
+
+use serde::{Deserialize, Serialize};
 
 /// The request retry strategy.
 #[derive(Debug)]
@@ -14,30 +21,78 @@ impl Default for RetryStrategy {
 }
 
 impl RetryStrategy {
-    pub fn wait(&self, current_retry_count: u64) -> Duration {
-        match self {
-            Self::Constant(c) => c.wait,
-            Self::Dynamic(d) => d.wait_time(current_retry_count),
+    /// Returns how long to wait before the next retry.
+    /// <br> Jitter is applied first, then the cap, a server sent `Retry-After` is always waited out in full.
+    pub fn wait(&self, context: &RetryContext) -> Duration {
+        let (wait, jitter, max_wait) = match self {
+            Self::Constant(c) => (c.wait, c.jitter, c.max_wait),
+            Self::Dynamic(d) => (d.wait_time(context), d.jitter(), d.max_wait()),
+        };
+
+        let wait = jitter.apply(wait);
+        let wait = match max_wait {
+            Some(max_wait) => wait.min(max_wait),
+            None => wait,
+        };
+
+        match context.retry_after {
+            Some(retry_after) => wait.max(retry_after),
+            None => wait,
         }
     }
 
+    /// Like [wait](RetryStrategy::wait), for a retry that's going to happen, which is logged with the `tracing` feature.
+    pub(crate) fn retry_wait(&self, context: &RetryContext) -> Duration {
+        let wait = self.wait(context);
+        trace_retry(context, wait);
+
+        wait
+    }
+
+    /// Number of times a failed request is retried, on top of its first attempt.
     pub fn count(&self) -> NonZeroU64 {
         match self {
             Self::Constant(c) => c.count,
             Self::Dynamic(d) => d.retry_count(),
         }
     }
+
+    /// Most times a request is attempted, its first attempt and every [retry](RetryStrategy::count).
+    pub fn max_attempts(&self) -> u64 {
+        self.count().get().saturating_add(1)
+    }
+}
+
+/// Logs a retry that's going to happen after `wait` with the `tracing` feature, does nothing without it.
+pub(crate) fn trace_retry(context: &RetryContext, wait: Duration) {
+    #[cfg(feature = "tracing")]
+    tracing::info!(
+        endpoint = context.endpoint,
+        attempt = context.attempt,
+        error = context.last_error.map(tracing::field::display),
+        ?wait,
+        "Retrying B2 call"
+    );
+
+    #[cfg(not(feature = "tracing"))]
+    let _ = (context, wait);
 }
 
 /// Dictates requests are retried.
 #[derive(Debug, Clone)]
 pub struct ConstantRetryStrategy {
-    /// Number of times to retry.
+    /// Number of times to retry, on top of the first attempt.
     /// <br> Default 3.
     pub count: NonZeroU64,
     /// How much to wait between retries.
     /// <br> Default 1 seconds.
     pub wait: Duration,
+    /// Jitter applied to the wait.
+    /// <br> Default is [RetryJitter::None].
+    pub jitter: RetryJitter,
+    /// Max time to wait between retries, not counting a server sent `Retry-After`.
+    /// <br> Default is None.
+    pub max_wait: Option<Duration>,
 }
 
 impl Default for ConstantRetryStrategy {
@@ -45,26 +100,129 @@ impl Default for ConstantRetryStrategy {
         Self {
             count: NonZeroU64::try_from(3).expect("valid number"),
             wait: Duration::from_secs(1),
+            jitter: RetryJitter::None,
+            max_wait: None,
+        }
+    }
+}
+
+/// Randomizes retry waits so many clients failing together don't retry together.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum RetryJitter {
+    #[default]
+    None,
+    /// Waits a random time between 0 and the full wait.
+    Full,
+    /// Waits half the wait, plus a random time between 0 and the other half.
+    Equal,
+}
+
+impl RetryJitter {
+    pub fn apply(&self, wait: Duration) -> Duration {
+        match self {
+            Self::None => wait,
+            Self::Full => wait.mul_f64(rand::random::<f64>()),
+            Self::Equal => {
+                let half = wait / 2;
+                half + half.mul_f64(rand::random::<f64>())
+            }
         }
     }
 }
 
+/// What is known about a failed request when deciding how long to wait before retrying it.
+#[derive(Debug, Clone, Copy)]
+pub struct RetryContext<'a> {
+    /// The B2 endpoint that failed, e.g. `b2_upload_part`.
+    pub endpoint: &'a str,
+    /// The number of the attempt about to be made, the first request being attempt 1.
+    pub attempt: u64,
+    /// The error the last attempt failed with.
+    pub last_error: Option<&'a (dyn Error + Send + Sync)>,
+    /// The `Retry-After` the server responded with, if any.
+    pub retry_after: Option<Duration>,
+}
+
 /// A dynamic retry strategy.
 pub trait DynamicRetryStrategy: std::fmt::Debug {
-    /// Returns the wait time
-    fn wait_time(&self, current_retry_count: u64) -> Duration;
+    /// Returns the wait time, before jitter and the cap are applied.
+    fn wait_time(&self, context: &RetryContext) -> Duration;
+    /// Number of times to retry, on top of the first attempt.
     fn retry_count(&self) -> NonZeroU64;
+
+    /// Jitter applied to [wait_time](DynamicRetryStrategy::wait_time).
+    fn jitter(&self) -> RetryJitter {
+        RetryJitter::None
+    }
+
+    /// Max time to wait between retries, not counting a server sent `Retry-After`.
+    fn max_wait(&self) -> Option<Duration> {
+        None
+    }
 }
 
 #[derive(Debug)]
 pub struct DefaultRetryStrategy;
 
 impl DynamicRetryStrategy for DefaultRetryStrategy {
-    fn wait_time(&self, current_retry_count: u64) -> Duration {
-        Duration::from_secs_f64((current_retry_count * 2) as f64 / 1.2)
+    fn wait_time(&self, context: &RetryContext) -> Duration {
+        Duration::from_secs_f64((context.attempt * 2) as f64 / 1.2)
     }
 
     fn retry_count(&self) -> NonZeroU64 {
         NonZeroU64::try_from(5).expect("valid number")
     }
 }
+
+/// Caps how much a whole task can retry, shared between all of its requests (e.g. every part of a large file),
+/// so a failing network surfaces an error quickly instead of multiplying per request retries.
+/// <br> Any limit left as None is unlimited.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RetryBudget {
+    /// Max number of retries across the whole task.
+    pub max_retries: Option<u64>,
+    /// Max number of bytes that were sent by failed requests and have to be sent again.
+    pub max_wasted_bytes: Option<u64>,
+    /// Max total time spent on failed requests and waiting to retry them.
+    pub max_wasted_time: Option<Duration>,
+}
+
+/// Tracks what was spent from a [RetryBudget] across a task.
+#[derive(Debug)]
+pub(crate) struct RetryBudgetTracker {
+    budget: RetryBudget,
+    retries: AtomicU64,
+    wasted_bytes: AtomicU64,
+    wasted_millis: AtomicU64,
+}
+
+impl RetryBudgetTracker {
+    pub fn new(budget: RetryBudget) -> Self {
+        Self {
+            budget,
+            retries: AtomicU64::new(0),
+            wasted_bytes: AtomicU64::new(0),
+            wasted_millis: AtomicU64::new(0),
+        }
+    }
+
+    /// Records a retry, returns false if it goes over the budget.
+    pub fn spend(&self, wasted_bytes: u64, wasted_time: Duration) -> bool {
+        let retries = self.retries.fetch_add(1, Ordering::Relaxed) + 1;
+        let bytes = self.wasted_bytes.fetch_add(wasted_bytes, Ordering::Relaxed) + wasted_bytes;
+        let wasted_millis = wasted_time.as_millis() as u64;
+        let millis = self
+            .wasted_millis
+            .fetch_add(wasted_millis, Ordering::Relaxed)
+            + wasted_millis;
+
+        let over_retries = self.budget.max_retries.is_some_and(|max| retries > max);
+        let over_bytes = self.budget.max_wasted_bytes.is_some_and(|max| bytes > max);
+        let over_time = self
+            .budget
+            .max_wasted_time
+            .is_some_and(|max| Duration::from_millis(millis) > max);
+
+        !(over_retries || over_bytes || over_time)
+    }
+}
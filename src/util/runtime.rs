@@ -0,0 +1,79 @@
+use std::{fmt::Debug, future::Future, pin::pin, sync::Arc};
+
+use futures::{
+    channel::oneshot,
+    future::{select, BoxFuture, Either},
+};
+
+/// Executor the [simple client](crate::simple_client::B2SimpleClient) drives its HTTP requests on,
+/// refer to [B2SimpleClient::with_runtime](crate::simple_client::B2SimpleClient::with_runtime).
+/// <br> reqwest's connections need a Tokio reactor, so an implementation has to run the futures on one,
+/// what it lets the caller do is await the client from any other executor.
+pub trait Runtime: Debug + Send + Sync {
+    /// Runs the future to completion in the background.
+    fn spawn(&self, future: BoxFuture<'static, ()>);
+}
+
+/// A [Runtime] spawning on a Tokio runtime through its [Handle](tokio::runtime::Handle).
+/// ```rust
+/// # use std::sync::Arc;
+/// # use backblaze_b2_client::util::{Runtime, TokioRuntime};
+/// let tokio = tokio::runtime::Runtime::new().unwrap();
+/// let runtime = TokioRuntime::new(tokio.handle().clone());
+/// let (sender, receiver) = futures::channel::oneshot::channel();
+///
+/// runtime.spawn(Box::pin(async move {
+///     sender.send(tokio::runtime::Handle::try_current().is_ok()).unwrap();
+/// }));
+///
+/// // Any executor can wait on the result.
+/// assert!(futures::executor::block_on(receiver).unwrap());
+/// ```
+#[derive(Debug, Clone)]
+pub struct TokioRuntime {
+    handle: tokio::runtime::Handle,
+}
+
+impl TokioRuntime {
+    pub fn new(handle: tokio::runtime::Handle) -> Self {
+        Self { handle }
+    }
+
+    /// The runtime as a shared [Runtime].
+    pub fn shared(handle: tokio::runtime::Handle) -> Arc<dyn Runtime> {
+        Arc::new(Self::new(handle))
+    }
+}
+
+impl Runtime for TokioRuntime {
+    fn spawn(&self, future: BoxFuture<'static, ()>) {
+        self.handle.spawn(future);
+    }
+}
+
+/// Runs the future on the runtime if there's one, or in place otherwise.
+/// <br> The spawned future is dropped once its result isn't awaited anymore.
+pub(crate) async fn run_on<F>(runtime: Option<&Arc<dyn Runtime>>, future: F) -> F::Output
+where
+    F: Future + Send + 'static,
+    F::Output: Send,
+{
+    let Some(runtime) = runtime else {
+        return future.await;
+    };
+
+    let (mut sender, receiver) = oneshot::channel();
+
+    runtime.spawn(Box::pin(async move {
+        let output = match select(pin!(future), sender.cancellation()).await {
+            Either::Left((output, _)) => output,
+            Either::Right(_) => return,
+        };
+
+        let _ = sender.send(output);
+    }));
+
+    receiver
+        .await
+        .expect("the runtime dropped a future before it finished, it was likely shut down")
+}
@@ -0,0 +1,80 @@
+use std::sync::{Arc, OnceLock};
+
+use async_stream::stream;
+use bytes::Bytes;
+use futures::StreamExt;
+use futures_core::Stream;
+use sha1_smol::Sha1;
+
+/// `X-Bz-Content-Sha1` value telling B2 the content's SHA1 follows the content, as 40 hex digits at the end of the body.
+pub const HEX_DIGITS_AT_END: &str = "hex_digits_at_end";
+
+/// Length of the SHA1 hex sent after the content, `Content-Length` has to include it.
+pub const SHA1_HEX_LENGTH: u64 = 40;
+
+/// The SHA1 of the content streamed through [append_sha1_hex], set once the content has been read to the end.
+#[derive(Debug, Clone, Default)]
+pub struct Sha1AtEnd {
+    digest: Arc<OnceLock<String>>,
+}
+
+impl Sha1AtEnd {
+    /// The SHA1 hex that was appended, None until the whole content went through the stream.
+    pub fn get(&self) -> Option<&str> {
+        self.digest.get().map(String::as_str)
+    }
+}
+
+/// Wraps a content stream to hash it as it goes, and send its SHA1 hex after the last chunk.
+/// <br> For uploading with [HEX_DIGITS_AT_END] as the content SHA1, so the content doesn't have to be read twice or held in memory
+/// to hash it first. `Content-Length` is the content's length plus [SHA1_HEX_LENGTH].
+/// ```rust
+/// # use backblaze_b2_client::util::append_sha1_hex;
+/// # use bytes::Bytes;
+/// # use futures::{executor::block_on, stream, StreamExt};
+/// let content = stream::iter([Ok::<_, ()>(Bytes::from("hello ")), Ok(Bytes::from("world"))]);
+/// let (body, sha1) = append_sha1_hex(content);
+///
+/// let body: Vec<Bytes> = block_on(body.map(Result::unwrap).collect());
+///
+/// assert_eq!(body.concat(), b"hello world2aae6c35c94fcfb415dbe95f408b9ce91ee846ed");
+/// assert_eq!(sha1.get(), Some("2aae6c35c94fcfb415dbe95f408b9ce91ee846ed"));
+/// ```
+pub fn append_sha1_hex<S, E>(
+    content: S,
+) -> (
+    impl Stream<Item = Result<Bytes, E>> + Send + 'static,
+    Sha1AtEnd,
+)
+where
+    S: Stream<Item = Result<Bytes, E>> + Send + 'static,
+    E: Send + 'static,
+{
+    let sha1_at_end = Sha1AtEnd::default();
+    let digest = sha1_at_end.digest.clone();
+
+    let body = stream! {
+        let mut content = Box::pin(content);
+        let mut sha1 = Sha1::new();
+
+        while let Some(chunk) = content.next().await {
+            match chunk {
+                Ok(chunk) => {
+                    sha1.update(&chunk);
+                    yield Ok(chunk);
+                }
+                Err(error) => {
+                    yield Err(error);
+                    return;
+                }
+            }
+        }
+
+        let hex = sha1.digest().to_string();
+        digest.set(hex.clone()).ok();
+
+        yield Ok(Bytes::from(hex));
+    };
+
+    (body, sha1_at_end)
+}
@@ -1,5 +1,7 @@
 use std::time::{Duration, Instant};
 
+use super::Clock;
+
 #[derive(Debug)]
 pub struct TimeSeriesDataPoint<T> {
     pub data: T,
@@ -7,11 +9,13 @@ pub struct TimeSeriesDataPoint<T> {
 }
 
 impl<T> TimeSeriesDataPoint<T> {
-    pub fn new(data: T) -> Self {
-        Self {
-            data,
-            time: Instant::now(),
-        }
+    /// A point recorded at the clock's current time.
+    pub fn new(data: T, clock: &dyn Clock) -> Self {
+        Self::at(data, clock.now())
+    }
+
+    pub fn at(data: T, time: Instant) -> Self {
+        Self { data, time }
     }
 }
 
@@ -35,40 +39,53 @@ impl<T, const N: usize> RollingTimeSeries<T, N> {
         }
     }
 
-    pub fn get_valid_points(&self) -> Vec<&TimeSeriesDataPoint<T>> {
+    /// Points younger than the max age at the clock's current time.
+    pub fn get_valid_points(&self, clock: &dyn Clock) -> Vec<&TimeSeriesDataPoint<T>> {
+        self.get_valid_points_at(clock.now())
+    }
+
+    /// Points younger than the max age at the given time.
+    pub fn get_valid_points_at(&self, now: Instant) -> Vec<&TimeSeriesDataPoint<T>> {
         self.data_points
             .iter()
             .filter_map(|curr| match curr {
-                Some(point) if point.time.elapsed() < self.max_age => Some(point),
+                Some(point) if now.saturating_duration_since(point.time) < self.max_age => {
+                    Some(point)
+                }
                 Some(_) | None => None,
             })
             .collect()
     }
 
-    pub fn add_value(&mut self, value: T) {
+    /// Adds a value recorded at the clock's current time, see [add_value_at](RollingTimeSeries::add_value_at).
+    pub fn add_value(&mut self, value: T, clock: &dyn Clock) {
+        self.add_value_at(value, clock.now())
+    }
+
+    /// Adds a value recorded at the given time, replacing an expired or the oldest point when full.
+    pub fn add_value_at(&mut self, value: T, now: Instant) {
+        let age = |time: Instant| now.saturating_duration_since(time);
         let mut oldest_datapoint: &mut Option<TimeSeriesDataPoint<T>> = &mut None;
 
         for data_point in self.data_points.iter_mut() {
             match data_point {
-                Some(dp) if dp.time.elapsed() >= self.max_age => {
-                    *data_point = Some(TimeSeriesDataPoint::new(value));
+                Some(dp) if age(dp.time) >= self.max_age => {
+                    *data_point = Some(TimeSeriesDataPoint::at(value, now));
                     return;
                 }
                 Some(dp) => match oldest_datapoint {
-                    Some(dp2) if dp.time.elapsed() > dp2.time.elapsed() => {
-                        oldest_datapoint = data_point
-                    }
+                    Some(dp2) if age(dp.time) > age(dp2.time) => oldest_datapoint = data_point,
                     Some(_) | None => oldest_datapoint = data_point,
                 },
                 None => {
-                    *data_point = Some(TimeSeriesDataPoint::new(value));
+                    *data_point = Some(TimeSeriesDataPoint::at(value, now));
                     return;
                 }
             }
         }
 
-        if let Some(_) = oldest_datapoint {
-            *oldest_datapoint = Some(TimeSeriesDataPoint::new(value));
+        if oldest_datapoint.is_some() {
+            *oldest_datapoint = Some(TimeSeriesDataPoint::at(value, now));
         }
     }
 }
@@ -0,0 +1,82 @@
+//! Checks the typed definitions against the JSON examples of Backblaze's API docs, vendored in `tests/b2_api_examples`.
+//! <br> Every example is read into its definition and written back, a field that doesn't make it through unchanged
+//! is one the definition is missing or reads into something else.
+
+use backblaze_b2_client::definitions::responses::{
+    B2GetDownloadAuthorizationBodyResponse, B2GetUploadPartUrlResponse, B2GetUploadUrlResponse,
+    B2ListFilesResponse, B2ListPartsResponse,
+};
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value;
+
+/// Reads the example into `T` and writes it back, panicking with every field that didn't make it through unchanged.
+fn assert_conforms<T: DeserializeOwned + Serialize>(example: &str) {
+    let typed: T = serde_json::from_str(example).expect("the example reads into the definition");
+    let example: Value = serde_json::from_str(example).expect("examples are valid JSON");
+    let written = serde_json::to_value(&typed).expect("the definition writes to JSON");
+
+    let mut gaps = vec![];
+    compare(&example, &written, String::new(), &mut gaps);
+
+    assert!(gaps.is_empty(), "{}", gaps.join("\n"));
+}
+
+fn compare(expected: &Value, actual: &Value, path: String, gaps: &mut Vec<String>) {
+    match (expected, actual) {
+        (Value::Object(expected), Value::Object(actual)) => {
+            for (key, expected_value) in expected {
+                let field_path = match path.is_empty() {
+                    true => key.clone(),
+                    false => format!("{}.{}", path, key),
+                };
+
+                match actual.get(key) {
+                    Some(actual_value) => compare(expected_value, actual_value, field_path, gaps),
+                    // Definitions leave out empty options
+                    None if expected_value.is_null() => {}
+                    None => gaps.push(format!("{} is missing", field_path)),
+                }
+            }
+        }
+        (Value::Array(expected), Value::Array(actual)) if expected.len() == actual.len() => {
+            for (index, (expected, actual)) in expected.iter().zip(actual).enumerate() {
+                compare(expected, actual, format!("{}[{}]", path, index), gaps);
+            }
+        }
+        (expected, actual) if expected == actual => {}
+        (expected, actual) => {
+            gaps.push(format!("{} changed from {} to {}", path, expected, actual))
+        }
+    }
+}
+
+#[test]
+fn get_upload_url() {
+    assert_conforms::<B2GetUploadUrlResponse>(include_str!(
+        "b2_api_examples/b2_get_upload_url.json"
+    ));
+}
+
+#[test]
+fn get_upload_part_url() {
+    assert_conforms::<B2GetUploadPartUrlResponse>(include_str!(
+        "b2_api_examples/b2_get_upload_part_url.json"
+    ));
+}
+
+#[test]
+fn list_file_names() {
+    assert_conforms::<B2ListFilesResponse>(include_str!("b2_api_examples/b2_list_file_names.json"));
+}
+
+#[test]
+fn list_parts() {
+    assert_conforms::<B2ListPartsResponse>(include_str!("b2_api_examples/b2_list_parts.json"));
+}
+
+#[test]
+fn get_download_authorization() {
+    assert_conforms::<B2GetDownloadAuthorizationBodyResponse>(include_str!(
+        "b2_api_examples/b2_get_download_authorization.json"
+    ));
+}
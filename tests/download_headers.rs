@@ -0,0 +1,46 @@
+//! Reads download details from random, mostly malformed, response headers, none of which may panic.
+
+use std::collections::HashMap;
+
+use backblaze_b2_client::definitions::shared::B2FileDownloadDetails;
+
+const NAMES: [&str; 7] = [
+    "x-bz-file-name",
+    "x-bz-content-sha1",
+    "x-bz-file-id",
+    "content-length",
+    "content-type",
+    "x-bz-upload-timestamp",
+    "x-bz-info-a",
+];
+
+const VALUES: [&str; 10] = [
+    "",
+    "none",
+    "12",
+    "-1",
+    "18446744073709551616",
+    "%ZZ",
+    "+%E2%82%AC",
+    "\u{0}",
+    "photo.jpg",
+    "1.5",
+];
+
+#[test]
+fn malformed_headers_dont_panic() {
+    for _ in 0..10_000 {
+        let mut headers: HashMap<String, String> = NAMES
+            .iter()
+            .filter(|_| rand::random_range(0..8) != 0)
+            .map(|name| {
+                (
+                    name.to_string(),
+                    VALUES[rand::random_range(0..VALUES.len())].to_string(),
+                )
+            })
+            .collect();
+
+        let _ = B2FileDownloadDetails::from_headers(&mut headers);
+    }
+}
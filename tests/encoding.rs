@@ -0,0 +1,121 @@
+//! Every printable ASCII character through each encoder, and the characters whose handling depends on where they're sent.
+
+use backblaze_b2_client::util::encoding::*;
+
+/// `(raw, encoded, encoded as a path segment)`, the header, file info and URL path encoders all give `encoded`.
+const MATRIX: [(&str, &str, &str); 40] = [
+    (" ", "%20", "%20"),
+    ("!", "!", "!"),
+    ("\"", "%22", "%22"),
+    ("#", "%23", "%23"),
+    ("$", "$", "$"),
+    ("%", "%25", "%25"),
+    ("&", "%26", "%26"),
+    ("'", "'", "'"),
+    ("(", "(", "("),
+    (")", ")", ")"),
+    ("*", "*", "*"),
+    ("+", "%2B", "%2B"),
+    (",", "%2C", "%2C"),
+    ("-", "-", "-"),
+    (".", ".", "."),
+    ("/", "/", "%2F"),
+    ("0", "0", "0"),
+    ("9", "9", "9"),
+    (":", ":", ":"),
+    (";", ";", ";"),
+    ("<", "%3C", "%3C"),
+    ("=", "=", "="),
+    (">", "%3E", "%3E"),
+    ("?", "%3F", "%3F"),
+    ("@", "@", "@"),
+    ("A", "A", "A"),
+    ("Z", "Z", "Z"),
+    ("[", "%5B", "%5B"),
+    ("\\", "%5C", "%5C"),
+    ("]", "%5D", "%5D"),
+    ("^", "%5E", "%5E"),
+    ("_", "_", "_"),
+    ("`", "%60", "%60"),
+    ("a", "a", "a"),
+    ("z", "z", "z"),
+    ("{", "%7B", "%7B"),
+    ("|", "%7C", "%7C"),
+    ("}", "%7D", "%7D"),
+    ("~", "~", "~"),
+    ("\u{8045}", "%E8%81%85", "%E8%81%85"),
+];
+
+#[test]
+fn matrix() {
+    for (raw, encoded, encoded_segment) in MATRIX {
+        assert_eq!(encode_header_value(raw), encoded, "header value {raw:?}");
+        assert_eq!(
+            encode_file_info_value(raw),
+            encoded,
+            "file info value {raw:?}"
+        );
+        assert_eq!(encode_url_path(raw), encoded, "url path {raw:?}");
+        assert_eq!(
+            encode_url_path_segment(raw),
+            encoded_segment,
+            "url path segment {raw:?}"
+        );
+        assert_eq!(decode_header_value(encoded), raw, "decoded {encoded:?}");
+    }
+}
+
+#[test]
+fn plus() {
+    // B2 decodes `+` as a space in headers, a literal plus has to be sent as `%2B` everywhere
+    assert_eq!(encode_header_value("a+b"), "a%2Bb");
+    assert_eq!(encode_file_info_value("a+b"), "a%2Bb");
+    assert_eq!(encode_url_path("a+b"), "a%2Bb");
+    assert_eq!(encode_url_path_segment("a+b"), "a%2Bb");
+    assert_eq!(encode_query_value("a+b"), "a%2Bb");
+
+    assert_eq!(decode_header_value("a+b%2Bc"), "a b+c");
+}
+
+#[test]
+fn question_mark() {
+    // Would start the query in a URL
+    assert_eq!(encode_header_value("a?b"), "a%3Fb");
+    assert_eq!(encode_file_info_value("a?b"), "a%3Fb");
+    assert_eq!(encode_url_path("a?b"), "a%3Fb");
+    assert_eq!(encode_url_path_segment("a?b"), "a%3Fb");
+    assert_eq!(encode_query_value("a?b"), "a%3Fb");
+}
+
+#[test]
+fn slash() {
+    // Only a path keeps it, a segment like a bucket name can't span several
+    assert_eq!(encode_header_value("a/b"), "a/b");
+    assert_eq!(encode_file_info_value("a/b"), "a/b");
+    assert_eq!(encode_url_path("a/b"), "a/b");
+    assert_eq!(encode_url_path_segment("a/b"), "a%2Fb");
+    assert_eq!(encode_query_value("a/b"), "a%2Fb");
+}
+
+#[test]
+fn control_characters() {
+    for control in (0u8..0x20).chain([0x7f]).map(char::from) {
+        let raw = control.to_string();
+        let encoded = format!("%{:02X}", control as u8);
+
+        assert_eq!(encode_header_value(&raw), encoded, "header value {raw:?}");
+        assert_eq!(
+            encode_file_info_value(&raw),
+            encoded,
+            "file info value {raw:?}"
+        );
+        assert_eq!(encode_url_path(&raw), encoded, "url path {raw:?}");
+        assert_eq!(
+            encode_url_path_segment(&raw),
+            encoded,
+            "url path segment {raw:?}"
+        );
+        assert_eq!(encode_query_value(&raw), encoded, "query value {raw:?}");
+        assert_eq!(decode_header_value(&encoded), raw, "decoded {encoded:?}");
+    }
+}
@@ -0,0 +1,127 @@
+//! The upload headers build their [HeaderMap] by hand instead of through serde, these check both give the same headers.
+
+use backblaze_b2_client::{
+    definitions::{
+        headers::{B2UploadFileHeaders, B2UploadPartHeaders},
+        shared::{
+            B2FileLegalHold, B2FileRetentionMode, B2ServerSideEncryption,
+            B2ServerSideEncryptionAlgorithm,
+        },
+    },
+    util::{serialize_into_header_map, IntoHeaderMap},
+};
+use reqwest::header::HeaderMap;
+
+const SHA1: &str = "2aae6c35c94fcfb415dbe95f408b9ce91ee846ed";
+const AUTHORIZATION: &str = "4_0022623512fc8f80000000001_01a8bb8b_a1b2c3_upld_abcdefghijk=";
+
+fn assert_same_headers<T: IntoHeaderMap + Clone>(headers: T) {
+    let serialized = serialize_into_header_map(&headers).expect("the serde path builds");
+    let direct: HeaderMap = headers.into_header_map().expect("the direct path builds");
+
+    assert_eq!(direct, serialized);
+}
+
+#[test]
+fn part_headers() {
+    assert_same_headers(
+        B2UploadPartHeaders::builder()
+            .authorization(AUTHORIZATION.into())
+            .part_number(42)
+            .content_length(100 * 1024 * 1024)
+            .content_sha1(SHA1.into())
+            .build(),
+    );
+}
+
+#[test]
+fn part_headers_with_customer_key() {
+    assert_same_headers(
+        B2UploadPartHeaders::builder()
+            .authorization(AUTHORIZATION.into())
+            .part_number(1)
+            .content_length(5_000_000)
+            .content_sha1("hex_digits_at_end".into())
+            .server_side_encryption_customer_algorithm(Some(
+                B2ServerSideEncryptionAlgorithm::AES256,
+            ))
+            .server_side_encryption_customer_key(Some("a2V5".into()))
+            .server_side_encryption_customer_key_md5(Some("bWQ1".into()))
+            .build(),
+    );
+}
+
+#[test]
+fn file_headers() {
+    assert_same_headers(
+        B2UploadFileHeaders::builder()
+            .authorization(AUTHORIZATION.into())
+            .file_name("photos/2024/summer/beach%20day.jpg".into())
+            .content_type("b2/x-auto".into())
+            .content_length(4 * 1024 * 1024)
+            .content_sha1(SHA1.into())
+            .build(),
+    );
+}
+
+#[test]
+fn file_headers_with_every_field() {
+    assert_same_headers(
+        B2UploadFileHeaders::builder()
+            .authorization(AUTHORIZATION.into())
+            .file_name("photos/2024/summer/beach%20day.jpg".into())
+            .content_type("image/jpeg".into())
+            .content_length(4 * 1024 * 1024)
+            .content_sha1(SHA1.into())
+            .src_last_modified_millis(Some(1_700_000_000_000))
+            .b2_content_disposition(Some("attachment".into()))
+            .b2_content_language(Some("en".into()))
+            .b2_expires(Some("Thu, 01 Dec 2094 16:00:00 GMT".into()))
+            .b2_cache_control(Some("max-age=3600".into()))
+            .b2_content_encoding(Some("gzip".into()))
+            .custom_upload_timestamp(Some(1_600_000_000_000))
+            .legal_hold(Some(B2FileLegalHold::On))
+            .retention_mode(Some(B2FileRetentionMode::Governance))
+            .retention_retain_until_timestamp(Some(1_800_000_000_000))
+            .server_side_encryption_customer_algorithm(Some(
+                B2ServerSideEncryptionAlgorithm::AES256,
+            ))
+            .server_side_encryption_customer_key(Some("a2V5".into()))
+            .server_side_encryption_customer_key_md5(Some("bWQ1".into()))
+            .build(),
+    );
+}
+
+#[test]
+fn invalid_values_fail_both_paths() {
+    let headers = B2UploadFileHeaders::builder()
+        .authorization(AUTHORIZATION.into())
+        .file_name("line\nbreak".into())
+        .content_type("b2/x-auto".into())
+        .content_length(1)
+        .content_sha1(SHA1.into())
+        .build();
+
+    assert!(serialize_into_header_map(&headers).is_err());
+    assert!(headers.into_header_map().is_err());
+}
+
+// The serde path writes `server_side_encryption` as JSON, B2 takes SSE-B2 as just the algorithm,
+// so only the direct path is checked for it
+#[test]
+fn file_headers_sse_b2() {
+    let headers = B2UploadFileHeaders::builder()
+        .authorization(AUTHORIZATION.into())
+        .file_name("file.txt".into())
+        .content_type("text/plain".into())
+        .content_length(1)
+        .content_sha1(SHA1.into())
+        .server_side_encryption(Some(B2ServerSideEncryption::SseB2 {
+            algorithm: B2ServerSideEncryptionAlgorithm::AES256,
+        }))
+        .build()
+        .into_header_map()
+        .unwrap();
+
+    assert_eq!(headers["x-bz-server-side-encryption"], "AES256");
+}
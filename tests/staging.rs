@@ -0,0 +1,108 @@
+//! A stage that fails or is dropped halfway has to remove its temporary file.
+
+use std::{
+    io,
+    path::{Path, PathBuf},
+    pin::Pin,
+    task::{Context, Poll},
+    time::Duration,
+};
+
+use backblaze_b2_client::tasks::upload::{StagingArea, TempDirStaging};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWriteExt, ReadBuf};
+
+/// A stream that fails on its first read.
+struct FailingStream;
+
+impl AsyncRead for FailingStream {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+        _buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        Poll::Ready(Err(io::Error::other("stream failed")))
+    }
+}
+
+fn staging_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("b2-staging-test-{}-{}", name, std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+
+    dir
+}
+
+/// Waits for the cleanup, which removes the file in the background.
+async fn assert_emptied(dir: &Path) {
+    for _ in 0..200 {
+        if std::fs::read_dir(dir).unwrap().next().is_none() {
+            std::fs::remove_dir(dir).ok();
+            return;
+        }
+
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+
+    panic!("staging left files in {}", dir.display());
+}
+
+#[test]
+fn failed_stream_leaves_no_file() {
+    tokio::runtime::Runtime::new().unwrap().block_on(async {
+        let dir = staging_dir("failed-stream");
+        let staging = TempDirStaging::new(dir.clone(), None);
+        let mut stream = io::Cursor::new(vec![1u8; 1024]).chain(FailingStream);
+
+        assert!(staging.stage(&mut stream).await.is_err());
+        assert_emptied(&dir).await;
+        assert_eq!(staging.disk_usage(), 0);
+    });
+}
+
+#[test]
+fn stage_over_limit_leaves_no_file() {
+    tokio::runtime::Runtime::new().unwrap().block_on(async {
+        let dir = staging_dir("over-limit");
+        let staging = TempDirStaging::new(dir.clone(), Some(16));
+        let mut stream = io::Cursor::new(vec![1u8; 1024]);
+
+        assert!(staging.stage(&mut stream).await.is_err());
+        assert_emptied(&dir).await;
+        assert_eq!(staging.disk_usage(), 0);
+    });
+}
+
+#[test]
+fn dropped_stage_leaves_no_file() {
+    tokio::runtime::Runtime::new().unwrap().block_on(async {
+        let dir = staging_dir("dropped");
+        let staging = TempDirStaging::new(dir.clone(), None);
+        let (mut writer, mut stream) = tokio::io::duplex(1024);
+
+        // The stream never ends, so the stage is still waiting on it when dropped
+        writer.write_all(&[1u8; 512]).await.unwrap();
+        let stage = staging.stage(&mut stream);
+
+        assert!(tokio::time::timeout(Duration::from_millis(50), stage)
+            .await
+            .is_err());
+        assert_emptied(&dir).await;
+        assert_eq!(staging.disk_usage(), 0);
+    });
+}
+
+#[test]
+fn dropped_staged_file_leaves_no_file() {
+    tokio::runtime::Runtime::new().unwrap().block_on(async {
+        let dir = staging_dir("staged");
+        let staging = TempDirStaging::new(dir.clone(), None);
+        let mut stream = io::Cursor::new(vec![1u8; 1024]);
+
+        let staged = staging.stage(&mut stream).await.unwrap();
+        assert_eq!(staged.size(), 1024);
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 1);
+
+        drop(staged);
+        assert_emptied(&dir).await;
+        assert_eq!(staging.disk_usage(), 0);
+    });
+}